@@ -0,0 +1,455 @@
+//! The unwind tables of the ARM exception handling ABI (EHABI), which 32-bit
+//! ARM files have instead of `.eh_frame`: the index in `.ARM.exidx` (the
+//! `PT_ARM_EXIDX` segment), with one entry per function, and the table
+//! entries in `.ARM.extab` which it points to for functions whose unwind
+//! instructions do not fit in the index.
+
+use ElfFile;
+use header::{Class, Machine};
+use reader::Reader;
+use program::{self, TYPE_ARM_EXIDX};
+use sections::{ShType, SHT_ARM_EXIDX};
+
+/// The second word of index entries for functions which cannot be unwound.
+pub const EXIDX_CANTUNWIND: u32 = 1;
+
+/// An `.ARM.exidx` section and the `.ARM.extab` data it refers to.
+#[derive(Clone, Copy, Debug)]
+pub struct ExceptionIndex<'a> {
+    index: &'a [u8],
+    index_address: u32,
+    table: &'a [u8],
+    table_address: u32,
+}
+
+impl<'a> ExceptionIndex<'a> {
+    /// `index` is loaded at `index_address`, and `table`, which holds the
+    /// table entries, at `table_address`.
+    pub fn new(index: &'a [u8],
+               index_address: u32,
+               table: &'a [u8],
+               table_address: u32)
+               -> Result<ExceptionIndex<'a>, &'static str> {
+        check!(index.len() % 8 == 0, "Exception index size is not a multiple of 8");
+        Ok(ExceptionIndex { index, index_address, table, table_address })
+    }
+
+    /// The `.ARM.exidx` section of `elf_file`, or if it has no section
+    /// headers, the `PT_ARM_EXIDX` segment. Table entries are read from the
+    /// loadable segment holding the index, or failing that, `.ARM.extab`.
+    pub fn load(elf_file: &ElfFile<'a>) -> Result<Option<ExceptionIndex<'a>>, &'static str> {
+        check!(elf_file.header.pt1.class() == Class::ThirtyTwo &&
+               elf_file.header.pt2.machine().as_machine() == Machine::Arm,
+               "Not a 32-bit ARM file");
+        elf_file.check_tables(false)?;
+
+        let section = elf_file.find_section_by_name(".ARM.exidx").filter(|section| {
+            section.get_type() == Ok(ShType::ProcessorSpecific(SHT_ARM_EXIDX))
+        });
+        let (index, index_address) = match section {
+            Some(section) => (section.data_checked(elf_file)?, section.address()),
+            None => {
+                let segment = elf_file.program_iter().find(|ph| {
+                    ph.get_type() == Ok(program::Type::ProcessorSpecific(TYPE_ARM_EXIDX))
+                });
+                match segment {
+                    Some(segment) => (segment.data_checked(elf_file)?, segment.virtual_addr()),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let segment = elf_file.program_iter().find(|ph| {
+            ph.get_type() == Ok(program::Type::Load) && ph.virtual_addr() <= index_address &&
+            index_address - ph.virtual_addr() < ph.file_size()
+        });
+        let (table, table_address) = match segment {
+            Some(segment) => (segment.data_checked(elf_file)?, segment.virtual_addr()),
+            None => match elf_file.find_section_by_name(".ARM.extab") {
+                Some(section) => (section.data_checked(elf_file)?, section.address()),
+                None => (&[][..], 0),
+            },
+        };
+        ExceptionIndex::new(index, index_address as u32, table, table_address as u32).map(Some)
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len() / 8
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// The index entry at `index`.
+    pub fn entry(&self, index: usize) -> Result<IndexEntry, &'static str> {
+        check!(index < self.len(), "Index entry out of range");
+        let mut reader = Reader::new(&self.index[index * 8..index * 8 + 8]);
+        let address = self.index_address.wrapping_add(index as u32 * 8);
+        let function = reader.u32()?;
+        check!(function & 0x8000_0000 == 0, "Invalid exception index entry");
+        let function = prel31(function, address);
+        let data = match reader.u32()? {
+            EXIDX_CANTUNWIND => EntryData::CantUnwind,
+            word if word & 0x8000_0000 != 0 => EntryData::Inline(word),
+            word => EntryData::Table(prel31(word, address.wrapping_add(4))),
+        };
+        Ok(IndexEntry { function, data })
+    }
+
+    /// The index entries, in order of address.
+    pub fn entries(&self) -> impl Iterator<Item = Result<IndexEntry, &'static str>> + 'a {
+        let index = *self;
+        (0..self.len()).map(move |i| index.entry(i))
+    }
+
+    /// Finds the index entry for the function containing `pc`, which should
+    /// not have the Thumb bit set: the last one starting at or before it.
+    pub fn find(&self, pc: u32) -> Result<Option<IndexEntry>, &'static str> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.entry(middle)?.function <= pc {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        if low == 0 {
+            return Ok(None);
+        }
+        self.entry(low - 1).map(Some)
+    }
+
+    /// The personality routine and unwind instructions of `entry`, or `None`
+    /// if it cannot be unwound.
+    pub fn unwind_data(&self, entry: &IndexEntry) -> Result<Option<UnwindData<'a>>, &'static str> {
+        let address = match entry.data {
+            EntryData::CantUnwind => return Ok(None),
+            EntryData::Inline(word) => {
+                check!(word >> 24 == 0x80, "Unsupported inline personality routine");
+                return Ok(Some(UnwindData {
+                    personality: Personality::Compact(0),
+                    bytes: Bytes::new(word, 3, &[]),
+                    data: &[],
+                }));
+            }
+            EntryData::Table(address) => address,
+        };
+        let offset = address.wrapping_sub(self.table_address) as usize;
+        let mut reader = Reader::new(self.table.get(offset..).ok_or("Exception table entry out of range")?);
+        let word = reader.u32()?;
+        let (personality, word) = if word & 0x8000_0000 != 0 {
+            (Personality::Compact((word >> 24) & 0xf), word)
+        } else {
+            // The generic model, in which the data is up to the routine. GCC
+            // and LLVM give it instructions in the same form as for
+            // `__aeabi_unwind_cpp_pr1`, but with three bytes in the first
+            // word.
+            (Personality::Generic(prel31(word, address)), reader.u32()?)
+        };
+        let bytes = match personality {
+            Personality::Compact(0) => Bytes::new(word, 3, &[]),
+            Personality::Compact(1) | Personality::Compact(2) => {
+                Bytes::new(word, 2, reader.bytes((word >> 16 & 0xff) as u64 * 4)?)
+            }
+            Personality::Compact(_) => return Err("Unknown personality routine index"),
+            Personality::Generic(_) => Bytes::new(word, 3, reader.bytes((word >> 24) as u64 * 4)?),
+        };
+        Ok(Some(UnwindData { personality, bytes, data: reader.data }))
+    }
+
+    /// Unwinds one frame: sets `registers` (`r0` to `r15`) to the caller's.
+    /// Returns false if there is no unwind information for the program
+    /// counter or the frame cannot be unwound. `read_memory` fills its buffer
+    /// from an address, or returns false if that memory cannot be read.
+    pub fn step<F>(&self, registers: &mut [u32; 16], read_memory: F) -> Result<bool, &'static str>
+        where F: FnMut(u32, &mut [u8]) -> bool
+    {
+        let entry = match self.find(registers[15] & !1)? {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        match self.unwind_data(&entry)? {
+            Some(data) => execute(data.instructions(), registers, read_memory),
+            None => Ok(false),
+        }
+    }
+}
+
+// Decodes a 31-bit offset from `address`.
+fn prel31(word: u32, address: u32) -> u32 {
+    address.wrapping_add((((word << 1) as i32) >> 1) as u32)
+}
+
+/// An entry in `.ARM.exidx`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The address of the function.
+    pub function: u32,
+    pub data: EntryData,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryData {
+    CantUnwind,
+    /// Up to three bytes of unwind instructions for
+    /// `__aeabi_unwind_cpp_pr0`, in the low bytes of the word.
+    Inline(u32),
+    /// The address of an entry in `.ARM.extab`.
+    Table(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Personality {
+    /// `__aeabi_unwind_cpp_pr0`, `pr1` or `pr2`, which use the compact model.
+    Compact(u32),
+    /// The address of a personality routine, such as `__gxx_personality_v0`.
+    Generic(u32),
+}
+
+/// How to unwind a function.
+#[derive(Clone, Copy, Debug)]
+pub struct UnwindData<'a> {
+    pub personality: Personality,
+    pub bytes: Bytes<'a>,
+    /// What follows the unwind instructions in `.ARM.extab`: the descriptors
+    /// for `pr1` and `pr2`, or the language-specific data for generic
+    /// personality routines. Its end is not recorded.
+    pub data: &'a [u8],
+}
+
+impl<'a> UnwindData<'a> {
+    pub fn instructions(&self) -> Instructions<'a> {
+        Instructions { bytes: self.bytes }
+    }
+}
+
+/// The bytes of unwind instructions, which are taken from each word from the
+/// most significant down.
+#[derive(Clone, Copy, Debug)]
+pub struct Bytes<'a> {
+    word: u32,
+    // The number of bytes left in `word`.
+    remaining: u8,
+    words: &'a [u8],
+}
+
+impl<'a> Bytes<'a> {
+    fn new(word: u32, remaining: u8, words: &'a [u8]) -> Bytes<'a> {
+        Bytes { word, remaining, words }
+    }
+}
+
+impl<'a> Iterator for Bytes<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.remaining == 0 {
+            let mut reader = Reader::new(self.words);
+            self.word = reader.u32().ok()?;
+            self.words = reader.data;
+            self.remaining = 4;
+        }
+        self.remaining -= 1;
+        Some((self.word >> (self.remaining * 8)) as u8)
+    }
+}
+
+/// An unwind instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// Adds to the virtual stack pointer (`vsp`).
+    VspAdd(u32),
+    /// Subtracts from `vsp`.
+    VspSub(u32),
+    /// Sets `vsp` to the value of a core register.
+    SetVsp(u8),
+    /// Pops the core registers in a mask, in which bit n is rn.
+    Pop(u16),
+    /// Pops `count` VFP double registers from `first`, saved by `FSTMFDX`
+    /// (which adds a word) if `fstmfdx` and otherwise by `VPUSH`.
+    PopVfp { first: u8, count: u8, fstmfdx: bool },
+    /// Pops `count` iWMMXt data registers from `first`.
+    PopWmmxData { first: u8, count: u8 },
+    /// Pops the iWMMXt control registers in a mask.
+    PopWmmxControl(u8),
+    RefuseToUnwind,
+    Finish,
+    /// A spare or reserved opcode.
+    Spare(u8),
+}
+
+/// Decodes unwind instructions. The instructions end with `Finish`, or when
+/// the bytes do.
+#[derive(Clone, Copy, Debug)]
+pub struct Instructions<'a> {
+    bytes: Bytes<'a>,
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let opcode = self.bytes.next()?;
+        Some(self.decode(opcode))
+    }
+}
+
+impl<'a> Instructions<'a> {
+    fn operand(&mut self) -> Result<u8, &'static str> {
+        self.bytes.next().ok_or("Unwind instruction is truncated")
+    }
+
+    fn decode(&mut self, opcode: u8) -> Result<Instruction, &'static str> {
+        Ok(match opcode {
+            0x00..=0x3f => Instruction::VspAdd(((opcode & 0x3f) as u32) * 4 + 4),
+            0x40..=0x7f => Instruction::VspSub(((opcode & 0x3f) as u32) * 4 + 4),
+            0x80..=0x8f => {
+                let mask = ((opcode & 0xf) as u16) << 8 | self.operand()? as u16;
+                match mask {
+                    0 => Instruction::RefuseToUnwind,
+                    mask => Instruction::Pop(mask << 4),
+                }
+            }
+            0x9d | 0x9f => Instruction::Spare(opcode),
+            0x90..=0x9f => Instruction::SetVsp(opcode & 0xf),
+            // r4 to r(4 + n), and r14 for 0xa8 to 0xaf.
+            0xa0..=0xaf => {
+                let mask = ((2u16 << (opcode & 0x7)) - 1) << 4;
+                Instruction::Pop(if opcode & 0x8 != 0 { mask | 1 << 14 } else { mask })
+            }
+            0xb0 => Instruction::Finish,
+            0xb1 => match self.operand()? {
+                mask if mask != 0 && mask & 0xf0 == 0 => Instruction::Pop(mask as u16),
+                _ => Instruction::Spare(opcode),
+            },
+            0xb2 => {
+                let mut value = 0u32;
+                let mut shift = 0;
+                loop {
+                    let byte = self.operand()?;
+                    check!(shift < 32, "Unwind instruction operand is too large");
+                    value |= ((byte & 0x7f) as u32) << shift;
+                    shift += 7;
+                    if byte & 0x80 == 0 {
+                        break;
+                    }
+                }
+                Instruction::VspAdd(value.wrapping_mul(4).wrapping_add(0x204))
+            }
+            0xb3 | 0xc8 | 0xc9 => {
+                let operand = self.operand()?;
+                let first = operand >> 4;
+                let count = (operand & 0xf) + 1;
+                match opcode {
+                    0xb3 => Instruction::PopVfp { first, count, fstmfdx: true },
+                    0xc8 => Instruction::PopVfp { first: first + 16, count, fstmfdx: false },
+                    _ => Instruction::PopVfp { first, count, fstmfdx: false },
+                }
+            }
+            0xb8..=0xbf => Instruction::PopVfp { first: 8, count: (opcode & 0x7) + 1, fstmfdx: true },
+            0xc0..=0xc5 => Instruction::PopWmmxData { first: 10, count: (opcode & 0x7) + 1 },
+            0xc6 => {
+                let operand = self.operand()?;
+                Instruction::PopWmmxData { first: operand >> 4, count: (operand & 0xf) + 1 }
+            }
+            0xc7 => match self.operand()? {
+                mask if mask != 0 && mask & 0xf0 == 0 => Instruction::PopWmmxControl(mask),
+                _ => Instruction::Spare(opcode),
+            },
+            0xd0..=0xd7 => Instruction::PopVfp { first: 8, count: (opcode & 0x7) + 1, fstmfdx: false },
+            _ => Instruction::Spare(opcode),
+        })
+    }
+}
+
+/// Runs unwind instructions on the core registers of a frame, leaving the
+/// caller's. Returns false if the frame refuses to be unwound. See
+/// `ExceptionIndex::step` for `read_memory`.
+pub fn execute<F>(instructions: Instructions,
+                  registers: &mut [u32; 16],
+                  mut read_memory: F)
+                  -> Result<bool, &'static str>
+    where F: FnMut(u32, &mut [u8]) -> bool
+{
+    let mut vsp = registers[13];
+    let mut pc_popped = false;
+    for instruction in instructions {
+        match instruction? {
+            Instruction::VspAdd(n) => vsp = vsp.wrapping_add(n),
+            Instruction::VspSub(n) => vsp = vsp.wrapping_sub(n),
+            Instruction::SetVsp(register) => vsp = registers[register as usize],
+            Instruction::Pop(mask) => {
+                for (register, value) in registers.iter_mut().enumerate() {
+                    if mask & (1 << register) != 0 {
+                        let mut buf = [0; 4];
+                        check!(read_memory(vsp, &mut buf), "Memory cannot be read");
+                        *value = u32::from_ne_bytes(buf);
+                        vsp = vsp.wrapping_add(4);
+                    }
+                }
+                if mask & (1 << 13) != 0 {
+                    vsp = registers[13];
+                }
+                pc_popped |= mask & (1 << 15) != 0;
+            }
+            Instruction::PopVfp { count, fstmfdx, .. } => {
+                vsp = vsp.wrapping_add(count as u32 * 8 + if fstmfdx { 4 } else { 0 });
+            }
+            Instruction::PopWmmxData { count, .. } => vsp = vsp.wrapping_add(count as u32 * 8),
+            Instruction::PopWmmxControl(mask) => vsp = vsp.wrapping_add(mask.count_ones() * 4),
+            Instruction::RefuseToUnwind => return Ok(false),
+            Instruction::Finish => break,
+            Instruction::Spare(_) => return Err("Spare unwind instruction"),
+        }
+    }
+    registers[13] = vsp;
+    if !pc_popped {
+        registers[15] = registers[14];
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+
+    #[test]
+    fn step() {
+        // An index at 0x2000 with functions at 0x1000 (vsp += 8, then pop
+        // {r4, r14}), 0x1100 (with an `.ARM.extab` entry at 0x3000) and
+        // 0x1200, which cannot be unwound.
+        let mut index = Vec::new();
+        for &word in &[0x7fff_f000u32, 0x8001_a8b0, 0x7fff_f0f8, 0x0000_0ff4, 0x7fff_f1f0, EXIDX_CANTUNWIND] {
+            index.extend_from_slice(&word.to_ne_bytes());
+        }
+        let mut table = Vec::new();
+        for &word in &[0x8101_b2abu32, 0x0184_83b0] {
+            table.extend_from_slice(&word.to_ne_bytes());
+        }
+        let index = ExceptionIndex::new(&index, 0x2000, &table, 0x3000).unwrap();
+        assert!(index.find(0xfff).unwrap().is_none());
+
+        let entry = index.find(0x1180).unwrap().unwrap();
+        assert_eq!(entry, IndexEntry { function: 0x1100, data: EntryData::Table(0x3000) });
+        let data = index.unwind_data(&entry).unwrap().unwrap();
+        assert_eq!(data.personality, Personality::Compact(1));
+        let instructions = data.instructions().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(instructions, [Instruction::VspAdd(1200), Instruction::Pop(0x4830), Instruction::Finish]);
+
+        // Memory holds its own address.
+        let read_memory = |address: u32, buf: &mut [u8]| {
+            buf.copy_from_slice(&address.to_ne_bytes());
+            true
+        };
+        let mut registers = [0; 16];
+        registers[13] = 0x100;
+        registers[15] = 0x1051;
+        assert!(index.step(&mut registers, read_memory).unwrap());
+        assert_eq!((registers[4], registers[13], registers[14], registers[15]), (0x108, 0x110, 0x10c, 0x10c));
+        registers[15] = 0x1200;
+        assert!(!index.step(&mut registers, read_memory).unwrap());
+    }
+}
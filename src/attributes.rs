@@ -0,0 +1,428 @@
+//! Build attributes, which record the target and ABI choices an object was
+//! built with so that incompatible objects can be caught at link time: the
+//! `.ARM.attributes` and `.riscv.attributes` sections (`PT_RISCV_ATTRIBUTES`).
+//!
+//! The section holds one subsection per vendor, such as "aeabi" or "riscv".
+//! Each holds attributes for the whole file, or for some of its sections or
+//! symbols. An attribute is a ULEB128 tag followed by a ULEB128 or string
+//! value.
+
+use ElfFile;
+use header::{Class, Machine};
+use reader::Reader;
+use program::{self, TYPE_RISCV_ATTRIBUTES};
+use sections::{ShType, SHT_ARM_ATTRIBUTES, SHT_RISCV_ATTRIBUTES};
+
+/// The first byte of an attributes section.
+pub const FORMAT_VERSION: u8 = b'A';
+
+pub const TAG_FILE: u64 = 1;
+pub const TAG_SECTION: u64 = 2;
+pub const TAG_SYMBOL: u64 = 3;
+
+// Tags of the "aeabi" subsection.
+pub const TAG_CPU_RAW_NAME: u64 = 4;
+pub const TAG_CPU_NAME: u64 = 5;
+pub const TAG_CPU_ARCH: u64 = 6;
+pub const TAG_CPU_ARCH_PROFILE: u64 = 7;
+pub const TAG_ARM_ISA_USE: u64 = 8;
+pub const TAG_THUMB_ISA_USE: u64 = 9;
+pub const TAG_FP_ARCH: u64 = 10;
+pub const TAG_WMMX_ARCH: u64 = 11;
+pub const TAG_ADVANCED_SIMD_ARCH: u64 = 12;
+pub const TAG_PCS_CONFIG: u64 = 13;
+pub const TAG_ABI_PCS_R9_USE: u64 = 14;
+pub const TAG_ABI_PCS_RW_DATA: u64 = 15;
+pub const TAG_ABI_PCS_RO_DATA: u64 = 16;
+pub const TAG_ABI_PCS_GOT_USE: u64 = 17;
+pub const TAG_ABI_PCS_WCHAR_T: u64 = 18;
+pub const TAG_ABI_FP_ROUNDING: u64 = 19;
+pub const TAG_ABI_FP_DENORMAL: u64 = 20;
+pub const TAG_ABI_FP_EXCEPTIONS: u64 = 21;
+pub const TAG_ABI_FP_USER_EXCEPTIONS: u64 = 22;
+pub const TAG_ABI_FP_NUMBER_MODEL: u64 = 23;
+pub const TAG_ABI_ALIGN_NEEDED: u64 = 24;
+pub const TAG_ABI_ALIGN_PRESERVED: u64 = 25;
+pub const TAG_ABI_ENUM_SIZE: u64 = 26;
+pub const TAG_ABI_HARDFP_USE: u64 = 27;
+pub const TAG_ABI_VFP_ARGS: u64 = 28;
+pub const TAG_ABI_WMMX_ARGS: u64 = 29;
+pub const TAG_ABI_OPTIMIZATION_GOALS: u64 = 30;
+pub const TAG_ABI_FP_OPTIMIZATION_GOALS: u64 = 31;
+pub const TAG_COMPATIBILITY: u64 = 32;
+pub const TAG_CPU_UNALIGNED_ACCESS: u64 = 34;
+pub const TAG_FP_HP_EXTENSION: u64 = 36;
+pub const TAG_ABI_FP_16BIT_FORMAT: u64 = 38;
+pub const TAG_MPEXTENSION_USE: u64 = 42;
+pub const TAG_DIV_USE: u64 = 44;
+pub const TAG_DSP_EXTENSION: u64 = 46;
+pub const TAG_MVE_ARCH: u64 = 48;
+pub const TAG_PAC_EXTENSION: u64 = 50;
+pub const TAG_BTI_EXTENSION: u64 = 52;
+pub const TAG_NODEFAULTS: u64 = 64;
+pub const TAG_ALSO_COMPATIBLE_WITH: u64 = 65;
+pub const TAG_T2EE_USE: u64 = 66;
+pub const TAG_CONFORMANCE: u64 = 67;
+pub const TAG_VIRTUALIZATION_USE: u64 = 68;
+pub const TAG_FRAMEPOINTER_USE: u64 = 72;
+pub const TAG_BTI_USE: u64 = 74;
+pub const TAG_PACRET_USE: u64 = 76;
+
+// Tags of the "riscv" subsection.
+pub const TAG_RISCV_STACK_ALIGN: u64 = 4;
+pub const TAG_RISCV_ARCH: u64 = 5;
+pub const TAG_RISCV_UNALIGNED_ACCESS: u64 = 6;
+pub const TAG_RISCV_PRIV_SPEC: u64 = 8;
+pub const TAG_RISCV_PRIV_SPEC_MINOR: u64 = 10;
+pub const TAG_RISCV_PRIV_SPEC_REVISION: u64 = 12;
+pub const TAG_RISCV_ATOMIC_ABI: u64 = 14;
+pub const TAG_RISCV_X3_REG_USAGE: u64 = 16;
+
+/// An attributes section.
+#[derive(Clone, Copy, Debug)]
+pub struct Attributes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Attributes<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Attributes<'a>, &'static str> {
+        check!(data.first() == Some(&FORMAT_VERSION), "Unknown attributes format");
+        Ok(Attributes { data: &data[1..] })
+    }
+
+    /// The attributes section of an ARM or RISC-V file, or for a RISC-V file
+    /// without section headers, the `PT_RISCV_ATTRIBUTES` segment.
+    pub fn load(elf_file: &ElfFile<'a>) -> Result<Option<Attributes<'a>>, &'static str> {
+        let is_64 = match elf_file.header.pt1.class() {
+            Class::ThirtyTwo => false,
+            Class::SixtyFour => true,
+            Class::None | Class::Other(_) => return Err("Invalid ELF class"),
+        };
+        elf_file.check_tables(is_64)?;
+        let sh_type = match elf_file.header.pt2.machine().as_machine() {
+            Machine::Arm => SHT_ARM_ATTRIBUTES,
+            Machine::RISC_V => SHT_RISCV_ATTRIBUTES,
+            _ => return Ok(None),
+        };
+        for section in elf_file.section_iter() {
+            if section.get_type() == Ok(ShType::ProcessorSpecific(sh_type)) {
+                return Attributes::new(section.data_checked(elf_file)?).map(Some);
+            }
+        }
+        if sh_type == SHT_RISCV_ATTRIBUTES {
+            for segment in elf_file.program_iter() {
+                if segment.get_type() == Ok(program::Type::ProcessorSpecific(TYPE_RISCV_ATTRIBUTES)) {
+                    return Attributes::new(segment.data_checked(elf_file)?).map(Some);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn subsections(&self) -> Subsections<'a> {
+        Subsections { data: self.data }
+    }
+
+    /// The subsection of `vendor`, such as "aeabi".
+    pub fn subsection(&self, vendor: &str) -> Result<Option<Subsection<'a>>, &'static str> {
+        for subsection in self.subsections() {
+            let subsection = subsection?;
+            if subsection.vendor == vendor {
+                return Ok(Some(subsection));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The value of `tag` for the whole file in the subsection of `vendor`.
+    pub fn file_attribute(&self, vendor: &str, tag: u64) -> Result<Option<Value<'a>>, &'static str> {
+        match self.subsection(vendor)? {
+            Some(subsection) => subsection.file_attribute(tag),
+            None => Ok(None),
+        }
+    }
+
+    /// `Tag_CPU_arch`, the ARM architecture version: 10 for ARMv7, 14 for
+    /// ARMv8-A and so on.
+    pub fn cpu_arch(&self) -> Result<Option<u64>, &'static str> {
+        Ok(self.file_attribute("aeabi", TAG_CPU_ARCH)?.and_then(|value| value.as_u64()))
+    }
+
+    /// `Tag_FP_arch`, the floating-point architecture: 0 for none, 3 for
+    /// VFPv3 and so on.
+    pub fn fp_arch(&self) -> Result<Option<u64>, &'static str> {
+        Ok(self.file_attribute("aeabi", TAG_FP_ARCH)?.and_then(|value| value.as_u64()))
+    }
+
+    /// `Tag_ABI_VFP_args`, how floating-point arguments are passed: 0 in core
+    /// registers, 1 in VFP registers.
+    pub fn vfp_args(&self) -> Result<Option<u64>, &'static str> {
+        Ok(self.file_attribute("aeabi", TAG_ABI_VFP_ARGS)?.and_then(|value| value.as_u64()))
+    }
+
+    /// `Tag_RISCV_arch`, the ISA string, such as `rv64i2p1_m2p0_c2p0`.
+    pub fn riscv_arch(&self) -> Result<Option<&'a str>, &'static str> {
+        Ok(self.file_attribute("riscv", TAG_RISCV_ARCH)?.and_then(|value| value.as_str()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Subsections<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Subsections<'a> {
+    type Item = Result<Subsection<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let result = self.read();
+        if result.is_err() {
+            self.data = &[];
+        }
+        Some(result)
+    }
+}
+
+impl<'a> Subsections<'a> {
+    fn read(&mut self) -> Result<Subsection<'a>, &'static str> {
+        let mut reader = Reader::new(self.data);
+        // The length includes itself.
+        let length = reader.u32()?;
+        check!(length >= 4, "Attributes subsection is too short");
+        let mut reader = Reader::new(reader.bytes(length as u64 - 4)?);
+        self.data = &self.data[length as usize..];
+        let vendor = reader.c_str()?;
+        Ok(Subsection { vendor, data: reader.data })
+    }
+}
+
+/// The attributes of one vendor.
+#[derive(Clone, Copy, Debug)]
+pub struct Subsection<'a> {
+    pub vendor: &'a str,
+    data: &'a [u8],
+}
+
+impl<'a> Subsection<'a> {
+    /// The groups of attributes, each for the whole file or for some
+    /// sections or symbols.
+    pub fn groups(&self) -> Groups<'a> {
+        Groups { vendor: self.vendor, data: self.data }
+    }
+
+    /// The value of `tag` for the whole file.
+    pub fn file_attribute(&self, tag: u64) -> Result<Option<Value<'a>>, &'static str> {
+        for group in self.groups() {
+            let group = group?;
+            if group.scope != Scope::File {
+                continue;
+            }
+            for attribute in group.attributes() {
+                let attribute = attribute?;
+                if attribute.tag == tag {
+                    return Ok(Some(attribute.value));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Groups<'a> {
+    vendor: &'a str,
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Groups<'a> {
+    type Item = Result<Group<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let result = self.read();
+        if result.is_err() {
+            self.data = &[];
+        }
+        Some(result)
+    }
+}
+
+impl<'a> Groups<'a> {
+    fn read(&mut self) -> Result<Group<'a>, &'static str> {
+        let mut reader = Reader::new(self.data);
+        let scope = match reader.uleb()? {
+            TAG_FILE => Scope::File,
+            TAG_SECTION => Scope::Section,
+            TAG_SYMBOL => Scope::Symbol,
+            _ => return Err("Unknown attributes scope"),
+        };
+        // The size includes the tag and itself.
+        let size = reader.u32()? as usize;
+        let header_size = self.data.len() - reader.data.len();
+        check!(size >= header_size, "Attributes group is too short");
+        let mut reader = Reader::new(reader.bytes((size - header_size) as u64)?);
+        self.data = &self.data[size..];
+
+        // Section and symbol indices are ended by a zero.
+        let mut indices: &[u8] = &[];
+        if scope != Scope::File {
+            let start = reader.data;
+            loop {
+                let end = reader.data;
+                if reader.uleb()? == 0 {
+                    indices = &start[..start.len() - end.len()];
+                    break;
+                }
+            }
+        }
+        Ok(Group { scope, indices, vendor: self.vendor, data: reader.data })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    File,
+    Section,
+    Symbol,
+}
+
+/// Attributes which apply to the whole file, or to some of its sections or
+/// symbols.
+#[derive(Clone, Copy, Debug)]
+pub struct Group<'a> {
+    pub scope: Scope,
+    indices: &'a [u8],
+    vendor: &'a str,
+    data: &'a [u8],
+}
+
+impl<'a> Group<'a> {
+    /// The indices of the sections or symbols the attributes apply to.
+    pub fn indices(&self) -> impl Iterator<Item = Result<u64, &'static str>> + 'a {
+        let mut reader = Reader::new(self.indices);
+        ::core::iter::from_fn(move || {
+            if reader.data.is_empty() {
+                return None;
+            }
+            let result = reader.uleb();
+            if result.is_err() {
+                reader.data = &[];
+            }
+            Some(result)
+        })
+    }
+
+    pub fn attributes(&self) -> AttributeIter<'a> {
+        AttributeIter { vendor: self.vendor, data: self.data }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AttributeIter<'a> {
+    vendor: &'a str,
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for AttributeIter<'a> {
+    type Item = Result<Attribute<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let mut reader = Reader::new(self.data);
+        let result = self.read(&mut reader);
+        self.data = if result.is_ok() { reader.data } else { &[] };
+        Some(result)
+    }
+}
+
+impl<'a> AttributeIter<'a> {
+    fn read(&self, reader: &mut Reader<'a>) -> Result<Attribute<'a>, &'static str> {
+        let tag = reader.uleb()?;
+        // Tags from 32 up have string values if they are odd. The "aeabi"
+        // tags below that have integer values except for the CPU names,
+        // and `Tag_compatibility` has both.
+        let value = match (self.vendor, tag) {
+            ("aeabi", TAG_CPU_RAW_NAME) | ("aeabi", TAG_CPU_NAME) => Value::String(reader.c_str()?),
+            ("aeabi", TAG_COMPATIBILITY) => Value::IntegerAndString(reader.uleb()?, reader.c_str()?),
+            ("aeabi", tag) if tag < 32 => Value::Integer(reader.uleb()?),
+            (_, tag) if tag % 2 == 1 => Value::String(reader.c_str()?),
+            _ => Value::Integer(reader.uleb()?),
+        };
+        Ok(Attribute { tag, value })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    pub tag: u64,
+    pub value: Value<'a>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value<'a> {
+    Integer(u64),
+    String(&'a str),
+    /// A flag and a vendor name, for `Tag_compatibility`.
+    IntegerAndString(u64, &'a str),
+}
+
+impl<'a> Value<'a> {
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Integer(value) | Value::IntegerAndString(value, _) => Some(value),
+            Value::String(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            Value::String(value) | Value::IntegerAndString(_, value) => Some(value),
+            Value::Integer(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+
+    #[test]
+    fn aeabi() {
+        // Attributes for the whole file, and hard-float arguments just for
+        // sections 3 and 4.
+        let file = [1, 0, 0, 0, 0, 5, b'A', b'9', 0, 6, 10, 32, 1, b'g', b'n', b'u', 0, 28, 0];
+        let sections = [2, 0, 0, 0, 0, 3, 4, 0, 28, 1];
+        let mut subsection = b"aeabi\0".to_vec();
+        for group in &[&file[..], &sections[..]] {
+            let start = subsection.len();
+            subsection.extend_from_slice(group);
+            subsection[start + 1..start + 5].copy_from_slice(&(group.len() as u32).to_ne_bytes());
+        }
+        let mut data = vec![FORMAT_VERSION];
+        data.extend_from_slice(&(subsection.len() as u32 + 4).to_ne_bytes());
+        data.extend_from_slice(&subsection);
+
+        let attributes = Attributes::new(&data).unwrap();
+        assert_eq!(attributes.cpu_arch(), Ok(Some(10)));
+        assert_eq!(attributes.vfp_args(), Ok(Some(0)));
+        assert_eq!(attributes.file_attribute("aeabi", TAG_CPU_NAME), Ok(Some(Value::String("A9"))));
+        assert_eq!(attributes.file_attribute("aeabi", TAG_COMPATIBILITY),
+                   Ok(Some(Value::IntegerAndString(1, "gnu"))));
+        assert_eq!(attributes.riscv_arch(), Ok(None));
+
+        let subsection = attributes.subsection("aeabi").unwrap().unwrap();
+        let group = subsection.groups().nth(1).unwrap().unwrap();
+        assert_eq!(group.scope, Scope::Section);
+        assert_eq!(group.indices().collect::<Vec<_>>(), [Ok(3), Ok(4)]);
+        assert_eq!(group.attributes().collect::<Vec<_>>(),
+                   [Ok(Attribute { tag: TAG_ABI_VFP_ARGS, value: Value::Integer(1) })]);
+    }
+}
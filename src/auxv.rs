@@ -0,0 +1,435 @@
+//! The auxiliary vector: the `(type, value)` pairs the kernel places on a new
+//! process's stack after the environment, as found in `/proc/<pid>/auxv` and
+//! in the `NT_AUXV` note of core files.
+
+use core::convert::TryInto;
+
+use header::{Class, Data};
+
+/// The type of an auxiliary vector entry (`AT_*`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuxvType {
+    /// Ends the vector.
+    Null,
+    Ignore,
+    /// A file descriptor for the program, when the kernel did not map it.
+    ExecFd,
+    /// The address of the program headers.
+    Phdr,
+    /// The size of a program header.
+    Phent,
+    /// The number of program headers.
+    Phnum,
+    Pagesz,
+    /// The address the interpreter was loaded at.
+    Base,
+    Flags,
+    /// The program's entry point.
+    Entry,
+    NotElf,
+    Uid,
+    Euid,
+    Gid,
+    Egid,
+    /// The address of a string naming the platform.
+    Platform,
+    Hwcap,
+    Clktck,
+    Secure,
+    /// The address of a string naming the real platform.
+    BasePlatform,
+    /// The address of 16 random bytes.
+    Random,
+    Hwcap2,
+    RseqFeatureSize,
+    RseqAlign,
+    Hwcap3,
+    Hwcap4,
+    /// The address of the path the program was executed as.
+    ExecFn,
+    /// The entry point of the vDSO's system call trampoline (x86).
+    Sysinfo,
+    /// The address of the vDSO's ELF header.
+    SysinfoEhdr,
+    MinSigStkSz,
+    Other(u64),
+}
+
+impl AuxvType {
+    pub fn from_raw(raw: u64) -> AuxvType {
+        match raw {
+            0 => AuxvType::Null,
+            1 => AuxvType::Ignore,
+            2 => AuxvType::ExecFd,
+            3 => AuxvType::Phdr,
+            4 => AuxvType::Phent,
+            5 => AuxvType::Phnum,
+            6 => AuxvType::Pagesz,
+            7 => AuxvType::Base,
+            8 => AuxvType::Flags,
+            9 => AuxvType::Entry,
+            10 => AuxvType::NotElf,
+            11 => AuxvType::Uid,
+            12 => AuxvType::Euid,
+            13 => AuxvType::Gid,
+            14 => AuxvType::Egid,
+            15 => AuxvType::Platform,
+            16 => AuxvType::Hwcap,
+            17 => AuxvType::Clktck,
+            23 => AuxvType::Secure,
+            24 => AuxvType::BasePlatform,
+            25 => AuxvType::Random,
+            26 => AuxvType::Hwcap2,
+            27 => AuxvType::RseqFeatureSize,
+            28 => AuxvType::RseqAlign,
+            29 => AuxvType::Hwcap3,
+            30 => AuxvType::Hwcap4,
+            31 => AuxvType::ExecFn,
+            32 => AuxvType::Sysinfo,
+            33 => AuxvType::SysinfoEhdr,
+            51 => AuxvType::MinSigStkSz,
+            other => AuxvType::Other(other),
+        }
+    }
+
+    pub fn raw(self) -> u64 {
+        match self {
+            AuxvType::Null => 0,
+            AuxvType::Ignore => 1,
+            AuxvType::ExecFd => 2,
+            AuxvType::Phdr => 3,
+            AuxvType::Phent => 4,
+            AuxvType::Phnum => 5,
+            AuxvType::Pagesz => 6,
+            AuxvType::Base => 7,
+            AuxvType::Flags => 8,
+            AuxvType::Entry => 9,
+            AuxvType::NotElf => 10,
+            AuxvType::Uid => 11,
+            AuxvType::Euid => 12,
+            AuxvType::Gid => 13,
+            AuxvType::Egid => 14,
+            AuxvType::Platform => 15,
+            AuxvType::Hwcap => 16,
+            AuxvType::Clktck => 17,
+            AuxvType::Secure => 23,
+            AuxvType::BasePlatform => 24,
+            AuxvType::Random => 25,
+            AuxvType::Hwcap2 => 26,
+            AuxvType::RseqFeatureSize => 27,
+            AuxvType::RseqAlign => 28,
+            AuxvType::Hwcap3 => 29,
+            AuxvType::Hwcap4 => 30,
+            AuxvType::ExecFn => 31,
+            AuxvType::Sysinfo => 32,
+            AuxvType::SysinfoEhdr => 33,
+            AuxvType::MinSigStkSz => 51,
+            AuxvType::Other(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuxvEntry {
+    pub type_: AuxvType,
+    pub value: u64,
+}
+
+impl AuxvEntry {
+    pub fn new(type_: AuxvType, value: u64) -> AuxvEntry {
+        AuxvEntry { type_, value }
+    }
+}
+
+// The word size and byte order of a vector.
+#[derive(Clone, Copy, Debug)]
+struct Words {
+    is_64: bool,
+    big_endian: bool,
+}
+
+impl Words {
+    fn new(class: Class, data: Data) -> Result<Words, &'static str> {
+        let is_64 = match class {
+            Class::ThirtyTwo => false,
+            Class::SixtyFour => true,
+            _ => return Err("Invalid class"),
+        };
+        let big_endian = match data {
+            Data::LittleEndian => false,
+            Data::BigEndian => true,
+            _ => return Err("Invalid data encoding"),
+        };
+        Ok(Words { is_64, big_endian })
+    }
+
+    fn size(self) -> usize {
+        if self.is_64 { 8 } else { 4 }
+    }
+
+    fn read(self, data: &[u8]) -> u64 {
+        match (self.is_64, self.big_endian) {
+            (true, false) => u64::from_le_bytes(data[..8].try_into().unwrap()),
+            (true, true) => u64::from_be_bytes(data[..8].try_into().unwrap()),
+            (false, false) => u32::from_le_bytes(data[..4].try_into().unwrap()).into(),
+            (false, true) => u32::from_be_bytes(data[..4].try_into().unwrap()).into(),
+        }
+    }
+
+    fn write(self, buf: &mut [u8], value: u64) -> Result<(), &'static str> {
+        match (self.is_64, self.big_endian) {
+            (true, false) => buf[..8].copy_from_slice(&value.to_le_bytes()),
+            (true, true) => buf[..8].copy_from_slice(&value.to_be_bytes()),
+            (false, big_endian) => {
+                check!(value <= u32::MAX.into(), "Value does not fit in a 32-bit word");
+                let value = value as u32;
+                buf[..4].copy_from_slice(&if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An auxiliary vector in memory.
+#[derive(Clone, Copy, Debug)]
+pub struct Auxv<'a> {
+    data: &'a [u8],
+    words: Words,
+}
+
+impl<'a> Auxv<'a> {
+    /// Reads a vector of `class` words in the byte order `data_encoding`.
+    /// `data` may end with `AT_NULL` or just stop.
+    pub fn new(data: &'a [u8], class: Class, data_encoding: Data) -> Result<Auxv<'a>, &'static str> {
+        let words = Words::new(class, data_encoding)?;
+        check!(data.len() % (2 * words.size()) == 0,
+               "Auxiliary vector is not a whole number of entries");
+        Ok(Auxv { data, words })
+    }
+
+    /// The entries, up to but not including `AT_NULL`.
+    pub fn iter(&self) -> AuxvIter<'a> {
+        AuxvIter { data: self.data, words: self.words }
+    }
+
+    /// The value of the first entry of type `type_`.
+    pub fn get(&self, type_: AuxvType) -> Option<u64> {
+        self.iter().find(|entry| entry.type_ == type_).map(|entry| entry.value)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AuxvIter<'a> {
+    data: &'a [u8],
+    words: Words,
+}
+
+impl<'a> Iterator for AuxvIter<'a> {
+    type Item = AuxvEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let size = self.words.size();
+        if self.data.is_empty() {
+            return None;
+        }
+        let type_ = AuxvType::from_raw(self.words.read(self.data));
+        let value = self.words.read(&self.data[size..]);
+        if type_ == AuxvType::Null {
+            self.data = &[];
+            return None;
+        }
+        self.data = &self.data[2 * size..];
+        Some(AuxvEntry { type_, value })
+    }
+}
+
+/// Writes `entries` and a terminating `AT_NULL` to `buf`, returning the
+/// number of bytes written.
+pub fn write(entries: &[AuxvEntry], class: Class, data_encoding: Data, buf: &mut [u8])
+             -> Result<usize, &'static str> {
+    let words = Words::new(class, data_encoding)?;
+    let size = (entries.len() + 1) * 2 * words.size();
+    check!(buf.len() >= size, "Buffer is too small");
+    let null = AuxvEntry::new(AuxvType::Null, 0);
+    for (entry, buf) in entries.iter().chain(Some(&null)).zip(buf.chunks_mut(2 * words.size())) {
+        words.write(buf, entry.type_.raw())?;
+        words.write(&mut buf[words.size()..], entry.value)?;
+    }
+    Ok(size)
+}
+
+/// The initial stack of a new process, laid out as the Linux kernel does:
+/// from the stack pointer up, `argc`, the `argv` and `envp` pointer arrays,
+/// the auxiliary vector, and then the strings and random bytes they point to.
+#[derive(Clone, Copy, Debug)]
+pub struct InitialStack<'a> {
+    pub class: Class,
+    pub data_encoding: Data,
+    pub args: &'a [&'a str],
+    pub env: &'a [&'a str],
+    /// Entries other than those below, which are added when set.
+    pub auxv: &'a [AuxvEntry],
+    /// Stored for `AT_EXECFN`.
+    pub exec_fn: Option<&'a str>,
+    /// Stored for `AT_PLATFORM`.
+    pub platform: Option<&'a str>,
+    /// Stored for `AT_RANDOM`.
+    pub random: Option<[u8; 16]>,
+}
+
+impl<'a> InitialStack<'a> {
+    pub fn new(class: Class, data_encoding: Data) -> InitialStack<'a> {
+        InitialStack {
+            class,
+            data_encoding,
+            args: &[],
+            env: &[],
+            auxv: &[],
+            exec_fn: None,
+            platform: None,
+            random: None,
+        }
+    }
+
+    /// An upper bound on the number of bytes `write` uses.
+    pub fn size(&self) -> Result<usize, &'static str> {
+        let words = Words::new(self.class, self.data_encoding)?;
+        Ok(self.pointers_size(words) + self.strings_size(words) + 15)
+    }
+
+    /// Writes the stack to `buf`, which is the memory just below `stack_top`
+    /// in the new process. Returns the initial stack pointer.
+    pub fn write(&self, buf: &mut [u8], stack_top: u64) -> Result<u64, &'static str> {
+        let words = Words::new(self.class, self.data_encoding)?;
+        for s in self.args.iter().chain(self.env).chain(&self.exec_fn).chain(&self.platform) {
+            check!(!s.contains('\0'), "String contains a null byte");
+        }
+        let bottom = stack_top.checked_sub(buf.len() as u64).ok_or("Stack is below address 0")?;
+        let too_small = "Stack is too small";
+
+        // The strings, from the top down: a null word, then the strings
+        // pointed to by `AT_EXECFN`, `envp` and `argv`, and then those pointed
+        // to by `AT_PLATFORM` and `AT_RANDOM`.
+        let mut top = stack_top.checked_sub(words.size() as u64).filter(|&t| t >= bottom).ok_or(too_small)?;
+        let mut push = |bytes: &[u8], nul: bool| -> Result<u64, &'static str> {
+            let len = bytes.len() as u64 + nul as u64;
+            top = top.checked_sub(len).filter(|&t| t >= bottom).ok_or(too_small)?;
+            let start = (top - bottom) as usize;
+            buf[start..start + bytes.len()].copy_from_slice(bytes);
+            if nul {
+                buf[start + bytes.len()] = 0;
+            }
+            Ok(top)
+        };
+        let exec_fn = self.exec_fn.map(|s| push(s.as_bytes(), true)).transpose()?;
+        for s in self.env.iter().rev().chain(self.args.iter().rev()) {
+            let _ = push(s.as_bytes(), true)?;
+        }
+        let platform = self.platform.map(|s| push(s.as_bytes(), true)).transpose()?;
+        let random = self.random.map(|bytes| push(&bytes, false)).transpose()?;
+
+        let sp = top.checked_sub(self.pointers_size(words) as u64).ok_or(too_small)? & !15;
+        check!(sp >= bottom, too_small);
+        let mut offset = (sp - bottom) as usize;
+        let mut put = |value: u64| -> Result<(), &'static str> {
+            words.write(&mut buf[offset..], value)?;
+            offset += words.size();
+            Ok(())
+        };
+
+        // Walk the strings back up to find their addresses.
+        put(self.args.len() as u64)?;
+        let mut address = top + random.map_or(0, |_| 16) + self.platform.map_or(0, |s| s.len() as u64 + 1);
+        for s in self.args {
+            put(address)?;
+            address += s.len() as u64 + 1;
+        }
+        put(0)?;
+        for s in self.env {
+            put(address)?;
+            address += s.len() as u64 + 1;
+        }
+        put(0)?;
+        let extra = [(AuxvType::ExecFn, exec_fn), (AuxvType::Platform, platform), (AuxvType::Random, random)];
+        let extra = extra.iter().filter_map(|&(type_, value)| value.map(|value| AuxvEntry::new(type_, value)));
+        for entry in self.auxv.iter().cloned().chain(extra) {
+            put(entry.type_.raw())?;
+            put(entry.value)?;
+        }
+        put(0)?;
+        put(0)?;
+        Ok(sp)
+    }
+
+    // argc, argv, envp and the auxiliary vector.
+    fn pointers_size(&self, words: Words) -> usize {
+        let extra = self.exec_fn.is_some() as usize + self.platform.is_some() as usize +
+                    self.random.is_some() as usize;
+        words.size() * (1 + self.args.len() + 1 + self.env.len() + 1 + 2 * (self.auxv.len() + extra + 1))
+    }
+
+    fn strings_size(&self, words: Words) -> usize {
+        let strings = self.args.iter().chain(self.env).chain(&self.exec_fn).chain(&self.platform);
+        words.size() + strings.map(|s| s.len() + 1).sum::<usize>() + self.random.map_or(0, |_| 16)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let entries = [AuxvEntry::new(AuxvType::Pagesz, 0x1000), AuxvEntry::new(AuxvType::Other(99), 7)];
+        let mut buf = [0xff; 24];
+        assert_eq!(write(&entries, Class::ThirtyTwo, Data::BigEndian, &mut buf), Ok(24));
+        assert_eq!(&buf[..8], &[0, 0, 0, 6, 0, 0, 0x10, 0]);
+        let auxv = Auxv::new(&buf, Class::ThirtyTwo, Data::BigEndian).unwrap();
+        assert_eq!(auxv.iter().collect::<Vec<_>>(), entries);
+        assert_eq!(auxv.get(AuxvType::Pagesz), Some(0x1000));
+        assert_eq!(auxv.get(AuxvType::Entry), None);
+        assert!(Auxv::new(&buf[..20], Class::ThirtyTwo, Data::BigEndian).is_err());
+        assert!(write(&entries, Class::ThirtyTwo, Data::BigEndian, &mut buf[..23]).is_err());
+    }
+
+    #[test]
+    fn initial_stack() {
+        let auxv = [AuxvEntry::new(AuxvType::Pagesz, 0x1000)];
+        let stack = InitialStack {
+            args: &["/bin/a", "-v"],
+            env: &["A=1"],
+            auxv: &auxv,
+            exec_fn: Some("/bin/a"),
+            random: Some([7; 16]),
+            ..InitialStack::new(Class::SixtyFour, Data::LittleEndian)
+        };
+        let top = 0x8000_0000;
+        let mut buf = vec![0; stack.size().unwrap()];
+        let sp = stack.write(&mut buf, top).unwrap();
+        assert_eq!(sp % 16, 0);
+        let bottom = top - buf.len() as u64;
+        let word = |address: u64| {
+            let offset = (address - bottom) as usize;
+            u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+        };
+        let string = |address: u64| {
+            let start = (address - bottom) as usize;
+            let len = buf[start..].iter().position(|&b| b == 0).unwrap();
+            String::from_utf8(buf[start..start + len].to_vec()).unwrap()
+        };
+
+        assert_eq!(word(sp), 2);
+        assert_eq!((string(word(sp + 8)), string(word(sp + 16)), word(sp + 24)), ("/bin/a".into(), "-v".into(), 0));
+        assert_eq!((string(word(sp + 32)), word(sp + 40)), ("A=1".into(), 0));
+        let auxv = Auxv::new(&buf[(sp + 48 - bottom) as usize..], Class::SixtyFour, Data::LittleEndian).unwrap();
+        assert_eq!(auxv.get(AuxvType::Pagesz), Some(0x1000));
+        assert_eq!(string(auxv.get(AuxvType::ExecFn).unwrap()), "/bin/a");
+        let random = (auxv.get(AuxvType::Random).unwrap() - bottom) as usize;
+        assert_eq!(buf[random..random + 16], [7; 16]);
+        assert_eq!(auxv.iter().count(), 3);
+        assert_eq!(word(top - 8), 0);
+
+        assert_eq!(stack.write(&mut buf[..64], top), Err("Stack is too small"));
+    }
+}
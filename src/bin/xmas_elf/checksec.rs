@@ -0,0 +1,118 @@
+//! The `checksec` subcommand, which reports the exploit mitigations each
+//! file was built with.
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "json")]
+use serde_json;
+
+use xmas_elf::ElfFile;
+use xmas_elf::header::Machine;
+use xmas_elf::security::{self, Pie, Relro, SecurityReport};
+
+use image::read_file;
+use names;
+
+#[cfg_attr(feature = "json", derive(Serialize))]
+struct Report<'a> {
+    file: &'a str,
+    relro: &'static str,
+    stack_protector: bool,
+    nx_stack: bool,
+    pie: &'static str,
+    rpath: Option<&'a str>,
+    runpath: Option<&'a str>,
+    fortify_source: bool,
+    fortified: usize,
+    writable_executable_segments: usize,
+    /// The control-flow protection features for the machine, by name.
+    control_flow: BTreeMap<&'static str, bool>,
+    /// The highest x86-64 ISA level needed.
+    x86_isa_needed: Option<&'static str>,
+}
+
+impl<'a> Report<'a> {
+    fn new(file: &'a str, machine: Machine, report: &SecurityReport<'a>) -> Report<'a> {
+        let control_flow = match machine {
+            Machine::X86 | Machine::X86_64 => vec![("IBT", report.ibt()), ("SHSTK", report.shstk())],
+            Machine::AArch64 => vec![("BTI", report.bti()), ("PAC", report.pac())],
+            _ => Vec::new(),
+        }.into_iter().collect();
+        let x86_isa_needed = report.properties
+            .and_then(|p| p.x86_isa_needed)
+            .and_then(|levels| levels.highest())
+            .map(|level| names::X86_ISA_LEVELS[level as usize - 1].1);
+        Report {
+            file,
+            relro: match report.relro {
+                Relro::None => "none",
+                Relro::Partial => "partial",
+                Relro::Full => "full",
+            },
+            stack_protector: report.stack_protector,
+            nx_stack: report.nx_stack,
+            pie: match report.pie {
+                Pie::No => "no",
+                Pie::Yes => "yes",
+                Pie::SharedObject => "dso",
+            },
+            rpath: report.rpath,
+            runpath: report.runpath,
+            fortify_source: report.fortify_source(),
+            fortified: report.fortified,
+            writable_executable_segments: report.writable_executable_segments,
+            control_flow,
+            x86_isa_needed,
+        }
+    }
+
+    fn display(&self) {
+        let yes_no = |b| if b { "yes" } else { "no" };
+        println!("{}:", self.file);
+        println!("  RELRO:          {}", self.relro);
+        println!("  Stack canary:   {}", yes_no(self.stack_protector));
+        println!("  NX stack:       {}", yes_no(self.nx_stack));
+        println!("  PIE:            {}", self.pie);
+        println!("  RPATH:          {}", self.rpath.unwrap_or("none"));
+        println!("  RUNPATH:        {}", self.runpath.unwrap_or("none"));
+        println!("  FORTIFY_SOURCE: {} ({} fortified functions)", yes_no(self.fortify_source), self.fortified);
+        println!("  W+X segments:   {}", self.writable_executable_segments);
+        for (name, &enabled) in &self.control_flow {
+            println!("  {:<16}{}", format!("{}:", name), yes_no(enabled));
+        }
+        if let Some(level) = self.x86_isa_needed {
+            println!("  x86 ISA needed: {}", level);
+        }
+    }
+}
+
+// `checksec [--json] <elf>...`
+pub fn checksec(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match &**arg {
+            "--json" if cfg!(feature = "json") => json = true,
+            "--json" => return Err("this build does not support '--json'; rebuild with the `json` feature".to_owned()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        return Err("expected at least one file".to_owned());
+    }
+
+    for path in paths {
+        let buf = read_file(path)?;
+        let elf_file = ElfFile::new(&buf).map_err(|e| format!("{}: {}", path, e))?;
+        let report = security::report(&elf_file).map_err(|e| format!("{}: {}", path, e))?;
+        let report = Report::new(path, elf_file.header.pt2.machine().as_machine(), &report);
+        if json {
+            #[cfg(feature = "json")]
+            println!("{}", serde_json::to_string(&report).map_err(|e| e.to_string())?);
+        } else {
+            report.display();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,273 @@
+//! The `binary`, `ihex`, `srec` and `uf2` subcommands, which write (or check)
+//! firmware images made from the loadable segments of an ELF file.
+
+use std::convert::TryFrom;
+use std::path::Path;
+use std::fs;
+use xmas_elf::{ElfFile, ihex, image, srec, uf2};
+use xmas_elf::image::{AddressKind, BinaryImage, LoadSegment, VerifyError};
+
+// Reads a whole file, with the file name in any error.
+pub fn read_file<P: AsRef<Path>>(name: P) -> Result<Vec<u8>, String> {
+    let name = name.as_ref();
+    fs::read(name).map_err(|e| format!("could not read {}: {}", name.display(), e))
+}
+
+// The largest flat image written without `--max-size`. Segments far apart,
+// such as flash and RAM, make an image the size of the gap between them.
+const DEFAULT_MAX_IMAGE_SIZE: u64 = 256 << 20;
+
+// Parses a byte given in decimal or, with a `0x` prefix, hex.
+fn parse_byte(s: &str) -> Result<u8, String> {
+    let result = if let Some(hex) = s.strip_prefix("0x") {
+        u8::from_str_radix(hex, 16)
+    } else {
+        s.parse()
+    };
+    result.map_err(|_| format!("invalid byte value: {}", s))
+}
+
+// Parses a size given in decimal or, with a `0x` prefix, hex.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let result = if let Some(hex) = s.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else {
+        s.parse()
+    };
+    result.map_err(|_| format!("invalid size: {}", s))
+}
+
+// `binary [--vaddr] [--gap-fill <byte>] [--allow-overlap] [--max-size <bytes>] <elf> <output>`
+//
+// Writes the loadable segments as a flat image, like `objcopy -O binary`.
+pub fn write_binary_image(args: &[String]) -> Result<(), String> {
+    let mut kind = AddressKind::Physical;
+    let mut fill = 0;
+    let mut allow_overlap = false;
+    let mut max_size = DEFAULT_MAX_IMAGE_SIZE;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--vaddr" => kind = AddressKind::Virtual,
+            "--gap-fill" => {
+                fill = parse_byte(args.next().ok_or("--gap-fill requires a value")?)?;
+            }
+            "--allow-overlap" => allow_overlap = true,
+            "--max-size" => max_size = parse_size(args.next().ok_or("--max-size requires a value")?)?,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+    let (input, output) = match &*paths {
+        [input, output] => (input, output),
+        _ => return Err("expected an input and an output file".to_owned()),
+    };
+
+    let buf = read_file(input)?;
+    let elf_file = ElfFile::new(&buf)?;
+    let image = BinaryImage::new(&elf_file, kind)?;
+    if let Some((a, b)) = image.overlapping_segments() {
+        let msg = format!("segments at {:#x}..{:#x} and {:#x}..{:#x} overlap",
+                          a.address, a.end(), b.address, b.end());
+        if !allow_overlap {
+            return Err(format!("{} (use --allow-overlap to write anyway)", msg));
+        }
+        eprintln!("warning: {}", msg);
+    }
+
+    if image.size() > max_size {
+        return Err(format!("image is {:#x} bytes, more than the limit of {:#x} (use --max-size to raise it)",
+                           image.size(), max_size));
+    }
+    let size = usize::try_from(image.size()).map_err(|_| "image is too large")?;
+    let mut data = vec![0; size];
+    image.write(&mut data, fill)?;
+    fs::write(output, &data).map_err(|e| format!("could not write {}: {}", output, e))?;
+    println!("base address: {:#x}", image.base_address());
+    println!("size:         {:#x}", image.size());
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    IntelHex,
+    SRecord,
+}
+
+fn describe_verify_error(err: VerifyError, file: &str) -> String {
+    match err {
+        VerifyError::Malformed(e) => format!("{}: {}", file, e),
+        VerifyError::Differs { address, expected, found } => {
+            format!("byte at {:#x} is {:#04x}, expected {:#04x}", address, found, expected)
+        }
+        VerifyError::Unexpected { address } => {
+            format!("image has data at {:#x}, which is not in the ELF file", address)
+        }
+        VerifyError::Missing { address } => format!("image has no data for {:#x}", address),
+    }
+}
+
+// The loadable segments of a file, or the named allocated sections.
+fn image_segments<'a>(elf_file: &ElfFile<'a>,
+                      kind: AddressKind,
+                      section_names: &[&String])
+                      -> Result<Vec<LoadSegment<'a>>, String> {
+    if section_names.is_empty() {
+        return Ok(image::load_segments(elf_file, kind).collect::<Result<_, _>>()?);
+    }
+    // Reports unreadable header tables before sections are looked up.
+    image::load_segments(elf_file, kind).next().transpose()?;
+    section_names.iter().map(|&name| {
+        let section = elf_file.find_section_by_name(name)
+            .ok_or_else(|| format!("no section named {}", name))?;
+        image::section_data(elf_file, section, kind).map_err(|e| format!("{}: {}", name, e))
+    }).collect()
+}
+
+// `ihex|srec [--vaddr] [--section <name>]... [--address-size 16|24|32] [--verify] <elf> <file>`
+//
+// Writes the loadable segments, or the named sections, as Intel HEX or
+// S-records. With `--verify`, checks an existing file against the ELF instead.
+pub fn text_image(format: TextFormat, args: &[String]) -> Result<(), String> {
+    let mut kind = AddressKind::Physical;
+    let mut section_names = Vec::new();
+    let mut address_size = None;
+    let mut verify = false;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--vaddr" => kind = AddressKind::Virtual,
+            "--section" => section_names.push(args.next().ok_or("--section requires a name")?),
+            "--address-size" if format == TextFormat::SRecord => {
+                address_size = Some(match args.next().map(|s| &**s) {
+                    Some("16") => srec::AddressSize::Bits16,
+                    Some("24") => srec::AddressSize::Bits24,
+                    Some("32") => srec::AddressSize::Bits32,
+                    _ => return Err("--address-size must be 16, 24 or 32".to_owned()),
+                });
+            }
+            "--verify" => verify = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+    let (input, file) = match &*paths {
+        [input, file] => (input, file),
+        _ => return Err("expected an ELF file and an image file".to_owned()),
+    };
+
+    let buf = read_file(input)?;
+    let elf_file = ElfFile::new(&buf)?;
+    let segments = image_segments(&elf_file, kind, &section_names)?;
+    let entry_point = elf_file.header.pt2.entry_point();
+
+    if verify {
+        let text = fs::read_to_string(file).map_err(|e| format!("could not read {}: {}", file, e))?;
+        let segment_iter = segments.iter().cloned().map(Ok);
+        let (result, found_entry_point) = match format {
+            TextFormat::IntelHex => {
+                (image::verify(segment_iter, ihex::data_chunks(&text)), ihex::entry_point(&text)?)
+            }
+            TextFormat::SRecord => {
+                (image::verify(segment_iter, srec::data_chunks(&text)), srec::entry_point(&text)?)
+            }
+        };
+        result.map_err(|e| describe_verify_error(e, file))?;
+        if let Some(found) = found_entry_point {
+            if found != entry_point {
+                return Err(format!("entry point is {:#x}, expected {:#x}", found, entry_point));
+            }
+        }
+        println!("{} matches {}", file, input);
+        return Ok(());
+    }
+
+    let mut out = String::new();
+    match format {
+        TextFormat::IntelHex => ihex::write(&mut out, segments.iter().cloned().map(Ok), Some(entry_point))?,
+        TextFormat::SRecord => {
+            let max_address = segments.iter()
+                .filter(|s| !s.data.is_empty())
+                .map(|s| s.end() - 1)
+                .fold(entry_point, u64::max);
+            let address_size = address_size.or_else(|| srec::AddressSize::for_address(max_address))
+                .ok_or("addresses do not fit in 32 bits")?;
+            let header = Path::new(file).file_name().map_or("".into(), |name| name.to_string_lossy());
+            srec::write(&mut out,
+                        segments.iter().cloned().map(Ok),
+                        address_size,
+                        header.as_bytes(),
+                        Some(entry_point))?;
+        }
+    }
+    fs::write(file, out).map_err(|e| format!("could not write {}: {}", file, e))
+}
+
+// Parses a UF2 family ID given as a number or a well-known chip name.
+fn parse_family_id(s: &str) -> Result<u32, String> {
+    Ok(match &*s.to_ascii_lowercase() {
+        "samd21" => uf2::FAMILY_ID_SAMD21,
+        "samd51" => uf2::FAMILY_ID_SAMD51,
+        "nrf52" => uf2::FAMILY_ID_NRF52,
+        "nrf52833" => uf2::FAMILY_ID_NRF52833,
+        "nrf52840" => uf2::FAMILY_ID_NRF52840,
+        "stm32f4" => uf2::FAMILY_ID_STM32F4,
+        "esp32s2" => uf2::FAMILY_ID_ESP32S2,
+        "rp2040" => uf2::FAMILY_ID_RP2040,
+        "rp2350-arm-s" => uf2::FAMILY_ID_RP2350_ARM_S,
+        "rp2350-riscv" => uf2::FAMILY_ID_RP2350_RISCV,
+        "rp2350-arm-ns" => uf2::FAMILY_ID_RP2350_ARM_NS,
+        _ => {
+            let result = match s.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => s.parse(),
+            };
+            result.map_err(|_| format!("unknown family ID: {}", s))?
+        }
+    })
+}
+
+// `uf2 [--vaddr] [--family-id <id>] [--verify] <elf> <file>`
+//
+// Writes the loadable segments as a UF2 image, placed by load address. With
+// `--verify`, checks an existing UF2 file against the ELF instead.
+pub fn uf2_image(args: &[String]) -> Result<(), String> {
+    let mut kind = AddressKind::Physical;
+    let mut family_id = None;
+    let mut verify = false;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--vaddr" => kind = AddressKind::Virtual,
+            "--family-id" => {
+                family_id = Some(parse_family_id(args.next().ok_or("--family-id requires a value")?)?);
+            }
+            "--verify" => verify = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+    let (input, file) = match &*paths {
+        [input, file] => (input, file),
+        _ => return Err("expected an ELF file and a UF2 file".to_owned()),
+    };
+
+    let buf = read_file(input)?;
+    let elf_file = ElfFile::new(&buf)?;
+    let segments = image::load_segments(&elf_file, kind);
+
+    if verify {
+        let data = read_file(file)?;
+        uf2::verify(segments, &data, family_id).map_err(|e| describe_verify_error(e, file))?;
+        println!("{} matches {}", file, input);
+        return Ok(());
+    }
+
+    let blocks = uf2::blocks(segments, family_id)?;
+    println!("blocks: {}", blocks.num_blocks());
+    let data: Vec<u8> = blocks.flat_map(|block| block.to_vec()).collect();
+    fs::write(file, data).map_err(|e| format!("could not write {}: {}", file, e))
+}
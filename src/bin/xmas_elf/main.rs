@@ -0,0 +1,60 @@
+// `is_multiple_of` is too new for the compilers the crate supports.
+#![allow(clippy::manual_is_multiple_of)]
+
+extern crate xmas_elf;
+#[cfg(feature = "json")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "json")]
+extern crate serde_json;
+
+mod checksec;
+mod image;
+mod names;
+mod readelf;
+
+use std::env;
+use std::process;
+
+use image::{text_image, uf2_image, write_binary_image, TextFormat};
+
+fn exit_on_error(result: Result<(), String>) {
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let mut args = env::args();
+    let program_name = args.next().unwrap_or_else(|| "xmas_elf".to_owned());
+    let args: Vec<String> = args.collect();
+
+    match args.first().map(|s| &**s) {
+        Some("binary") => exit_on_error(write_binary_image(&args[1..])),
+        Some("ihex") => exit_on_error(text_image(TextFormat::IntelHex, &args[1..])),
+        Some("srec") => exit_on_error(text_image(TextFormat::SRecord, &args[1..])),
+        Some("uf2") => exit_on_error(uf2_image(&args[1..])),
+        Some("checksec") => exit_on_error(checksec::checksec(&args[1..])),
+        Some(_) => {
+            if !readelf::run("xmas_elf", &args) {
+                process::exit(1);
+            }
+        }
+        None => {
+            println!("usage: {} <option(s)> <elf-file(s)>", program_name);
+            println!("       {} binary [--vaddr] [--gap-fill <byte>] [--allow-overlap] [--max-size <bytes>] \
+                      <elf> <output>",
+                     program_name);
+            println!("       {} ihex [--vaddr] [--section <name>]... [--verify] <elf> <file>",
+                     program_name);
+            println!("       {} srec [--vaddr] [--section <name>]... [--address-size 16|24|32] [--verify] \
+                      <elf> <file>",
+                     program_name);
+            println!("       {} uf2 [--vaddr] [--family-id <id>] [--verify] <elf> <file>", program_name);
+            println!("       {} checksec [--json] <elf-file(s)>", program_name);
+            println!("{}", readelf::USAGE);
+            process::exit(1);
+        }
+    }
+}
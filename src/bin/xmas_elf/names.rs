@@ -0,0 +1,624 @@
+//! The names readelf uses for the numeric values in an ELF file.
+
+use xmas_elf::header::Machine;
+
+pub fn os_abi(os_abi: u8) -> String {
+    match os_abi {
+        0 => "UNIX - System V".to_owned(),
+        1 => "UNIX - HP-UX".to_owned(),
+        2 => "UNIX - NetBSD".to_owned(),
+        3 => "UNIX - GNU".to_owned(),
+        6 => "UNIX - Solaris".to_owned(),
+        7 => "UNIX - AIX".to_owned(),
+        8 => "UNIX - IRIX".to_owned(),
+        9 => "UNIX - FreeBSD".to_owned(),
+        10 => "UNIX - TRU64".to_owned(),
+        12 => "UNIX - OpenBSD".to_owned(),
+        13 => "VMS - OpenVMS".to_owned(),
+        97 => "ARM".to_owned(),
+        255 => "Standalone App".to_owned(),
+        other => format!("<unknown: {:x}>", other),
+    }
+}
+
+pub fn machine(machine: u16) -> String {
+    match machine {
+        0 => "None".to_owned(),
+        2 => "Sparc".to_owned(),
+        3 => "Intel 80386".to_owned(),
+        8 => "MIPS R3000".to_owned(),
+        20 => "PowerPC".to_owned(),
+        21 => "PowerPC64".to_owned(),
+        22 => "IBM S/390".to_owned(),
+        40 => "ARM".to_owned(),
+        42 => "Renesas / SuperH SH".to_owned(),
+        43 => "Sparc v9".to_owned(),
+        50 => "Intel IA-64".to_owned(),
+        62 => "Advanced Micro Devices X86-64".to_owned(),
+        183 => "AArch64".to_owned(),
+        243 => "RISC-V".to_owned(),
+        247 => "Linux BPF".to_owned(),
+        258 => "LoongArch".to_owned(),
+        other => format!("<unknown>: 0x{:x}", other),
+    }
+}
+
+pub fn section_type(machine: Machine, sh_type: u32) -> String {
+    let name = match sh_type {
+        0 => "NULL",
+        1 => "PROGBITS",
+        2 => "SYMTAB",
+        3 => "STRTAB",
+        4 => "RELA",
+        5 => "HASH",
+        6 => "DYNAMIC",
+        7 => "NOTE",
+        8 => "NOBITS",
+        9 => "REL",
+        10 => "SHLIB",
+        11 => "DYNSYM",
+        14 => "INIT_ARRAY",
+        15 => "FINI_ARRAY",
+        16 => "PREINIT_ARRAY",
+        17 => "GROUP",
+        18 => "SYMTAB SECTION INDICES",
+        19 => "RELR",
+        0x6fff4c00 => "LLVM_ODRTAB",
+        0x6fff4c01 => "LLVM_LINKER_OPTIONS",
+        0x6fff4c02 => "LLVM_CALL_GRAPH_PROFILE",
+        0x6fff4c03 => "LLVM_ADDRSIG",
+        0x6ffffff5 => "GNU_ATTRIBUTES",
+        0x6ffffff6 => "GNU_HASH",
+        0x6ffffff7 => "GNU_LIBLIST",
+        0x6ffffffd => "VERDEF",
+        0x6ffffffe => "VERNEED",
+        0x6fffffff => "VERSYM",
+        0x70000001 if machine == Machine::X86_64 => "X86_64_UNWIND",
+        0x70000001 if machine == Machine::Arm => "ARM_EXIDX",
+        0x70000003 if machine == Machine::Arm => "ARM_ATTRIBUTES",
+        0x70000003 if machine == Machine::AArch64 => "AARCH64_ATTRIBUTES",
+        0x70000003 if machine == Machine::RISC_V => "RISCV_ATTRIBUTES",
+        0x7000002a if is_mips(machine) => "MIPS_ABIFLAGS",
+        0x60000000..=0x6fffffff => return format!("LOOS+0x{:x}", sh_type - 0x60000000),
+        0x70000000..=0x7fffffff => return format!("LOPROC+0x{:x}", sh_type - 0x70000000),
+        0x80000000..=0xffffffff => return format!("LOUSER+0x{:x}", sh_type - 0x80000000),
+        _ => return format!("{:08x}: <unknown>", sh_type),
+    };
+    name.to_owned()
+}
+
+// GNU treats the little-endian R3000 value as MIPS too.
+fn is_mips(machine: Machine) -> bool {
+    matches!(machine, Machine::Mips | Machine::MipsRs3Le)
+}
+
+pub fn segment_type(machine: Machine, p_type: u32) -> String {
+    let name = match p_type {
+        0 => "NULL",
+        1 => "LOAD",
+        2 => "DYNAMIC",
+        3 => "INTERP",
+        4 => "NOTE",
+        5 => "SHLIB",
+        6 => "PHDR",
+        7 => "TLS",
+        0x6474e550 => "GNU_EH_FRAME",
+        0x6474e551 => "GNU_STACK",
+        0x6474e552 => "GNU_RELRO",
+        0x6474e553 => "GNU_PROPERTY",
+        0x6474e554 => "GNU_SFRAME",
+        0x70000001 if machine == Machine::Arm => "EXIDX",
+        0x70000003 if machine == Machine::RISC_V => "RISCV_ATTRIBUTES",
+        0x70000000 if is_mips(machine) => "REGINFO",
+        0x70000001 if is_mips(machine) => "RTPROC",
+        0x70000002 if is_mips(machine) => "OPTIONS",
+        0x70000003 if is_mips(machine) => "ABIFLAGS",
+        0x60000000..=0x6fffffff => return format!("LOOS+0x{:x}", p_type - 0x60000000),
+        0x70000000..=0x7fffffff => return format!("LOPROC+0x{:x}", p_type - 0x70000000),
+        _ => return format!("<unknown>: {:x}", p_type),
+    };
+    name.to_owned()
+}
+
+pub fn dynamic_tag(tag: u64) -> String {
+    let name = match tag {
+        0 => "NULL",
+        1 => "NEEDED",
+        2 => "PLTRELSZ",
+        3 => "PLTGOT",
+        4 => "HASH",
+        5 => "STRTAB",
+        6 => "SYMTAB",
+        7 => "RELA",
+        8 => "RELASZ",
+        9 => "RELAENT",
+        10 => "STRSZ",
+        11 => "SYMENT",
+        12 => "INIT",
+        13 => "FINI",
+        14 => "SONAME",
+        15 => "RPATH",
+        16 => "SYMBOLIC",
+        17 => "REL",
+        18 => "RELSZ",
+        19 => "RELENT",
+        20 => "PLTREL",
+        21 => "DEBUG",
+        22 => "TEXTREL",
+        23 => "JMPREL",
+        24 => "BIND_NOW",
+        25 => "INIT_ARRAY",
+        26 => "FINI_ARRAY",
+        27 => "INIT_ARRAYSZ",
+        28 => "FINI_ARRAYSZ",
+        29 => "RUNPATH",
+        30 => "FLAGS",
+        32 => "PREINIT_ARRAY",
+        33 => "PREINIT_ARRAYSZ",
+        34 => "SYMTAB_SHNDX",
+        35 => "RELRSZ",
+        36 => "RELR",
+        37 => "RELRENT",
+        0x6ffffdf5 => "GNU_PRELINKED",
+        0x6ffffdf6 => "GNU_CONFLICTSZ",
+        0x6ffffdf7 => "GNU_LIBLISTSZ",
+        0x6ffffdf8 => "CHECKSUM",
+        0x6ffffdf9 => "PLTPADSZ",
+        0x6ffffdfa => "MOVEENT",
+        0x6ffffdfb => "MOVESZ",
+        0x6ffffdfc => "FEATURE",
+        0x6ffffdfd => "POSFLAG_1",
+        0x6ffffdfe => "SYMINSZ",
+        0x6ffffdff => "SYMINENT",
+        0x6ffffef5 => "GNU_HASH",
+        0x6ffffef6 => "TLSDESC_PLT",
+        0x6ffffef7 => "TLSDESC_GOT",
+        0x6ffffef8 => "GNU_CONFLICT",
+        0x6ffffef9 => "GNU_LIBLIST",
+        0x6ffffefa => "CONFIG",
+        0x6ffffefb => "DEPAUDIT",
+        0x6ffffefc => "AUDIT",
+        0x6ffffefd => "PLTPAD",
+        0x6ffffefe => "MOVETAB",
+        0x6ffffeff => "SYMINFO",
+        0x6ffffff0 => "VERSYM",
+        0x6ffffff9 => "RELACOUNT",
+        0x6ffffffa => "RELCOUNT",
+        0x6ffffffb => "FLAGS_1",
+        0x6ffffffc => "VERDEF",
+        0x6ffffffd => "VERDEFNUM",
+        0x6ffffffe => "VERNEED",
+        0x6fffffff => "VERNEEDNUM",
+        0x7ffffffd => "AUXILIARY",
+        0x7fffffff => "FILTER",
+        0x60000000..=0x6fffffff => return format!("LOOS+0x{:x}", tag - 0x60000000),
+        0x70000000..=0x7fffffff => return format!("LOPROC+0x{:x}", tag - 0x70000000),
+        _ => return format!("<unknown>: {:x}", tag),
+    };
+    name.to_owned()
+}
+
+pub fn symbol_type(st_type: u8) -> String {
+    match st_type {
+        0 => "NOTYPE".to_owned(),
+        1 => "OBJECT".to_owned(),
+        2 => "FUNC".to_owned(),
+        3 => "SECTION".to_owned(),
+        4 => "FILE".to_owned(),
+        5 => "COMMON".to_owned(),
+        6 => "TLS".to_owned(),
+        10 => "IFUNC".to_owned(),
+        13..=15 => format!("<processor specific>: {}", st_type),
+        11..=12 => format!("<OS specific>: {}", st_type),
+        _ => format!("<unknown>: {}", st_type),
+    }
+}
+
+pub fn symbol_binding(binding: u8) -> String {
+    match binding {
+        0 => "LOCAL".to_owned(),
+        1 => "GLOBAL".to_owned(),
+        2 => "WEAK".to_owned(),
+        10 => "UNIQUE".to_owned(),
+        13..=15 => format!("<processor specific>: {}", binding),
+        11..=12 => format!("<OS specific>: {}", binding),
+        _ => format!("<unknown>: {}", binding),
+    }
+}
+
+pub fn symbol_visibility(other: u8) -> &'static str {
+    match other & 0x3 {
+        0 => "DEFAULT",
+        1 => "INTERNAL",
+        2 => "HIDDEN",
+        _ => "PROTECTED",
+    }
+}
+
+pub fn note_type(owner: &str, file_is_core: bool, n_type: u32) -> String {
+    let name = match (owner, n_type) {
+        ("GNU", 1) => "NT_GNU_ABI_TAG (ABI version tag)",
+        ("GNU", 2) => "NT_GNU_HWCAP (DSO-supplied software HWCAP info)",
+        ("GNU", 3) => "NT_GNU_BUILD_ID (unique build ID bitstring)",
+        ("GNU", 4) => "NT_GNU_GOLD_VERSION (gold version)",
+        ("GNU", 5) => "NT_GNU_PROPERTY_TYPE_0",
+        ("stapsdt", 3) => "NT_STAPSDT (SystemTap probe descriptors)",
+        ("Go", 4) => "GO BUILDID",
+        ("FDO", 0xcafe1a7e) => "FDO_PACKAGING_METADATA",
+        ("CORE", _) | ("LINUX", _) if file_is_core => match n_type {
+            1 => "NT_PRSTATUS (prstatus structure)",
+            2 => "NT_FPREGSET (floating point registers)",
+            3 => "NT_PRPSINFO (prpsinfo structure)",
+            4 => "NT_TASKSTRUCT (task structure)",
+            6 => "NT_AUXV (auxiliary vector)",
+            0x200 => "NT_386_TLS (x86 TLS information)",
+            0x202 => "NT_X86_XSTATE (x86 XSAVE extended state)",
+            0x400 => "NT_ARM_VFP (arm VFP registers)",
+            0x401 => "NT_ARM_TLS (AArch TLS registers)",
+            0x405 => "NT_ARM_SVE (AArch SVE registers)",
+            0x406 => "NT_ARM_PAC_MASK (AArch pointer authentication code masks)",
+            0x46494c45 => "NT_FILE (mapped files)",
+            0x53494749 => "NT_SIGINFO (siginfo_t data)",
+            _ => return format!("Unknown note type: (0x{:08x})", n_type),
+        },
+        _ => return format!("Unknown note type: (0x{:08x})", n_type),
+    };
+    name.to_owned()
+}
+
+/// The names of the bits set in `value`, lowest first, as readelf prints
+/// them, or `none` if there are none.
+pub fn bitmask(mut value: u32, names: &[(u32, &str)], none: &str) -> String {
+    if value == 0 {
+        return none.to_owned();
+    }
+    let mut result = Vec::new();
+    while value != 0 {
+        let bit = value & value.wrapping_neg();
+        value &= !bit;
+        match names.iter().find(|&&(b, _)| b == bit) {
+            Some(&(_, name)) => result.push(name.to_owned()),
+            None => result.push(format!("<unknown: {:x}>", bit)),
+        }
+    }
+    result.join(", ")
+}
+
+pub const X86_ISA_LEVELS: &[(u32, &str)] = &[
+    (0x1, "x86-64-baseline"),
+    (0x2, "x86-64-v2"),
+    (0x4, "x86-64-v3"),
+    (0x8, "x86-64-v4"),
+];
+
+pub const X86_FEATURES_1: &[(u32, &str)] = &[
+    (0x1, "IBT"),
+    (0x2, "SHSTK"),
+    (0x4, "LAM_U48"),
+    (0x8, "LAM_U57"),
+];
+
+pub const X86_FEATURES_2: &[(u32, &str)] = &[
+    (0x1, "x86"),
+    (0x2, "x87"),
+    (0x4, "MMX"),
+    (0x8, "XMM"),
+    (0x10, "YMM"),
+    (0x20, "ZMM"),
+    (0x40, "FXSR"),
+    (0x80, "XSAVE"),
+    (0x100, "XSAVEOPT"),
+    (0x200, "XSAVEC"),
+    (0x400, "TMM"),
+    (0x800, "MASK"),
+];
+
+pub const AARCH64_FEATURES_1: &[(u32, &str)] = &[
+    (0x1, "BTI"),
+    (0x2, "PAC"),
+    (0x4, "GCS"),
+];
+
+pub const NEEDED_1: &[(u32, &str)] = &[
+    (0x1, "indirect external access"),
+];
+
+pub fn relocation_type(machine: Machine, r_type: u32) -> Option<&'static str> {
+    let names: &[(u32, &str)] = match machine {
+        Machine::X86_64 => X86_64_RELOCATIONS,
+        Machine::X86 => I386_RELOCATIONS,
+        Machine::AArch64 => AARCH64_RELOCATIONS,
+        Machine::Arm => ARM_RELOCATIONS,
+        Machine::RISC_V => RISCV_RELOCATIONS,
+        _ => &[],
+    };
+    names.iter().find(|&&(value, _)| value == r_type).map(|&(_, name)| name)
+}
+
+const X86_64_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_X86_64_NONE"),
+    (1, "R_X86_64_64"),
+    (2, "R_X86_64_PC32"),
+    (3, "R_X86_64_GOT32"),
+    (4, "R_X86_64_PLT32"),
+    (5, "R_X86_64_COPY"),
+    (6, "R_X86_64_GLOB_DAT"),
+    (7, "R_X86_64_JUMP_SLOT"),
+    (8, "R_X86_64_RELATIVE"),
+    (9, "R_X86_64_GOTPCREL"),
+    (10, "R_X86_64_32"),
+    (11, "R_X86_64_32S"),
+    (12, "R_X86_64_16"),
+    (13, "R_X86_64_PC16"),
+    (14, "R_X86_64_8"),
+    (15, "R_X86_64_PC8"),
+    (16, "R_X86_64_DTPMOD64"),
+    (17, "R_X86_64_DTPOFF64"),
+    (18, "R_X86_64_TPOFF64"),
+    (19, "R_X86_64_TLSGD"),
+    (20, "R_X86_64_TLSLD"),
+    (21, "R_X86_64_DTPOFF32"),
+    (22, "R_X86_64_GOTTPOFF"),
+    (23, "R_X86_64_TPOFF32"),
+    (24, "R_X86_64_PC64"),
+    (25, "R_X86_64_GOTOFF64"),
+    (26, "R_X86_64_GOTPC32"),
+    (27, "R_X86_64_GOT64"),
+    (28, "R_X86_64_GOTPCREL64"),
+    (29, "R_X86_64_GOTPC64"),
+    (30, "R_X86_64_GOTPLT64"),
+    (31, "R_X86_64_PLTOFF64"),
+    (32, "R_X86_64_SIZE32"),
+    (33, "R_X86_64_SIZE64"),
+    (34, "R_X86_64_GOTPC32_TLSDESC"),
+    (35, "R_X86_64_TLSDESC_CALL"),
+    (36, "R_X86_64_TLSDESC"),
+    (37, "R_X86_64_IRELATIVE"),
+    (38, "R_X86_64_RELATIVE64"),
+    (41, "R_X86_64_GOTPCRELX"),
+    (42, "R_X86_64_REX_GOTPCRELX"),
+    (43, "R_X86_64_CODE_4_GOTPCRELX"),
+    (44, "R_X86_64_CODE_4_GOTTPOFF"),
+    (45, "R_X86_64_CODE_4_GOTPC32_TLSDESC"),
+];
+
+const I386_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_386_NONE"),
+    (1, "R_386_32"),
+    (2, "R_386_PC32"),
+    (3, "R_386_GOT32"),
+    (4, "R_386_PLT32"),
+    (5, "R_386_COPY"),
+    (6, "R_386_GLOB_DAT"),
+    (7, "R_386_JUMP_SLOT"),
+    (8, "R_386_RELATIVE"),
+    (9, "R_386_GOTOFF"),
+    (10, "R_386_GOTPC"),
+    (11, "R_386_32PLT"),
+    (14, "R_386_TLS_TPOFF"),
+    (15, "R_386_TLS_IE"),
+    (16, "R_386_TLS_GOTIE"),
+    (17, "R_386_TLS_LE"),
+    (18, "R_386_TLS_GD"),
+    (19, "R_386_TLS_LDM"),
+    (20, "R_386_16"),
+    (21, "R_386_PC16"),
+    (22, "R_386_8"),
+    (23, "R_386_PC8"),
+    (24, "R_386_TLS_GD_32"),
+    (25, "R_386_TLS_GD_PUSH"),
+    (26, "R_386_TLS_GD_CALL"),
+    (27, "R_386_TLS_GD_POP"),
+    (28, "R_386_TLS_LDM_32"),
+    (29, "R_386_TLS_LDM_PUSH"),
+    (30, "R_386_TLS_LDM_CALL"),
+    (31, "R_386_TLS_LDM_POP"),
+    (32, "R_386_TLS_LDO_32"),
+    (33, "R_386_TLS_IE_32"),
+    (34, "R_386_TLS_LE_32"),
+    (35, "R_386_TLS_DTPMOD32"),
+    (36, "R_386_TLS_DTPOFF32"),
+    (37, "R_386_TLS_TPOFF32"),
+    (38, "R_386_SIZE32"),
+    (39, "R_386_TLS_GOTDESC"),
+    (40, "R_386_TLS_DESC_CALL"),
+    (41, "R_386_TLS_DESC"),
+    (42, "R_386_IRELATIVE"),
+    (43, "R_386_GOT32X"),
+];
+
+const AARCH64_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_AARCH64_NONE"),
+    (257, "R_AARCH64_ABS64"),
+    (258, "R_AARCH64_ABS32"),
+    (259, "R_AARCH64_ABS16"),
+    (260, "R_AARCH64_PREL64"),
+    (261, "R_AARCH64_PREL32"),
+    (262, "R_AARCH64_PREL16"),
+    (263, "R_AARCH64_MOVW_UABS_G0"),
+    (264, "R_AARCH64_MOVW_UABS_G0_NC"),
+    (265, "R_AARCH64_MOVW_UABS_G1"),
+    (266, "R_AARCH64_MOVW_UABS_G1_NC"),
+    (267, "R_AARCH64_MOVW_UABS_G2"),
+    (268, "R_AARCH64_MOVW_UABS_G2_NC"),
+    (269, "R_AARCH64_MOVW_UABS_G3"),
+    (270, "R_AARCH64_MOVW_SABS_G0"),
+    (271, "R_AARCH64_MOVW_SABS_G1"),
+    (272, "R_AARCH64_MOVW_SABS_G2"),
+    (273, "R_AARCH64_LD_PREL_LO19"),
+    (274, "R_AARCH64_ADR_PREL_LO21"),
+    (275, "R_AARCH64_ADR_PREL_PG_HI21"),
+    (276, "R_AARCH64_ADR_PREL_PG_HI21_NC"),
+    (277, "R_AARCH64_ADD_ABS_LO12_NC"),
+    (278, "R_AARCH64_LDST8_ABS_LO12_NC"),
+    (279, "R_AARCH64_TSTBR14"),
+    (280, "R_AARCH64_CONDBR19"),
+    (282, "R_AARCH64_JUMP26"),
+    (283, "R_AARCH64_CALL26"),
+    (284, "R_AARCH64_LDST16_ABS_LO12_NC"),
+    (285, "R_AARCH64_LDST32_ABS_LO12_NC"),
+    (286, "R_AARCH64_LDST64_ABS_LO12_NC"),
+    (287, "R_AARCH64_MOVW_PREL_G0"),
+    (288, "R_AARCH64_MOVW_PREL_G0_NC"),
+    (289, "R_AARCH64_MOVW_PREL_G1"),
+    (290, "R_AARCH64_MOVW_PREL_G1_NC"),
+    (291, "R_AARCH64_MOVW_PREL_G2"),
+    (292, "R_AARCH64_MOVW_PREL_G2_NC"),
+    (293, "R_AARCH64_MOVW_PREL_G3"),
+    (299, "R_AARCH64_LDST128_ABS_LO12_NC"),
+    (307, "R_AARCH64_GOTREL64"),
+    (308, "R_AARCH64_GOTREL32"),
+    (309, "R_AARCH64_GOT_LD_PREL19"),
+    (310, "R_AARCH64_LD64_GOTOFF_LO15"),
+    (311, "R_AARCH64_ADR_GOT_PAGE"),
+    (312, "R_AARCH64_LD64_GOT_LO12_NC"),
+    (313, "R_AARCH64_LD64_GOTPAGE_LO15"),
+    (512, "R_AARCH64_TLSGD_ADR_PREL21"),
+    (513, "R_AARCH64_TLSGD_ADR_PAGE21"),
+    (514, "R_AARCH64_TLSGD_ADD_LO12_NC"),
+    (517, "R_AARCH64_TLSLD_ADR_PREL21"),
+    (518, "R_AARCH64_TLSLD_ADR_PAGE21"),
+    (519, "R_AARCH64_TLSLD_ADD_LO12_NC"),
+    (539, "R_AARCH64_TLSIE_MOVW_GOTTPREL_G1"),
+    (540, "R_AARCH64_TLSIE_MOVW_GOTTPREL_G0_NC"),
+    (541, "R_AARCH64_TLSIE_ADR_GOTTPREL_PAGE21"),
+    (542, "R_AARCH64_TLSIE_LD64_GOTTPREL_LO12_NC"),
+    (543, "R_AARCH64_TLSIE_LD_GOTTPREL_PREL19"),
+    (544, "R_AARCH64_TLSLE_MOVW_TPREL_G2"),
+    (545, "R_AARCH64_TLSLE_MOVW_TPREL_G1"),
+    (546, "R_AARCH64_TLSLE_MOVW_TPREL_G1_NC"),
+    (547, "R_AARCH64_TLSLE_MOVW_TPREL_G0"),
+    (548, "R_AARCH64_TLSLE_MOVW_TPREL_G0_NC"),
+    (549, "R_AARCH64_TLSLE_ADD_TPREL_HI12"),
+    (550, "R_AARCH64_TLSLE_ADD_TPREL_LO12"),
+    (551, "R_AARCH64_TLSLE_ADD_TPREL_LO12_NC"),
+    (560, "R_AARCH64_TLSDESC_LD_PREL19"),
+    (561, "R_AARCH64_TLSDESC_ADR_PREL21"),
+    (562, "R_AARCH64_TLSDESC_ADR_PAGE21"),
+    (563, "R_AARCH64_TLSDESC_LD64_LO12"),
+    (564, "R_AARCH64_TLSDESC_ADD_LO12"),
+    (569, "R_AARCH64_TLSDESC_CALL"),
+    (1024, "R_AARCH64_COPY"),
+    (1025, "R_AARCH64_GLOB_DAT"),
+    (1026, "R_AARCH64_JUMP_SLOT"),
+    (1027, "R_AARCH64_RELATIVE"),
+    (1028, "R_AARCH64_TLS_DTPMOD"),
+    (1029, "R_AARCH64_TLS_DTPREL"),
+    (1030, "R_AARCH64_TLS_TPREL"),
+    (1031, "R_AARCH64_TLSDESC"),
+    (1032, "R_AARCH64_IRELATIVE"),
+];
+
+const ARM_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_ARM_NONE"),
+    (1, "R_ARM_PC24"),
+    (2, "R_ARM_ABS32"),
+    (3, "R_ARM_REL32"),
+    (4, "R_ARM_LDR_PC_G0"),
+    (5, "R_ARM_ABS16"),
+    (6, "R_ARM_ABS12"),
+    (7, "R_ARM_THM_ABS5"),
+    (8, "R_ARM_ABS8"),
+    (9, "R_ARM_SBREL32"),
+    (10, "R_ARM_THM_CALL"),
+    (11, "R_ARM_THM_PC8"),
+    (12, "R_ARM_BREL_ADJ"),
+    (13, "R_ARM_TLS_DESC"),
+    (17, "R_ARM_TLS_DTPMOD32"),
+    (18, "R_ARM_TLS_DTPOFF32"),
+    (19, "R_ARM_TLS_TPOFF32"),
+    (20, "R_ARM_COPY"),
+    (21, "R_ARM_GLOB_DAT"),
+    (22, "R_ARM_JUMP_SLOT"),
+    (23, "R_ARM_RELATIVE"),
+    (24, "R_ARM_GOTOFF32"),
+    (25, "R_ARM_BASE_PREL"),
+    (26, "R_ARM_GOT_BREL"),
+    (27, "R_ARM_PLT32"),
+    (28, "R_ARM_CALL"),
+    (29, "R_ARM_JUMP24"),
+    (30, "R_ARM_THM_JUMP24"),
+    (31, "R_ARM_BASE_ABS"),
+    (38, "R_ARM_TARGET1"),
+    (40, "R_ARM_V4BX"),
+    (41, "R_ARM_TARGET2"),
+    (42, "R_ARM_PREL31"),
+    (43, "R_ARM_MOVW_ABS_NC"),
+    (44, "R_ARM_MOVT_ABS"),
+    (45, "R_ARM_MOVW_PREL_NC"),
+    (46, "R_ARM_MOVT_PREL"),
+    (47, "R_ARM_THM_MOVW_ABS_NC"),
+    (48, "R_ARM_THM_MOVT_ABS"),
+    (49, "R_ARM_THM_MOVW_PREL_NC"),
+    (50, "R_ARM_THM_MOVT_PREL"),
+    (51, "R_ARM_THM_JUMP19"),
+    (96, "R_ARM_GOT_PREL"),
+    (102, "R_ARM_THM_JUMP11"),
+    (103, "R_ARM_THM_JUMP8"),
+    (104, "R_ARM_TLS_GD32"),
+    (105, "R_ARM_TLS_LDM32"),
+    (106, "R_ARM_TLS_LDO32"),
+    (107, "R_ARM_TLS_IE32"),
+    (108, "R_ARM_TLS_LE32"),
+    (160, "R_ARM_IRELATIVE"),
+];
+
+const RISCV_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_RISCV_NONE"),
+    (1, "R_RISCV_32"),
+    (2, "R_RISCV_64"),
+    (3, "R_RISCV_RELATIVE"),
+    (4, "R_RISCV_COPY"),
+    (5, "R_RISCV_JUMP_SLOT"),
+    (6, "R_RISCV_TLS_DTPMOD32"),
+    (7, "R_RISCV_TLS_DTPMOD64"),
+    (8, "R_RISCV_TLS_DTPREL32"),
+    (9, "R_RISCV_TLS_DTPREL64"),
+    (10, "R_RISCV_TLS_TPREL32"),
+    (11, "R_RISCV_TLS_TPREL64"),
+    (12, "R_RISCV_TLSDESC"),
+    (16, "R_RISCV_BRANCH"),
+    (17, "R_RISCV_JAL"),
+    (18, "R_RISCV_CALL"),
+    (19, "R_RISCV_CALL_PLT"),
+    (20, "R_RISCV_GOT_HI20"),
+    (21, "R_RISCV_TLS_GOT_HI20"),
+    (22, "R_RISCV_TLS_GD_HI20"),
+    (23, "R_RISCV_PCREL_HI20"),
+    (24, "R_RISCV_PCREL_LO12_I"),
+    (25, "R_RISCV_PCREL_LO12_S"),
+    (26, "R_RISCV_HI20"),
+    (27, "R_RISCV_LO12_I"),
+    (28, "R_RISCV_LO12_S"),
+    (29, "R_RISCV_TPREL_HI20"),
+    (30, "R_RISCV_TPREL_LO12_I"),
+    (31, "R_RISCV_TPREL_LO12_S"),
+    (32, "R_RISCV_TPREL_ADD"),
+    (33, "R_RISCV_ADD8"),
+    (34, "R_RISCV_ADD16"),
+    (35, "R_RISCV_ADD32"),
+    (36, "R_RISCV_ADD64"),
+    (37, "R_RISCV_SUB8"),
+    (38, "R_RISCV_SUB16"),
+    (39, "R_RISCV_SUB32"),
+    (40, "R_RISCV_SUB64"),
+    (41, "R_RISCV_GOT32_PCREL"),
+    (43, "R_RISCV_ALIGN"),
+    (44, "R_RISCV_RVC_BRANCH"),
+    (45, "R_RISCV_RVC_JUMP"),
+    (51, "R_RISCV_RELAX"),
+    (52, "R_RISCV_SUB6"),
+    (53, "R_RISCV_SET6"),
+    (54, "R_RISCV_SET8"),
+    (55, "R_RISCV_SET16"),
+    (56, "R_RISCV_SET32"),
+    (57, "R_RISCV_32_PCREL"),
+    (58, "R_RISCV_IRELATIVE"),
+    (59, "R_RISCV_PLT32"),
+    (60, "R_RISCV_SET_ULEB128"),
+    (61, "R_RISCV_SUB_ULEB128"),
+    (62, "R_RISCV_TLSDESC_HI20"),
+    (63, "R_RISCV_TLSDESC_LOAD_LO12"),
+    (64, "R_RISCV_TLSDESC_ADD_LO12"),
+    (65, "R_RISCV_TLSDESC_CALL"),
+];
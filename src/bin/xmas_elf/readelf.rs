@@ -0,0 +1,1660 @@
+//! A readelf-style display of the headers, tables and sections of ELF files.
+//!
+//! The output follows GNU readelf's, in both its default layout and its wide
+//! (`-W`) one, so that scripts which parse readelf's output can use this
+//! instead.
+
+use std::fmt::Write;
+use std::str;
+
+use xmas_elf::ElfFile;
+use xmas_elf::gnu_property;
+use xmas_elf::group::SectionGroup;
+use xmas_elf::header::{self, Class, Data, Machine};
+use xmas_elf::program::{self, ProgramHeader, SegmentData};
+use xmas_elf::sections::{self, NoteIter, SectionData, SectionHeader, ShType};
+use xmas_elf::symbol_table::Entry;
+use xmas_elf::symbol_version::{self, VerdefIter, VerneedIter, VersionIndex, VersionSymbols};
+
+use image::read_file;
+use names;
+
+#[cfg(feature = "json")]
+mod json;
+
+pub const USAGE: &str = "\
+Display information about the contents of ELF format files
+ Options are:
+  -a --all               Equivalent to: -h -l -S -g -s -r -d -V -n
+  -h --file-header       Display the ELF file header
+  -l --program-headers   Display the program headers
+     --segments          An alias for --program-headers
+  -S --section-headers   Display the sections' header
+     --sections          An alias for --section-headers
+  -g --section-groups    Display the section groups
+  -e --headers           Equivalent to: -h -l -S
+  -s --syms              Display the symbol table
+     --symbols           An alias for --syms
+     --dyn-syms          Display the dynamic symbol table
+  -n --notes             Display the core notes (if present)
+  -r --relocs            Display the relocations (if present)
+  -d --dynamic           Display the dynamic section (if present)
+  -V --version-info      Display the version sections (if present)
+  -x --hex-dump=<number|name>
+                         Dump the contents of section <number|name> as bytes
+  -p --string-dump=<number|name>
+                         Dump the contents of section <number|name> as strings
+  -W --wide              Allow output width to exceed 80 characters
+     --json              Display everything as JSON, one document per file
+                         (needs the `json` feature)";
+
+const PT_PHDR: u32 = 6;
+const PT_TLS: u32 = 7;
+const PT_GNU_EH_FRAME: u32 = 0x6474e550;
+const PT_GNU_STACK: u32 = 0x6474e551;
+const PT_GNU_RELRO: u32 = 0x6474e552;
+const PT_GNU_SFRAME: u32 = 0x6474e554;
+const PT_GNU_MBIND_LO: u32 = 0x6474e555;
+const PT_GNU_MBIND_HI: u32 = 0x6474f554;
+
+const SHT_NOBITS: u32 = 8;
+const SHT_RELR: u32 = 19;
+
+const SHF_GNU_RETAIN: u64 = 0x200000;
+
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_STRSZ: u64 = 10;
+const DT_SONAME: u64 = 14;
+const DT_RPATH: u64 = 15;
+const DT_PLTREL: u64 = 20;
+const DT_RUNPATH: u64 = 29;
+const DT_FLAGS: u64 = 30;
+const DT_FLAGS_1: u64 = 0x6ffffffb;
+
+const DF_FLAGS: &[(u64, &str)] = &[(0x1, "ORIGIN"),
+                                   (0x2, "SYMBOLIC"),
+                                   (0x4, "TEXTREL"),
+                                   (0x8, "BIND_NOW"),
+                                   (0x10, "STATIC_TLS")];
+
+const DF_1_FLAGS: &[&str] = &["NOW", "GLOBAL", "GROUP", "NODELETE", "LOADFLTR", "INITFIRST", "NOOPEN",
+                              "ORIGIN", "DIRECT", "TRANS", "INTERPOSE", "NODEFLIB", "NODUMP", "CONFALT",
+                              "ENDFILTEE", "DISPRELDNE", "DISPRELPND", "NODIRECT", "IGNMULDEF", "NOKSYMS",
+                              "NOHDR", "EDITED", "NORELOC", "SYMINTPOSE", "GLOBAUDIT", "SINGLETON", "STUB",
+                              "PIE"];
+
+#[derive(Debug, Default)]
+struct Options {
+    file_header: bool,
+    program_headers: bool,
+    section_headers: bool,
+    section_groups: bool,
+    symbols: bool,
+    dyn_syms: bool,
+    notes: bool,
+    relocs: bool,
+    dynamic: bool,
+    version_info: bool,
+    hex_dumps: Vec<String>,
+    string_dumps: Vec<String>,
+    wide: bool,
+    json: bool,
+}
+
+impl Options {
+    fn headers(&mut self) {
+        self.file_header = true;
+        self.program_headers = true;
+        self.section_headers = true;
+    }
+
+    fn all(&mut self) {
+        self.headers();
+        self.section_groups = true;
+        self.symbols = true;
+        self.relocs = true;
+        self.dynamic = true;
+        self.version_info = true;
+        self.notes = true;
+    }
+
+    fn any(&self) -> bool {
+        self.file_header || self.program_headers || self.section_headers || self.section_groups ||
+        self.symbols || self.dyn_syms || self.notes || self.relocs || self.dynamic || self.version_info ||
+        !self.hex_dumps.is_empty() || !self.string_dumps.is_empty() || self.json
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<(Options, Vec<&String>), String> {
+    let mut options = Options::default();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, value) = match long.find('=') {
+                Some(i) => (&long[..i], Some(&long[i + 1..])),
+                None => (long, None),
+            };
+            match name {
+                "all" => options.all(),
+                "file-header" => options.file_header = true,
+                "program-headers" | "segments" => options.program_headers = true,
+                "section-headers" | "sections" => options.section_headers = true,
+                "section-groups" => options.section_groups = true,
+                "headers" => options.headers(),
+                "syms" | "symbols" => options.symbols = true,
+                "dyn-syms" => options.dyn_syms = true,
+                "notes" => options.notes = true,
+                "relocs" => options.relocs = true,
+                "dynamic" => options.dynamic = true,
+                "version-info" => options.version_info = true,
+                "hex-dump" | "string-dump" => {
+                    let value = match value {
+                        Some(value) => value.to_owned(),
+                        None => args.next().ok_or_else(|| format!("option '--{}' requires an argument", name))?.clone(),
+                    };
+                    if name == "hex-dump" {
+                        options.hex_dumps.push(value);
+                    } else {
+                        options.string_dumps.push(value);
+                    }
+                }
+                "wide" => options.wide = true,
+                "json" if cfg!(feature = "json") => options.json = true,
+                "json" => return Err("this build does not support '--json'; rebuild with the `json` feature".to_owned()),
+                _ => return Err(format!("unrecognized option '{}'", arg)),
+            }
+        } else if arg.len() > 1 && arg.starts_with('-') {
+            for (i, c) in arg.char_indices().skip(1) {
+                match c {
+                    'a' => options.all(),
+                    'h' => options.file_header = true,
+                    'l' => options.program_headers = true,
+                    'S' => options.section_headers = true,
+                    'g' => options.section_groups = true,
+                    'e' => options.headers(),
+                    's' => options.symbols = true,
+                    'n' => options.notes = true,
+                    'r' => options.relocs = true,
+                    'd' => options.dynamic = true,
+                    'V' => options.version_info = true,
+                    'W' => options.wide = true,
+                    'x' | 'p' => {
+                        let rest = &arg[i + 1..];
+                        let value = if rest.is_empty() {
+                            args.next().ok_or_else(|| format!("option requires an argument -- '{}'", c))?.clone()
+                        } else {
+                            rest.to_owned()
+                        };
+                        if c == 'x' {
+                            options.hex_dumps.push(value);
+                        } else {
+                            options.string_dumps.push(value);
+                        }
+                        break;
+                    }
+                    _ => return Err(format!("invalid option -- '{}'", c)),
+                }
+            }
+        } else {
+            files.push(arg);
+        }
+    }
+    Ok((options, files))
+}
+
+/// Displays the files and options given in `args`, readelf-style. Returns
+/// whether everything could be displayed; errors have already been reported.
+pub fn run(program_name: &str, args: &[String]) -> bool {
+    let (options, files) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}: {}", program_name, e);
+            eprintln!("Usage: {} <option(s)> elf-file(s)\n{}", program_name, USAGE);
+            return false;
+        }
+    };
+    if !options.any() || files.is_empty() {
+        eprintln!("Usage: {} <option(s)> elf-file(s)\n{}", program_name, USAGE);
+        return false;
+    }
+
+    let mut ok = true;
+    for path in &files {
+        let mut report = |result: Result<(), String>| {
+            if let Err(e) = result {
+                eprintln!("{}: Error: {}", program_name, e);
+                ok = false;
+            }
+        };
+        let input = match read_file(path) {
+            Ok(input) => input,
+            Err(e) => {
+                report(Err(e));
+                continue;
+            }
+        };
+        let file = match File::new(&input) {
+            Ok(file) => file,
+            Err(e) => {
+                report(Err(format!("{}: {}", path, e)));
+                continue;
+            }
+        };
+        #[cfg(feature = "json")]
+        {
+            if options.json {
+                report(json::display(&file, path));
+                continue;
+            }
+        }
+        if files.len() > 1 {
+            println!("\nFile: {}", path);
+        }
+
+        if options.file_header {
+            file.display_file_header();
+        }
+        if options.section_headers {
+            file.display_section_headers(options.file_header, options.wide);
+        }
+        if options.section_groups {
+            report(file.display_section_groups());
+        }
+        if options.program_headers {
+            report(file.display_program_headers(options.file_header, options.wide));
+        }
+        if options.dynamic {
+            report(file.display_dynamic());
+        }
+        if options.relocs {
+            report(file.display_relocations(options.wide));
+        }
+        if options.symbols || options.dyn_syms {
+            report(file.display_symbols(!options.symbols, options.wide));
+        }
+        if options.version_info {
+            report(file.display_versions());
+        }
+        if options.notes {
+            report(file.display_notes(options.wide));
+        }
+        for name in &options.hex_dumps {
+            report(file.display_hex_dump(name));
+        }
+        for name in &options.string_dumps {
+            report(file.display_string_dump(name));
+        }
+    }
+    ok
+}
+
+// A file whose header tables have been checked, so that the library can be
+// used to read them without panicking.
+struct File<'a> {
+    elf: ElfFile<'a>,
+    is_64: bool,
+    machine: Machine,
+    segments: Vec<ProgramHeader<'a>>,
+    sections: Vec<SectionHeader<'a>>,
+}
+
+struct Relocation {
+    offset: u64,
+    info: u64,
+    type_: u32,
+    symbol: u32,
+    addend: Option<i64>,
+}
+
+struct DynamicTable<'a> {
+    offset: u64,
+    entries: Vec<(u64, u64)>,
+    strings: &'a [u8],
+}
+
+#[derive(Clone, Copy)]
+struct VersionName<'a> {
+    name: &'a str,
+    // Whether the version is required from another object, rather than
+    // defined by this one.
+    needed: bool,
+}
+
+impl<'a> File<'a> {
+    fn new(input: &'a [u8]) -> Result<File<'a>, String> {
+        if !input.starts_with(&header::MAGIC) {
+            return Err("Not an ELF file - it has the wrong magic bytes at the start".to_owned());
+        }
+        let elf = ElfFile::new(input)?;
+        let native = if cfg!(target_endian = "little") { Data::LittleEndian } else { Data::BigEndian };
+        if elf.header.pt1.data() != native {
+            return Err("only files with the host's byte order are supported".to_owned());
+        }
+        let is_64 = elf.header.pt1.class() == Class::SixtyFour;
+        let pt2 = elf.header.pt2;
+
+        let mut segments = Vec::new();
+        if pt2.ph_count() > 0 {
+            check_table(input, pt2.ph_offset(), pt2.ph_count(), pt2.ph_entry_size(),
+                        if is_64 { 56 } else { 32 }, is_64, "program headers")?;
+            for i in 0..pt2.ph_count() {
+                segments.push(elf.program_header(i)?);
+            }
+        }
+        let mut sections = Vec::new();
+        if pt2.sh_count() > 0 {
+            if pt2.sh_count() >= sections::SHN_LORESERVE {
+                return Err("too many section headers".to_owned());
+            }
+            check_table(input, pt2.sh_offset(), pt2.sh_count(), pt2.sh_entry_size(),
+                        if is_64 { 64 } else { 40 }, is_64, "section headers")?;
+            for i in 0..pt2.sh_count() {
+                sections.push(elf.section_header(i)?);
+            }
+        }
+
+        Ok(File {
+            machine: pt2.machine().as_machine(),
+            elf,
+            is_64,
+            segments,
+            sections,
+        })
+    }
+
+    fn section_data(&self, section: SectionHeader<'a>) -> Result<&'a [u8], String> {
+        if section.type_().0 == 0 || section.type_().0 == SHT_NOBITS {
+            return Ok(&[]);
+        }
+        self.file_range(section.offset(), section.size())
+            .ok_or_else(|| format!("section '{}' extends past the end of the file", self.section_name(section)))
+    }
+
+    fn segment_data(&self, segment: ProgramHeader<'a>) -> Result<&'a [u8], String> {
+        self.file_range(segment.offset(), segment.file_size())
+            .ok_or_else(|| "segment extends past the end of the file".to_owned())
+    }
+
+    fn file_range(&self, offset: u64, size: u64) -> Option<&'a [u8]> {
+        let end = offset.checked_add(size)?;
+        self.elf.input.get(offset as usize..end as usize)
+    }
+
+    // The contents of the string table at section index `index`, or nothing if
+    // there is no such string table.
+    fn strings(&self, index: u32) -> &'a [u8] {
+        self.sections.get(index as usize)
+            .filter(|s| s.get_type() == Ok(ShType::StrTab))
+            .and_then(|s| self.file_range(s.offset(), s.size()))
+            .unwrap_or(&[])
+    }
+
+    fn section_name(&self, section: SectionHeader<'a>) -> &'a str {
+        c_str(self.strings(self.elf.header.pt2.sh_str_index() as u32), section.name())
+    }
+
+    fn find_section(&self, name_or_index: &str) -> Option<(usize, SectionHeader<'a>)> {
+        let index = match name_or_index.parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => 1 + self.sections.iter().skip(1).position(|&s| self.section_name(s) == name_or_index)?,
+        };
+        self.sections.get(index).map(|&s| (index, s))
+    }
+
+    fn sections_of_type(&self, sh_type: u32) -> impl Iterator<Item = SectionHeader<'a>> + '_ {
+        self.sections.iter().cloned().filter(move |s| s.type_().0 == sh_type)
+    }
+
+    // Checks that a section holds a whole number of aligned table entries, so
+    // that the library can read it.
+    fn check_table_section(&self, section: SectionHeader<'a>, entry_size: usize) -> Result<(), String> {
+        let data = self.section_data(section)?;
+        let align = if self.is_64 { 8 } else { 4 };
+        if data.len() % entry_size != 0 || data.as_ptr() as usize % align != 0 {
+            return Err(format!("section '{}' is not a well-formed table", self.section_name(section)));
+        }
+        Ok(())
+    }
+
+    fn symbols(&self, section: SectionHeader<'a>) -> Result<Vec<&'a dyn Entry>, String> {
+        self.check_table_section(section, if self.is_64 { 24 } else { 16 })?;
+        Ok(match section.get_data(&self.elf)? {
+            SectionData::SymbolTable32(entries) => entries.iter().map(|e| e as &dyn Entry).collect(),
+            SectionData::SymbolTable64(entries) => entries.iter().map(|e| e as &dyn Entry).collect(),
+            SectionData::DynSymbolTable32(entries) => entries.iter().map(|e| e as &dyn Entry).collect(),
+            SectionData::DynSymbolTable64(entries) => entries.iter().map(|e| e as &dyn Entry).collect(),
+            _ => return Err(format!("section '{}' is not a symbol table", self.section_name(section))),
+        })
+    }
+
+    fn relocations(&self, section: SectionHeader<'a>) -> Result<Vec<Relocation>, String> {
+        let is_rela = section.get_type() == Ok(ShType::Rela);
+        let entry_size = match (self.is_64, is_rela) {
+            (true, true) => 24,
+            (true, false) => 16,
+            (false, true) => 12,
+            (false, false) => 8,
+        };
+        self.check_table_section(section, entry_size)?;
+        Ok(match section.get_data(&self.elf)? {
+            SectionData::Rela64(relas) => relas.iter().map(|r| Relocation {
+                offset: r.get_offset(),
+                info: ((r.get_symbol_table_index() as u64) << 32) | r.get_type() as u64,
+                type_: r.get_type(),
+                symbol: r.get_symbol_table_index(),
+                addend: Some(r.get_addend() as i64),
+            }).collect(),
+            SectionData::Rela32(relas) => relas.iter().map(|r| Relocation {
+                offset: r.get_offset() as u64,
+                info: ((r.get_symbol_table_index() as u64) << 8) | r.get_type() as u64,
+                type_: r.get_type() as u32,
+                symbol: r.get_symbol_table_index(),
+                addend: Some(r.get_addend() as i32 as i64),
+            }).collect(),
+            SectionData::Rel64(rels) => rels.iter().map(|r| Relocation {
+                offset: r.get_offset(),
+                info: ((r.get_symbol_table_index() as u64) << 32) | r.get_type() as u64,
+                type_: r.get_type(),
+                symbol: r.get_symbol_table_index(),
+                addend: None,
+            }).collect(),
+            SectionData::Rel32(rels) => rels.iter().map(|r| Relocation {
+                offset: r.get_offset() as u64,
+                info: ((r.get_symbol_table_index() as u64) << 8) | r.get_type() as u64,
+                type_: r.get_type() as u32,
+                symbol: r.get_symbol_table_index(),
+                addend: None,
+            }).collect(),
+            _ => return Err(format!("section '{}' is not a relocation table", self.section_name(section))),
+        })
+    }
+
+    // The dynamic table, from the dynamic section or, failing that, the
+    // dynamic segment. Entries after the first DT_NULL are left out.
+    fn dynamic_table(&self) -> Result<Option<DynamicTable<'a>>, String> {
+        let (offset, data, strings) = if let Some(section) = self.sections_of_type(6).next() {
+            self.check_table_section(section, if self.is_64 { 16 } else { 8 })?;
+            (section.offset(), section.get_data(&self.elf)?, Some(self.strings(section.link())))
+        } else if let Some(&segment) = self.segments.iter().find(|s| s.get_type() == Ok(program::Type::Dynamic)) {
+            let data = self.segment_data(segment)?;
+            let entry_size = if self.is_64 { 16 } else { 8 };
+            if data.len() % entry_size != 0 || data.as_ptr() as usize % (entry_size / 2) != 0 {
+                return Err("the dynamic segment is not a well-formed table".to_owned());
+            }
+            let data = match segment.get_data(&self.elf)? {
+                SegmentData::Dynamic32(entries) => SectionData::Dynamic32(entries),
+                SegmentData::Dynamic64(entries) => SectionData::Dynamic64(entries),
+                _ => unreachable!(),
+            };
+            (segment.offset(), data, None)
+        } else {
+            return Ok(None);
+        };
+
+        let mut entries: Vec<(u64, u64)> = match data {
+            SectionData::Dynamic32(entries) => {
+                entries.iter().map(|d| (d.raw_tag() as u64, d.raw_val() as u64)).collect()
+            }
+            SectionData::Dynamic64(entries) => entries.iter().map(|d| (d.raw_tag(), d.raw_val())).collect(),
+            _ => return Err("the dynamic section is not a dynamic table".to_owned()),
+        };
+        if let Some(end) = entries.iter().position(|&(tag, _)| tag == DT_NULL) {
+            entries.truncate(end + 1);
+        }
+
+        // Without section headers, find the strings through the segments.
+        let strings = strings.unwrap_or_else(|| {
+            let value = |tag| entries.iter().find(|e| e.0 == tag).map(|e| e.1);
+            value(DT_STRTAB).and_then(|address| {
+                let size = value(DT_STRSZ)?;
+                let load = self.segments.iter().find(|s| {
+                    s.get_type() == Ok(program::Type::Load) && s.virtual_addr() <= address &&
+                    address - s.virtual_addr() < s.file_size()
+                })?;
+                self.file_range(load.offset() + (address - load.virtual_addr()), size)
+            }).unwrap_or(&[])
+        });
+        Ok(Some(DynamicTable { offset, entries, strings }))
+    }
+
+    // The names of the versions used in the version symbol table, by version
+    // index.
+    fn version_names(&self) -> Result<Vec<Option<VersionName<'a>>>, String> {
+        let mut names = Vec::new();
+        let mut set = |index: u16, name| {
+            let index = index as usize;
+            if names.len() <= index {
+                names.resize(index + 1, None);
+            }
+            names[index] = Some(name);
+        };
+        for section in self.sections_of_type(sections::SHT_GNU_VERDEF) {
+            let strings = self.strings(section.link());
+            for verdef in VerdefIter::new(self.section_data(section)?) {
+                let verdef = verdef?;
+                if let Some(verdaux) = verdef.names().next() {
+                    set(verdef.index, VersionName { name: c_str(strings, verdaux?.name), needed: false });
+                }
+            }
+        }
+        for section in self.sections_of_type(sections::SHT_GNU_VERNEED) {
+            let strings = self.strings(section.link());
+            for verneed in VerneedIter::new(self.section_data(section)?) {
+                for vernaux in verneed?.versions() {
+                    let vernaux = vernaux?;
+                    set(vernaux.other, VersionName { name: c_str(strings, vernaux.name), needed: true });
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    fn version_symbols(&self) -> Result<Option<VersionSymbols<'a>>, String> {
+        match self.sections_of_type(sections::SHT_GNU_VERSYM).next() {
+            Some(section) => Ok(Some(VersionSymbols::new(self.section_data(section)?))),
+            None => Ok(None),
+        }
+    }
+
+    fn file_type(&self) -> String {
+        match self.elf.header.pt2.type_().0 {
+            0 => "NONE (None)".to_owned(),
+            1 => "REL (Relocatable file)".to_owned(),
+            2 => "EXEC (Executable file)".to_owned(),
+            3 => {
+                let flags_1 = self.dynamic_table().ok().and_then(|d| d).and_then(|d| {
+                    d.entries.iter().find(|e| e.0 == DT_FLAGS_1).map(|e| e.1)
+                });
+                if flags_1.unwrap_or(0) & 0x08000000 != 0 {
+                    "DYN (Position-Independent Executable file)".to_owned()
+                } else {
+                    "DYN (Shared object file)".to_owned()
+                }
+            }
+            4 => "CORE (Core file)".to_owned(),
+            0xfe00..=0xfeff => format!("OS Specific: ({:x})", self.elf.header.pt2.type_().0),
+            0xff00..=0xffff => format!("Processor Specific: ({:x})", self.elf.header.pt2.type_().0),
+            other => format!("<unknown>: {:x}", other),
+        }
+    }
+
+    // SHF_GNU_RETAIN is only defined for the GNU and FreeBSD ABIs.
+    fn has_retain_flag(&self) -> bool {
+        let os_abi = self.elf.header.pt1.os_abi.0;
+        os_abi == 3 || os_abi == 9
+    }
+
+    fn display_file_header(&self) {
+        let pt1 = self.elf.header.pt1;
+        let pt2 = self.elf.header.pt2;
+        let field = |label: &str, value: String| println!("  {:<35}{}", label, value);
+
+        println!("ELF Header:");
+        let mut magic = String::new();
+        for b in &self.elf.input[..16] {
+            write!(magic, "{:02x} ", b).unwrap();
+        }
+        println!("  Magic:   {}", magic);
+        field("Class:", if self.is_64 { "ELF64" } else { "ELF32" }.to_owned());
+        field("Data:", match pt1.data() {
+            Data::LittleEndian => "2's complement, little endian".to_owned(),
+            Data::BigEndian => "2's complement, big endian".to_owned(),
+            _ => "none".to_owned(),
+        });
+        field("Version:", match pt1.version() {
+            header::Version::Current => "1 (current)".to_owned(),
+            header::Version::None => "0".to_owned(),
+            header::Version::Other(v) => format!("{} <unknown>", v),
+        });
+        field("OS/ABI:", names::os_abi(pt1.os_abi.0));
+        field("ABI Version:", pt1.abi_version.to_string());
+        field("Type:", self.file_type());
+        field("Machine:", names::machine(pt2.machine().0));
+        field("Version:", format!("0x{:x}", pt2.version()));
+        field("Entry point address:", format!("0x{:x}", pt2.entry_point()));
+        field("Start of program headers:", format!("{} (bytes into file)", pt2.ph_offset()));
+        field("Start of section headers:", format!("{} (bytes into file)", pt2.sh_offset()));
+        field("Flags:", format!("0x{:x}", pt2.flags()));
+        field("Size of this header:", format!("{} (bytes)", pt2.header_size()));
+        field("Size of program headers:", format!("{} (bytes)", pt2.ph_entry_size()));
+        field("Number of program headers:", pt2.ph_count().to_string());
+        field("Size of section headers:", format!("{} (bytes)", pt2.sh_entry_size()));
+        field("Number of section headers:", pt2.sh_count().to_string());
+        field("Section header string table index:", pt2.sh_str_index().to_string());
+    }
+
+    fn display_section_headers(&self, after_file_header: bool, wide: bool) {
+        if self.sections.is_empty() {
+            println!("\nThere are no sections in this file.");
+            return;
+        }
+        if !after_file_header {
+            println!("There are {} section headers, starting at offset 0x{:x}:",
+                     self.sections.len(),
+                     self.elf.header.pt2.sh_offset());
+        }
+        println!("\nSection Headers:");
+        if !self.is_64 {
+            println!("  [Nr] Name              Type            Addr     Off    Size   ES Flg Lk Inf Al");
+        } else if wide {
+            println!("  [Nr] Name              Type            Address          Off    Size   ES Flg Lk Inf Al");
+        } else {
+            println!("  [Nr] Name              Type             Address           Offset");
+            println!("       Size              EntSize          Flags  Link  Info  Align");
+        }
+        for (i, &section) in self.sections.iter().enumerate() {
+            let name = if i == 0 { "" } else { self.section_name(section) };
+            let type_ = names::section_type(self.machine, section.type_().0);
+            let flags = section_flags(section.flags(), self.has_retain_flag());
+            print!("  [{:2}] {:<17} ", i, truncate_name(name, 17, wide));
+            if wide {
+                print!("{:<15} ", type_);
+            } else {
+                print!("{:<15.15} ", type_);
+            }
+            if !self.is_64 || wide {
+                let address = if self.is_64 {
+                    format!("{:016x}", section.address())
+                } else {
+                    format!("{:08x}", section.address())
+                };
+                println!("{} {:06x} {:06x} {:02x} {:>3} {:2} {:3} {:2}",
+                         address,
+                         section.offset(),
+                         section.size(),
+                         section.entry_size(),
+                         flags,
+                         section.link(),
+                         section.info(),
+                         section.align());
+            } else {
+                println!(" {:016x}  {:08x}", section.address(), section.offset());
+                println!("       {:016x}  {:016x} {:>3}      {:2}   {:3}     {}",
+                         section.size(),
+                         section.entry_size(),
+                         flags,
+                         section.link(),
+                         section.info(),
+                         section.align());
+            }
+        }
+        println!("Key to Flags:");
+        println!("  W (write), A (alloc), X (execute), M (merge), S (strings), I (info),");
+        println!("  L (link order), O (extra OS processing required), G (group), T (TLS),");
+        println!("  C (compressed), x (unknown), o (OS specific), E (exclude),");
+        let retain = if self.has_retain_flag() { "R (retain), " } else { "" };
+        match self.machine {
+            Machine::X86_64 => println!("  {}D (mbind), l (large), p (processor specific)", retain),
+            Machine::Arm => println!("  {}D (mbind), y (purecode), p (processor specific)", retain),
+            _ => println!("  {}D (mbind), p (processor specific)", retain),
+        }
+    }
+
+    fn display_section_groups(&self) -> Result<(), String> {
+        let mut found = false;
+        for (index, &section) in self.sections.iter().enumerate() {
+            if section.get_type() != Ok(ShType::Group) {
+                continue;
+            }
+            found = true;
+            let group = SectionGroup::new(&self.elf, index as u16)?;
+            println!("\n{}group section [{:5}] `{}' [{}] contains {} sections:",
+                     group_flags(group.flags()),
+                     index,
+                     self.section_name(section),
+                     group.signature(&self.elf)?,
+                     group.len());
+            println!("   [Index]    Name");
+            for member in group.member_indices() {
+                match self.sections.get(member as usize) {
+                    Some(&member_section) if member != 0 => {
+                        println!("   [{:5}]   {}", member, self.section_name(member_section));
+                    }
+                    _ => eprintln!("xmas_elf: Error: section [{:5}] in group section [{:5}] > maximum section [{:5}]",
+                                   member,
+                                   index,
+                                   self.sections.len() - 1),
+                }
+            }
+        }
+        if !found {
+            println!("\nThere are no section groups in this file.");
+        }
+        Ok(())
+    }
+
+    fn display_program_headers(&self, after_file_header: bool, wide: bool) -> Result<(), String> {
+        if self.segments.is_empty() {
+            println!("\nThere are no program headers in this file.");
+            return Ok(());
+        }
+        if !after_file_header {
+            println!("\nElf file type is {}", self.file_type());
+            println!("Entry point 0x{:x}", self.elf.header.pt2.entry_point());
+            println!("There are {} program headers, starting at offset {}",
+                     self.segments.len(),
+                     self.elf.header.pt2.ph_offset());
+        }
+        println!("\nProgram Headers:");
+        if !self.is_64 {
+            println!("  Type           Offset   VirtAddr   PhysAddr   FileSiz MemSiz  Flg Align");
+        } else if wide {
+            println!("  Type           Offset   VirtAddr           PhysAddr           FileSiz  MemSiz   Flg Align");
+        } else {
+            println!("  Type           Offset             VirtAddr           PhysAddr");
+            println!("                 FileSiz            MemSiz              Flags  Align");
+        }
+        for &segment in &self.segments {
+            let flags = segment.flags();
+            let flags = format!("{}{}{}",
+                                if flags.is_read() { 'R' } else { ' ' },
+                                if flags.is_write() { 'W' } else { ' ' },
+                                if flags.is_execute() { 'E' } else { ' ' });
+            let align = if segment.align() == 0 { "0".to_owned() } else { format!("{:#x}", segment.align()) };
+            let type_ = names::segment_type(self.machine, raw_segment_type(segment));
+            if self.is_64 && !wide {
+                println!("  {:<14.14} 0x{:016x} 0x{:016x} 0x{:016x}",
+                         type_,
+                         segment.offset(),
+                         segment.virtual_addr(),
+                         segment.physical_addr());
+                println!("                 0x{:016x} 0x{:016x}  {}    0x{:x}",
+                         segment.file_size(),
+                         segment.mem_size(),
+                         flags,
+                         segment.align());
+            } else if self.is_64 {
+                println!("  {:<14.14} 0x{:06x} 0x{:016x} 0x{:016x} 0x{:06x} 0x{:06x} {} {}",
+                         type_,
+                         segment.offset(),
+                         segment.virtual_addr(),
+                         segment.physical_addr(),
+                         segment.file_size(),
+                         segment.mem_size(),
+                         flags,
+                         align);
+            } else {
+                println!("  {:<14.14} 0x{:06x} 0x{:08x} 0x{:08x} 0x{:05x} 0x{:05x} {} {}",
+                         type_,
+                         segment.offset(),
+                         segment.virtual_addr(),
+                         segment.physical_addr(),
+                         segment.file_size(),
+                         segment.mem_size(),
+                         flags,
+                         align);
+            }
+            if segment.get_type() == Ok(program::Type::Interp) {
+                println!("      [Requesting program interpreter: {}]", c_str(self.segment_data(segment)?, 0));
+            }
+        }
+
+        if self.sections.is_empty() {
+            return Ok(());
+        }
+        println!("\n Section to Segment mapping:");
+        println!("  Segment Sections...");
+        for (i, &segment) in self.segments.iter().enumerate() {
+            let mut line = format!("   {:02}     ", i);
+            for &section in self.sections.iter().skip(1) {
+                if section_in_segment(section, segment) {
+                    line.push_str(self.section_name(section));
+                    line.push(' ');
+                }
+            }
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    fn display_dynamic(&self) -> Result<(), String> {
+        let table = match self.dynamic_table()? {
+            Some(table) => table,
+            None => {
+                println!("\nThere is no dynamic section in this file.");
+                return Ok(());
+            }
+        };
+        println!("\nDynamic section at offset 0x{:x} contains {}:",
+                 table.offset,
+                 entries(table.entries.len()));
+        println!("  Tag        Type                         Name/Value");
+        for &(tag, value) in &table.entries {
+            let name = names::dynamic_tag(tag);
+            let width = if self.is_64 { 19 } else { 27 };
+            let padding = width.max(name.len() + 1) - name.len();
+            if self.is_64 {
+                print!(" 0x{:016x} ({}){:3$}", tag, name, "", padding);
+            } else {
+                print!(" 0x{:08x} ({}){:3$}", tag, name, "", padding);
+            }
+
+            let string = || c_str(table.strings, value as u32);
+            match tag {
+                DT_NEEDED => println!("Shared library: [{}]", string()),
+                DT_SONAME => println!("Library soname: [{}]", string()),
+                DT_RPATH => println!("Library rpath: [{}]", string()),
+                DT_RUNPATH => println!("Library runpath: [{}]", string()),
+                DT_PLTREL => match value {
+                    7 => println!("RELA"),
+                    17 => println!("REL"),
+                    _ => println!("0x{:x}", value),
+                },
+                DT_FLAGS => println!("{}", flag_names(value, DF_FLAGS.iter().cloned())),
+                DT_FLAGS_1 => {
+                    let names = DF_1_FLAGS.iter().enumerate().map(|(bit, &name)| (1 << bit, name));
+                    println!("Flags: {}", flag_names(value, names));
+                }
+                2 | 8 | 9 | 10 | 11 | 18 | 19 | 27 | 28 | 33 | 35 | 37 | 0x6ffffdf6 | 0x6ffffdf7 |
+                0x6ffffdf9 | 0x6ffffdfa | 0x6ffffdfb | 0x6ffffdfe | 0x6ffffdff => {
+                    println!("{} (bytes)", value)
+                }
+                0x6ffffff9 | 0x6ffffffa | 0x6ffffffd | 0x6fffffff => println!("{}", value),
+                _ => println!("0x{:x}", value),
+            }
+        }
+        Ok(())
+    }
+
+    fn display_relocations(&self, wide: bool) -> Result<(), String> {
+        let mut found = false;
+        let version_names = self.version_names()?;
+        let version_symbols = self.version_symbols()?;
+        for &section in &self.sections {
+            if section.type_().0 == SHT_RELR {
+                found = true;
+                self.display_relr(section)?;
+                continue;
+            }
+            let is_rela = match section.get_type() {
+                Ok(ShType::Rela) => true,
+                Ok(ShType::Rel) => false,
+                _ => continue,
+            };
+            found = true;
+            let relocations = self.relocations(section)?;
+            println!("\nRelocation section '{}' at offset 0x{:x} contains {}:",
+                     self.section_name(section),
+                     section.offset(),
+                     entries(relocations.len()));
+            let addend = if is_rela { " + Addend" } else { "" };
+            match (self.is_64, wide) {
+                (true, true) => println!("    Offset             Info             Type               \
+                                          Symbol's Value  Symbol's Name{}", addend),
+                (true, false) => println!("  Offset          Info           Type           Sym. Value    Sym. Name{}", addend),
+                (false, true) => println!(" Offset     Info    Type                Sym. Value  Symbol's Name{}", addend),
+                (false, false) => println!(" Offset     Info    Type            Sym.Value  Sym. Name{}", addend),
+            }
+
+            let symbol_table = match section.link() {
+                0 => None,
+                link => self.sections.get(link as usize).cloned(),
+            };
+            let (symbols, strings, is_dynamic) = match symbol_table {
+                Some(table) => (self.symbols(table)?,
+                                self.strings(table.link()),
+                                table.get_type() == Ok(ShType::DynSym)),
+                None => (Vec::new(), &[][..], false),
+            };
+            for relocation in relocations {
+                let mut line = if !self.is_64 {
+                    format!("{:08x}  {:08x} ", relocation.offset, relocation.info)
+                } else if wide {
+                    format!("{:016x}  {:016x} ", relocation.offset, relocation.info)
+                } else {
+                    format!("{:012x}  {:012x} ", relocation.offset, relocation.info)
+                };
+                match names::relocation_type(self.machine, relocation.type_) {
+                    Some(type_) if wide => write!(line, "{:<22}", type_).unwrap(),
+                    Some(type_) => write!(line, "{:<17.17}", type_).unwrap(),
+                    None => write!(line, "unrecognized: {:<7x}", relocation.type_).unwrap(),
+                }
+
+                if relocation.symbol == 0 {
+                    if let Some(addend) = relocation.addend {
+                        let width = if self.is_64 { 20 } else { 12 };
+                        write!(line, "{:1$}{2:x}", "", width, addend).unwrap();
+                    }
+                } else {
+                    match symbols.get(relocation.symbol as usize) {
+                        Some(&symbol) => {
+                            let symbol_name = self.symbol_name(symbol, strings);
+                            let mut name = truncate_name(symbol_name, 22, wide);
+                            if is_dynamic {
+                                let version = version_symbols.and_then(|v| v.get(relocation.symbol as usize));
+                                let suffix = version_suffix(symbol, symbol_name, version, &version_names, false);
+                                name.push_str(&suffix);
+                            }
+                            if self.is_64 {
+                                write!(line, " {:016x} {}", symbol.value(), name).unwrap();
+                            } else {
+                                write!(line, " {:08x}   {}", symbol.value(), name).unwrap();
+                            }
+                        }
+                        None => write!(line, " <corrupt symbol index: {}>", relocation.symbol).unwrap(),
+                    }
+                    if let Some(addend) = relocation.addend {
+                        if addend < 0 {
+                            write!(line, " - {:x}", addend.unsigned_abs()).unwrap();
+                        } else {
+                            write!(line, " + {:x}", addend).unwrap();
+                        }
+                    }
+                }
+                println!("{}", line);
+            }
+        }
+        if !found {
+            println!("\nThere are no relocations in this file.");
+        }
+        Ok(())
+    }
+
+    // Relative relocations in the compact RELR format: an address, followed
+    // by bitmaps of which of the following words are also relocated.
+    fn display_relr(&self, section: SectionHeader<'a>) -> Result<(), String> {
+        let word_size = if self.is_64 { 8 } else { 4 };
+        let data = self.section_data(section)?;
+        if data.len() % word_size != 0 {
+            return Err(format!("section '{}' is not a well-formed table", self.section_name(section)));
+        }
+        let mut offsets = Vec::new();
+        let mut base = 0;
+        for word in data.chunks(word_size) {
+            let entry = if self.is_64 {
+                u64::from_ne_bytes([word[0], word[1], word[2], word[3], word[4], word[5], word[6], word[7]])
+            } else {
+                u32::from_ne_bytes([word[0], word[1], word[2], word[3]]) as u64
+            };
+            if entry & 1 == 0 {
+                offsets.push(entry);
+                base = entry.wrapping_add(word_size as u64);
+            } else {
+                let bits = word_size as u64 * 8 - 1;
+                for bit in 0..bits {
+                    if entry >> (bit + 1) & 1 != 0 {
+                        offsets.push(base.wrapping_add(bit * word_size as u64));
+                    }
+                }
+                base = base.wrapping_add(bits * word_size as u64);
+            }
+        }
+
+        println!("\nRelocation section '{}' at offset 0x{:x} contains {}:",
+                 self.section_name(section),
+                 section.offset(),
+                 entries(data.len() / word_size));
+        println!("  {} offsets", offsets.len());
+        for offset in offsets {
+            if self.is_64 {
+                println!("{:016x}", offset);
+            } else {
+                println!("{:08x}", offset);
+            }
+        }
+        Ok(())
+    }
+
+    fn symbol_name(&self, symbol: &dyn Entry, strings: &'a [u8]) -> &'a str {
+        let name = c_str(strings, symbol.name());
+        if name.is_empty() && symbol.info() & 0xf == 3 {
+            if let Some(&section) = self.sections.get(symbol.shndx() as usize) {
+                if symbol.shndx() < sections::SHN_LORESERVE {
+                    return self.section_name(section);
+                }
+            }
+        }
+        name
+    }
+
+    fn display_symbols(&self, dynamic_only: bool, wide: bool) -> Result<(), String> {
+        let version_names = self.version_names()?;
+        let version_symbols = self.version_symbols()?;
+        for &section in &self.sections {
+            let is_dynamic = match section.get_type() {
+                Ok(ShType::DynSym) => true,
+                Ok(ShType::SymTab) if !dynamic_only => false,
+                _ => continue,
+            };
+            let symbols = self.symbols(section)?;
+            let strings = self.strings(section.link());
+            println!("\nSymbol table '{}' contains {}:", self.section_name(section), entries(symbols.len()));
+            if self.is_64 {
+                println!("   Num:    Value          Size Type    Bind   Vis      Ndx Name");
+            } else {
+                println!("   Num:    Value  Size Type    Bind   Vis      Ndx Name");
+            }
+            for (i, &symbol) in symbols.iter().enumerate() {
+                let ndx = match symbol.shndx() {
+                    sections::SHN_UNDEF => "UND".to_owned(),
+                    sections::SHN_ABS => "ABS".to_owned(),
+                    sections::SHN_COMMON => "COM".to_owned(),
+                    sections::SHN_XINDEX => "XIX".to_owned(),
+                    sections::SHN_LOPROC..=sections::SHN_HIPROC => "PRC".to_owned(),
+                    sections::SHN_LOOS..=sections::SHN_HIOS => "OS".to_owned(),
+                    ndx if ndx >= sections::SHN_LORESERVE => format!("RSV[0x{:04x}]", ndx),
+                    ndx => ndx.to_string(),
+                };
+                let symbol_name = self.symbol_name(symbol, strings);
+                let suffix = if is_dynamic {
+                    let version = version_symbols.and_then(|v| v.get(i));
+                    version_suffix(symbol, symbol_name, version, &version_names, true)
+                } else {
+                    String::new()
+                };
+                // The version comes out of the 21 columns left for the name.
+                // Like readelf, print nothing at all if that leaves none, and
+                // pad the name if it leaves less than none.
+                let mut name = match 21 - suffix.len() as isize {
+                    _ if wide => symbol_name.to_owned(),
+                    0 => String::new(),
+                    width if width < 0 => {
+                        let width = width.unsigned_abs();
+                        format!("{:<1$}", truncate_name(symbol_name, width, false), width)
+                    }
+                    width => truncate_name(symbol_name, width as usize, false),
+                };
+                name.push_str(&suffix);
+                let value = if self.is_64 {
+                    format!("{:016x}", symbol.value())
+                } else {
+                    format!("{:08x}", symbol.value())
+                };
+                println!("{:6}: {} {:>5} {:<7} {:<6} {:<7} {:>4} {}",
+                         i,
+                         value,
+                         symbol_size(symbol.size()),
+                         names::symbol_type(symbol.info() & 0xf),
+                         names::symbol_binding(symbol.info() >> 4),
+                         names::symbol_visibility(symbol.get_other() as u8),
+                         ndx,
+                         name);
+            }
+        }
+        Ok(())
+    }
+
+    fn display_versions(&self) -> Result<(), String> {
+        let mut found = false;
+        let version_names = self.version_names()?;
+        for &section in &self.sections {
+            let sh_type = section.type_().0;
+            let kind = match sh_type {
+                sections::SHT_GNU_VERSYM => "Version symbols",
+                sections::SHT_GNU_VERNEED => "Version needs",
+                sections::SHT_GNU_VERDEF => "Version definition",
+                _ => continue,
+            };
+            found = true;
+            let data = self.section_data(section)?;
+            let count = if sh_type == sections::SHT_GNU_VERSYM { data.len() / 2 } else { section.info() as usize };
+            println!("\n{} section '{}' contains {}:", kind, self.section_name(section), entries(count));
+            let address = if self.is_64 {
+                format!("{:016x}", section.address())
+            } else {
+                format!("{:08x}", section.address())
+            };
+            let link_name = self.sections.get(section.link() as usize).map_or("<corrupt>", |&s| self.section_name(s));
+            println!(" Addr: 0x{}  Offset: 0x{:08x}  Link: {} ({})",
+                     address,
+                     section.offset(),
+                     section.link(),
+                     link_name);
+
+            let strings = self.strings(section.link());
+            match sh_type {
+                sections::SHT_GNU_VERSYM => {
+                    let versions: Vec<_> = VersionSymbols::new(data).iter().collect();
+                    for (row, chunk) in versions.chunks(4).enumerate() {
+                        let mut line = format!("  {:03x}:", row * 4);
+                        for &version in chunk {
+                            match version.0 {
+                                0 => line.push_str("   0 (*local*)    "),
+                                1 => line.push_str("   1 (*global*)   "),
+                                _ => {
+                                    let mut cell = format!("{:4x}{}",
+                                                           version.index(),
+                                                           if version.is_hidden() { 'h' } else { ' ' });
+                                    if let Some(Some(v)) = version_names.get(version.index() as usize) {
+                                        // readelf pads with a width of `12 - len`, which C's
+                                        // printf treats as left-aligned when negative.
+                                        let width = (12 - v.name.len() as isize).unsigned_abs();
+                                        write!(cell, "({}{:<2$}", v.name, ")", width).unwrap();
+                                    }
+                                    write!(line, "{:<18}", cell).unwrap();
+                                }
+                            }
+                        }
+                        println!("{}", line);
+                    }
+                }
+                sections::SHT_GNU_VERNEED => {
+                    for verneed in VerneedIter::new(data) {
+                        let verneed = verneed?;
+                        println!("  {}: Version: {}  File: {}  Cnt: {}",
+                                 hex06(verneed.offset),
+                                 verneed.version,
+                                 c_str(strings, verneed.file),
+                                 verneed.aux_count);
+                        for vernaux in verneed.versions() {
+                            let vernaux = vernaux?;
+                            println!("  {}:   Name: {}  Flags: {}  Version: {}",
+                                     hex06(vernaux.offset),
+                                     c_str(strings, vernaux.name),
+                                     version_flags(vernaux.flags),
+                                     vernaux.other);
+                        }
+                    }
+                }
+                _ => {
+                    for verdef in VerdefIter::new(data) {
+                        let verdef = verdef?;
+                        let mut names = verdef.names();
+                        let name = match names.next() {
+                            Some(verdaux) => c_str(strings, verdaux?.name),
+                            None => "",
+                        };
+                        println!("  {}: Rev: {}  Flags: {}  Index: {}  Cnt: {}  Name: {}",
+                                 hex06(verdef.offset),
+                                 verdef.version,
+                                 version_flags(verdef.flags),
+                                 verdef.index,
+                                 verdef.aux_count,
+                                 name);
+                        for (i, verdaux) in names.enumerate() {
+                            let verdaux = verdaux?;
+                            println!("  {}: Parent {}: {}", hex06(verdaux.offset), i + 1, c_str(strings, verdaux.name));
+                        }
+                    }
+                }
+            }
+        }
+        if !found {
+            println!("\nNo version information found in this file.");
+        }
+        Ok(())
+    }
+
+    fn display_notes(&self, wide: bool) -> Result<(), String> {
+        let is_core = self.elf.header.pt2.type_().0 == 4;
+        let note_sections: Vec<_> = self.sections_of_type(7).collect();
+        if !note_sections.is_empty() {
+            for section in note_sections {
+                println!("\nDisplaying notes found in: {}", self.section_name(section));
+                self.display_note_data(self.section_data(section)?, section.align(), is_core, wide)?;
+            }
+        } else {
+            for &segment in &self.segments {
+                if segment.get_type() == Ok(program::Type::Note) {
+                    println!("\nDisplaying notes found at file offset 0x{:08x} with length 0x{:08x}:",
+                             segment.offset(),
+                             segment.file_size());
+                    self.display_note_data(self.segment_data(segment)?, segment.align(), is_core, wide)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Like readelf, the decoded description follows the type on the same
+    // line in wide mode, and on the next one otherwise.
+    fn display_note_data(&self, data: &'a [u8], align: u64, is_core: bool, wide: bool) -> Result<(), String> {
+        println!("  Owner                Data size \tDescription");
+        let separator = if wide { "\t" } else { "\n" };
+        for note in NoteIter::new(data, align) {
+            let note = note?;
+            print!("  {:<20} 0x{:08x}\t{}{}",
+                   note.name,
+                   note.desc.len(),
+                   names::note_type(note.name, is_core, note.type_),
+                   separator);
+            let word = |i: usize| {
+                note.desc.get(i * 4..i * 4 + 4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            };
+            match (note.name, note.type_) {
+                ("GNU", 1) if note.desc.len() >= 16 => {
+                    let os = match word(0).unwrap() {
+                        0 => "Linux".to_owned(),
+                        1 => "Hurd".to_owned(),
+                        2 => "Solaris".to_owned(),
+                        3 => "FreeBSD".to_owned(),
+                        4 => "NetBSD".to_owned(),
+                        5 => "Syllable".to_owned(),
+                        other => format!("Unknown OS: {}", other),
+                    };
+                    println!("    OS: {}, ABI: {}.{}.{}", os, word(1).unwrap(), word(2).unwrap(), word(3).unwrap());
+                }
+                ("GNU", 3) => println!("    Build ID: {}", hex_string(note.desc)),
+                ("GNU", 4) => println!("    Version: {}", c_str(note.desc, 0)),
+                ("GNU", 5) => println!("      Properties: {}", self.gnu_properties(note.desc)),
+                ("stapsdt", 3) => println!("{}", self.stapsdt_note(note.desc)),
+                _ if note.desc.is_empty() => {
+                    if wide {
+                        println!();
+                    }
+                }
+                _ => println!("   description data: {}", note.desc.iter().map(|b| format!("{:02x} ", b)).collect::<String>()),
+            }
+        }
+        Ok(())
+    }
+
+    // The properties of an NT_GNU_PROPERTY_TYPE_0 note, as a comma-separated
+    // list.
+    fn gnu_properties(&self, desc: &[u8]) -> String {
+        let word_size = if self.is_64 { 8 } else { 4 };
+        if desc.len() < 8 || desc.len() % word_size != 0 {
+            return format!("<corrupt GNU_PROPERTY_TYPE, size = {:#x}>", desc.len());
+        }
+        let is_x86 = matches!(self.machine, Machine::X86 | Machine::X86_64);
+        let mut properties = Vec::new();
+        for property in gnu_property::properties(desc, self.is_64) {
+            let property = match property {
+                Ok(property) => property,
+                Err(_) => {
+                    properties.push("<corrupt property>".to_owned());
+                    break;
+                }
+            };
+            let type_ = property.type_;
+            let bitmask = |label: &str, names: &[(u32, &str)], none: &str| match property.u32() {
+                Some(value) => format!("{}: {}", label, names::bitmask(value, names, none)),
+                None => format!("{}: <corrupt length: {:#x}> ", label, property.data.len()),
+            };
+            let description = match type_ {
+                gnu_property::GNU_PROPERTY_X86_ISA_1_USED if is_x86 => {
+                    bitmask("x86 ISA used", names::X86_ISA_LEVELS, "")
+                }
+                gnu_property::GNU_PROPERTY_X86_ISA_1_NEEDED if is_x86 => {
+                    bitmask("x86 ISA needed", names::X86_ISA_LEVELS, "")
+                }
+                gnu_property::GNU_PROPERTY_X86_FEATURE_1_AND if is_x86 => {
+                    bitmask("x86 feature", names::X86_FEATURES_1, "<None>")
+                }
+                gnu_property::GNU_PROPERTY_X86_FEATURE_2_USED if is_x86 => {
+                    bitmask("x86 feature used", names::X86_FEATURES_2, "<None>")
+                }
+                gnu_property::GNU_PROPERTY_X86_FEATURE_2_NEEDED if is_x86 => {
+                    bitmask("x86 feature needed", names::X86_FEATURES_2, "<None>")
+                }
+                gnu_property::GNU_PROPERTY_AARCH64_FEATURE_1_AND if self.machine == Machine::AArch64 => {
+                    bitmask("AArch64 feature", names::AARCH64_FEATURES_1, "")
+                }
+                gnu_property::GNU_PROPERTY_STACK_SIZE => match property.data.len() {
+                    len if len == word_size => {
+                        let value = property.data.iter().rev().fold(0, |acc, &b| acc << 8 | b as u64);
+                        format!("stack size: {:#x}", value)
+                    }
+                    len => format!("stack size: <corrupt length: {:#x}> ", len),
+                },
+                gnu_property::GNU_PROPERTY_NO_COPY_ON_PROTECTED => match property.data.len() {
+                    0 => "no copy on protected ".to_owned(),
+                    len => format!("no copy on protected <corrupt length: {:#x}> ", len),
+                },
+                gnu_property::GNU_PROPERTY_1_NEEDED => bitmask("1_needed", names::NEEDED_1, "<None>"),
+                gnu_property::GNU_PROPERTY_UINT32_AND_LO..=gnu_property::GNU_PROPERTY_UINT32_OR_HI => {
+                    let kind = if type_ <= gnu_property::GNU_PROPERTY_UINT32_AND_HI { "AND" } else { "OR" };
+                    match property.u32() {
+                        Some(value) => format!("UINT32_{} ({:#x}): {:#x}", kind, type_, value),
+                        None => format!("UINT32_{} ({:#x}): <corrupt length: {:#x}> ",
+                                        kind,
+                                        type_,
+                                        property.data.len()),
+                    }
+                }
+                _ => {
+                    let kind = if type_ < gnu_property::GNU_PROPERTY_LOPROC {
+                        "unknown"
+                    } else if type_ < gnu_property::GNU_PROPERTY_LOUSER {
+                        "processor-specific"
+                    } else {
+                        "application-specific"
+                    };
+                    format!("<{} type {:#x} data: {}>",
+                            kind,
+                            type_,
+                            property.data.iter().map(|b| format!("{:02x} ", b)).collect::<String>())
+                }
+            };
+            properties.push(description);
+        }
+        properties.join(", ")
+    }
+
+    // A SystemTap probe: its location, base and semaphore addresses, then the
+    // provider, name and argument strings.
+    fn stapsdt_note(&self, desc: &[u8]) -> String {
+        let word_size = if self.is_64 { 8 } else { 4 };
+        if desc.len() < word_size * 3 {
+            return "    <corrupt>".to_owned();
+        }
+        let words: Vec<u64> = desc[..word_size * 3].chunks(word_size).map(|w| {
+            w.iter().rev().fold(0, |acc, &b| acc << 8 | b as u64)
+        }).collect();
+        let mut strings = desc[word_size * 3..].split(|&b| b == 0).map(|s| str::from_utf8(s).unwrap_or("<corrupt>"));
+        let mut next = || strings.next().unwrap_or("<corrupt>");
+        let (provider, name, arguments) = (next(), next(), next());
+        let address = |a: u64| if self.is_64 { format!("0x{:016x}", a) } else { format!("0x{:08x}", a) };
+        format!("    Provider: {}\n    Name: {}\n    Location: {}, Base: {}, Semaphore: {}\n    Arguments: {}",
+                provider,
+                name,
+                address(words[0]),
+                address(words[1]),
+                address(words[2]),
+                arguments)
+    }
+
+    fn dump_section(&self, name: &str, kind: &str) -> Result<Option<(SectionHeader<'a>, &'a [u8])>, String> {
+        let (index, section) = match self.find_section(name) {
+            Some(found) => found,
+            None => {
+                eprintln!("xmas_elf: Warning: Section '{}' was not dumped because it does not exist", name);
+                return Ok(None);
+            }
+        };
+        let data = self.section_data(section)?;
+        if data.is_empty() {
+            println!("Section '{}' has no data to dump.", self.section_name(section));
+            return Ok(None);
+        }
+        println!("\n{} dump of section '{}':", kind, self.section_name(section));
+        let relocated = self.sections.iter().any(|s| {
+            (s.get_type() == Ok(ShType::Rela) || s.get_type() == Ok(ShType::Rel)) && s.info() as usize == index
+        });
+        if relocated {
+            println!(" NOTE: This section has relocations against it, but these have NOT been applied to this dump.");
+        }
+        Ok(Some((section, data)))
+    }
+
+    fn display_hex_dump(&self, name: &str) -> Result<(), String> {
+        let (section, data) = match self.dump_section(name, "Hex")? {
+            Some(dump) => dump,
+            None => return Ok(()),
+        };
+        for (i, chunk) in data.chunks(16).enumerate() {
+            let mut line = format!("  0x{:08x} ", section.address() + i as u64 * 16);
+            for j in 0..16 {
+                match chunk.get(j) {
+                    Some(b) => write!(line, "{:02x}", b).unwrap(),
+                    None => line.push_str("  "),
+                }
+                if j % 4 == 3 {
+                    line.push(' ');
+                }
+            }
+            line.extend(chunk.iter().map(|&b| if (0x20..0x7f).contains(&b) { b as char } else { '.' }));
+            println!("{}", line);
+        }
+        println!();
+        Ok(())
+    }
+
+    fn display_string_dump(&self, name: &str) -> Result<(), String> {
+        let data = match self.dump_section(name, "String")? {
+            Some((_, data)) => data,
+            None => return Ok(()),
+        };
+        let mut found = false;
+        let mut offset = 0;
+        while offset < data.len() {
+            if data[offset] == 0 {
+                offset += 1;
+                continue;
+            }
+            let len = data[offset..].iter().position(|&b| b == 0).unwrap_or(data.len() - offset);
+            let mut string = String::new();
+            for c in String::from_utf8_lossy(&data[offset..offset + len]).chars() {
+                match c {
+                    '\0'..='\x1f' => {
+                        string.push('^');
+                        string.push((c as u8 + 0x40) as char);
+                    }
+                    '\x7f' => string.push_str("^?"),
+                    c => string.push(c),
+                }
+            }
+            println!("  [{:6x}]  {}", offset, string);
+            found = true;
+            offset += len;
+        }
+        if !found {
+            println!("  No strings found in this section.");
+        }
+        println!();
+        Ok(())
+    }
+}
+
+// Checks that a table of headers lies within the file, has the entry size the
+// library expects and is aligned.
+fn check_table(input: &[u8],
+               offset: u64,
+               count: u16,
+               entry_size: u16,
+               expected_entry_size: u16,
+               is_64: bool,
+               what: &str)
+               -> Result<(), String> {
+    if entry_size != expected_entry_size {
+        return Err(format!("the {} have an unexpected entry size of {}", what, entry_size));
+    }
+    if offset.checked_add(count as u64 * entry_size as u64).is_none_or(|end| end > input.len() as u64) {
+        return Err(format!("the {} extend past the end of the file", what));
+    }
+    let align = if is_64 { 8 } else { 4 };
+    if (input.as_ptr() as usize).wrapping_add(offset as usize) % align != 0 {
+        return Err(format!("the {} are misaligned", what));
+    }
+    Ok(())
+}
+
+fn raw_segment_type(segment: ProgramHeader) -> u32 {
+    match segment {
+        ProgramHeader::Ph32(ph) => ph.type_.0,
+        ProgramHeader::Ph64(ph) => ph.type_.0,
+    }
+}
+
+// Whether readelf would list `section` as part of `segment`, following
+// binutils' ELF_SECTION_IN_SEGMENT_STRICT and ignoring .tbss outside PT_TLS.
+fn section_in_segment(section: SectionHeader, segment: ProgramHeader) -> bool {
+    let p_type = raw_segment_type(segment);
+    let is_tls = section.flags() & sections::SHF_TLS != 0;
+    let is_alloc = section.flags() & sections::SHF_ALLOC != 0;
+    let is_nobits = section.type_().0 == SHT_NOBITS;
+    if is_tls && is_nobits && p_type != PT_TLS {
+        return false;
+    }
+    let size = section.size();
+
+    let tls_ok = if is_tls {
+        p_type == PT_TLS || p_type == PT_GNU_RELRO || p_type == 1
+    } else {
+        p_type != PT_TLS && p_type != PT_PHDR
+    };
+    let alloc_ok = is_alloc || !(p_type == 1 || p_type == 2 || p_type == PT_GNU_EH_FRAME ||
+                                 p_type == PT_GNU_STACK || p_type == PT_GNU_RELRO ||
+                                 p_type == PT_GNU_SFRAME ||
+                                 (PT_GNU_MBIND_LO..=PT_GNU_MBIND_HI).contains(&p_type));
+    // Whether `start..start + size` lies within `base..base + len`. A range
+    // whose arithmetic overflows is not in the segment.
+    let within = |start: u64, base: u64, len: u64| {
+        start >= base && start - base <= len.wrapping_sub(1) &&
+        (start - base).checked_add(size).is_some_and(|end| end <= len)
+    };
+    let offset_ok = is_nobits || within(section.offset(), segment.offset(), segment.file_size());
+    let address_ok = !is_alloc || within(section.address(), segment.virtual_addr(), segment.mem_size());
+    // An empty section at the very start or end of a dynamic segment is not
+    // part of it.
+    let strictly_inside = |start: u64, base: u64, len: u64| {
+        start > base && base.checked_add(len).is_some_and(|end| start < end)
+    };
+    let dynamic_ok = p_type != 2 || size != 0 || segment.mem_size() == 0 ||
+                     (strictly_inside(section.offset(), segment.offset(), segment.file_size()) &&
+                      (!is_alloc || strictly_inside(section.address(), segment.virtual_addr(), segment.mem_size())));
+    tls_ok && alloc_ok && offset_ok && address_ok && dynamic_ok
+}
+
+fn section_flags(flags: u64, has_retain_flag: bool) -> String {
+    let mut result = String::new();
+    for bit in 0..64 {
+        let flag = 1 << bit;
+        if flags & flag == 0 {
+            continue;
+        }
+        result.push(match flag {
+            sections::SHF_WRITE => 'W',
+            sections::SHF_ALLOC => 'A',
+            sections::SHF_EXECINSTR => 'X',
+            sections::SHF_MERGE => 'M',
+            sections::SHF_STRINGS => 'S',
+            sections::SHF_INFO_LINK => 'I',
+            sections::SHF_LINK_ORDER => 'L',
+            sections::SHF_OS_NONCONFORMING => 'O',
+            sections::SHF_GROUP => 'G',
+            sections::SHF_TLS => 'T',
+            sections::SHF_COMPRESSED => 'C',
+            0x80000000 => 'E',
+            SHF_GNU_RETAIN if has_retain_flag => 'R',
+            f if f & sections::SHF_MASKOS != 0 => 'o',
+            f if f & sections::SHF_MASKPROC != 0 => 'p',
+            _ => 'x',
+        });
+    }
+    result
+}
+
+fn group_flags(flags: u32) -> String {
+    let flags = flags as u64;
+    if flags == 0 {
+        return String::new();
+    }
+    if flags == sections::GRP_COMDAT {
+        return "COMDAT ".to_owned();
+    }
+    format!("[0x{:x}: {}{}{}]",
+            flags,
+            if flags & sections::GRP_MASKOS != 0 { "<OS specific>" } else { "" },
+            if flags & sections::GRP_MASKPROC != 0 { "<PROC specific>" } else { "" },
+            if flags & !(sections::GRP_COMDAT | sections::GRP_MASKOS | sections::GRP_MASKPROC) != 0 {
+                "<unknown>"
+            } else {
+                ""
+            })
+}
+
+// The version suffix readelf adds to a dynamic symbol's name: `@@VERSION` for
+// a default version this file defines, `@VERSION` for other versions, and, in
+// symbol tables, the version index for versions needed from other files.
+fn version_suffix(symbol: &dyn Entry,
+                  name: &str,
+                  version: Option<VersionIndex>,
+                  names: &[Option<VersionName>],
+                  with_index: bool)
+                  -> String {
+    let version = match version {
+        Some(version) if !version.is_local() && !version.is_global() => version,
+        _ => return String::new(),
+    };
+    match names.get(version.index() as usize) {
+        Some(&Some(name)) if name.needed || symbol.shndx() == sections::SHN_UNDEF => {
+            if with_index {
+                format!("@{} ({})", name.name, version.index())
+            } else {
+                format!("@{}", name.name)
+            }
+        }
+        // The symbol which names a version definition itself.
+        Some(&Some(version_name)) if version_name.name == name => String::new(),
+        Some(&Some(name)) if version.is_hidden() => format!("@{}", name.name),
+        Some(&Some(name)) => format!("@@{}", name.name),
+        _ => String::new(),
+    }
+}
+
+// Like readelf's `print_symbol`: outside wide mode, a name longer than
+// `width` is cut short, ending in `[...]`.
+fn truncate_name(name: &str, width: usize, wide: bool) -> String {
+    if wide || name.len() <= width {
+        return name.to_owned();
+    }
+    let keep = width.saturating_sub(5);
+    let end = name.char_indices().nth(keep).map_or(name.len(), |(i, _)| i);
+    format!("{}[...]", &name[..end])
+}
+
+// readelf switches to hex for sizes which would not fit the column.
+fn symbol_size(size: u64) -> String {
+    if size < 100000 {
+        size.to_string()
+    } else {
+        format!("0x{:x}", size)
+    }
+}
+
+fn version_flags(flags: u16) -> String {
+    if flags == 0 {
+        return "none".to_owned();
+    }
+    let mut names = Vec::new();
+    if flags & symbol_version::VER_FLG_BASE != 0 {
+        names.push("BASE");
+    }
+    if flags & symbol_version::VER_FLG_WEAK != 0 {
+        names.push("WEAK");
+    }
+    if flags & symbol_version::VER_FLG_INFO != 0 {
+        names.push("INFO");
+    }
+    if flags & !(symbol_version::VER_FLG_BASE | symbol_version::VER_FLG_WEAK | symbol_version::VER_FLG_INFO) != 0 {
+        names.push("<unknown>");
+    }
+    names.join(" | ")
+}
+
+fn flag_names<I: Iterator<Item = (u64, &'static str)>>(value: u64, names: I) -> String {
+    let mut known = 0;
+    let mut result: Vec<String> = Vec::new();
+    for (flag, name) in names {
+        known |= flag;
+        if value & flag != 0 {
+            result.push(name.to_owned());
+        }
+    }
+    if value & !known != 0 {
+        result.push(format!("0x{:x}", value & !known));
+    }
+    result.join(" ")
+}
+
+// Like C's `%#06x`.
+fn hex06(value: usize) -> String {
+    if value == 0 {
+        "000000".to_owned()
+    } else {
+        format!("{:#06x}", value)
+    }
+}
+
+fn hex_string(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn entries(count: usize) -> String {
+    format!("{} {}", count, if count == 1 { "entry" } else { "entries" })
+}
+
+// A null-terminated string at `offset` in `data`.
+fn c_str(data: &[u8], offset: u32) -> &str {
+    match data.get(offset as usize..) {
+        Some(rest) => {
+            let len = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+            str::from_utf8(&rest[..len]).unwrap_or("<corrupt>")
+        }
+        None => "<corrupt>",
+    }
+}
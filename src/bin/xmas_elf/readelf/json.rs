@@ -0,0 +1,440 @@
+//! The `--json` output: one JSON document per file, on a single line.
+//!
+//! Each item carries the decoded names alongside a `raw` snapshot of the
+//! header or table entry it came from. Fields are only ever added, and
+//! `schema_version` is bumped if an existing field changes meaning.
+
+use serde_json;
+
+use xmas_elf::header::HeaderSnapshot;
+use xmas_elf::program::{self, SegmentSnapshot};
+use xmas_elf::sections::{NoteIter, SectionSnapshot, ShType};
+use xmas_elf::symbol_table::SymbolSnapshot;
+
+use super::{hex_string, section_flags, section_in_segment, raw_segment_type, File, DT_NEEDED, DT_RPATH,
+            DT_RUNPATH, DT_SONAME};
+use names;
+
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    file: &'a str,
+    header: Header,
+    sections: Vec<Section<'a>>,
+    segments: Vec<Segment<'a>>,
+    symbols: Vec<Symbol<'a>>,
+    dynamic: Vec<Dynamic<'a>>,
+    needed: Vec<&'a str>,
+    soname: Option<&'a str>,
+    build_id: Option<String>,
+    notes: Vec<Note<'a>>,
+    relocations: Vec<Relocation<'a>>,
+}
+
+#[derive(Serialize)]
+struct Header {
+    #[serde(rename = "type")]
+    type_: String,
+    machine: String,
+    os_abi: String,
+    raw: HeaderSnapshot,
+}
+
+#[derive(Serialize)]
+struct Section<'a> {
+    index: usize,
+    name: &'a str,
+    #[serde(rename = "type")]
+    type_: String,
+    flags: String,
+    raw: SectionSnapshot,
+}
+
+#[derive(Serialize)]
+struct Segment<'a> {
+    index: usize,
+    #[serde(rename = "type")]
+    type_: String,
+    flags: String,
+    sections: Vec<&'a str>,
+    raw: SegmentSnapshot,
+}
+
+#[derive(Serialize)]
+struct Symbol<'a> {
+    table: &'a str,
+    index: usize,
+    name: &'a str,
+    #[serde(rename = "type")]
+    type_: String,
+    binding: String,
+    visibility: &'static str,
+    version: Option<Version<'a>>,
+    raw: SymbolSnapshot,
+}
+
+#[derive(Serialize)]
+struct Version<'a> {
+    name: &'a str,
+    hidden: bool,
+    /// Whether the version is required from another object.
+    needed: bool,
+}
+
+#[derive(Serialize)]
+struct Dynamic<'a> {
+    tag: u64,
+    name: String,
+    value: u64,
+    /// The string `value` refers to, for tags whose value is a string.
+    string: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Note<'a> {
+    /// The section the note is in, if the file has section headers.
+    section: Option<&'a str>,
+    owner: &'a str,
+    #[serde(rename = "type")]
+    type_: u32,
+    type_name: String,
+    desc: String,
+}
+
+#[derive(Serialize)]
+struct Relocation<'a> {
+    section: &'a str,
+    offset: u64,
+    info: u64,
+    #[serde(rename = "type")]
+    type_: u32,
+    type_name: Option<&'static str>,
+    symbol: u32,
+    symbol_name: Option<&'a str>,
+    addend: Option<i64>,
+}
+
+/// Prints the JSON document for `file`.
+pub fn display(file: &File, path: &str) -> Result<(), String> {
+    let document = document(file, path)?;
+    println!("{}", serde_json::to_string(&document).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+fn document<'a>(file: &File<'a>, path: &'a str) -> Result<Document<'a>, String> {
+    let pt2 = file.elf.header.pt2;
+    let header = Header {
+        type_: file.file_type(),
+        machine: names::machine(pt2.machine().0),
+        os_abi: names::os_abi(file.elf.header.pt1.os_abi.0),
+        raw: file.elf.header.snapshot(),
+    };
+
+    let sections = file.sections.iter().enumerate().map(|(index, &section)| Section {
+        index,
+        name: if index == 0 { "" } else { file.section_name(section) },
+        type_: names::section_type(file.machine, section.type_().0),
+        flags: section_flags(section.flags(), file.has_retain_flag()),
+        raw: section.snapshot(),
+    }).collect();
+
+    let segments = file.segments.iter().enumerate().map(|(index, &segment)| {
+        let flags = segment.flags();
+        Segment {
+            index,
+            type_: names::segment_type(file.machine, raw_segment_type(segment)),
+            flags: format!("{}{}{}",
+                           if flags.is_read() { "R" } else { "" },
+                           if flags.is_write() { "W" } else { "" },
+                           if flags.is_execute() { "E" } else { "" }),
+            sections: file.sections.iter().skip(1)
+                .filter(|&&section| section_in_segment(section, segment))
+                .map(|&section| file.section_name(section))
+                .collect(),
+            raw: segment.snapshot(),
+        }
+    }).collect();
+
+    let version_names = file.version_names()?;
+    let version_symbols = file.version_symbols()?;
+    let mut symbols = Vec::new();
+    for &section in &file.sections {
+        let is_dynamic = match section.get_type() {
+            Ok(ShType::DynSym) => true,
+            Ok(ShType::SymTab) => false,
+            _ => continue,
+        };
+        let strings = file.strings(section.link());
+        for (index, &symbol) in file.symbols(section)?.iter().enumerate() {
+            let version = version_symbols.filter(|_| is_dynamic)
+                .and_then(|v| v.get(index))
+                .filter(|v| !v.is_local() && !v.is_global())
+                .and_then(|v| {
+                    let name = version_names.get(v.index() as usize).cloned()??;
+                    Some(Version { name: name.name, hidden: v.is_hidden(), needed: name.needed })
+                });
+            symbols.push(Symbol {
+                table: file.section_name(section),
+                index,
+                name: file.symbol_name(symbol, strings),
+                type_: names::symbol_type(symbol.info() & 0xf),
+                binding: names::symbol_binding(symbol.info() >> 4),
+                visibility: names::symbol_visibility(symbol.get_other() as u8),
+                version,
+                raw: symbol.snapshot(),
+            });
+        }
+    }
+
+    let mut dynamic = Vec::new();
+    let mut needed = Vec::new();
+    let mut soname = None;
+    if let Some(table) = file.dynamic_table()? {
+        for &(tag, value) in &table.entries {
+            let string = match tag {
+                DT_NEEDED | DT_SONAME | DT_RPATH | DT_RUNPATH => Some(super::c_str(table.strings, value as u32)),
+                _ => None,
+            };
+            match (tag, string) {
+                (DT_NEEDED, Some(name)) => needed.push(name),
+                (DT_SONAME, Some(name)) => soname = Some(name),
+                _ => {}
+            }
+            dynamic.push(Dynamic { tag, name: names::dynamic_tag(tag), value, string });
+        }
+    }
+
+    let is_core = pt2.type_().0 == 4;
+    let mut notes = Vec::new();
+    let mut note_data = Vec::new();
+    for section in file.sections_of_type(7) {
+        note_data.push((Some(file.section_name(section)), file.section_data(section)?, section.align()));
+    }
+    if note_data.is_empty() {
+        for &segment in &file.segments {
+            if segment.get_type() == Ok(program::Type::Note) {
+                note_data.push((None, file.segment_data(segment)?, segment.align()));
+            }
+        }
+    }
+    for (section, data, align) in note_data {
+        for note in NoteIter::new(data, align) {
+            let note = note?;
+            notes.push(Note {
+                section,
+                owner: note.name,
+                type_: note.type_,
+                type_name: names::note_type(note.name, is_core, note.type_),
+                desc: hex_string(note.desc),
+            });
+        }
+    }
+    let build_id = notes.iter().find(|n| n.owner == "GNU" && n.type_ == 3).map(|n| n.desc.clone());
+
+    let mut relocations = Vec::new();
+    for &section in &file.sections {
+        match section.get_type() {
+            Ok(ShType::Rela) | Ok(ShType::Rel) => {}
+            _ => continue,
+        }
+        let (symbols, strings) = match file.sections.get(section.link() as usize) {
+            Some(&table) if section.link() != 0 => (file.symbols(table)?, file.strings(table.link())),
+            _ => (Vec::new(), &[][..]),
+        };
+        for relocation in file.relocations(section)? {
+            let symbol_name = match relocation.symbol {
+                0 => None,
+                index => symbols.get(index as usize).map(|&symbol| file.symbol_name(symbol, strings)),
+            };
+            relocations.push(Relocation {
+                section: file.section_name(section),
+                offset: relocation.offset,
+                info: relocation.info,
+                type_: relocation.type_,
+                type_name: names::relocation_type(file.machine, relocation.type_),
+                symbol: relocation.symbol,
+                symbol_name,
+                addend: relocation.addend,
+            });
+        }
+    }
+
+    Ok(Document {
+        schema_version: SCHEMA_VERSION,
+        file: path,
+        header,
+        sections,
+        segments,
+        symbols,
+        dynamic,
+        needed,
+        soname,
+        build_id,
+        notes,
+        relocations,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A little-endian ELF64 executable with a four-byte `.text` section in a
+    // loadable segment, and a symbol `main` for it.
+    fn mk_executable() -> Vec<u8> {
+        let shstrtab = b"\0.text\0.shstrtab\0.strtab\0.symtab\0";
+        let strtab = b"\0main\0";
+        let mut symtab = vec![0; 24];
+        symtab.extend_from_slice(&1u32.to_le_bytes());
+        symtab.extend_from_slice(&[0x12, 0]);
+        symtab.extend_from_slice(&1u16.to_le_bytes());
+        symtab.extend_from_slice(&0x40_0080u64.to_le_bytes());
+        symtab.extend_from_slice(&4u64.to_le_bytes());
+
+        // (name, type, flags, address, link, info, entry size) for each section
+        // after the null one.
+        let headers: [(u32, u32, u64, u64, u32, u32, u64); 4] = [(1, 1, 6, 0x40_0080, 0, 0, 0),
+                                                                 (7, 3, 0, 0, 0, 0, 0),
+                                                                 (17, 3, 0, 0, 0, 0, 0),
+                                                                 (25, 2, 0, 0, 3, 1, 24)];
+        let contents: [&[u8]; 4] = [&[0x31, 0xc0, 0xc3, 0x90], shstrtab, strtab, &symtab];
+
+        let mut file = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+        file.resize(128, 0);
+        let mut offsets = Vec::new();
+        for content in &contents {
+            offsets.push(file.len() as u64);
+            file.extend_from_slice(content);
+            file.resize(file.len().next_multiple_of(8), 0);
+        }
+        let sh_offset = file.len() as u64;
+        file[16..18].copy_from_slice(&2u16.to_le_bytes());
+        file[18..20].copy_from_slice(&62u16.to_le_bytes());
+        file[20..24].copy_from_slice(&1u32.to_le_bytes());
+        file[24..32].copy_from_slice(&0x40_0080u64.to_le_bytes());
+        file[32..40].copy_from_slice(&64u64.to_le_bytes());
+        file[40..48].copy_from_slice(&sh_offset.to_le_bytes());
+        file[52..54].copy_from_slice(&64u16.to_le_bytes());
+        file[54..56].copy_from_slice(&56u16.to_le_bytes());
+        file[56..58].copy_from_slice(&1u16.to_le_bytes());
+        file[58..60].copy_from_slice(&64u16.to_le_bytes());
+        file[60..62].copy_from_slice(&(headers.len() as u16 + 1).to_le_bytes());
+        file[62..64].copy_from_slice(&2u16.to_le_bytes());
+
+        // PT_LOAD, R+X, covering the file up to the end of `.text`.
+        file[64..72].copy_from_slice(&[1, 0, 0, 0, 5, 0, 0, 0]);
+        let words: [u64; 6] = [0, 0x40_0000, 0x40_0000, 132, 132, 0x1000];
+        for (i, word) in words.iter().enumerate() {
+            file[72 + i * 8..80 + i * 8].copy_from_slice(&word.to_le_bytes());
+        }
+
+        file.extend_from_slice(&[0; 64]);
+        for (i, &(name, sh_type, flags, address, link, info, entry_size)) in headers.iter().enumerate() {
+            file.extend_from_slice(&name.to_le_bytes());
+            file.extend_from_slice(&sh_type.to_le_bytes());
+            let words: [u64; 4] = [flags, address, offsets[i], contents[i].len() as u64];
+            for word in words.iter() {
+                file.extend_from_slice(&word.to_le_bytes());
+            }
+            file.extend_from_slice(&link.to_le_bytes());
+            file.extend_from_slice(&info.to_le_bytes());
+            file.extend_from_slice(&1u64.to_le_bytes());
+            file.extend_from_slice(&entry_size.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn snapshot() {
+        let input = mk_executable();
+        let file = File::new(&input).unwrap();
+        let document = document(&file, "a.out").unwrap();
+        let expected = serde_json::json!({
+            "schema_version": 1, "file": "a.out",
+            "header": {
+                "type": "EXEC (Executable file)", "machine": "Advanced Micro Devices X86-64",
+                "os_abi": "UNIX - System V",
+                "raw": {
+                    "class": 2, "data": 1, "version": 1, "os_abi": 0, "abi_version": 0, "type": 2,
+                    "machine": 62, "file_version": 1, "entry_point": 4194432, "ph_offset": 64,
+                    "sh_offset": 232, "flags": 0, "header_size": 64, "ph_entry_size": 56, "ph_count": 1,
+                    "sh_entry_size": 64, "sh_count": 5, "sh_str_index": 2
+                }
+            },
+            "sections": [
+                {
+                    "index": 0, "name": "", "type": "NULL", "flags": "",
+                    "raw": {
+                        "name": 0, "type": 0, "flags": 0, "address": 0, "offset": 0, "size": 0,
+                        "link": 0, "info": 0, "align": 0, "entry_size": 0
+                    }
+                },
+                {
+                    "index": 1, "name": ".text", "type": "PROGBITS", "flags": "AX",
+                    "raw": {
+                        "name": 1, "type": 1, "flags": 6, "address": 4194432, "offset": 128, "size": 4,
+                        "link": 0, "info": 0, "align": 1, "entry_size": 0
+                    }
+                },
+                {
+                    "index": 2, "name": ".shstrtab", "type": "STRTAB", "flags": "",
+                    "raw": {
+                        "name": 7, "type": 3, "flags": 0, "address": 0, "offset": 136, "size": 33,
+                        "link": 0, "info": 0, "align": 1, "entry_size": 0
+                    }
+                },
+                {
+                    "index": 3, "name": ".strtab", "type": "STRTAB", "flags": "",
+                    "raw": {
+                        "name": 17, "type": 3, "flags": 0, "address": 0, "offset": 176, "size": 6,
+                        "link": 0, "info": 0, "align": 1, "entry_size": 0
+                    }
+                },
+                {
+                    "index": 4, "name": ".symtab", "type": "SYMTAB", "flags": "",
+                    "raw": {
+                        "name": 25, "type": 2, "flags": 0, "address": 0, "offset": 184, "size": 48,
+                        "link": 3, "info": 1, "align": 1, "entry_size": 24
+                    }
+                }
+            ],
+            "segments": [
+                {
+                    "index": 0, "type": "LOAD", "flags": "RE", "sections": [".text"],
+                    "raw": {
+                        "type": 1, "flags": 5, "offset": 0, "virtual_addr": 4194304,
+                        "physical_addr": 4194304, "file_size": 132, "mem_size": 132, "align": 4096
+                    }
+                }
+            ],
+            "symbols": [
+                {
+                    "table": ".symtab", "index": 0, "name": "", "type": "NOTYPE", "binding": "LOCAL",
+                    "visibility": "DEFAULT", "version": null,
+                    "raw": {"name": 0, "info": 0, "other": 0, "shndx": 0, "value": 0, "size": 0}
+                },
+                {
+                    "table": ".symtab", "index": 1, "name": "main", "type": "FUNC", "binding": "GLOBAL",
+                    "visibility": "DEFAULT", "version": null,
+                    "raw": {"name": 1, "info": 18, "other": 0, "shndx": 1, "value": 4194432, "size": 4}
+                }
+            ],
+            "dynamic": [], "needed": [], "soname": null, "build_id": null, "notes": [],
+            "relocations": []
+        });
+        assert_eq!(serde_json::to_value(&document).unwrap(), expected);
+
+        // The raw snapshots read back as they were written.
+        let raw = |value: &serde_json::Value| value["raw"].clone();
+        let header: HeaderSnapshot = serde_json::from_value(raw(&expected["header"])).unwrap();
+        assert_eq!(header, file.elf.header.snapshot());
+        let section: SectionSnapshot = serde_json::from_value(raw(&expected["sections"][4])).unwrap();
+        assert_eq!(section, file.sections[4].snapshot());
+        let segment: SegmentSnapshot = serde_json::from_value(raw(&expected["segments"][0])).unwrap();
+        assert_eq!(segment, file.segments[0].snapshot());
+        let symbol: SymbolSnapshot = serde_json::from_value(raw(&expected["symbols"][1])).unwrap();
+        assert_eq!(symbol, file.symbols(file.sections[4]).unwrap()[1].snapshot());
+    }
+}
@@ -0,0 +1,279 @@
+//! Copies of a file with sections compressed (`SHF_COMPRESSED`) or
+//! decompressed, like `objcopy --compress-debug-sections` and
+//! `--decompress-debug-sections`. zlib needs the `compression` or `inflate`
+//! feature, and zstd needs `compression`.
+//!
+//! The headers and everything in segments stay where they are; the sections
+//! after the last segment are laid out again in their original order, followed
+//! by the section header table. Like the rest of the crate, this needs files to
+//! have the host's byte order.
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+
+#[cfg(all(feature = "compression", not(feature = "inflate")))]
+use flate2::Compression;
+#[cfg(all(feature = "compression", not(feature = "inflate")))]
+use flate2::write::ZlibEncoder;
+#[cfg(feature = "inflate")]
+use miniz_oxide;
+#[cfg(all(feature = "compression", not(feature = "inflate")))]
+use std::io::Write;
+
+use ElfFile;
+use header::{Class, Data};
+use sections::{CompressionType, SectionHeader, ShType, SHF_ALLOC, SHF_COMPRESSED};
+
+/// Copies `elf_file`, compressing its `.debug_*` sections with
+/// `compression_type`.
+pub fn compress_debug_sections(elf_file: &ElfFile,
+                               compression_type: CompressionType)
+                               -> Result<Vec<u8>, &'static str> {
+    compress_sections(elf_file, compression_type, |elf_file, section| {
+        section.get_name(elf_file).is_ok_and(|name| name.starts_with(".debug_"))
+    })
+}
+
+/// Copies `elf_file`, compressing with `compression_type` the sections for
+/// which `select` returns true. Only sections which are not allocated, not
+/// already compressed, and not inside a segment are offered. As with objcopy,
+/// a section which would not get smaller is left as it is.
+pub fn compress_sections<'a, F>(elf_file: &ElfFile<'a>,
+                                compression_type: CompressionType,
+                                mut select: F)
+                                -> Result<Vec<u8>, &'static str>
+    where F: FnMut(&ElfFile<'a>, &SectionHeader<'a>) -> bool
+{
+    let is_64 = is_64(elf_file)?;
+    // `ch_type`.
+    let type_ = match compression_type {
+        CompressionType::Zlib => 1,
+        #[cfg(feature = "compression")]
+        CompressionType::Zstd => 2,
+        #[cfg(not(feature = "compression"))]
+        CompressionType::Zstd => return Err("zstd compression needs the `compression` feature"),
+        _ => return Err("Unsupported compression type"),
+    };
+
+    rewrite(elf_file, |section| {
+        if section.flags() & (SHF_ALLOC | SHF_COMPRESSED) != 0 || !select(elf_file, section) {
+            return Ok(None);
+        }
+        let data = section.raw_data(elf_file);
+        let compressed = compress(type_, data)?;
+
+        let mut contents = Vec::with_capacity(24 + compressed.len());
+        contents.extend_from_slice(&type_.to_ne_bytes());
+        if is_64 {
+            contents.extend_from_slice(&0u32.to_ne_bytes());
+            contents.extend_from_slice(&(data.len() as u64).to_ne_bytes());
+            contents.extend_from_slice(&section.align().to_ne_bytes());
+        } else {
+            contents.extend_from_slice(&(data.len() as u32).to_ne_bytes());
+            contents.extend_from_slice(&(section.align() as u32).to_ne_bytes());
+        }
+        contents.extend_from_slice(&compressed);
+        if contents.len() >= data.len() {
+            return Ok(None);
+        }
+
+        Ok(Some(Contents {
+            data: Cow::Owned(contents),
+            flags: section.flags() | SHF_COMPRESSED,
+            align: if is_64 { 8 } else { 4 },
+        }))
+    })
+}
+
+/// Copies `elf_file`, decompressing every `SHF_COMPRESSED` section. Sections
+/// in the older `.zdebug_*` format are left alone, since decompressing them
+/// would mean renaming them too.
+pub fn decompress_sections(elf_file: &ElfFile) -> Result<Vec<u8>, &'static str> {
+    let is_64 = is_64(elf_file)?;
+    rewrite(elf_file, |section| {
+        if section.flags() & SHF_COMPRESSED == 0 {
+            return Ok(None);
+        }
+        // The alignment of the decompressed data, `ch_addralign`.
+        let raw = section.raw_data(elf_file);
+        let align = if is_64 {
+            raw.get(16..24).map(|b| u64::from_ne_bytes(b.try_into().unwrap()))
+        } else {
+            raw.get(8..12).map(|b| u32::from_ne_bytes(b.try_into().unwrap()) as u64)
+        };
+        Ok(Some(Contents {
+            data: section.decompressed_data(elf_file)?,
+            flags: section.flags() & !SHF_COMPRESSED,
+            align: align.ok_or("Unexpected EOF in compressed section")?,
+        }))
+    })
+}
+
+// The new contents of a section.
+struct Contents<'a> {
+    data: Cow<'a, [u8]>,
+    flags: u64,
+    align: u64,
+}
+
+fn is_64(elf_file: &ElfFile) -> Result<bool, &'static str> {
+    let native = if cfg!(target_endian = "little") { Data::LittleEndian } else { Data::BigEndian };
+    check!(elf_file.header.pt1.data() == native, "File does not have the host's byte order");
+    match elf_file.header.pt1.class() {
+        Class::ThirtyTwo => Ok(false),
+        Class::SixtyFour => Ok(true),
+        Class::None | Class::Other(_) => Err("Invalid ELF class"),
+    }
+}
+
+// Copies `elf_file`, replacing the contents of the sections after the last
+// segment for which `convert` returns some.
+fn rewrite<'a, F>(elf_file: &ElfFile<'a>, mut convert: F) -> Result<Vec<u8>, &'static str>
+    where F: FnMut(&SectionHeader<'a>) -> Result<Option<Contents<'a>>, &'static str>
+{
+    let input = elf_file.input;
+    let header = elf_file.header.pt2;
+    let is_64 = is_64(elf_file)?;
+    elf_file.check_tables(is_64)?;
+    let entry_size = if is_64 { 64 } else { 40 };
+    let count = header.sh_count() as usize;
+    check!(count > 0, "File has no section headers");
+    let table = &input[header.sh_offset() as usize..][..count * entry_size];
+
+    // The end of the headers and segments, which are not moved.
+    let mut fixed_end = (header.header_size() as u64)
+        .max(header.ph_offset().saturating_add(header.ph_count() as u64 * header.ph_entry_size() as u64));
+    for ph in elf_file.program_iter() {
+        fixed_end = fixed_end.max(ph.offset().saturating_add(ph.file_size()));
+    }
+    check!(fixed_end <= input.len() as u64, "Segment out of range");
+
+    let sections: Vec<SectionHeader<'a>> = elf_file.section_iter().collect();
+    let mut moved: Vec<usize> = (1..count).filter(|&i| sections[i].offset() >= fixed_end).collect();
+    moved.sort_by_key(|&i| sections[i].offset());
+
+    let mut output = input[..fixed_end as usize].to_vec();
+    let mut patches = Vec::with_capacity(moved.len());
+    for i in moved {
+        let section = &sections[i];
+        let contents = match section.get_type() {
+            Ok(ShType::Null) | Ok(ShType::NoBits) => None,
+            Err(e) => return Err(e),
+            Ok(_) => {
+                check!(section.offset().checked_add(section.size()).is_some_and(|end| end <= input.len() as u64),
+                       "Section data out of range");
+                Some(convert(section)?.unwrap_or(Contents {
+                    data: Cow::Borrowed(section.raw_data(elf_file)),
+                    flags: section.flags(),
+                    align: section.align(),
+                }))
+            }
+        };
+        let align = contents.as_ref().map_or(section.align(), |c| c.align).max(1);
+        check!(align.is_power_of_two(), "Section alignment is not a power of two");
+        check!(align <= input.len() as u64, "Section alignment too large");
+        let offset = output.len().next_multiple_of(align as usize);
+        output.resize(offset, 0);
+        let (size, flags) = match contents {
+            Some(contents) => {
+                output.extend_from_slice(&contents.data);
+                (contents.data.len() as u64, contents.flags)
+            }
+            None => (section.size(), section.flags()),
+        };
+        patches.push((i, offset as u64, size, flags, align));
+    }
+
+    output.resize(output.len().next_multiple_of(if is_64 { 8 } else { 4 }), 0);
+    let table_offset = output.len();
+    output.extend_from_slice(table);
+    // The offsets of sh_flags, sh_offset, sh_size and sh_addralign.
+    let fields = if is_64 { [8, 24, 32, 48] } else { [8, 16, 20, 32] };
+    for (i, offset, size, flags, align) in patches {
+        let entry = &mut output[table_offset + i * entry_size..][..entry_size];
+        for (&field, value) in fields.iter().zip([flags, offset, size, align]) {
+            put_word(entry, field, value, is_64)?;
+        }
+    }
+    put_word(&mut output, if is_64 { 40 } else { 32 }, table_offset as u64, is_64)?;
+    Ok(output)
+}
+
+// Writes `value` at `offset` as an address-sized word.
+fn put_word(buf: &mut [u8], offset: usize, value: u64, is_64: bool) -> Result<(), &'static str> {
+    if is_64 {
+        buf[offset..offset + 8].copy_from_slice(&value.to_ne_bytes());
+    } else {
+        let value = u32::try_from(value).map_err(|_| "Value too large for a 32-bit file")?;
+        buf[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+    }
+    Ok(())
+}
+
+fn compress(type_: u32, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    match type_ {
+        #[cfg(feature = "compression")]
+        2 => zstd::bulk::compress(data, 0).map_err(|_| "Compression error"),
+        _ => deflate(data),
+    }
+}
+
+#[cfg(all(feature = "compression", not(feature = "inflate")))]
+fn deflate(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).and_then(|()| encoder.finish()).map_err(|_| "Compression error")
+}
+
+#[cfg(feature = "inflate")]
+fn deflate(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    Ok(miniz_oxide::deflate::compress_to_vec_zlib(data, 6))
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        if cfg!(target_endian = "big") {
+            return;
+        }
+        // A little-endian x86-64 relocatable file with a `.debug_info`
+        // section and the section name table, followed by the section headers.
+        let debug_info: Vec<u8> = (0..1000).map(|i| (i % 10) as u8).collect();
+        let names = b"\0.debug_info\0.shstrtab\0";
+        let names_offset = 64 + debug_info.len();
+        let table_offset = (names_offset + names.len()).next_multiple_of(8);
+        let mut data = b"\x7fELF\x02\x01\x01".to_vec();
+        data.resize(16, 0);
+        let header = [(1, 2), (62, 2), (1, 4), (0, 8), (0, 8), (table_offset, 8), (0, 4), (64, 2), (56, 2),
+                      (0, 2), (64, 2), (3, 2), (2, 2)];
+        for &(value, size) in &header {
+            data.extend_from_slice(&(value as u64).to_le_bytes()[..size]);
+        }
+        data.extend_from_slice(&debug_info);
+        data.extend_from_slice(names);
+        data.resize(table_offset + 64, 0);
+        for &(name, type_, offset, size) in &[(1, 1, 64, debug_info.len()), (13, 3, names_offset, names.len())] {
+            let fields = [(name, 4), (type_, 4), (0, 8), (0, 8), (offset, 8), (size, 8), (0, 4), (0, 4), (1, 8),
+                          (0, 8)];
+            for &(value, size) in &fields {
+                data.extend_from_slice(&(value as u64).to_le_bytes()[..size]);
+            }
+        }
+        let elf = ElfFile::new(&data).unwrap();
+
+        let compressed = compress_debug_sections(&elf, CompressionType::Zlib).unwrap();
+        assert!(compressed.len() < data.len());
+        let compressed_elf = ElfFile::new(&compressed).unwrap();
+        let section = compressed_elf.find_section_by_name(".debug_info").unwrap();
+        assert_eq!((section.flags(), section.align()), (SHF_COMPRESSED, 8));
+        assert_eq!(section.decompressed_data(&compressed_elf).unwrap(), &debug_info[..]);
+        let names_section = compressed_elf.find_section_by_name(".shstrtab").unwrap();
+        assert_eq!(names_section.raw_data(&compressed_elf), &names[..]);
+
+        assert_eq!(decompress_sections(&compressed_elf).unwrap(), data);
+    }
+}
@@ -0,0 +1,825 @@
+//! The notes of Linux core files: per-thread status and registers, process
+//! information, the mapped file table and the auxiliary vector.
+//!
+//! (This would be called `core`, but that name is taken by libcore.)
+
+use core::cmp;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Seek};
+#[cfg(feature = "std")]
+use std::path::{Component, Path, PathBuf};
+
+use ElfFile;
+use auxv::Auxv;
+use header::{Class, Data, Machine, Type};
+use program::{ProgramHeader, ProgramIter, Type as ProgramType};
+use reader::{c_str, Reader};
+use sections::{Note, NoteIter};
+
+// Note types, in notes owned by "CORE".
+pub const NT_PRSTATUS: u32 = 1;
+pub const NT_PRFPREG: u32 = 2;
+pub const NT_PRPSINFO: u32 = 3;
+pub const NT_TASKSTRUCT: u32 = 4;
+pub const NT_AUXV: u32 = 6;
+pub const NT_SIGINFO: u32 = 0x53494749;
+pub const NT_FILE: u32 = 0x46494c45;
+// Note types, in notes owned by "LINUX".
+pub const NT_PRXFPREG: u32 = 0x46e62b7f;
+pub const NT_386_TLS: u32 = 0x200;
+pub const NT_X86_XSTATE: u32 = 0x202;
+pub const NT_ARM_TLS: u32 = 0x401;
+pub const NT_ARM_HW_BREAK: u32 = 0x402;
+pub const NT_ARM_HW_WATCH: u32 = 0x403;
+pub const NT_ARM_SYSTEM_CALL: u32 = 0x404;
+pub const NT_ARM_PAC_MASK: u32 = 0x406;
+
+/// The architectures whose register sets can be read from `NT_PRSTATUS`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arch {
+    X86_64,
+    I386,
+    AArch64,
+    RiscV64,
+}
+
+const X86_64_REGISTERS: &[&str] = &["r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax",
+                                    "rcx", "rdx", "rsi", "rdi", "orig_rax", "rip", "cs", "eflags", "rsp", "ss",
+                                    "fs_base", "gs_base", "ds", "es", "fs", "gs"];
+const I386_REGISTERS: &[&str] = &["ebx", "ecx", "edx", "esi", "edi", "ebp", "eax", "ds", "es", "fs", "gs",
+                                  "orig_eax", "eip", "cs", "eflags", "esp", "ss"];
+const AARCH64_REGISTERS: &[&str] = &["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11",
+                                     "x12", "x13", "x14", "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22",
+                                     "x23", "x24", "x25", "x26", "x27", "x28", "x29", "x30", "sp", "pc", "pstate"];
+const RISCV64_REGISTERS: &[&str] = &["pc", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2",
+                                     "a3", "a4", "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9",
+                                     "s10", "s11", "t3", "t4", "t5", "t6"];
+
+impl Arch {
+    /// The architecture of `elf_file`, if it is one of those supported.
+    pub fn of(elf_file: &ElfFile) -> Option<Arch> {
+        match (elf_file.header.pt2.machine().as_machine(), elf_file.header.pt1.class()) {
+            (Machine::X86_64, Class::SixtyFour) => Some(Arch::X86_64),
+            (Machine::X86, Class::ThirtyTwo) => Some(Arch::I386),
+            (Machine::AArch64, Class::SixtyFour) => Some(Arch::AArch64),
+            (Machine::RISC_V, Class::SixtyFour) => Some(Arch::RiscV64),
+            _ => None,
+        }
+    }
+
+    /// The names of the general registers, in the order of the kernel's
+    /// `elf_gregset_t`.
+    pub fn register_names(self) -> &'static [&'static str] {
+        match self {
+            Arch::X86_64 => X86_64_REGISTERS,
+            Arch::I386 => I386_REGISTERS,
+            Arch::AArch64 => AARCH64_REGISTERS,
+            Arch::RiscV64 => RISCV64_REGISTERS,
+        }
+    }
+
+    fn word_size(self) -> usize {
+        match self {
+            Arch::I386 => 4,
+            Arch::X86_64 | Arch::AArch64 | Arch::RiscV64 => 8,
+        }
+    }
+
+    fn pc_index(self) -> usize {
+        match self {
+            Arch::X86_64 => 16,
+            Arch::I386 => 12,
+            Arch::AArch64 => 32,
+            Arch::RiscV64 => 0,
+        }
+    }
+
+    fn sp_index(self) -> usize {
+        match self {
+            Arch::X86_64 => 19,
+            Arch::I386 => 15,
+            Arch::AArch64 => 31,
+            Arch::RiscV64 => 2,
+        }
+    }
+}
+
+/// A core file, for reading its notes.
+#[derive(Clone, Copy, Debug)]
+pub struct CoreFile<'b, 'a: 'b> {
+    pub elf_file: &'b ElfFile<'a>,
+    arch: Option<Arch>,
+    is_64: bool,
+}
+
+impl<'b, 'a> CoreFile<'b, 'a> {
+    pub fn new(elf_file: &'b ElfFile<'a>) -> Result<CoreFile<'b, 'a>, &'static str> {
+        check!(elf_file.header.pt2.type_().as_type() == Type::Core, "Not a core file");
+        let native = if cfg!(target_endian = "little") { Data::LittleEndian } else { Data::BigEndian };
+        check!(elf_file.header.pt1.data() == native, "Core file does not have the host's byte order");
+        Ok(CoreFile {
+            elf_file,
+            arch: Arch::of(elf_file),
+            is_64: elf_file.header.pt1.class() == Class::SixtyFour,
+        })
+    }
+
+    /// The architecture, if its register set is understood.
+    pub fn arch(&self) -> Option<Arch> {
+        self.arch
+    }
+
+    /// All the notes in the file's note segments.
+    pub fn notes(&self) -> CoreNoteIter<'b, 'a> {
+        CoreNoteIter {
+            segments: ProgramIter {
+                file: self.elf_file,
+                next_index: 0,
+            },
+            notes: None,
+        }
+    }
+
+    /// The status of each thread, the one which caused the dump first.
+    pub fn threads(&self) -> impl Iterator<Item = Result<PrStatus<'a>, &'static str>> + 'b {
+        let core = *self;
+        self.notes().filter_map(move |note| match note {
+            Ok(note) if note.name == "CORE" && note.type_ == NT_PRSTATUS => Some(core.parse_prstatus(note.desc)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+    }
+
+    /// The process information note.
+    pub fn process_info(&self) -> Result<Option<PrPsInfo<'a>>, &'static str> {
+        self.find_note(NT_PRPSINFO)?.map(|desc| self.parse_prpsinfo(desc)).transpose()
+    }
+
+    /// The signal information of the thread which caused the dump.
+    pub fn signal_info(&self) -> Result<Option<SigInfo>, &'static str> {
+        self.find_note(NT_SIGINFO)?.map(|desc| self.parse_siginfo(desc)).transpose()
+    }
+
+    /// The table of files mapped into the process.
+    pub fn mapped_files(&self) -> Result<Option<MappedFiles<'a>>, &'static str> {
+        self.find_note(NT_FILE)?.map(|desc| MappedFiles::new(desc, self.is_64)).transpose()
+    }
+
+    /// The auxiliary vector the process was started with.
+    pub fn auxv(&self) -> Result<Option<Auxv<'a>>, &'static str> {
+        let class = self.elf_file.header.pt1.class();
+        let data = self.elf_file.header.pt1.data();
+        self.find_note(NT_AUXV)?.map(|desc| Auxv::new(desc, class, data)).transpose()
+    }
+
+    /// Fills `buf` with the process's memory at `address`. Memory in a
+    /// segment beyond what was written to the file reads as zeros.
+    pub fn read_memory(&self, address: u64, buf: &mut [u8]) -> Result<(), MemoryError> {
+        self.read_memory_(address, buf, None)
+    }
+
+    /// Like `read_memory`, but memory beyond what was written to the file is
+    /// read from the mapped file it came from (according to the `NT_FILE`
+    /// note), and is an error if `files` does not have it. Only memory no
+    /// file is mapped at reads as zeros. The kernel leaves out the contents
+    /// of read-only file mappings, such as the text of executables and
+    /// libraries, so this is needed to read code.
+    pub fn read_memory_with_files(&self,
+                                  address: u64,
+                                  buf: &mut [u8],
+                                  files: &mut dyn MappedFileSource)
+                                  -> Result<(), MemoryError> {
+        self.read_memory_(address, buf, Some(files))
+    }
+
+    fn read_memory_(&self,
+                    mut address: u64,
+                    mut buf: &mut [u8],
+                    mut files: Option<&mut dyn MappedFileSource>)
+                    -> Result<(), MemoryError> {
+        let mapped_files = match files {
+            Some(_) => self.mapped_files().map_err(MemoryError::Malformed)?,
+            None => None,
+        };
+        while !buf.is_empty() {
+            let segment = self.load_segment(address)?;
+            let segment_offset = address - segment.virtual_addr();
+            let mut len = cmp::min(buf.len() as u64, segment.mem_size() - segment_offset);
+
+            if segment_offset < segment.file_size() {
+                // The part which is in the core file.
+                len = cmp::min(len, segment.file_size() - segment_offset);
+                let data = segment.data_checked(self.elf_file).map_err(|_| {
+                    MemoryError::Malformed("Segment is out of range of the file")
+                })?;
+                let start = segment_offset as usize;
+                let (chunk, rest) = buf.split_at_mut(len as usize);
+                chunk.copy_from_slice(&data[start..start + len as usize]);
+                buf = rest;
+            } else {
+                // The rest, which is in a mapped file if one covers it, and
+                // zeros otherwise.
+                let mapping = match mapped_files {
+                    Some(mapped_files) => mapped_files.find(address).map_err(MemoryError::Malformed)?,
+                    None => None,
+                };
+                if let Some(mapping) = mapping {
+                    len = cmp::min(len, mapping.end - address);
+                }
+                let (chunk, rest) = buf.split_at_mut(len as usize);
+                match (mapping, files.as_mut()) {
+                    (Some(mapping), Some(files)) => {
+                        let offset = mapping.file_offset.checked_add(address - mapping.start);
+                        if !offset.is_some_and(|offset| files.read(mapping.path, offset, chunk)) {
+                            return Err(MemoryError::FileNotRead { address });
+                        }
+                    }
+                    _ => {
+                        for b in chunk.iter_mut() {
+                            *b = 0;
+                        }
+                    }
+                }
+                buf = rest;
+            }
+
+            address = address.wrapping_add(len);
+        }
+        Ok(())
+    }
+
+    // The loadable segment containing `address`.
+    fn load_segment(&self, address: u64) -> Result<ProgramHeader<'a>, MemoryError> {
+        for i in 0..self.elf_file.header.pt2.ph_count() {
+            let segment = self.elf_file.program_header(i).map_err(MemoryError::Malformed)?;
+            if segment.get_type() == Ok(ProgramType::Load) && address >= segment.virtual_addr() &&
+               address - segment.virtual_addr() < segment.mem_size() {
+                return Ok(segment);
+            }
+        }
+        Err(MemoryError::NotDumped { address })
+    }
+
+    fn find_note(&self, type_: u32) -> Result<Option<&'a [u8]>, &'static str> {
+        for note in self.notes() {
+            let note = note?;
+            if note.name == "CORE" && note.type_ == type_ {
+                return Ok(Some(note.desc));
+            }
+        }
+        Ok(None)
+    }
+
+    // `struct elf_prstatus`.
+    fn parse_prstatus(&self, desc: &'a [u8]) -> Result<PrStatus<'a>, &'static str> {
+        let arch = self.arch.ok_or("Registers of this architecture are not supported")?;
+        let word = arch.word_size();
+        let times = 16 + 2 * word + 16;
+        let registers = times + 8 * word;
+        let registers_size = arch.register_names().len() * word;
+        check!(desc.len() >= registers + registers_size + 4, "NT_PRSTATUS note is truncated");
+        let time = |i: usize| -> Result<TimeVal, &'static str> {
+            let offset = times + i * 2 * word;
+            Ok(TimeVal {
+                seconds: at(desc, offset).signed_word(self.is_64)?,
+                microseconds: at(desc, offset + word).signed_word(self.is_64)?,
+            })
+        };
+        Ok(PrStatus {
+            signal_info: SigInfo {
+                signal: at(desc, 0).i32()?,
+                code: at(desc, 4).i32()?,
+                errno: at(desc, 8).i32()?,
+                address: None,
+            },
+            current_signal: at(desc, 12).u16()?,
+            pending_signals: at(desc, 16).word(self.is_64)?,
+            held_signals: at(desc, 16 + word).word(self.is_64)?,
+            pid: at(desc, 16 + 2 * word).i32()?,
+            ppid: at(desc, 20 + 2 * word).i32()?,
+            pgrp: at(desc, 24 + 2 * word).i32()?,
+            sid: at(desc, 28 + 2 * word).i32()?,
+            user_time: time(0)?,
+            system_time: time(1)?,
+            children_user_time: time(2)?,
+            children_system_time: time(3)?,
+            registers: Registers {
+                arch,
+                data: &desc[registers..registers + registers_size],
+            },
+            fp_valid: at(desc, registers + registers_size).i32()? != 0,
+        })
+    }
+
+    // `struct elf_prpsinfo`.
+    fn parse_prpsinfo(&self, desc: &'a [u8]) -> Result<PrPsInfo<'a>, &'static str> {
+        let word = if self.is_64 { 8 } else { 4 };
+        // The uid and gid are 16 bits on i386, and 32 bits elsewhere.
+        let id_size = if self.arch == Some(Arch::I386) { 2 } else { 4 };
+        let ids = 2 * word;
+        let pids = ids + 2 * id_size;
+        let file_name = pids + 16;
+        check!(desc.len() >= file_name + 16 + 80, "NT_PRPSINFO note is truncated");
+        let id = |offset| {
+            let mut r = at(desc, offset);
+            if id_size == 2 { r.u16().map(u32::from) } else { r.u32() }
+        };
+        Ok(PrPsInfo {
+            state: desc[0],
+            state_name: desc[1] as char,
+            zombie: desc[2] != 0,
+            nice: desc[3] as i8,
+            flags: at(desc, word).word(self.is_64)?,
+            uid: id(ids)?,
+            gid: id(ids + id_size)?,
+            pid: at(desc, pids).i32()?,
+            ppid: at(desc, pids + 4).i32()?,
+            pgrp: at(desc, pids + 8).i32()?,
+            sid: at(desc, pids + 12).i32()?,
+            file_name: c_str(&desc[file_name..file_name + 16], 0)?,
+            args: c_str(&desc[file_name + 16..file_name + 16 + 80], 0)?,
+        })
+    }
+
+    // The start of `siginfo_t`, and the fault address for the signals which
+    // have one.
+    fn parse_siginfo(&self, desc: &'a [u8]) -> Result<SigInfo, &'static str> {
+        let address_offset = if self.is_64 { 16 } else { 12 };
+        check!(desc.len() >= address_offset + 8, "NT_SIGINFO note is truncated");
+        let signal = at(desc, 0).i32()?;
+        let address = match signal {
+            SIGILL | SIGTRAP | SIGBUS | SIGFPE | SIGSEGV => Some(at(desc, address_offset).word(self.is_64)?),
+            _ => None,
+        };
+        Ok(SigInfo {
+            signal,
+            errno: at(desc, 4).i32()?,
+            code: at(desc, 8).i32()?,
+            address,
+        })
+    }
+}
+
+const SIGILL: i32 = 4;
+const SIGTRAP: i32 = 5;
+const SIGBUS: i32 = 7;
+const SIGFPE: i32 = 8;
+const SIGSEGV: i32 = 11;
+
+/// Why memory could not be read from a core file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    /// The core file is malformed.
+    Malformed(&'static str),
+    /// No loadable segment contains `address`, so it was not mapped in the
+    /// process, or the kernel did not dump it.
+    NotDumped { address: u64 },
+    /// The memory at `address` was left out of the core file, and could not
+    /// be read from the file mapped there.
+    FileNotRead { address: u64 },
+}
+
+/// Somewhere to read the files mapped into a crashed process from, such as a
+/// directory holding copies of them.
+pub trait MappedFileSource {
+    /// Fills `buf` from `offset` in the file at `path`, as recorded in the
+    /// core file. Returns false if the file or that part of it is not
+    /// available.
+    fn read(&mut self, path: &str, offset: u64, buf: &mut [u8]) -> bool;
+}
+
+/// Reads mapped files from a directory. A file mapped from `/usr/lib/x.so`
+/// is looked for at `<root>/usr/lib/x.so`, and then at `<root>/x.so` if
+/// `by_file_name` is set. Paths with `..` components are never read.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct MappedFileDir {
+    pub root: PathBuf,
+    pub by_file_name: bool,
+}
+
+#[cfg(feature = "std")]
+impl MappedFileDir {
+    pub fn new<P: Into<PathBuf>>(root: P) -> MappedFileDir {
+        MappedFileDir {
+            root: root.into(),
+            by_file_name: false,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl MappedFileSource for MappedFileDir {
+    fn read(&mut self, path: &str, offset: u64, buf: &mut [u8]) -> bool {
+        let relative = Path::new(path.trim_start_matches('/'));
+        if !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return false;
+        }
+        let by_name = if self.by_file_name { relative.file_name() } else { None };
+        let candidates = [Some(self.root.join(relative)), by_name.map(|name| self.root.join(name))];
+        candidates.iter().flatten().any(|candidate| {
+            let mut read = || -> io::Result<()> {
+                let mut file = fs::File::open(candidate)?;
+                let _ = file.seek(io::SeekFrom::Start(offset))?;
+                file.read_exact(buf)
+            };
+            read().is_ok()
+        })
+    }
+}
+
+/// Iterates over the notes in all the note segments of a core file.
+#[derive(Clone, Debug)]
+pub struct CoreNoteIter<'b, 'a: 'b> {
+    segments: ProgramIter<'b, 'a>,
+    notes: Option<NoteIter<'a>>,
+}
+
+impl<'b, 'a> Iterator for CoreNoteIter<'b, 'a> {
+    type Item = Result<Note<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(note) = self.notes.as_mut().and_then(Iterator::next) {
+                return Some(note);
+            }
+            let segment = self.segments.next()?;
+            if segment.get_type() == Ok(ProgramType::Note) {
+                match segment.data_checked(self.segments.file) {
+                    Ok(data) => self.notes = Some(NoteIter::new(data, segment.align())),
+                    Err(e) => {
+                        self.segments.next_index = self.segments.file.header.pt2.ph_count();
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A `struct timeval`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeVal {
+    pub seconds: i64,
+    pub microseconds: i64,
+}
+
+/// Which signal stopped a thread, and why.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SigInfo {
+    pub signal: i32,
+    pub code: i32,
+    pub errno: i32,
+    /// The faulting address, for SIGILL, SIGTRAP, SIGBUS, SIGFPE and SIGSEGV.
+    /// Only the `NT_SIGINFO` note has this.
+    pub address: Option<u64>,
+}
+
+/// The status of a thread (`NT_PRSTATUS`).
+#[derive(Clone, Copy, Debug)]
+pub struct PrStatus<'a> {
+    pub signal_info: SigInfo,
+    pub current_signal: u16,
+    pub pending_signals: u64,
+    pub held_signals: u64,
+    pub pid: i32,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub sid: i32,
+    pub user_time: TimeVal,
+    pub system_time: TimeVal,
+    pub children_user_time: TimeVal,
+    pub children_system_time: TimeVal,
+    pub registers: Registers<'a>,
+    /// Whether there is an `NT_PRFPREG` note for this thread.
+    pub fp_valid: bool,
+}
+
+/// The general registers of a thread.
+#[derive(Clone, Copy, Debug)]
+pub struct Registers<'a> {
+    arch: Arch,
+    data: &'a [u8],
+}
+
+impl<'a> Registers<'a> {
+    pub fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    pub fn len(&self) -> usize {
+        self.arch.register_names().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The value of the register at `index` in `Arch::register_names`.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        let word = self.arch.word_size();
+        let data = self.data.get(index * word..(index + 1) * word)?;
+        Reader::new(data).word(word == 8).ok()
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<u64> {
+        self.arch.register_names().iter().position(|&n| n == name).and_then(|i| self.get(i))
+    }
+
+    /// The program counter.
+    pub fn pc(&self) -> u64 {
+        self.get(self.arch.pc_index()).unwrap()
+    }
+
+    /// The stack pointer.
+    pub fn sp(&self) -> u64 {
+        self.get(self.arch.sp_index()).unwrap()
+    }
+
+    /// The registers' names and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + 'a {
+        let registers = *self;
+        self.arch.register_names().iter().enumerate().map(move |(i, &name)| (name, registers.get(i).unwrap()))
+    }
+}
+
+/// Information about the process (`NT_PRPSINFO`).
+#[derive(Clone, Copy, Debug)]
+pub struct PrPsInfo<'a> {
+    /// The numeric process state.
+    pub state: u8,
+    /// The process state as shown by ps, e.g., 'R' or 'S'.
+    pub state_name: char,
+    pub zombie: bool,
+    pub nice: i8,
+    pub flags: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: i32,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub sid: i32,
+    /// The executable's file name, truncated to 15 bytes.
+    pub file_name: &'a str,
+    /// The start of the command line, truncated to 79 bytes. Arguments are
+    /// separated by spaces.
+    pub args: &'a str,
+}
+
+/// The files mapped into the process (`NT_FILE`).
+#[derive(Clone, Copy, Debug)]
+pub struct MappedFiles<'a> {
+    pub page_size: u64,
+    count: usize,
+    desc: &'a [u8],
+    is_64: bool,
+}
+
+impl<'a> MappedFiles<'a> {
+    fn new(desc: &'a [u8], is_64: bool) -> Result<MappedFiles<'a>, &'static str> {
+        let word = if is_64 { 8 } else { 4 };
+        check!(desc.len() >= 2 * word, "NT_FILE note is truncated");
+        let count = at(desc, 0).word(is_64)? as usize;
+        let names = count.checked_mul(3 * word)
+            .and_then(|size| size.checked_add(2 * word))
+            .ok_or("NT_FILE note is truncated")?;
+        check!(names <= desc.len(), "NT_FILE note is truncated");
+        Ok(MappedFiles {
+            page_size: at(desc, word).word(is_64)?,
+            count,
+            desc,
+            is_64,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn iter(&self) -> MappedFileIter<'a> {
+        let word = if self.is_64 { 8 } else { 4 };
+        MappedFileIter {
+            files: *self,
+            index: 0,
+            name_offset: 2 * word + self.count * 3 * word,
+        }
+    }
+
+    /// The mapping which covers `address`.
+    pub fn find(&self, address: u64) -> Result<Option<MappedFile<'a>>, &'static str> {
+        for file in self.iter() {
+            let file = file?;
+            if file.start <= address && address < file.end {
+                return Ok(Some(file));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A file mapping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MappedFile<'a> {
+    pub start: u64,
+    pub end: u64,
+    /// The offset in the file of `start`, in bytes.
+    pub file_offset: u64,
+    pub path: &'a str,
+}
+
+#[derive(Clone, Debug)]
+pub struct MappedFileIter<'a> {
+    files: MappedFiles<'a>,
+    index: usize,
+    name_offset: usize,
+}
+
+impl<'a> Iterator for MappedFileIter<'a> {
+    type Item = Result<MappedFile<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.files.count {
+            return None;
+        }
+        let MappedFiles { desc, is_64, page_size, .. } = self.files;
+        let word = if is_64 { 8 } else { 4 };
+        let entry = 2 * word + self.index * 3 * word;
+        self.index += 1;
+        let result = (|| {
+            let path = c_str(desc, self.name_offset)?;
+            self.name_offset += path.len() + 1;
+            Ok(MappedFile {
+                start: at(desc, entry).word(is_64)?,
+                end: at(desc, entry + word).word(is_64)?,
+                file_offset: at(desc, entry + 2 * word).word(is_64)?.wrapping_mul(page_size),
+                path,
+            })
+        })();
+        if result.is_err() {
+            self.index = self.files.count;
+        }
+        Some(result)
+    }
+}
+
+// A reader of the native-endian fields of a core note from `offset`.
+fn at<'a>(desc: &'a [u8], offset: usize) -> Reader<'a> {
+    Reader::new(desc.get(offset..).unwrap_or(&[]))
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+    use header::test::mk_elf_header;
+
+    fn words(values: &[u64]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_ne_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn mapped_files() {
+        let mut desc = words(&[2, 0x1000, 0x400000, 0x401000, 0, 0x600000, 0x603000, 2]);
+        desc.extend_from_slice(b"/bin/a\0/lib/b.so\0");
+        let files = MappedFiles::new(&desc, true).unwrap();
+        assert_eq!((files.len(), files.page_size), (2, 0x1000));
+        let b = MappedFile { start: 0x600000, end: 0x603000, file_offset: 0x2000, path: "/lib/b.so" };
+        assert_eq!(files.find(0x601234), Ok(Some(b)));
+        assert_eq!(files.find(0x500000), Ok(None));
+        assert_eq!(files.iter().next(), Some(Ok(MappedFile {
+            start: 0x400000,
+            end: 0x401000,
+            file_offset: 0,
+            path: "/bin/a",
+        })));
+
+        // A count larger than the note is an error, not a panic.
+        desc[..8].copy_from_slice(&u64::MAX.to_ne_bytes());
+        assert!(MappedFiles::new(&desc, true).is_err());
+    }
+
+    #[test]
+    fn prstatus_and_prpsinfo() {
+        if cfg!(target_endian = "big") {
+            return;
+        }
+        for &(arch, class, machine) in &[(Arch::X86_64, 2, 62), (Arch::I386, 1, 3), (Arch::AArch64, 2, 183),
+                                         (Arch::RiscV64, 2, 243)] {
+            let mut header = mk_elf_header(class);
+            header[16..18].copy_from_slice(&4u16.to_le_bytes());
+            header[18..20].copy_from_slice(&(machine as u16).to_le_bytes());
+            let elf = ElfFile::new(&header).unwrap();
+            let core = CoreFile::new(&elf).unwrap();
+            assert_eq!(core.arch(), Some(arch));
+
+            let word = arch.word_size();
+            let put = |desc: &mut Vec<u8>, offset: usize, value: u64, size: usize| {
+                desc[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+            };
+            let registers = 16 + 2 * word + 16 + 8 * word;
+            let mut desc = vec![0; registers + arch.register_names().len() * word + 4];
+            put(&mut desc, 0, 11, 4);
+            put(&mut desc, 16 + 2 * word, 1234, 4);
+            put(&mut desc, registers + arch.pc_index() * word, 0x401000, word);
+            put(&mut desc, registers + arch.sp_index() * word, 0x7ff0, word);
+            let status = core.parse_prstatus(&desc).unwrap();
+            assert_eq!((status.pid, status.signal_info.signal), (1234, 11));
+            assert_eq!((status.registers.pc(), status.registers.sp()), (0x401000, 0x7ff0));
+            assert!(core.parse_prstatus(&desc[..desc.len() - 1]).is_err());
+
+            let pids = 2 * word + if arch == Arch::I386 { 4 } else { 8 };
+            let mut desc = vec![0; pids + 16 + 16 + 80];
+            desc[1] = b'R';
+            put(&mut desc, pids, 1234, 4);
+            desc[pids + 16..pids + 21].copy_from_slice(b"a.out");
+            desc[pids + 32..pids + 41].copy_from_slice(b"./a.out x");
+            let info = core.parse_prpsinfo(&desc).unwrap();
+            assert_eq!((info.pid, info.state_name, info.file_name, info.args), (1234, 'R', "a.out", "./a.out x"));
+        }
+    }
+
+    #[test]
+    fn read_memory() {
+        if cfg!(target_endian = "big") {
+            return;
+        }
+        // An NT_FILE note mapping /bin/a at 0x1000.
+        let mut note = words(&[1, 0x1000, 0x1000, 0x1008, 0]);
+        note.extend_from_slice(b"/bin/a\0\0");
+        let mut notes = Vec::new();
+        for &value in &[5, note.len() as u32, NT_FILE] {
+            notes.extend_from_slice(&value.to_le_bytes());
+        }
+        notes.extend_from_slice(b"CORE\0\0\0\0");
+        notes.extend_from_slice(&note);
+
+        // A little-endian x86-64 core with the note and one segment at
+        // 0x1000, four bytes of which are in the file.
+        let mut data = b"\x7fELF\x02\x01\x01".to_vec();
+        data.resize(16, 0);
+        let notes_offset = 64 + 2 * 56;
+        let fields = [(4, 2), (62, 2), (1, 4), (0, 8), (64, 8), (0, 8), (0, 4), (64, 2), (56, 2), (2, 2),
+                      (64, 2), (0, 2), (0, 2),
+                      (4, 4), (4, 4), (notes_offset, 8), (0, 8), (0, 8), (notes.len(), 8), (0, 8), (4, 8),
+                      (1, 4), (6, 4), (notes_offset + notes.len(), 8), (0x1000, 8), (0, 8), (4, 8), (8, 8),
+                      (1, 8)];
+        for &(value, size) in &fields {
+            data.extend_from_slice(&(value as u64).to_le_bytes()[..size]);
+        }
+        data.extend_from_slice(&notes);
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        let elf = ElfFile::new(&data).unwrap();
+        let core = CoreFile::new(&elf).unwrap();
+
+        let mut buf = [0xff; 6];
+        assert_eq!(core.read_memory(0x1001, &mut buf), Ok(()));
+        assert_eq!(buf, [2, 3, 4, 0, 0, 0]);
+        assert_eq!(core.read_memory(0x1006, &mut buf), Err(MemoryError::NotDumped { address: 0x1008 }));
+
+        struct Source;
+        impl MappedFileSource for Source {
+            fn read(&mut self, path: &str, offset: u64, buf: &mut [u8]) -> bool {
+                assert_eq!((path, offset), ("/bin/a", 4));
+                buf.copy_from_slice(b"abcd");
+                true
+            }
+        }
+        assert_eq!(core.read_memory_with_files(0x1002, &mut buf, &mut Source), Ok(()));
+        assert_eq!(buf, *b"\x03\x04abcd");
+
+        // A mapped file which cannot be read is an error, not zeros.
+        struct Missing;
+        impl MappedFileSource for Missing {
+            fn read(&mut self, _: &str, _: u64, _: &mut [u8]) -> bool {
+                false
+            }
+        }
+        assert_eq!(core.read_memory_with_files(0x1002, &mut buf, &mut Missing),
+                   Err(MemoryError::FileNotRead { address: 0x1004 }));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn mapped_file_dir() {
+        let root = std::env::temp_dir().join(format!("xmas-elf-mapped-{}", std::process::id()));
+        fs::create_dir_all(root.join("lib")).unwrap();
+        fs::write(root.join("lib/a.so"), b"abcd").unwrap();
+        let mut dir = MappedFileDir::new(root.join("lib"));
+        let mut buf = [0; 2];
+        assert!(dir.read("/a.so", 1, &mut buf));
+        assert_eq!(buf, *b"bc");
+        assert!(!dir.read("/../lib/a.so", 0, &mut buf));
+        assert!(!dir.read("/usr/lib/a.so", 0, &mut buf));
+        dir.by_file_name = true;
+        assert!(dir.read("/usr/lib/a.so", 2, &mut buf));
+        assert_eq!(buf, *b"cd");
+        assert!(!dir.read("/../lib/a.so", 0, &mut buf));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+//! Finding the separate debug file for a stripped binary, the way GDB does:
+//! by build ID under `.build-id/`, then by the name in `.gnu_debuglink`,
+//! checking its CRC. The search needs the `std` feature; the CRC does not.
+
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::string::String;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use {ElfFile, Extensions};
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xedb88320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Updates `crc` with `data`, using the CRC-32 that `.gnu_debuglink`
+/// checksums are made with (the same as zlib's). Start with a `crc` of 0.
+pub fn crc32(crc: u32, data: &[u8]) -> u32 {
+    !data.iter().fold(!crc, |crc, &b| CRC32_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+/// Where debug files are looked for.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct DebugFileLocator {
+    /// The global debug directories, searched in order. The default is
+    /// `/usr/lib/debug`.
+    pub debug_dirs: Vec<PathBuf>,
+}
+
+#[cfg(feature = "std")]
+impl Default for DebugFileLocator {
+    fn default() -> DebugFileLocator {
+        DebugFileLocator { debug_dirs: vec![PathBuf::from("/usr/lib/debug")] }
+    }
+}
+
+/// How a debug file was found.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoundBy {
+    BuildId,
+    DebugLink,
+    /// Through the `.gnu_debugaltlink` of another debug file.
+    AltLink,
+}
+
+/// A debug file and its contents.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct DebugFile {
+    pub path: PathBuf,
+    pub data: Vec<u8>,
+    pub found_by: FoundBy,
+}
+
+#[cfg(feature = "std")]
+impl DebugFile {
+    pub fn elf_file(&self) -> Result<ElfFile<'_>, &'static str> {
+        ElfFile::new(&self.data)
+    }
+}
+
+#[cfg(feature = "std")]
+impl DebugFileLocator {
+    /// A locator which searches `dir` rather than `/usr/lib/debug`.
+    pub fn with_debug_dir<P: Into<PathBuf>>(dir: P) -> DebugFileLocator {
+        DebugFileLocator { debug_dirs: vec![dir.into()] }
+    }
+
+    /// Finds the debug file for `elf_file`, which was read from `path`.
+    ///
+    /// The places tried are, in order: `<debug dir>/.build-id/xx/rest.debug`
+    /// for each debug directory, where `xxrest` is the build ID in hex; then
+    /// for the `.gnu_debuglink` name, the binary's directory, its `.debug`
+    /// subdirectory, and its directory under each debug directory. A file
+    /// found by build ID must have the same build ID, and one found by
+    /// debuglink must match the CRC (and the build ID, if both have one).
+    pub fn find(&self, elf_file: &ElfFile, path: &Path) -> Option<DebugFile> {
+        let build_id = elf_file.get_gnu_buildid();
+        if let Some(build_id) = build_id {
+            if let Some(file) = self.find_by_build_id(build_id, FoundBy::BuildId) {
+                return Some(file);
+            }
+        }
+
+        let (name, crc) = elf_file.get_gnu_debuglink()?;
+        let dir = fs::canonicalize(path).ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf))
+            .or_else(|| path.parent().map(Path::to_path_buf))?;
+        let mut candidates = vec![dir.join(name), dir.join(".debug").join(name)];
+        for debug_dir in &self.debug_dirs {
+            candidates.push(debug_dir.join(dir.strip_prefix("/").unwrap_or(&dir)).join(name));
+        }
+        candidates.into_iter().find_map(|candidate| {
+            let data = fs::read(&candidate).ok()?;
+            if crc32(0, &data) != crc {
+                return None;
+            }
+            let file = DebugFile { path: candidate, data, found_by: FoundBy::DebugLink };
+            let matches = match (build_id, file.elf_file().ok()?.get_gnu_buildid()) {
+                (Some(expected), Some(found)) => expected == found,
+                _ => true,
+            };
+            if matches { Some(file) } else { None }
+        })
+    }
+
+    /// Finds the supplementary file (made by `dwz`) named in the
+    /// `.gnu_debugaltlink` section of `debug_file`, which was read from
+    /// `path`. A relative name is relative to the debug file's directory; if
+    /// the file is not there, it is looked for by build ID. It must have the
+    /// build ID given in the link.
+    pub fn find_alt(&self, debug_file: &ElfFile, path: &Path) -> Option<DebugFile> {
+        let (name, build_id) = debug_file.get_gnu_debugaltlink()?;
+        let name = Path::new(name);
+        let candidate = match path.parent() {
+            Some(dir) if name.is_relative() => dir.join(name),
+            _ => name.to_path_buf(),
+        };
+        fs::read(&candidate).ok()
+            .map(|data| DebugFile { path: candidate, data, found_by: FoundBy::AltLink })
+            .filter(|file| file.elf_file().ok().and_then(|elf| elf.get_gnu_buildid()) == Some(build_id))
+            .or_else(|| self.find_by_build_id(build_id, FoundBy::AltLink))
+    }
+
+    fn find_by_build_id(&self, build_id: &[u8], found_by: FoundBy) -> Option<DebugFile> {
+        if build_id.len() < 2 {
+            return None;
+        }
+        let hex: String = build_id.iter().map(|b| format!("{:02x}", b)).collect();
+        self.debug_dirs.iter().find_map(|debug_dir| {
+            let candidate = debug_dir.join(".build-id").join(&hex[..2]).join(format!("{}.debug", &hex[2..]));
+            let data = fs::read(&candidate).ok()?;
+            let file = DebugFile { path: candidate, data, found_by };
+            if file.elf_file().ok()?.get_gnu_buildid() == Some(build_id) { Some(file) } else { None }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf43926);
+        assert_eq!(crc32(0, b""), 0);
+    }
+}
@@ -0,0 +1,150 @@
+//! Loading a file's DWARF for reading with gimli. Sections are found by name,
+//! including in the older `.zdebug_*` form, and decompressed if need be.
+//!
+//! ```ignore
+//! let sections = elf_file.dwarf()?;
+//! let dwarf = sections.dwarf();
+//! let mut units = dwarf.units();
+//! ```
+
+use alloc::borrow::Cow;
+
+use gimli::{self, DwarfFileType, EndianSlice, RunTimeEndian, SectionId};
+
+use ElfFile;
+use header::{Class, Data};
+use sections::ShType;
+
+/// The DWARF sections of a file, which a `gimli::Dwarf` borrows.
+#[derive(Debug)]
+pub struct DwarfSections<'a> {
+    sections: gimli::DwarfSections<Cow<'a, [u8]>>,
+    sup: Option<gimli::DwarfSections<Cow<'a, [u8]>>>,
+    file_type: DwarfFileType,
+    endian: RunTimeEndian,
+}
+
+impl<'a> DwarfSections<'a> {
+    /// Loads the DWARF sections of `elf_file`.
+    pub fn load(elf_file: &ElfFile<'a>) -> Result<DwarfSections<'a>, &'static str> {
+        DwarfSections::load_(elf_file, DwarfFileType::Main)
+    }
+
+    /// Loads the `.debug_*.dwo` sections of `elf_file`, a split DWARF file.
+    /// Call `make_dwo` with the skeleton unit's `Dwarf` on the result of
+    /// `dwarf` to read it.
+    pub fn load_dwo(elf_file: &ElfFile<'a>) -> Result<DwarfSections<'a>, &'static str> {
+        DwarfSections::load_(elf_file, DwarfFileType::Dwo)
+    }
+
+    fn load_(elf_file: &ElfFile<'a>, file_type: DwarfFileType) -> Result<DwarfSections<'a>, &'static str> {
+        Ok(DwarfSections {
+            sections: load_sections(elf_file, file_type)?,
+            sup: None,
+            file_type,
+            endian: endian(elf_file)?,
+        })
+    }
+
+    /// Loads the sections of `sup_file`, the supplementary file named by
+    /// `.gnu_debugaltlink` (see `debug_file::DebugFileLocator::find_alt`).
+    pub fn load_sup(&mut self, sup_file: &ElfFile<'a>) -> Result<(), &'static str> {
+        check!(endian(sup_file)? == self.endian, "Supplementary file has a different byte order");
+        self.sup = Some(load_sections(sup_file, DwarfFileType::Main)?);
+        Ok(())
+    }
+
+    pub fn endian(&self) -> RunTimeEndian {
+        self.endian
+    }
+
+    /// A `gimli::Dwarf` reading these sections.
+    pub fn dwarf<'s>(&'s self) -> gimli::Dwarf<EndianSlice<'s, RunTimeEndian>> {
+        let endian = self.endian;
+        let borrow = |section: &'s Cow<'a, [u8]>| EndianSlice::new(section, endian);
+        let mut dwarf = match self.sup {
+            Some(ref sup) => self.sections.borrow_with_sup(sup, borrow),
+            None => self.sections.borrow(borrow),
+        };
+        dwarf.file_type = self.file_type;
+        dwarf
+    }
+}
+
+/// The byte order of `elf_file`, as gimli wants it.
+pub fn endian(elf_file: &ElfFile) -> Result<RunTimeEndian, &'static str> {
+    match elf_file.header.pt1.data() {
+        Data::LittleEndian => Ok(RunTimeEndian::Little),
+        Data::BigEndian => Ok(RunTimeEndian::Big),
+        Data::None | Data::Other(_) => Err("Invalid ELF data encoding"),
+    }
+}
+
+fn load_sections<'a>(elf_file: &ElfFile<'a>,
+                     file_type: DwarfFileType)
+                     -> Result<gimli::DwarfSections<Cow<'a, [u8]>>, &'static str> {
+    let is_64 = match elf_file.header.pt1.class() {
+        Class::ThirtyTwo => false,
+        Class::SixtyFour => true,
+        Class::None | Class::Other(_) => return Err("Invalid ELF class"),
+    };
+    elf_file.check_tables(is_64)?;
+    gimli::DwarfSections::load(|id: SectionId| {
+        let name = match file_type {
+            DwarfFileType::Dwo => id.dwo_name(),
+            _ => Some(id.name()),
+        };
+        let section = match name.and_then(|name| elf_file.find_section_by_name(name)) {
+            Some(section) => section,
+            None => return Ok(Cow::Borrowed(&[][..])),
+        };
+        if section.get_type()? == ShType::NoBits {
+            return Ok(Cow::Borrowed(&[][..]));
+        }
+        let end = section.offset().checked_add(section.size());
+        check!(end.is_some_and(|end| end <= elf_file.input.len() as u64), "Section data out of range");
+        section.decompressed_data(elf_file)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+    use sections::test::{mk_file, mk_zdebug};
+
+    #[test]
+    fn no_sections() {
+        let mut data = b"\x7fELF\x02\x02\x01".to_vec();
+        data.resize(64, 0);
+        let elf = ElfFile::new(&data).unwrap();
+        let sections = elf.dwarf().unwrap();
+        assert_eq!(sections.endian(), RunTimeEndian::Big);
+        assert!(sections.dwarf().units().next().unwrap().is_none());
+    }
+
+    #[test]
+    fn zdebug_info() {
+        // A compile unit named "hello.c", in C99.
+        let abbrev = [0x01, 0x11, 0x00, 0x03, 0x08, 0x13, 0x0b, 0x00, 0x00, 0x00];
+        // Its 21 bytes of `.debug_info`, compressed.
+        let info = [0x78, 0xda, 0x13, 0x64, 0x60, 0x60, 0x60, 0x61, 0x00, 0x01, 0x0e, 0xc6, 0x8c, 0xd4, 0x9c, 0x9c,
+                    0x7c, 0xbd, 0x64, 0x06, 0x1e, 0x00, 0x12, 0x84, 0x02, 0xd0];
+        let input = mk_file(&[(".debug_abbrev", &abbrev), (".zdebug_info", &mk_zdebug(&info, 21))]);
+        let elf = ElfFile::new(&input).unwrap();
+        let sections = elf.dwarf().unwrap();
+        let dwarf = sections.dwarf();
+
+        let mut units = dwarf.units();
+        let unit = dwarf.unit(units.next().unwrap().unwrap()).unwrap();
+        assert!(units.next().unwrap().is_none());
+        assert_eq!(unit.header.version(), 4);
+        let mut entries = unit.entries();
+        let (_, entry) = entries.next_dfs().unwrap().unwrap();
+        assert_eq!(entry.tag(), gimli::DW_TAG_compile_unit);
+        let name = entry.attr_value(gimli::DW_AT_name).unwrap().unwrap();
+        assert_eq!(dwarf.attr_string(&unit, name).unwrap().slice(), b"hello.c");
+        assert_eq!(entry.attr_value(gimli::DW_AT_language).unwrap(),
+                   Some(gimli::AttributeValue::Language(gimli::DW_LANG_C99)));
+    }
+}
@@ -1,5 +1,6 @@
 use core::fmt;
-use zero::Pod;
+use core::mem;
+use zero::{read_array, Pod};
 use {P32, P64};
 
 #[derive(Debug)]
@@ -18,6 +19,7 @@ unsafe impl<P> Pod for Dynamic<P> where Tag_<P>: fmt::Debug {}
 pub struct Tag_<P>(P);
 
 #[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Tag<P> {
     Null,
     Needed,
@@ -56,11 +58,139 @@ pub enum Tag<P> {
     RelrSize,
     Relr,
     RelrEnt,
+    Crel,
+    AndroidRel,
+    AndroidRelSize,
+    AndroidRela,
+    AndroidRelaSize,
+    AndroidRelr,
+    AndroidRelrSize,
+    AndroidRelrEnt,
+    GnuPrelinked,
+    GnuConflictSize,
+    GnuLibListSize,
+    Checksum,
+    PltPadSize,
+    MoveEnt,
+    MoveSize,
+    Feature1,
+    PosFlag1,
+    SymInfoSize,
+    SymInfoEnt,
+    GnuHash,
+    TlsDescPlt,
+    TlsDescGot,
+    GnuConflict,
+    GnuLibList,
+    Config,
+    DepAudit,
+    Audit,
+    PltPad,
+    MoveTab,
+    SymInfo,
+    VerSym,
+    RelaCount,
+    RelCount,
     Flags1,
+    VerDef,
+    VerDefNum,
+    VerNeed,
+    VerNeedNum,
+    Auxiliary,
+    Used,
+    Filter,
     OsSpecific(P),
     ProcessorSpecific(P),
 }
 
+impl<P> Tag<P> {
+    /// The name, as in `DT_GNU_HASH` without the prefix.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Tag::Null => "NULL",
+            Tag::Needed => "NEEDED",
+            Tag::PltRelSize => "PLTRELSZ",
+            Tag::Pltgot => "PLTGOT",
+            Tag::Hash => "HASH",
+            Tag::StrTab => "STRTAB",
+            Tag::SymTab => "SYMTAB",
+            Tag::Rela => "RELA",
+            Tag::RelaSize => "RELASZ",
+            Tag::RelaEnt => "RELAENT",
+            Tag::StrSize => "STRSZ",
+            Tag::SymEnt => "SYMENT",
+            Tag::Init => "INIT",
+            Tag::Fini => "FINI",
+            Tag::SoName => "SONAME",
+            Tag::RPath => "RPATH",
+            Tag::Symbolic => "SYMBOLIC",
+            Tag::Rel => "REL",
+            Tag::RelSize => "RELSZ",
+            Tag::RelEnt => "RELENT",
+            Tag::PltRel => "PLTREL",
+            Tag::Debug => "DEBUG",
+            Tag::TextRel => "TEXTREL",
+            Tag::JmpRel => "JMPREL",
+            Tag::BindNow => "BIND_NOW",
+            Tag::InitArray => "INIT_ARRAY",
+            Tag::FiniArray => "FINI_ARRAY",
+            Tag::InitArraySize => "INIT_ARRAYSZ",
+            Tag::FiniArraySize => "FINI_ARRAYSZ",
+            Tag::RunPath => "RUNPATH",
+            Tag::Flags => "FLAGS",
+            Tag::PreInitArray => "PREINIT_ARRAY",
+            Tag::PreInitArraySize => "PREINIT_ARRAYSZ",
+            Tag::SymTabShIndex => "SYMTAB_SHNDX",
+            Tag::RelrSize => "RELRSZ",
+            Tag::Relr => "RELR",
+            Tag::RelrEnt => "RELRENT",
+            Tag::Crel => "CREL",
+            Tag::AndroidRel => "ANDROID_REL",
+            Tag::AndroidRelSize => "ANDROID_RELSZ",
+            Tag::AndroidRela => "ANDROID_RELA",
+            Tag::AndroidRelaSize => "ANDROID_RELASZ",
+            Tag::AndroidRelr => "ANDROID_RELR",
+            Tag::AndroidRelrSize => "ANDROID_RELRSZ",
+            Tag::AndroidRelrEnt => "ANDROID_RELRENT",
+            Tag::GnuPrelinked => "GNU_PRELINKED",
+            Tag::GnuConflictSize => "GNU_CONFLICTSZ",
+            Tag::GnuLibListSize => "GNU_LIBLISTSZ",
+            Tag::Checksum => "CHECKSUM",
+            Tag::PltPadSize => "PLTPADSZ",
+            Tag::MoveEnt => "MOVEENT",
+            Tag::MoveSize => "MOVESZ",
+            Tag::Feature1 => "FEATURE_1",
+            Tag::PosFlag1 => "POSFLAG_1",
+            Tag::SymInfoSize => "SYMINSZ",
+            Tag::SymInfoEnt => "SYMINENT",
+            Tag::GnuHash => "GNU_HASH",
+            Tag::TlsDescPlt => "TLSDESC_PLT",
+            Tag::TlsDescGot => "TLSDESC_GOT",
+            Tag::GnuConflict => "GNU_CONFLICT",
+            Tag::GnuLibList => "GNU_LIBLIST",
+            Tag::Config => "CONFIG",
+            Tag::DepAudit => "DEPAUDIT",
+            Tag::Audit => "AUDIT",
+            Tag::PltPad => "PLTPAD",
+            Tag::MoveTab => "MOVETAB",
+            Tag::SymInfo => "SYMINFO",
+            Tag::VerSym => "VERSYM",
+            Tag::RelaCount => "RELACOUNT",
+            Tag::RelCount => "RELCOUNT",
+            Tag::Flags1 => "FLAGS_1",
+            Tag::VerDef => "VERDEF",
+            Tag::VerDefNum => "VERDEFNUM",
+            Tag::VerNeed => "VERNEED",
+            Tag::VerNeedNum => "VERNEEDNUM",
+            Tag::Auxiliary => "AUXILIARY",
+            Tag::Used => "USED",
+            Tag::Filter => "FILTER",
+            Tag::OsSpecific(_) => "OS-specific",
+            Tag::ProcessorSpecific(_) => "processor-specific",
+        }
+    }
+}
+
 macro_rules! impls {
     ($p: ident) => {
         impl Dynamic<$p> {
@@ -68,6 +198,16 @@ macro_rules! impls {
                 self.tag.as_tag()
             }
 
+            /// The tag as stored, including tags which `get_tag` rejects.
+            pub fn raw_tag(&self) -> $p {
+                self.tag.0
+            }
+
+            /// The value or pointer, whichever the tag uses.
+            pub fn raw_val(&self) -> $p {
+                self.un
+            }
+
             pub fn get_val(&self) -> Result<$p, &'static str> {
                 match self.get_tag()? {
                     Tag::Needed
@@ -88,7 +228,29 @@ macro_rules! impls {
                     | Tag::PreInitArraySize
                     | Tag::RelrSize
                     | Tag::RelrEnt
+                    | Tag::AndroidRelSize
+                    | Tag::AndroidRelaSize
+                    | Tag::AndroidRelrSize
+                    | Tag::AndroidRelrEnt
+                    | Tag::GnuPrelinked
+                    | Tag::GnuConflictSize
+                    | Tag::GnuLibListSize
+                    | Tag::Checksum
+                    | Tag::PltPadSize
+                    | Tag::MoveEnt
+                    | Tag::MoveSize
+                    | Tag::Feature1
+                    | Tag::PosFlag1
+                    | Tag::SymInfoSize
+                    | Tag::SymInfoEnt
+                    | Tag::RelaCount
+                    | Tag::RelCount
                     | Tag::Flags1
+                    | Tag::VerDefNum
+                    | Tag::VerNeedNum
+                    | Tag::Auxiliary
+                    | Tag::Used
+                    | Tag::Filter
                     | Tag::OsSpecific(_)
                     | Tag::ProcessorSpecific(_) => Ok(self.un),
                     _ => Err("Invalid value"),
@@ -112,6 +274,24 @@ macro_rules! impls {
                     | Tag::PreInitArray
                     | Tag::SymTabShIndex
                     | Tag::Relr
+                    | Tag::Crel
+                    | Tag::AndroidRel
+                    | Tag::AndroidRela
+                    | Tag::AndroidRelr
+                    | Tag::GnuHash
+                    | Tag::TlsDescPlt
+                    | Tag::TlsDescGot
+                    | Tag::GnuConflict
+                    | Tag::GnuLibList
+                    | Tag::Config
+                    | Tag::DepAudit
+                    | Tag::Audit
+                    | Tag::PltPad
+                    | Tag::MoveTab
+                    | Tag::SymInfo
+                    | Tag::VerSym
+                    | Tag::VerDef
+                    | Tag::VerNeed
                     | Tag::OsSpecific(_)
                     | Tag::ProcessorSpecific(_) => Ok(self.un),
                     _ => Err("Invalid ptr"),
@@ -159,7 +339,47 @@ macro_rules! impls {
                     35 => Ok(Tag::RelrSize),
                     36 => Ok(Tag::Relr),
                     37 => Ok(Tag::RelrEnt),
+                    0x40000026 => Ok(Tag::Crel),
+                    0x6000000f => Ok(Tag::AndroidRel),
+                    0x60000010 => Ok(Tag::AndroidRelSize),
+                    0x60000011 => Ok(Tag::AndroidRela),
+                    0x60000012 => Ok(Tag::AndroidRelaSize),
+                    0x6fffe000 => Ok(Tag::AndroidRelr),
+                    0x6fffe001 => Ok(Tag::AndroidRelrSize),
+                    0x6fffe003 => Ok(Tag::AndroidRelrEnt),
+                    0x6ffffdf5 => Ok(Tag::GnuPrelinked),
+                    0x6ffffdf6 => Ok(Tag::GnuConflictSize),
+                    0x6ffffdf7 => Ok(Tag::GnuLibListSize),
+                    0x6ffffdf8 => Ok(Tag::Checksum),
+                    0x6ffffdf9 => Ok(Tag::PltPadSize),
+                    0x6ffffdfa => Ok(Tag::MoveEnt),
+                    0x6ffffdfb => Ok(Tag::MoveSize),
+                    0x6ffffdfc => Ok(Tag::Feature1),
+                    0x6ffffdfd => Ok(Tag::PosFlag1),
+                    0x6ffffdfe => Ok(Tag::SymInfoSize),
+                    0x6ffffdff => Ok(Tag::SymInfoEnt),
+                    0x6ffffef5 => Ok(Tag::GnuHash),
+                    0x6ffffef6 => Ok(Tag::TlsDescPlt),
+                    0x6ffffef7 => Ok(Tag::TlsDescGot),
+                    0x6ffffef8 => Ok(Tag::GnuConflict),
+                    0x6ffffef9 => Ok(Tag::GnuLibList),
+                    0x6ffffefa => Ok(Tag::Config),
+                    0x6ffffefb => Ok(Tag::DepAudit),
+                    0x6ffffefc => Ok(Tag::Audit),
+                    0x6ffffefd => Ok(Tag::PltPad),
+                    0x6ffffefe => Ok(Tag::MoveTab),
+                    0x6ffffeff => Ok(Tag::SymInfo),
+                    0x6ffffff0 => Ok(Tag::VerSym),
+                    0x6ffffff9 => Ok(Tag::RelaCount),
+                    0x6ffffffa => Ok(Tag::RelCount),
                     0x6ffffffb => Ok(Tag::Flags1),
+                    0x6ffffffc => Ok(Tag::VerDef),
+                    0x6ffffffd => Ok(Tag::VerDefNum),
+                    0x6ffffffe => Ok(Tag::VerNeed),
+                    0x6fffffff => Ok(Tag::VerNeedNum),
+                    0x7ffffffd => Ok(Tag::Auxiliary),
+                    0x7ffffffe => Ok(Tag::Used),
+                    0x7fffffff => Ok(Tag::Filter),
                     t if (0x6000000D..0x70000000).contains(&t) => Ok(Tag::OsSpecific(t)),
                     t if (0x70000000..0x80000000).contains(&t) => Ok(Tag::ProcessorSpecific(t)),
                     _ => Err("Invalid tag value"),
@@ -178,6 +398,24 @@ macro_rules! impls {
 impls!(P32);
 impls!(P64);
 
+// The entries of a dynamic table read from a file, checked to be aligned so
+// that they can be read in place. A partial entry at the end is left out.
+pub(crate) fn entries<P>(data: &[u8]) -> Result<&[Dynamic<P>], &'static str>
+where
+    Tag_<P>: fmt::Debug,
+{
+    let size = mem::size_of::<Dynamic<P>>();
+    check!(data.as_ptr() as usize % mem::align_of::<Dynamic<P>>() == 0, "Dynamic table is misaligned");
+    Ok(read_array(&data[..data.len() - data.len() % size]))
+}
+
+/* Flag values used in the DT_FLAGS .dynamic entry.  */
+pub const FLAG_ORIGIN: u64 = 0x00000001;
+pub const FLAG_SYMBOLIC: u64 = 0x00000002;
+pub const FLAG_TEXTREL: u64 = 0x00000004;
+pub const FLAG_BIND_NOW: u64 = 0x00000008;
+pub const FLAG_STATIC_TLS: u64 = 0x00000010;
+
 /* Flag values used in the DT_FLAGS_1 .dynamic entry.  */
 pub const FLAG_1_NOW: u64 = 0x00000001;
 pub const FLAG_1_GLOBAL: u64 = 0x00000002;
@@ -207,3 +445,48 @@ pub const FLAG_1_GLOBAUDIT: u64 = 0x01000000;
 pub const FLAG_1_SINGLETON: u64 = 0x02000000;
 pub const FLAG_1_STUB: u64 = 0x04000000;
 pub const FLAG_1_PIE: u64 = 0x08000000;
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    #[test]
+    fn type_names() {
+        let tags = [(0x6000000d, Tag::OsSpecific(0x6000000d), "OS-specific"),
+                    (0x6000000e, Tag::OsSpecific(0x6000000e), "OS-specific"),
+                    (0x6ffffd00, Tag::OsSpecific(0x6ffffd00), "OS-specific"),
+                    (0x6ffffdf5, Tag::GnuPrelinked, "GNU_PRELINKED"),
+                    (0x6ffffdf6, Tag::GnuConflictSize, "GNU_CONFLICTSZ"),
+                    (0x6ffffdf7, Tag::GnuLibListSize, "GNU_LIBLISTSZ"),
+                    (0x6ffffdf8, Tag::Checksum, "CHECKSUM"),
+                    (0x6ffffdfc, Tag::Feature1, "FEATURE_1"),
+                    (0x6ffffdff, Tag::SymInfoEnt, "SYMINENT"),
+                    (0x6ffffe00, Tag::OsSpecific(0x6ffffe00), "OS-specific"),
+                    (0x6ffffef5, Tag::GnuHash, "GNU_HASH"),
+                    (0x6ffffef6, Tag::TlsDescPlt, "TLSDESC_PLT"),
+                    (0x6ffffef7, Tag::TlsDescGot, "TLSDESC_GOT"),
+                    (0x6ffffef8, Tag::GnuConflict, "GNU_CONFLICT"),
+                    (0x6ffffef9, Tag::GnuLibList, "GNU_LIBLIST"),
+                    (0x6ffffeff, Tag::SymInfo, "SYMINFO"),
+                    (0x6ffffff0, Tag::VerSym, "VERSYM"),
+                    (0x6ffffff9, Tag::RelaCount, "RELACOUNT"),
+                    (0x6ffffffb, Tag::Flags1, "FLAGS_1"),
+                    (0x6ffffffc, Tag::VerDef, "VERDEF"),
+                    (0x6ffffffd, Tag::VerDefNum, "VERDEFNUM"),
+                    (0x6ffffffe, Tag::VerNeed, "VERNEED"),
+                    (0x6fffffff, Tag::VerNeedNum, "VERNEEDNUM"),
+                    (0x70000000, Tag::ProcessorSpecific(0x70000000), "processor-specific"),
+                    (0x7ffffffc, Tag::ProcessorSpecific(0x7ffffffc), "processor-specific"),
+                    (0x7ffffffd, Tag::Auxiliary, "AUXILIARY"),
+                    (0x7ffffffe, Tag::Used, "USED"),
+                    (0x7fffffff, Tag::Filter, "FILTER")];
+        for (value, tag, name) in tags {
+            assert_eq!(Tag_::<P64>(value).as_tag(), Ok(tag), "{:#x}", value);
+            assert_eq!(Tag_::<P32>(value as P32).as_tag().map(|tag| tag.name()), Ok(name));
+        }
+        assert!(Tag_::<P64>(0x6000000c).as_tag().is_err());
+        assert!(Tag_::<P64>(0x80000000).as_tag().is_err());
+    }
+}
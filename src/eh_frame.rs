@@ -0,0 +1,666 @@
+//! The call frame information in `.eh_frame`, which unwinders use to find
+//! callers' registers, and the search table for it in `.eh_frame_hdr` (the
+//! `PT_GNU_EH_FRAME` segment). The FDEs also give the bounds of functions in
+//! stripped files. `.debug_frame`, which has the same layout, can be read
+//! too; see `unwind` for running the instructions in them.
+
+use core::convert::TryFrom;
+
+use ElfFile;
+use header::Class;
+use reader::Reader;
+use program;
+use sections::{ShType, SHF_COMPRESSED};
+
+// Pointer encodings: the format, in the low four bits...
+pub const DW_EH_PE_ABSPTR: u8 = 0x00;
+pub const DW_EH_PE_ULEB128: u8 = 0x01;
+pub const DW_EH_PE_UDATA2: u8 = 0x02;
+pub const DW_EH_PE_UDATA4: u8 = 0x03;
+pub const DW_EH_PE_UDATA8: u8 = 0x04;
+pub const DW_EH_PE_SLEB128: u8 = 0x09;
+pub const DW_EH_PE_SDATA2: u8 = 0x0a;
+pub const DW_EH_PE_SDATA4: u8 = 0x0b;
+pub const DW_EH_PE_SDATA8: u8 = 0x0c;
+// ...what it is relative to...
+pub const DW_EH_PE_PCREL: u8 = 0x10;
+pub const DW_EH_PE_TEXTREL: u8 = 0x20;
+pub const DW_EH_PE_DATAREL: u8 = 0x30;
+pub const DW_EH_PE_FUNCREL: u8 = 0x40;
+pub const DW_EH_PE_ALIGNED: u8 = 0x50;
+// ...and whether it is the address of the pointer.
+pub const DW_EH_PE_INDIRECT: u8 = 0x80;
+/// No value is present.
+pub const DW_EH_PE_OMIT: u8 = 0xff;
+
+/// A decoded pointer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pointer {
+    Direct(u64),
+    /// The address of the pointer, which must be read from memory.
+    Indirect(u64),
+}
+
+/// The `.eh_frame` section, or `.debug_frame`.
+#[derive(Clone, Copy, Debug)]
+pub struct EhFrame<'a> {
+    data: &'a [u8],
+    address: u64,
+    address_size: u8,
+    is_debug_frame: bool,
+}
+
+impl<'a> EhFrame<'a> {
+    /// `address` is where `data` is loaded, which pc-relative pointers are
+    /// relative to, and `address_size` is 4 or 8, as for the file's class.
+    pub fn new(data: &'a [u8], address: u64, address_size: u8) -> EhFrame<'a> {
+        EhFrame { data, address, address_size, is_debug_frame: false }
+    }
+
+    /// A `.debug_frame` section, in which CIE pointers are section offsets
+    /// rather than relative, and addresses are usually absolute.
+    pub fn new_debug_frame(data: &'a [u8], address_size: u8) -> EhFrame<'a> {
+        EhFrame { data, address: 0, address_size, is_debug_frame: true }
+    }
+
+    /// The `.eh_frame` section of `elf_file`, or if it has no section headers,
+    /// the frame information `PT_GNU_EH_FRAME` points to.
+    pub fn load(elf_file: &ElfFile<'a>) -> Result<Option<EhFrame<'a>>, &'static str> {
+        let address_size = address_size(elf_file)?;
+        if let Some(section) = elf_file.find_section_by_name(".eh_frame") {
+            check!(section.get_type()? != ShType::NoBits, ".eh_frame has no data");
+            let data = section.data_checked(elf_file)?;
+            return Ok(Some(EhFrame::new(data, section.address(), address_size)));
+        }
+        let header = match EhFrameHdr::load(elf_file)? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let address = match header.eh_frame_ptr {
+            Pointer::Direct(address) => address,
+            Pointer::Indirect(_) => return Err("Indirect .eh_frame pointer"),
+        };
+        let data = segment_data_at(elf_file, address)?.ok_or(".eh_frame is not in a segment")?;
+        Ok(Some(EhFrame::new(data, address, address_size)))
+    }
+
+    /// The `.debug_frame` section of `elf_file`, which must not be compressed.
+    pub fn load_debug_frame(elf_file: &ElfFile<'a>) -> Result<Option<EhFrame<'a>>, &'static str> {
+        let address_size = address_size(elf_file)?;
+        match elf_file.find_section_by_name(".debug_frame") {
+            Some(section) => {
+                let compressed = section.flags() & SHF_COMPRESSED != 0 ||
+                                 section.get_name(elf_file).is_ok_and(|name| name.starts_with(".zdebug"));
+                check!(!compressed, ".debug_frame is compressed");
+                check!(section.get_type()? != ShType::NoBits, ".debug_frame has no data");
+                let data = section.data_checked(elf_file)?;
+                Ok(Some(EhFrame::new_debug_frame(data, address_size)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn is_debug_frame(&self) -> bool {
+        self.is_debug_frame
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The CIEs and FDEs, in order.
+    pub fn entries(&self) -> Entries<'a> {
+        Entries { eh_frame: *self, offset: 0 }
+    }
+
+    /// The FDEs, in order.
+    pub fn fdes(&self) -> impl Iterator<Item = Result<Fde<'a>, &'static str>> + 'a {
+        self.entries().filter_map(|entry| match entry {
+            Ok(Entry::Cie(_)) => None,
+            Ok(Entry::Fde(fde)) => Some(Ok(fde)),
+            Err(e) => Some(Err(e)),
+        })
+    }
+
+    // The record at `offset`: the data after its length, and the offset of
+    // the next record; or `None` at the terminator.
+    fn record_at(&self, offset: usize) -> Result<Option<Record<'a>>, &'static str> {
+        let mut reader = Reader::new(self.data.get(offset..).ok_or("CFI entry out of range")?);
+        let (length, length_size) = match reader.u32()? {
+            0 => return Ok(None),
+            0xffff_ffff => (reader.u64()?, 12),
+            length => (length as u64, 4),
+        };
+        let data = reader.bytes(length)?;
+        let mut reader = Reader::new(data);
+        let id_address = self.address_of(reader.data);
+        // The CIE id or pointer. In `.eh_frame` it is always 4 bytes, and a
+        // pointer is relative to itself.
+        let (id, cie_id) = match (self.is_debug_frame, length_size == 12) {
+            (true, true) => (reader.u64()?, u64::MAX),
+            (true, false) => (reader.u32()? as u64, 0xffff_ffff),
+            (false, _) => (reader.u32()? as u64, 0),
+        };
+        let cie = if id == cie_id {
+            None
+        } else if self.is_debug_frame {
+            Some(id)
+        } else {
+            Some(id_address.wrapping_sub(id).wrapping_sub(self.address))
+        };
+        Ok(Some(Record { reader, cie, next: offset + length_size + data.len() }))
+    }
+
+    // The entry at `offset`, and the offset of the next.
+    fn entry_at(&self, offset: usize) -> Result<Option<(Entry<'a>, usize)>, &'static str> {
+        let record = match self.record_at(offset)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let entry = match record.cie {
+            None => Entry::Cie(self.parse_cie(offset, record.reader)?),
+            Some(cie) => {
+                let cie = usize::try_from(cie).map_err(|_| "CIE out of range")?;
+                Entry::Fde(self.parse_fde(offset, self.cie_at(cie)?, record.reader)?)
+            }
+        };
+        Ok(Some((entry, record.next)))
+    }
+
+    /// The CIE at `offset`.
+    pub fn cie_at(&self, offset: usize) -> Result<Cie<'a>, &'static str> {
+        match self.record_at(offset)? {
+            Some(Record { reader, cie: None, .. }) => self.parse_cie(offset, reader),
+            _ => Err("Not a CIE"),
+        }
+    }
+
+    /// The FDE at `offset`.
+    pub fn fde_at(&self, offset: usize) -> Result<Fde<'a>, &'static str> {
+        match self.entry_at(offset)? {
+            Some((Entry::Fde(fde), _)) => Ok(fde),
+            _ => Err("Not an FDE"),
+        }
+    }
+
+    /// Finds the FDE covering `pc` by searching all of them; prefer
+    /// `find_fde`, which uses the search table if there is one.
+    pub fn find_fde(&self, pc: u64) -> Result<Option<Fde<'a>>, &'static str> {
+        for fde in self.fdes() {
+            let fde = fde?;
+            if fde.contains(pc) {
+                return Ok(Some(fde));
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse_cie(&self, offset: usize, mut reader: Reader<'a>) -> Result<Cie<'a>, &'static str> {
+        let version = reader.u8()?;
+        check!(version == 1 || version == 3 || version == 4, "Unsupported CIE version");
+        let augmentation = reader.c_str()?;
+        let mut address_size = self.address_size;
+        if version == 4 {
+            address_size = reader.u8()?;
+            check!(address_size == 4 || address_size == 8, "Unsupported CIE address size");
+            check!(reader.u8()? == 0, "Segment selectors are not supported");
+        }
+        let code_alignment_factor = reader.uleb()?;
+        let data_alignment_factor = reader.sleb()?;
+        let return_address_register = if version == 1 { reader.u8()? as u64 } else { reader.uleb()? };
+
+        let mut cie = Cie {
+            offset,
+            version,
+            augmentation,
+            address_size,
+            code_alignment_factor,
+            data_alignment_factor,
+            return_address_register,
+            fde_encoding: DW_EH_PE_ABSPTR,
+            lsda_encoding: DW_EH_PE_OMIT,
+            personality: None,
+            is_signal_frame: false,
+            has_augmentation_data: false,
+            initial_instructions: &[],
+        };
+        if let Some(rest) = augmentation.strip_prefix('z') {
+            cie.has_augmentation_data = true;
+            let length = reader.uleb()?;
+            let mut data = Reader::new(reader.bytes(length)?);
+            for c in rest.chars() {
+                match c {
+                    'L' => cie.lsda_encoding = data.u8()?,
+                    'P' => {
+                        let encoding = data.u8()?;
+                        cie.personality = Some(self.read_pointer(&mut data, encoding, address_size)?);
+                    }
+                    'R' => cie.fde_encoding = data.u8()?,
+                    'S' => cie.is_signal_frame = true,
+                    // The AArch64 pointer authentication key.
+                    'B' => {}
+                    // The rest of the augmentation data cannot be understood,
+                    // but its length lets it be skipped.
+                    _ => break,
+                }
+            }
+        } else {
+            check!(augmentation.is_empty(), "Unsupported CIE augmentation");
+        }
+        cie.initial_instructions = reader.data;
+        Ok(cie)
+    }
+
+    fn parse_fde(&self, offset: usize, cie: Cie<'a>, mut reader: Reader<'a>) -> Result<Fde<'a>, &'static str> {
+        let pc_begin = match self.read_pointer(&mut reader, cie.fde_encoding, cie.address_size)? {
+            Pointer::Direct(address) => address,
+            Pointer::Indirect(_) => return Err("Indirect FDE address"),
+        };
+        let pc_range = read_value(&mut reader, cie.fde_encoding & 0x0f, cie.address_size)?;
+        let mut lsda = None;
+        if cie.has_augmentation_data {
+            let length = reader.uleb()?;
+            let mut data = Reader::new(reader.bytes(length)?);
+            if cie.lsda_encoding != DW_EH_PE_OMIT {
+                lsda = Some(self.read_pointer(&mut data, cie.lsda_encoding, cie.address_size)?);
+            }
+        }
+        Ok(Fde { offset, cie, pc_begin, pc_range, lsda, instructions: reader.data })
+    }
+
+    fn address_of(&self, data: &[u8]) -> u64 {
+        self.address.wrapping_add((data.as_ptr() as usize - self.data.as_ptr() as usize) as u64)
+    }
+
+    fn read_pointer(&self, reader: &mut Reader<'a>, encoding: u8, address_size: u8) -> Result<Pointer, &'static str> {
+        let address = self.address_of(reader.data);
+        read_pointer(reader, encoding, address, None, address_size)
+    }
+}
+
+// A CIE or FDE: the data after the CIE id or pointer, the offset of the CIE
+// if it is an FDE, and the offset of the next record.
+struct Record<'a> {
+    reader: Reader<'a>,
+    cie: Option<u64>,
+    next: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Entries<'a> {
+    eh_frame: EhFrame<'a>,
+    offset: usize,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<Entry<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.eh_frame.data.len() {
+            return None;
+        }
+        match self.eh_frame.entry_at(self.offset) {
+            Ok(Some((entry, next))) => {
+                self.offset = next;
+                Some(Ok(entry))
+            }
+            Ok(None) => {
+                self.offset = self.eh_frame.data.len();
+                None
+            }
+            Err(e) => {
+                self.offset = self.eh_frame.data.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Entry<'a> {
+    Cie(Cie<'a>),
+    Fde(Fde<'a>),
+}
+
+/// A Common Information Entry, shared by FDEs.
+#[derive(Clone, Copy, Debug)]
+pub struct Cie<'a> {
+    /// The offset in the section.
+    pub offset: usize,
+    pub version: u8,
+    pub augmentation: &'a str,
+    pub address_size: u8,
+    pub code_alignment_factor: u64,
+    pub data_alignment_factor: i64,
+    pub return_address_register: u64,
+    /// The encoding of the FDEs' addresses.
+    pub fde_encoding: u8,
+    /// The encoding of the FDEs' LSDA pointers, or `DW_EH_PE_OMIT`.
+    pub lsda_encoding: u8,
+    pub personality: Option<Pointer>,
+    pub is_signal_frame: bool,
+    has_augmentation_data: bool,
+    pub initial_instructions: &'a [u8],
+}
+
+/// A Frame Description Entry, which describes a function.
+#[derive(Clone, Copy, Debug)]
+pub struct Fde<'a> {
+    /// The offset in the section.
+    pub offset: usize,
+    pub cie: Cie<'a>,
+    pub pc_begin: u64,
+    pub pc_range: u64,
+    /// The language-specific data area, used for exception handling.
+    pub lsda: Option<Pointer>,
+    pub instructions: &'a [u8],
+}
+
+impl<'a> Fde<'a> {
+    pub fn pc_end(&self) -> u64 {
+        self.pc_begin.wrapping_add(self.pc_range)
+    }
+
+    pub fn contains(&self, pc: u64) -> bool {
+        self.pc_begin <= pc && pc < self.pc_end()
+    }
+}
+
+/// The `.eh_frame_hdr` section, with a pointer to `.eh_frame` and usually a
+/// table of FDEs sorted by address.
+#[derive(Clone, Copy, Debug)]
+pub struct EhFrameHdr<'a> {
+    pub version: u8,
+    pub eh_frame_ptr: Pointer,
+    /// The number of entries in the table, 0 if there is none.
+    pub fde_count: u64,
+    table_encoding: u8,
+    table: &'a [u8],
+    // The address of the table.
+    table_address: u64,
+    address: u64,
+    address_size: u8,
+}
+
+impl<'a> EhFrameHdr<'a> {
+    /// `address` is where `data` is loaded, and `address_size` is 4 or 8, as
+    /// for the file's class.
+    pub fn new(data: &'a [u8], address: u64, address_size: u8) -> Result<EhFrameHdr<'a>, &'static str> {
+        let mut reader = Reader::new(data);
+        let version = reader.u8()?;
+        check!(version == 1, "Unsupported .eh_frame_hdr version");
+        let eh_frame_ptr_encoding = reader.u8()?;
+        let fde_count_encoding = reader.u8()?;
+        let table_encoding = reader.u8()?;
+        let at = |reader: &Reader| address.wrapping_add((data.len() - reader.data.len()) as u64);
+
+        let field = at(&reader);
+        let eh_frame_ptr = read_pointer(&mut reader, eh_frame_ptr_encoding, field, Some(address), address_size)?;
+        let mut fde_count = 0;
+        let mut table: &[u8] = &[];
+        let mut table_address = 0;
+        if fde_count_encoding != DW_EH_PE_OMIT && table_encoding != DW_EH_PE_OMIT {
+            let field = at(&reader);
+            fde_count = match read_pointer(&mut reader, fde_count_encoding, field, Some(address), address_size)? {
+                Pointer::Direct(count) => count,
+                Pointer::Indirect(_) => return Err("Indirect .eh_frame_hdr FDE count"),
+            };
+            let entry_size = value_size(table_encoding & 0x0f, address_size)
+                .ok_or("Unsupported .eh_frame_hdr table encoding")? * 2;
+            let length = fde_count.checked_mul(entry_size as u64).ok_or(".eh_frame_hdr table out of range")?;
+            table_address = at(&reader);
+            table = reader.bytes(length)?;
+        }
+        Ok(EhFrameHdr {
+            version,
+            eh_frame_ptr,
+            fde_count,
+            table_encoding,
+            table,
+            table_address,
+            address,
+            address_size,
+        })
+    }
+
+    /// The `.eh_frame_hdr` section of `elf_file`, or its `PT_GNU_EH_FRAME`
+    /// segment.
+    pub fn load(elf_file: &ElfFile<'a>) -> Result<Option<EhFrameHdr<'a>>, &'static str> {
+        let address_size = address_size(elf_file)?;
+        if let Some(section) = elf_file.find_section_by_name(".eh_frame_hdr") {
+            let data = section.data_checked(elf_file)?;
+            return EhFrameHdr::new(data, section.address(), address_size).map(Some);
+        }
+        let segment = elf_file.program_iter().find(|ph| ph.get_type() == Ok(program::Type::GnuEhFrame));
+        match segment {
+            Some(segment) => {
+                let data = segment.data_checked(elf_file)?;
+                EhFrameHdr::new(data, segment.virtual_addr(), address_size).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The table's entries: the start address of each function and the
+    /// address of its FDE.
+    pub fn table(&self) -> impl Iterator<Item = Result<(u64, u64), &'static str>> + 'a {
+        let header = *self;
+        (0..self.fde_count as usize).map(move |i| header.table_entry(i))
+    }
+
+    fn table_entry(&self, index: usize) -> Result<(u64, u64), &'static str> {
+        let entry_size = self.table.len() / self.fde_count as usize;
+        let start = index * entry_size;
+        let mut reader = Reader::new(&self.table[start..start + entry_size]);
+        let read = |reader: &mut Reader| {
+            let field_offset = reader.data.as_ptr() as usize - self.table.as_ptr() as usize;
+            let field = self.table_address.wrapping_add(field_offset as u64);
+            match read_pointer(reader, self.table_encoding, field, Some(self.address), self.address_size)? {
+                Pointer::Direct(value) => Ok(value),
+                Pointer::Indirect(_) => Err("Indirect .eh_frame_hdr table entry"),
+            }
+        };
+        Ok((read(&mut reader)?, read(&mut reader)?))
+    }
+
+    /// Finds the address of the FDE which may cover `pc`: the one for the
+    /// last function starting at or before it.
+    pub fn lookup(&self, pc: u64) -> Result<Option<u64>, &'static str> {
+        let (mut low, mut high) = (0, self.fde_count as usize);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.table_entry(middle)?.0 <= pc {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        if low == 0 {
+            return Ok(None);
+        }
+        self.table_entry(low - 1).map(|(_, fde)| Some(fde))
+    }
+}
+
+/// Finds the FDE covering `pc` in `elf_file`, using the `.eh_frame_hdr` table
+/// if there is one.
+pub fn find_fde<'a>(elf_file: &ElfFile<'a>, pc: u64) -> Result<Option<Fde<'a>>, &'static str> {
+    let eh_frame = match EhFrame::load(elf_file)? {
+        Some(eh_frame) => eh_frame,
+        None => return Ok(None),
+    };
+    match EhFrameHdr::load(elf_file)? {
+        Some(header) if header.fde_count > 0 => {
+            let fde = match header.lookup(pc)? {
+                Some(address) => address.wrapping_sub(eh_frame.address),
+                None => return Ok(None),
+            };
+            let fde = eh_frame.fde_at(usize::try_from(fde).map_err(|_| "FDE out of range")?)?;
+            Ok(if fde.contains(pc) { Some(fde) } else { None })
+        }
+        _ => eh_frame.find_fde(pc),
+    }
+}
+
+fn address_size(elf_file: &ElfFile) -> Result<u8, &'static str> {
+    let is_64 = match elf_file.header.pt1.class() {
+        Class::ThirtyTwo => false,
+        Class::SixtyFour => true,
+        Class::None | Class::Other(_) => return Err("Invalid ELF class"),
+    };
+    elf_file.check_tables(is_64)?;
+    Ok(if is_64 { 8 } else { 4 })
+}
+
+// The file data from `address` to the end of the loaded segment containing it.
+fn segment_data_at<'a>(elf_file: &ElfFile<'a>, address: u64) -> Result<Option<&'a [u8]>, &'static str> {
+    for segment in elf_file.program_iter() {
+        let start = segment.virtual_addr();
+        if segment.get_type() == Ok(program::Type::Load) && start <= address &&
+           address - start < segment.file_size() {
+            let data = segment.data_checked(elf_file)?;
+            return Ok(Some(&data[(address - start) as usize..]));
+        }
+    }
+    Ok(None)
+}
+
+// Reads a pointer with `encoding`, which is at `address`. `data_base` is what
+// `DW_EH_PE_datarel` pointers are relative to.
+fn read_pointer(reader: &mut Reader,
+                encoding: u8,
+                address: u64,
+                data_base: Option<u64>,
+                address_size: u8)
+                -> Result<Pointer, &'static str> {
+    check!(encoding != DW_EH_PE_OMIT, "Pointer is omitted");
+    let base = match encoding & 0x70 {
+        DW_EH_PE_ABSPTR => 0,
+        DW_EH_PE_PCREL => address,
+        DW_EH_PE_DATAREL => data_base.ok_or("Unsupported data-relative pointer")?,
+        DW_EH_PE_ALIGNED => {
+            let padding = address.next_multiple_of(address_size as u64) - address;
+            let _ = reader.bytes(padding)?;
+            0
+        }
+        _ => return Err("Unsupported pointer encoding"),
+    };
+    let mut value = base.wrapping_add(read_value(reader, encoding & 0x0f, address_size)?);
+    if address_size == 4 {
+        value &= 0xffff_ffff;
+    }
+    Ok(if encoding & DW_EH_PE_INDIRECT != 0 { Pointer::Indirect(value) } else { Pointer::Direct(value) })
+}
+
+// Reads a value in the pointer format `format`, sign extending signed ones.
+pub(crate) fn read_value(reader: &mut Reader, format: u8, address_size: u8) -> Result<u64, &'static str> {
+    Ok(match format {
+        DW_EH_PE_ABSPTR if address_size == 4 => reader.u32()? as u64,
+        DW_EH_PE_ABSPTR => reader.u64()?,
+        DW_EH_PE_ULEB128 => reader.uleb()?,
+        DW_EH_PE_UDATA2 => reader.u16()? as u64,
+        DW_EH_PE_UDATA4 => reader.u32()? as u64,
+        DW_EH_PE_UDATA8 => reader.u64()?,
+        DW_EH_PE_SLEB128 => reader.sleb()? as u64,
+        DW_EH_PE_SDATA2 => reader.u16()? as i16 as u64,
+        DW_EH_PE_SDATA4 => reader.u32()? as i32 as u64,
+        DW_EH_PE_SDATA8 => reader.u64()?,
+        _ => return Err("Unsupported pointer format"),
+    })
+}
+
+// The size of values in the pointer format `format`, if it is fixed.
+fn value_size(format: u8, address_size: u8) -> Option<usize> {
+    match format {
+        DW_EH_PE_ABSPTR => Some(address_size as usize),
+        DW_EH_PE_UDATA2 | DW_EH_PE_SDATA2 => Some(2),
+        DW_EH_PE_UDATA4 | DW_EH_PE_SDATA4 => Some(4),
+        DW_EH_PE_UDATA8 | DW_EH_PE_SDATA8 => Some(8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+    use sections::test::mk_file;
+
+    // An `.eh_frame` at 0x1000 with a CIE, and at offset 20 an FDE for
+    // 0x2000..0x2100.
+    fn mk_eh_frame() -> Vec<u8> {
+        let mut data = Vec::new();
+        // A CIE with a pc-relative, signed 4-byte FDE encoding, padded with
+        // `DW_CFA_nop`s.
+        data.extend_from_slice(&16u32.to_ne_bytes());
+        data.extend_from_slice(&0u32.to_ne_bytes());
+        data.extend_from_slice(&[1, b'z', b'R', 0, 1, 0x78, 16, 1, DW_EH_PE_PCREL | DW_EH_PE_SDATA4, 0, 0, 0]);
+        // An FDE for 0x2000..0x2100, whose CIE pointer is at offset 24.
+        data.extend_from_slice(&16u32.to_ne_bytes());
+        data.extend_from_slice(&24u32.to_ne_bytes());
+        data.extend_from_slice(&(0x2000u32 - 0x101c).to_ne_bytes());
+        data.extend_from_slice(&0x100u32.to_ne_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&0u32.to_ne_bytes());
+        data
+    }
+
+    #[test]
+    fn cie_and_fde() {
+        let data = mk_eh_frame();
+        let eh_frame = EhFrame::new(&data, 0x1000, 8);
+        let entries: Vec<_> = eh_frame.entries().collect::<Result<_, _>>().unwrap();
+        assert_eq!(entries.len(), 2);
+        let fde = eh_frame.fde_at(20).unwrap();
+        assert_eq!((fde.pc_begin, fde.pc_end()), (0x2000, 0x2100));
+        assert_eq!((fde.cie.code_alignment_factor, fde.cie.data_alignment_factor), (1, -8));
+        assert_eq!(fde.cie.return_address_register, 16);
+        assert_eq!(fde.instructions, &[0, 0, 0][..]);
+        assert_eq!(eh_frame.find_fde(0x20ff).unwrap().map(|fde| fde.offset), Some(20));
+        assert!(eh_frame.find_fde(0x2100).unwrap().is_none());
+        assert!(eh_frame.fde_at(0).is_err());
+    }
+
+    #[test]
+    fn eh_frame_hdr() {
+        // A second FDE, at offset 40, for 0x3000..0x3100.
+        let mut eh_frame = mk_eh_frame();
+        eh_frame.truncate(40);
+        eh_frame.extend_from_slice(&16u32.to_ne_bytes());
+        eh_frame.extend_from_slice(&44u32.to_ne_bytes());
+        eh_frame.extend_from_slice(&(0x3000u32 - 0x1030).to_ne_bytes());
+        eh_frame.extend_from_slice(&0x100u32.to_ne_bytes());
+        eh_frame.extend_from_slice(&[0, 0, 0, 0]);
+        eh_frame.extend_from_slice(&0u32.to_ne_bytes());
+
+        // A header at 0xf00 with a table of data-relative, signed 4-byte
+        // entries.
+        let mut hdr = vec![1, DW_EH_PE_PCREL | DW_EH_PE_SDATA4, DW_EH_PE_UDATA4, DW_EH_PE_DATAREL | DW_EH_PE_SDATA4];
+        hdr.extend_from_slice(&(0x1000u32 - 0xf04).to_ne_bytes());
+        hdr.extend_from_slice(&2u32.to_ne_bytes());
+        for &value in &[0x2000u32, 0x1014, 0x3000, 0x1028] {
+            hdr.extend_from_slice(&(value - 0xf00).to_ne_bytes());
+        }
+
+        let header = EhFrameHdr::new(&hdr, 0xf00, 8).unwrap();
+        assert_eq!((header.eh_frame_ptr, header.fde_count), (Pointer::Direct(0x1000), 2));
+        let table: Vec<_> = header.table().collect::<Result<_, _>>().unwrap();
+        assert_eq!(table, vec![(0x2000, 0x1014), (0x3000, 0x1028)]);
+        assert_eq!(header.lookup(0x1fff), Ok(None));
+        assert_eq!(header.lookup(0x2000), Ok(Some(0x1014)));
+        assert_eq!(header.lookup(0x2fff), Ok(Some(0x1014)));
+        assert_eq!(header.lookup(0x4000), Ok(Some(0x1028)));
+
+        // The same sections in a file, at those addresses.
+        let mut input = mk_file(&[(".eh_frame", &eh_frame), (".eh_frame_hdr", &hdr)]);
+        let headers = input.len() - 2 * 64;
+        input[headers + 16..headers + 24].copy_from_slice(&0x1000u64.to_le_bytes());
+        input[headers + 64 + 16..headers + 64 + 24].copy_from_slice(&0xf00u64.to_le_bytes());
+        let elf_file = ElfFile::new(&input).unwrap();
+        assert_eq!(EhFrameHdr::load(&elf_file).unwrap().map(|header| header.fde_count), Some(2));
+        assert_eq!(find_fde(&elf_file, 0x3080).unwrap().map(|fde| fde.offset), Some(40));
+        assert_eq!(find_fde(&elf_file, 0x2000).unwrap().map(|fde| fde.offset), Some(20));
+        assert!(find_fde(&elf_file, 0x1fff).unwrap().is_none());
+        assert!(find_fde(&elf_file, 0x2100).unwrap().is_none());
+    }
+}
@@ -0,0 +1,276 @@
+//! GNU program properties: the `NT_GNU_PROPERTY_TYPE_0` note in
+//! `.note.gnu.property` (and the `PT_GNU_PROPERTY` segment), which records
+//! the hardening features and ISA levels an object was built for.
+
+use core::convert::TryInto;
+
+use header::Machine;
+
+/// The type of the note in `.note.gnu.property`, owned by "GNU".
+pub const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+
+pub const GNU_PROPERTY_STACK_SIZE: u32 = 1;
+pub const GNU_PROPERTY_NO_COPY_ON_PROTECTED: u32 = 2;
+pub const GNU_PROPERTY_UINT32_AND_LO: u32 = 0xb0000000;
+pub const GNU_PROPERTY_UINT32_AND_HI: u32 = 0xb0007fff;
+pub const GNU_PROPERTY_UINT32_OR_LO: u32 = 0xb0008000;
+pub const GNU_PROPERTY_UINT32_OR_HI: u32 = 0xb000ffff;
+pub const GNU_PROPERTY_1_NEEDED: u32 = GNU_PROPERTY_UINT32_OR_LO;
+pub const GNU_PROPERTY_LOPROC: u32 = 0xc0000000;
+pub const GNU_PROPERTY_HIPROC: u32 = 0xdfffffff;
+pub const GNU_PROPERTY_LOUSER: u32 = 0xe0000000;
+pub const GNU_PROPERTY_HIUSER: u32 = 0xffffffff;
+
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc0000000;
+pub const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc0000002;
+pub const GNU_PROPERTY_X86_FEATURE_2_NEEDED: u32 = 0xc0008001;
+pub const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc0008002;
+pub const GNU_PROPERTY_X86_FEATURE_2_USED: u32 = 0xc0010001;
+pub const GNU_PROPERTY_X86_ISA_1_USED: u32 = 0xc0010002;
+
+pub const GNU_PROPERTY_1_NEEDED_INDIRECT_EXTERN_ACCESS: u32 = 0x1;
+
+pub const GNU_PROPERTY_X86_FEATURE_1_IBT: u32 = 0x1;
+pub const GNU_PROPERTY_X86_FEATURE_1_SHSTK: u32 = 0x2;
+pub const GNU_PROPERTY_X86_FEATURE_1_LAM_U48: u32 = 0x4;
+pub const GNU_PROPERTY_X86_FEATURE_1_LAM_U57: u32 = 0x8;
+
+pub const GNU_PROPERTY_X86_ISA_1_BASELINE: u32 = 0x1;
+pub const GNU_PROPERTY_X86_ISA_1_V2: u32 = 0x2;
+pub const GNU_PROPERTY_X86_ISA_1_V3: u32 = 0x4;
+pub const GNU_PROPERTY_X86_ISA_1_V4: u32 = 0x8;
+
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_BTI: u32 = 0x1;
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_PAC: u32 = 0x2;
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_GCS: u32 = 0x4;
+
+/// A single property: its type and undecoded data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Property<'a> {
+    pub type_: u32,
+    pub data: &'a [u8],
+}
+
+impl<'a> Property<'a> {
+    /// The data of a property that holds a 32-bit value, such as a bitmask.
+    pub fn u32(&self) -> Option<u32> {
+        self.data.try_into().ok().map(u32::from_ne_bytes)
+    }
+}
+
+/// The properties in the descriptor of an `NT_GNU_PROPERTY_TYPE_0` note.
+/// Each property's data is padded to the word size, so `is_64` is whether
+/// the file is 64-bit.
+pub fn properties(desc: &[u8], is_64: bool) -> PropertyIter<'_> {
+    PropertyIter { data: desc, is_64 }
+}
+
+#[derive(Clone, Debug)]
+pub struct PropertyIter<'a> {
+    data: &'a [u8],
+    is_64: bool,
+}
+
+impl<'a> PropertyIter<'a> {
+    fn next_property(&mut self) -> Result<Property<'a>, &'static str> {
+        check!(self.data.len() >= 8, "Property header is truncated");
+        let type_ = u32::from_ne_bytes(self.data[..4].try_into().unwrap());
+        let size = u32::from_ne_bytes(self.data[4..8].try_into().unwrap()) as usize;
+        check!(size <= self.data.len() - 8, "Property is truncated");
+        let data = &self.data[8..8 + size];
+        let align = if self.is_64 { 8 } else { 4 };
+        let padded = size.next_multiple_of(align);
+        self.data = self.data.get(8 + padded..).unwrap_or(&[]);
+        Ok(Property { type_, data })
+    }
+}
+
+impl<'a> Iterator for PropertyIter<'a> {
+    type Item = Result<Property<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let result = self.next_property();
+        if result.is_err() {
+            self.data = &[];
+        }
+        Some(result)
+    }
+}
+
+/// `GNU_PROPERTY_X86_FEATURE_1_AND`: the control-flow protection every input
+/// object was built with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct X86Features(pub u32);
+
+impl X86Features {
+    /// Indirect branch tracking.
+    pub fn is_ibt(&self) -> bool {
+        self.0 & GNU_PROPERTY_X86_FEATURE_1_IBT != 0
+    }
+
+    /// Shadow stack.
+    pub fn is_shstk(&self) -> bool {
+        self.0 & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0
+    }
+}
+
+/// `GNU_PROPERTY_X86_ISA_1_NEEDED` or `GNU_PROPERTY_X86_ISA_1_USED`: a set
+/// of x86-64 micro-architecture levels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct X86IsaLevels(pub u32);
+
+impl X86IsaLevels {
+    pub fn is_baseline(&self) -> bool {
+        self.0 & GNU_PROPERTY_X86_ISA_1_BASELINE != 0
+    }
+
+    pub fn is_v2(&self) -> bool {
+        self.0 & GNU_PROPERTY_X86_ISA_1_V2 != 0
+    }
+
+    pub fn is_v3(&self) -> bool {
+        self.0 & GNU_PROPERTY_X86_ISA_1_V3 != 0
+    }
+
+    pub fn is_v4(&self) -> bool {
+        self.0 & GNU_PROPERTY_X86_ISA_1_V4 != 0
+    }
+
+    /// The highest level in the set, with the baseline as level 1 and
+    /// x86-64-v4 as level 4.
+    pub fn highest(&self) -> Option<u32> {
+        [self.is_baseline(), self.is_v2(), self.is_v3(), self.is_v4()].iter()
+            .rposition(|&set| set)
+            .map(|index| index as u32 + 1)
+    }
+}
+
+/// `GNU_PROPERTY_AARCH64_FEATURE_1_AND`: the branch protection every input
+/// object was built with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AArch64Features(pub u32);
+
+impl AArch64Features {
+    /// Branch target identification.
+    pub fn is_bti(&self) -> bool {
+        self.0 & GNU_PROPERTY_AARCH64_FEATURE_1_BTI != 0
+    }
+
+    /// Pointer authentication of return addresses.
+    pub fn is_pac(&self) -> bool {
+        self.0 & GNU_PROPERTY_AARCH64_FEATURE_1_PAC != 0
+    }
+
+    /// Guarded control stack.
+    pub fn is_gcs(&self) -> bool {
+        self.0 & GNU_PROPERTY_AARCH64_FEATURE_1_GCS != 0
+    }
+}
+
+/// The properties of a file that are understood, decoded. A property the
+/// file does not have is `None` (or false).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GnuProperties {
+    pub stack_size: Option<u64>,
+    pub no_copy_on_protected: bool,
+    /// `GNU_PROPERTY_1_NEEDED`.
+    pub needed_1: Option<u32>,
+    pub x86_features: Option<X86Features>,
+    pub x86_isa_needed: Option<X86IsaLevels>,
+    pub x86_isa_used: Option<X86IsaLevels>,
+    /// `GNU_PROPERTY_X86_FEATURE_2_NEEDED`, the register sets needed.
+    pub x86_features_2_needed: Option<u32>,
+    /// `GNU_PROPERTY_X86_FEATURE_2_USED`, the register sets used.
+    pub x86_features_2_used: Option<u32>,
+    pub aarch64_features: Option<AArch64Features>,
+}
+
+impl GnuProperties {
+    /// Decodes the descriptor of an `NT_GNU_PROPERTY_TYPE_0` note from a file
+    /// for `machine`. Processor-specific properties are only decoded for the
+    /// machine they belong to; unknown properties are skipped.
+    pub fn parse(desc: &[u8], is_64: bool, machine: Machine) -> Result<GnuProperties, &'static str> {
+        let mut result = GnuProperties::default();
+        result.add(desc, is_64, machine)?;
+        Ok(result)
+    }
+
+    /// Adds the properties from another note to these.
+    pub fn add(&mut self, desc: &[u8], is_64: bool, machine: Machine) -> Result<(), &'static str> {
+        for property in properties(desc, is_64) {
+            let property = property?;
+            let value = property.u32();
+            let is_x86 = matches!(machine, Machine::X86 | Machine::X86_64);
+            match property.type_ {
+                GNU_PROPERTY_STACK_SIZE => {
+                    self.stack_size = Some(match (is_64, property.data.len()) {
+                        (true, 8) => u64::from_ne_bytes(property.data.try_into().unwrap()),
+                        (false, 4) => value.unwrap().into(),
+                        _ => return Err("Invalid stack size property"),
+                    });
+                }
+                GNU_PROPERTY_NO_COPY_ON_PROTECTED => self.no_copy_on_protected = true,
+                GNU_PROPERTY_1_NEEDED => self.needed_1 = Some(value.ok_or("Invalid property size")?),
+                GNU_PROPERTY_X86_FEATURE_1_AND if is_x86 => {
+                    self.x86_features = Some(X86Features(value.ok_or("Invalid property size")?));
+                }
+                GNU_PROPERTY_X86_ISA_1_NEEDED if is_x86 => {
+                    self.x86_isa_needed = Some(X86IsaLevels(value.ok_or("Invalid property size")?));
+                }
+                GNU_PROPERTY_X86_ISA_1_USED if is_x86 => {
+                    self.x86_isa_used = Some(X86IsaLevels(value.ok_or("Invalid property size")?));
+                }
+                GNU_PROPERTY_X86_FEATURE_2_NEEDED if is_x86 => {
+                    self.x86_features_2_needed = Some(value.ok_or("Invalid property size")?);
+                }
+                GNU_PROPERTY_X86_FEATURE_2_USED if is_x86 => {
+                    self.x86_features_2_used = Some(value.ok_or("Invalid property size")?);
+                }
+                GNU_PROPERTY_AARCH64_FEATURE_1_AND if machine == Machine::AArch64 => {
+                    self.aarch64_features = Some(AArch64Features(value.ok_or("Invalid property size")?));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+
+    fn desc(properties: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut desc = Vec::new();
+        for &(type_, data) in properties {
+            desc.extend_from_slice(&type_.to_ne_bytes());
+            desc.extend_from_slice(&(data.len() as u32).to_ne_bytes());
+            desc.extend_from_slice(data);
+            desc.resize(desc.len().next_multiple_of(8), 0);
+        }
+        desc
+    }
+
+    #[test]
+    fn parse() {
+        let desc = desc(&[(GNU_PROPERTY_X86_FEATURE_1_AND, &3u32.to_ne_bytes()),
+                          (GNU_PROPERTY_X86_ISA_1_NEEDED, &5u32.to_ne_bytes()),
+                          (GNU_PROPERTY_NO_COPY_ON_PROTECTED, &[]),
+                          (0xe0000001, &[1, 2, 3])]);
+        let properties = GnuProperties::parse(&desc, true, Machine::X86_64).unwrap();
+        assert!(properties.x86_features.unwrap().is_ibt() && properties.x86_features.unwrap().is_shstk());
+        assert_eq!(properties.x86_isa_needed.unwrap().highest(), Some(3));
+        assert!(properties.no_copy_on_protected);
+        assert_eq!(properties.aarch64_features, None);
+
+        // x86 properties are ignored on other machines.
+        let properties = GnuProperties::parse(&desc, true, Machine::AArch64).unwrap();
+        assert_eq!(properties.x86_features, None);
+
+        assert!(GnuProperties::parse(&desc[..desc.len() - 12], true, Machine::X86_64).is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Section groups (`SHT_GROUP`), which bind sections together so that a linker
+//! keeps or discards them as one.
+//!
+//! Most groups are COMDAT groups, which C++ compilers emit for inline functions
+//! and template instantiations. Every object which uses `std::vector<int>`
+//! carries its own copy, named by the group's signature symbol, and the linker
+//! keeps only the first copy it sees of each signature. `resolve_comdat_groups`
+//! repeats that decision over a set of relocatable objects, and needs `alloc`
+//! (the `compression` or `inflate` feature).
+
+#[cfg(any(feature = "compression", feature = "inflate"))]
+use alloc::collections::BTreeMap;
+use core::convert::{TryFrom, TryInto};
+use core::mem;
+
+use ElfFile;
+use header::Class;
+use reader::c_str;
+use sections::{SectionHeader, ShType, GRP_COMDAT, SHF_ALLOC, SHN_LORESERVE, SHN_UNDEF};
+use symbol_table::{self, Entry, Entry32, Entry64};
+use zero::{read, Pod};
+
+/// A section of type `SHT_GROUP`: a word of flags, followed by the section
+/// indices of the members.
+#[derive(Clone, Copy, Debug)]
+pub struct SectionGroup<'a> {
+    index: u16,
+    header: SectionHeader<'a>,
+    flags: u32,
+    members: &'a [u8],
+}
+
+impl<'a> SectionGroup<'a> {
+    /// The group in the section at `index`.
+    pub fn new(elf_file: &ElfFile<'a>, index: u16) -> Result<SectionGroup<'a>, &'static str> {
+        elf_file.check_tables(is_64(elf_file)?)?;
+        let header = section_at(elf_file, index as u32)?;
+        check!(header.get_type() == Ok(ShType::Group), "Not a section group");
+        SectionGroup::read(elf_file, index, header)
+    }
+
+    fn read(elf_file: &ElfFile<'a>, index: u16, header: SectionHeader<'a>)
+            -> Result<SectionGroup<'a>, &'static str> {
+        let data = header.data_checked(elf_file)?;
+        check!(data.len() >= 4 && data.len() % 4 == 0, "Invalid section group size");
+        let (flags, members) = data.split_at(4);
+        Ok(SectionGroup { index, header, flags: u32::from_ne_bytes(flags.try_into().unwrap()), members })
+    }
+
+    /// The index of the group's own section.
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub fn header(&self) -> SectionHeader<'a> {
+        self.header
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub fn is_comdat(&self) -> bool {
+        self.flags as u64 & GRP_COMDAT != 0
+    }
+
+    /// The number of members.
+    pub fn len(&self) -> usize {
+        self.members.len() / 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// The section indices of the members, as stored.
+    pub fn member_indices(&self) -> impl Iterator<Item = u32> + 'a {
+        self.members.chunks_exact(4).map(|index| u32::from_ne_bytes(index.try_into().unwrap()))
+    }
+
+    /// The section headers of the members.
+    pub fn members<'b>(&self, elf_file: &'b ElfFile<'a>)
+                       -> impl Iterator<Item = Result<SectionHeader<'a>, &'static str>> + 'b
+    where
+        'a: 'b,
+    {
+        self.member_indices().map(move |index| section_at(elf_file, index))
+    }
+
+    /// The name of the group's signature symbol: symbol `sh_info` in the
+    /// symbol table at `sh_link`. If that is a section symbol, the group is
+    /// named after the section, as GNU as does for `.section ...,comdat`.
+    pub fn signature(&self, elf_file: &ElfFile<'a>) -> Result<&'a str, &'static str> {
+        let is_64 = is_64(elf_file)?;
+        let symtab = section_at(elf_file, self.header.link())?;
+        check!(symtab.get_type() == Ok(ShType::SymTab), "Section group is not linked to a symbol table");
+        let symbols = symtab.data_checked(elf_file)?;
+        let symbol: &dyn Entry = if is_64 {
+            symbol_at::<Entry64>(symbols, self.header.info())?
+        } else {
+            symbol_at::<Entry32>(symbols, self.header.info())?
+        };
+
+        if symbol.get_type() == Ok(symbol_table::Type::Section) {
+            let section = section_at(elf_file, symbol.shndx() as u32)?;
+            return elf_file.get_shstr(section.name());
+        }
+        let strings = section_at(elf_file, symtab.link())?;
+        check!(strings.get_type() == Ok(ShType::StrTab), "Symbol table is not linked to a string table");
+        c_str(strings.data_checked(elf_file)?, symbol.name() as usize)
+    }
+
+    /// The total size of the members which take up memory (`SHF_ALLOC`), which
+    /// is what each discarded copy of a COMDAT group would have added to the
+    /// output. Relocations and other non-allocated members are not counted.
+    pub fn allocated_size(&self, elf_file: &ElfFile<'a>) -> Result<u64, &'static str> {
+        let mut size = 0u64;
+        for member in self.members(elf_file) {
+            let member = member?;
+            if member.flags() & SHF_ALLOC != 0 {
+                size = size.saturating_add(member.size());
+            }
+        }
+        Ok(size)
+    }
+}
+
+/// The section groups of `elf_file`, in section order.
+pub fn section_groups<'b, 'a>(elf_file: &'b ElfFile<'a>) -> Result<SectionGroups<'b, 'a>, &'static str> {
+    elf_file.check_tables(is_64(elf_file)?)?;
+    Ok(SectionGroups { elf_file, next_index: 0 })
+}
+
+#[derive(Clone, Debug)]
+pub struct SectionGroups<'b, 'a: 'b> {
+    elf_file: &'b ElfFile<'a>,
+    next_index: u16,
+}
+
+impl<'b, 'a> Iterator for SectionGroups<'b, 'a> {
+    type Item = Result<SectionGroup<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let count = self.elf_file.header.pt2.sh_count().min(SHN_LORESERVE);
+        while self.next_index < count {
+            let index = self.next_index;
+            self.next_index += 1;
+            let header = match self.elf_file.section_header(index) {
+                Ok(header) => header,
+                Err(e) => return Some(Err(e)),
+            };
+            if header.get_type() == Ok(ShType::Group) {
+                return Some(SectionGroup::read(self.elf_file, index, header));
+            }
+        }
+        None
+    }
+}
+
+/// Works out which COMDAT groups a linker keeps when it links `objects`, in
+/// that order. Like GNU ld, gold and lld, the first group with each signature
+/// is kept, and every later group with the same signature is discarded along
+/// with its members, whatever their contents.
+///
+/// This yields each COMDAT group, kept or not. Other groups are always kept,
+/// and are skipped.
+#[cfg(any(feature = "compression", feature = "inflate"))]
+pub fn resolve_comdat_groups<'b, 'a>(objects: &'b [ElfFile<'a>]) -> ComdatGroups<'b, 'a> {
+    ComdatGroups { objects, object: 0, groups: None, kept: BTreeMap::new() }
+}
+
+#[cfg(any(feature = "compression", feature = "inflate"))]
+#[derive(Clone, Debug)]
+pub struct ComdatGroups<'b, 'a: 'b> {
+    objects: &'b [ElfFile<'a>],
+    object: usize,
+    groups: Option<SectionGroups<'b, 'a>>,
+    // The object and section index of the first group seen with each
+    // signature.
+    kept: BTreeMap<&'a str, (usize, u16)>,
+}
+
+#[cfg(any(feature = "compression", feature = "inflate"))]
+impl<'b, 'a> Iterator for ComdatGroups<'b, 'a> {
+    type Item = Result<ComdatGroup<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let elf_file = self.objects.get(self.object)?;
+            let groups = match self.groups {
+                Some(ref mut groups) => groups,
+                None => match section_groups(elf_file) {
+                    Ok(groups) => self.groups.get_or_insert(groups),
+                    Err(e) => {
+                        self.object += 1;
+                        return Some(Err(e));
+                    }
+                },
+            };
+            let group = match groups.next() {
+                Some(Ok(group)) => group,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.object += 1;
+                    self.groups = None;
+                    continue;
+                }
+            };
+            if !group.is_comdat() {
+                continue;
+            }
+
+            let signature = match group.signature(elf_file) {
+                Ok(signature) => signature,
+                Err(e) => return Some(Err(e)),
+            };
+            let kept = *self.kept.entry(signature).or_insert((self.object, group.index));
+            return Some(Ok(ComdatGroup { object: self.object, group, signature, kept }));
+        }
+    }
+}
+
+/// A COMDAT group, and the linker's decision about it.
+#[cfg(any(feature = "compression", feature = "inflate"))]
+#[derive(Clone, Copy, Debug)]
+pub struct ComdatGroup<'a> {
+    /// The index of the object the group is in.
+    pub object: usize,
+    pub group: SectionGroup<'a>,
+    pub signature: &'a str,
+    /// The object and section index of the copy of the group which is kept.
+    pub kept: (usize, u16),
+}
+
+#[cfg(any(feature = "compression", feature = "inflate"))]
+impl<'a> ComdatGroup<'a> {
+    pub fn is_kept(&self) -> bool {
+        self.kept == (self.object, self.group.index)
+    }
+}
+
+fn is_64(elf_file: &ElfFile) -> Result<bool, &'static str> {
+    match elf_file.header.pt1.class() {
+        Class::ThirtyTwo => Ok(false),
+        Class::SixtyFour => Ok(true),
+        Class::None | Class::Other(_) => Err("Invalid ELF class"),
+    }
+}
+
+// Symbol `index` in the symbol table `data`.
+fn symbol_at<T: Pod>(data: &[u8], index: u32) -> Result<&T, &'static str> {
+    let size = mem::size_of::<T>();
+    let symbol = (index as usize).checked_mul(size)
+        .and_then(|start| data.get(start..start.checked_add(size)?))
+        .ok_or("Section group's signature symbol is out of range")?;
+    check!(symbol.as_ptr() as usize % mem::align_of::<T>() == 0, "Symbol table is misaligned");
+    Ok(read(symbol))
+}
+
+fn section_at<'a>(elf_file: &ElfFile<'a>, index: u32) -> Result<SectionHeader<'a>, &'static str> {
+    u16::try_from(index).ok()
+        .filter(|&index| index != SHN_UNDEF && index < elf_file.header.pt2.sh_count() && index < SHN_LORESERVE)
+        .ok_or("Section index is out of range")
+        .and_then(|index| elf_file.section_header(index))
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+    use sections::test::mk_file_with;
+
+    // A little-endian ELF64 relocatable object with, for each `(flags,
+    // signature)`, a `.text` section of 4 bytes and a group holding it.
+    fn mk_object(groups: &[(u32, &str)]) -> Vec<u8> {
+        let mut strtab = vec![0];
+        let mut symtab = vec![0; 24];
+        let mut data = Vec::new();
+        for (i, &(flags, signature)) in groups.iter().enumerate() {
+            symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+            symtab.extend_from_slice(&[0x12, 0]);
+            symtab.extend_from_slice(&(4 + 2 * i as u16).to_le_bytes());
+            symtab.extend_from_slice(&[0; 16]);
+            strtab.extend_from_slice(signature.as_bytes());
+            strtab.push(0);
+            data.push([flags, 4 + 2 * i as u32].iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<_>>());
+        }
+
+        // `.strtab` and `.symtab` are sections 2 and 3.
+        let mut sections = vec![(".strtab", 3, 0, 0, 0, &strtab[..]), (".symtab", 2, 0, 2, 1, &symtab[..])];
+        for (i, group) in data.iter().enumerate() {
+            sections.push((".text", 1, 0x206, 0, 0, &[0x90; 4]));
+            sections.push((".group", 17, 0, 3, i as u32 + 1, group));
+        }
+        mk_file_with(&sections)
+    }
+
+    #[test]
+    fn comdat_groups() {
+        let a = mk_object(&[(1, "_Z3maxIiET_S0_S0_"), (0, "plain")]);
+        let b = mk_object(&[(1, "_Z3minIiET_S0_S0_"), (1, "_Z3maxIiET_S0_S0_")]);
+        let objects = [ElfFile::new(&a).unwrap(), ElfFile::new(&b).unwrap()];
+
+        let group = SectionGroup::new(&objects[0], 5).unwrap();
+        assert!(group.is_comdat());
+        assert_eq!(group.len(), 1);
+        assert_eq!(group.signature(&objects[0]), Ok("_Z3maxIiET_S0_S0_"));
+        let members: Vec<_> = group.members(&objects[0]).map(|member| member.unwrap().size()).collect();
+        assert_eq!(members, [4]);
+        assert_eq!(group.allocated_size(&objects[0]), Ok(4));
+        assert!(SectionGroup::new(&objects[0], 4).is_err());
+        assert_eq!(section_groups(&objects[0]).unwrap().count(), 2);
+
+        #[cfg(any(feature = "compression", feature = "inflate"))]
+        {
+            let groups: Vec<_> = resolve_comdat_groups(&objects)
+                .map(|group| group.map(|group| (group.object, group.signature, group.is_kept(), group.kept)))
+                .collect();
+            assert_eq!(groups, [Ok((0, "_Z3maxIiET_S0_S0_", true, (0, 5))),
+                                Ok((1, "_Z3minIiET_S0_S0_", true, (1, 5))),
+                                Ok((1, "_Z3maxIiET_S0_S0_", false, (0, 5)))]);
+        }
+    }
+}
@@ -53,6 +53,72 @@ pub struct Header<'a> {
 
 // TODO add Header::section_count, because if sh_count = 0, then the real count is in the first section.
 
+impl<'a> Header<'a> {
+    /// An owned copy of the header's fields.
+    pub fn snapshot(&self) -> HeaderSnapshot {
+        HeaderSnapshot {
+            class: self.pt1.class.0,
+            data: self.pt1.data.0,
+            version: self.pt1.version.0,
+            os_abi: self.pt1.os_abi.0,
+            abi_version: self.pt1.abi_version,
+            type_: self.pt2.type_().0,
+            machine: self.pt2.machine().0,
+            file_version: self.pt2.version(),
+            entry_point: self.pt2.entry_point(),
+            ph_offset: self.pt2.ph_offset(),
+            sh_offset: self.pt2.sh_offset(),
+            flags: self.pt2.flags(),
+            header_size: self.pt2.header_size(),
+            ph_entry_size: self.pt2.ph_entry_size(),
+            ph_count: self.pt2.ph_count(),
+            sh_entry_size: self.pt2.sh_entry_size(),
+            sh_count: self.pt2.sh_count(),
+            sh_str_index: self.pt2.sh_str_index(),
+        }
+    }
+
+    /// The processor-specific flags (`e_flags`), decoded for the machine.
+    pub fn flags(&self) -> Flags {
+        let flags = self.pt2.flags();
+        match self.pt2.machine().as_machine() {
+            Machine::Arm => Flags::Arm(ArmFlags(flags)),
+            Machine::Mips | Machine::MipsRs3Le => Flags::Mips(MipsFlags(flags)),
+            Machine::PowerPC64 => Flags::PowerPC64(PowerPC64Flags(flags)),
+            Machine::RISC_V => Flags::RiscV(RiscVFlags(flags)),
+            Machine::LoongArch => Flags::LoongArch(LoongArchFlags(flags)),
+            _ => Flags::Other(flags),
+        }
+    }
+}
+
+/// The fields of a `Header`, as raw values, independent of the file they were
+/// read from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeaderSnapshot {
+    pub class: u8,
+    pub data: u8,
+    pub version: u8,
+    pub os_abi: u8,
+    pub abi_version: u8,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub type_: u16,
+    pub machine: u16,
+    /// The version in the second part of the header (`e_version`).
+    pub file_version: u32,
+    pub entry_point: u64,
+    pub ph_offset: u64,
+    pub sh_offset: u64,
+    pub flags: u32,
+    pub header_size: u16,
+    pub ph_entry_size: u16,
+    pub ph_count: u16,
+    pub sh_entry_size: u16,
+    pub sh_count: u16,
+    pub sh_str_index: u16,
+}
+
 impl<'a> fmt::Display for Header<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "ELF header:")?;
@@ -64,6 +130,7 @@ impl<'a> fmt::Display for Header<'a> {
         writeln!(f, "    abi version:      {:?}", self.pt1.abi_version)?;
         writeln!(f, "    padding:          {:?}", self.pt1.padding)?;
         write!(f, "{}", self.pt2)?;
+        writeln!(f, "    machine flags:    {}", self.flags())?;
         Ok(())
     }
 }
@@ -134,6 +201,7 @@ impl<'a> HeaderPt2<'a> {
     getter!(entry_point, u64);
     getter!(ph_offset, u64);
     getter!(sh_offset, u64);
+    getter!(flags, u32);
     getter!(ph_entry_size, u16);
     getter!(ph_count, u16);
     getter!(sh_entry_size, u16);
@@ -302,7 +370,7 @@ impl Version {
 }
 
 #[derive(Clone, Copy)]
-pub struct OsAbi_(u8);
+pub struct OsAbi_(pub u8);
 
 impl OsAbi_ {
     pub fn as_os_abi(self) -> OsAbi {
@@ -311,12 +379,22 @@ impl OsAbi_ {
             0x01 => OsAbi::HpUx,
             0x02 => OsAbi::NetBSD,
             0x03 => OsAbi::Linux,
+            0x04 => OsAbi::Hurd,
             0x06 => OsAbi::Solaris,
             0x07 => OsAbi::Aix,
             0x08 => OsAbi::Irix,
             0x09 => OsAbi::FreeBSD,
+            0x0A => OsAbi::Tru64,
+            0x0B => OsAbi::Modesto,
             0x0C => OsAbi::OpenBSD,
             0x0D => OsAbi::OpenVMS,
+            0x0E => OsAbi::Nsk,
+            0x0F => OsAbi::Aros,
+            0x10 => OsAbi::FenixOS,
+            0x11 => OsAbi::CloudABI,
+            0x12 => OsAbi::OpenVOS,
+            0x61 => OsAbi::Arm,
+            0xFF => OsAbi::Standalone,
             other => OsAbi::Other(other),
         }
     }
@@ -329,19 +407,61 @@ impl fmt::Debug for OsAbi_ {
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum OsAbi {
     // or None
     SystemV,
     HpUx,
     NetBSD,
+    // Also GNU, which includes the Hurd
     Linux,
+    Hurd,
     Solaris,
     Aix,
     Irix,
     FreeBSD,
+    Tru64,
+    Modesto,
     OpenBSD,
     OpenVMS,
-    Other(u8), // FIXME there are many, many more of these
+    Nsk,
+    Aros,
+    FenixOS,
+    CloudABI,
+    OpenVOS,
+    // Values from 64 are processor-specific; these two are the ones in use.
+    Arm,
+    Standalone,
+    Other(u8),
+}
+
+impl OsAbi {
+    /// A readable name, such as "Linux".
+    pub fn name(&self) -> &'static str {
+        match *self {
+            OsAbi::SystemV => "UNIX System V",
+            OsAbi::HpUx => "HP-UX",
+            OsAbi::NetBSD => "NetBSD",
+            OsAbi::Linux => "Linux",
+            OsAbi::Hurd => "GNU Hurd",
+            OsAbi::Solaris => "Solaris",
+            OsAbi::Aix => "AIX",
+            OsAbi::Irix => "IRIX",
+            OsAbi::FreeBSD => "FreeBSD",
+            OsAbi::Tru64 => "Tru64 UNIX",
+            OsAbi::Modesto => "Novell Modesto",
+            OsAbi::OpenBSD => "OpenBSD",
+            OsAbi::OpenVMS => "OpenVMS",
+            OsAbi::Nsk => "HP Non-Stop Kernel",
+            OsAbi::Aros => "AROS",
+            OsAbi::FenixOS => "FenixOS",
+            OsAbi::CloudABI => "CloudABI",
+            OsAbi::OpenVOS => "OpenVOS",
+            OsAbi::Arm => "ARM",
+            OsAbi::Standalone => "Standalone application",
+            OsAbi::Other(_) => "Unknown OS ABI",
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -376,55 +496,637 @@ pub enum Type {
     ProcessorSpecific(u16), // TODO OsSpecific
 }
 
-#[derive(Clone, Copy)]
-pub struct Machine_(u16);
+macro_rules! machines {
+    ($(($value: expr, $name: ident, $description: expr),)*) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+        #[non_exhaustive]
+        pub enum Machine {
+            $($name,)*
+            Other(u16),
+        }
 
-impl Machine_ {
-    pub fn as_machine(self) -> Machine {
-        match self.0 {
-            0x00 => Machine::None,
-            0x02 => Machine::Sparc,
-            0x03 => Machine::X86,
-            0x08 => Machine::Mips,
-            0x14 => Machine::PowerPC,
-            0x28 => Machine::Arm,
-            0x2A => Machine::SuperH,
-            0x32 => Machine::Ia64,
-            0x3E => Machine::X86_64,
-            0xB7 => Machine::AArch64,
-            0xF3 => Machine::RISC_V,
-            0xF7 => Machine::BPF,
-            other => Machine::Other(other),
+        impl Machine_ {
+            pub fn as_machine(self) -> Machine {
+                match self.0 {
+                    $($value => Machine::$name,)*
+                    other => Machine::Other(other),
+                }
+            }
+        }
+
+        impl Machine {
+            /// The value of `e_machine`.
+            pub fn value(&self) -> u16 {
+                match *self {
+                    $(Machine::$name => $value,)*
+                    Machine::Other(value) => value,
+                }
+            }
+
+            /// A readable name, such as "AMD x86-64".
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    $(Machine::$name => $description,)*
+                    Machine::Other(_) => "Unknown machine",
+                }
+            }
         }
     }
 }
 
+#[derive(Clone, Copy)]
+pub struct Machine_(pub u16);
+
 impl fmt::Debug for Machine_ {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.as_machine().fmt(f)
     }
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub enum Machine {
-    None,
-    Sparc,
-    X86,
-    Mips,
-    PowerPC,
-    Arm,
-    SuperH,
-    Ia64,
-    X86_64,
-    AArch64,
-    RISC_V,
-    BPF,
-    Other(u16), // FIXME there are many, many more of these
+// The gABI's EM_* values, with the variant for each.
+machines! {
+    (0, None, "No machine"),
+    (1, M32, "AT&T WE 32100"),
+    (2, Sparc, "SPARC"),
+    (3, X86, "Intel 80386"),
+    (4, M68k, "Motorola 68000"),
+    (5, M88k, "Motorola 88000"),
+    (6, IntelMcu, "Intel MCU"),
+    (7, I860, "Intel 80860"),
+    (8, Mips, "MIPS R3000"),
+    (9, S370, "IBM System/370"),
+    (10, MipsRs3Le, "MIPS R3000 little-endian"),
+    (15, PaRisc, "HP PA-RISC"),
+    (17, Vpp500, "Fujitsu VPP500"),
+    (18, Sparc32Plus, "SPARC v8+"),
+    (19, I960, "Intel 80960"),
+    (20, PowerPC, "PowerPC"),
+    (21, PowerPC64, "PowerPC64"),
+    (22, S390, "IBM S/390"),
+    (23, Spu, "IBM SPU/SPC"),
+    (36, V800, "NEC V800"),
+    (37, Fr20, "Fujitsu FR20"),
+    (38, Rh32, "TRW RH-32"),
+    (39, Rce, "Motorola RCE"),
+    (40, Arm, "ARM"),
+    (41, Alpha, "Digital Alpha"),
+    (42, SuperH, "Renesas SuperH"),
+    (43, SparcV9, "SPARC v9"),
+    (44, TriCore, "Siemens TriCore"),
+    (45, Arc, "Argonaut RISC Core"),
+    (46, H8_300, "Renesas H8/300"),
+    (47, H8_300H, "Renesas H8/300H"),
+    (48, H8S, "Renesas H8S"),
+    (49, H8_500, "Renesas H8/500"),
+    (50, Ia64, "Intel IA-64"),
+    (51, MipsX, "Stanford MIPS-X"),
+    (52, ColdFire, "Motorola ColdFire"),
+    (53, M68HC12, "Motorola M68HC12"),
+    (54, Mma, "Fujitsu MMA"),
+    (55, Pcp, "Siemens PCP"),
+    (56, NCpu, "Sony nCPU"),
+    (57, Ndr1, "Denso NDR1"),
+    (58, StarCore, "Motorola Star*Core"),
+    (59, Me16, "Toyota ME16"),
+    (60, St100, "STMicroelectronics ST100"),
+    (61, TinyJ, "Advanced Logic TinyJ"),
+    (62, X86_64, "AMD x86-64"),
+    (63, Pdsp, "Sony DSP"),
+    (64, Pdp10, "DEC PDP-10"),
+    (65, Pdp11, "DEC PDP-11"),
+    (66, Fx66, "Siemens FX66"),
+    (67, St9Plus, "STMicroelectronics ST9+"),
+    (68, St7, "STMicroelectronics ST7"),
+    (69, M68HC16, "Motorola MC68HC16"),
+    (70, M68HC11, "Motorola MC68HC11"),
+    (71, M68HC08, "Motorola MC68HC08"),
+    (72, M68HC05, "Motorola MC68HC05"),
+    (73, Svx, "Silicon Graphics SVx"),
+    (74, St19, "STMicroelectronics ST19"),
+    (75, Vax, "DEC VAX"),
+    (76, Cris, "Axis CRIS"),
+    (77, Javelin, "Infineon Javelin"),
+    (78, FirePath, "Element 14 FirePath"),
+    (79, Zsp, "LSI Logic ZSP"),
+    (80, Mmix, "Knuth MMIX"),
+    (81, Huany, "Harvard HUANY"),
+    (82, Prism, "SiTera Prism"),
+    (83, Avr, "Atmel AVR"),
+    (84, Fr30, "Fujitsu FR30"),
+    (85, D10V, "Mitsubishi D10V"),
+    (86, D30V, "Mitsubishi D30V"),
+    (87, V850, "NEC V850"),
+    (88, M32R, "Renesas M32R"),
+    (89, Mn10300, "Matsushita MN10300"),
+    (90, Mn10200, "Matsushita MN10200"),
+    (91, PicoJava, "picoJava"),
+    (92, OpenRisc, "OpenRISC"),
+    (93, ArcCompact, "ARCompact"),
+    (94, Xtensa, "Tensilica Xtensa"),
+    (95, VideoCore, "Alphamosaic VideoCore"),
+    (96, TmmGpp, "Thompson Multimedia GPP"),
+    (97, Ns32k, "National Semiconductor 32000"),
+    (98, Tpc, "Tenor Network TPC"),
+    (99, Snp1k, "Trebia SNP 1000"),
+    (100, St200, "STMicroelectronics ST200"),
+    (101, Ip2k, "Ubicom IP2xxx"),
+    (102, Max, "MAX processor"),
+    (103, Cr, "National Semiconductor CompactRISC"),
+    (104, F2MC16, "Fujitsu F2MC16"),
+    (105, Msp430, "TI MSP430"),
+    (106, Blackfin, "Analog Devices Blackfin"),
+    (107, SeC33, "Seiko Epson S1C33"),
+    (108, Sep, "Sharp embedded microprocessor"),
+    (109, Arca, "Arca RISC"),
+    (110, UniCore, "PKU UniCore"),
+    (111, Excess, "eXcess"),
+    (112, Dxp, "Icera Deep Execution Processor"),
+    (113, Nios2, "Altera Nios II"),
+    (114, Crx, "National Semiconductor CRX"),
+    (115, XGate, "Motorola XGATE"),
+    (116, C166, "Infineon C16x/XC16x"),
+    (117, M16C, "Renesas M16C"),
+    (118, DsPic30F, "Microchip dsPIC30F"),
+    (119, Ce, "Freescale Communication Engine"),
+    (120, M32C, "Renesas M32C"),
+    (131, Tsk3000, "Altium TSK3000"),
+    (132, Rs08, "Freescale RS08"),
+    (133, Sharc, "Analog Devices SHARC"),
+    (134, ECog2, "Cyan eCOG2"),
+    (135, Score7, "Sunplus S+core7"),
+    (136, Dsp24, "New Japan Radio 24-bit DSP"),
+    (137, VideoCore3, "Broadcom VideoCore III"),
+    (138, LatticeMico32, "Lattice Mico32"),
+    (139, SeC17, "Seiko Epson C17"),
+    (140, TiC6000, "TI TMS320C6000"),
+    (141, TiC2000, "TI TMS320C2000"),
+    (142, TiC5500, "TI TMS320C55x"),
+    (143, TiArp32, "TI ARP32"),
+    (144, TiPru, "TI PRU"),
+    (160, MmdspPlus, "STMicroelectronics 64-bit VLIW DSP"),
+    (161, CypressM8C, "Cypress M8C"),
+    (162, R32C, "Renesas R32C"),
+    (163, TriMedia, "NXP TriMedia"),
+    (164, Hexagon, "Qualcomm Hexagon"),
+    (165, I8051, "Intel 8051"),
+    (166, Stxp7x, "STMicroelectronics STxP7x"),
+    (167, Nds32, "Andes NDS32"),
+    (168, ECog1X, "Cyan eCOG1X"),
+    (169, MaxQ30, "Dallas MAXQ30"),
+    (170, Ximo16, "New Japan Radio 16-bit DSP"),
+    (171, Manik, "M2000 RISC"),
+    (172, CrayNv2, "Cray NV2"),
+    (173, Rx, "Renesas RX"),
+    (174, MetaG, "Imagination Meta"),
+    (175, Elbrus, "MCST Elbrus"),
+    (176, ECog16, "Cyan eCOG16"),
+    (177, Cr16, "National Semiconductor CompactRISC CR16"),
+    (178, ETpu, "Freescale eTPU"),
+    (179, Sle9x, "Infineon SLE9X"),
+    (180, L10M, "Intel L10M"),
+    (181, K10M, "Intel K10M"),
+    (183, AArch64, "AArch64"),
+    (185, Avr32, "Atmel AVR32"),
+    (186, Stm8, "STMicroelectronics STM8"),
+    (187, Tile64, "Tilera TILE64"),
+    (188, TilePro, "Tilera TILEPro"),
+    (189, MicroBlaze, "Xilinx MicroBlaze"),
+    (190, Cuda, "NVIDIA CUDA"),
+    (191, TileGx, "Tilera TILE-Gx"),
+    (192, CloudShield, "CloudShield"),
+    (193, CoreA1st, "KIPO-KAIST Core-A 1st generation"),
+    (194, CoreA2nd, "KIPO-KAIST Core-A 2nd generation"),
+    (195, ArcV2, "Synopsys ARCv2"),
+    (196, Open8, "Open8"),
+    (197, Rl78, "Renesas RL78"),
+    (198, VideoCore5, "Broadcom VideoCore V"),
+    (199, R78KOR, "Renesas 78KOR"),
+    (200, F56800EX, "Freescale 56800EX"),
+    (201, Ba1, "Beyond BA1"),
+    (202, Ba2, "Beyond BA2"),
+    (203, XCore, "XMOS xCORE"),
+    (204, MchpPic, "Microchip PIC"),
+    (205, IntelGt, "Intel Graphics Technology"),
+    (210, Km32, "KM211 KM32"),
+    (211, Kmx32, "KM211 KMX32"),
+    (212, Emx16, "KM211 KMX16"),
+    (213, Emx8, "KM211 KMX8"),
+    (214, KvArc, "KM211 KVARC"),
+    (215, Cdp, "Paneve CDP"),
+    (216, Coge, "Cognitive Smart Memory Processor"),
+    (217, Cool, "Bluechip CoolEngine"),
+    (218, Norc, "Nanoradio Optimized RISC"),
+    (219, CsrKalimba, "CSR Kalimba"),
+    (220, Z80, "Zilog Z80"),
+    (221, Visium, "Controls and Data Services VISIUMcore"),
+    (222, Ft32, "FTDI FT32"),
+    (223, Moxie, "Moxie"),
+    (224, AmdGpu, "AMD GPU"),
+    (243, RISC_V, "RISC-V"),
+    (244, Lanai, "Lanai 32-bit processor"),
+    (245, Ceva, "CEVA Processor Architecture Family"),
+    (246, CevaX2, "CEVA X2 Processor Family"),
+    (247, BPF, "Linux BPF"),
+    (248, GraphcoreIpu, "Graphcore Intelligent Processing Unit"),
+    (249, Img1, "Imagination Technologies"),
+    (250, Nfp, "Netronome Flow Processor"),
+    (251, Ve, "NEC Vector Engine"),
+    (252, CSky, "C-SKY"),
+    (253, ArcCompact364, "Synopsys ARCv2.3 64-bit"),
+    (254, Mcs6502, "MOS Technology MCS 6502 processor"),
+    (255, ArcCompact3, "Synopsys ARCv2.3 32-bit"),
+    (256, Kvx, "Kalray VLIW core of the MPPA processor family"),
+    (257, Wdc65816, "WDC 65816/65C816"),
+    (258, LoongArch, "LoongArch"),
+    (259, Kf32, "ChipON KungFu32"),
+    (260, U16U8Core, "LAPIS nX-U16/U8"),
+    (261, Tachyum, "Tachyum"),
+    (262, F56800EF, "NXP 56800EF Digital Signal Controller"),
+    (263, Sbf, "Solana Bytecode Format"),
+    (264, AiEngine, "AMD/Xilinx AIEngine architecture"),
+    (265, SimaMla, "SiMa MLA"),
+    (266, Bang, "Cambricon BANG"),
+    (267, LoongGpu, "Loongson LoongGPU"),
+    (268, Sw64, "Wuxi Institute of Advanced Technology SW64"),
+    (269, AieCtrlCode, "AMD/Xilinx AIEngine ctrlcode"),
 }
 
 // TODO any more constants that need to go in here?
 
+pub const EF_ARM_ABI_FLOAT_SOFT: u32 = 0x00000200;
+pub const EF_ARM_ABI_FLOAT_HARD: u32 = 0x00000400;
+pub const EF_ARM_BE8: u32 = 0x00800000;
+pub const EF_ARM_EABIMASK: u32 = 0xff000000;
+
+pub const EF_MIPS_NOREORDER: u32 = 0x00000001;
+pub const EF_MIPS_PIC: u32 = 0x00000002;
+pub const EF_MIPS_CPIC: u32 = 0x00000004;
+pub const EF_MIPS_ABI2: u32 = 0x00000020;
+pub const EF_MIPS_FP64: u32 = 0x00000200;
+pub const EF_MIPS_NAN2008: u32 = 0x00000400;
+pub const EF_MIPS_ABI: u32 = 0x0000f000;
+pub const EF_MIPS_ABI_O32: u32 = 0x00001000;
+pub const EF_MIPS_ABI_O64: u32 = 0x00002000;
+pub const EF_MIPS_ABI_EABI32: u32 = 0x00003000;
+pub const EF_MIPS_ABI_EABI64: u32 = 0x00004000;
+pub const EF_MIPS_ARCH: u32 = 0xf0000000;
+pub const EF_MIPS_ARCH_1: u32 = 0x00000000;
+pub const EF_MIPS_ARCH_2: u32 = 0x10000000;
+pub const EF_MIPS_ARCH_3: u32 = 0x20000000;
+pub const EF_MIPS_ARCH_4: u32 = 0x30000000;
+pub const EF_MIPS_ARCH_5: u32 = 0x40000000;
+pub const EF_MIPS_ARCH_32: u32 = 0x50000000;
+pub const EF_MIPS_ARCH_64: u32 = 0x60000000;
+pub const EF_MIPS_ARCH_32R2: u32 = 0x70000000;
+pub const EF_MIPS_ARCH_64R2: u32 = 0x80000000;
+pub const EF_MIPS_ARCH_32R6: u32 = 0x90000000;
+pub const EF_MIPS_ARCH_64R6: u32 = 0xa0000000;
+
+pub const EF_PPC64_ABI: u32 = 0x00000003;
+
+pub const EF_RISCV_RVC: u32 = 0x00000001;
+pub const EF_RISCV_FLOAT_ABI: u32 = 0x00000006;
+pub const EF_RISCV_FLOAT_ABI_SOFT: u32 = 0x00000000;
+pub const EF_RISCV_FLOAT_ABI_SINGLE: u32 = 0x00000002;
+pub const EF_RISCV_FLOAT_ABI_DOUBLE: u32 = 0x00000004;
+pub const EF_RISCV_FLOAT_ABI_QUAD: u32 = 0x00000006;
+pub const EF_RISCV_RVE: u32 = 0x00000008;
+pub const EF_RISCV_TSO: u32 = 0x00000010;
+
+pub const EF_LOONGARCH_ABI_MODIFIER_MASK: u32 = 0x00000007;
+pub const EF_LOONGARCH_ABI_SOFT_FLOAT: u32 = 0x00000001;
+pub const EF_LOONGARCH_ABI_SINGLE_FLOAT: u32 = 0x00000002;
+pub const EF_LOONGARCH_ABI_DOUBLE_FLOAT: u32 = 0x00000003;
+pub const EF_LOONGARCH_OBJABI_MASK: u32 = 0x000000c0;
+
+/// The processor-specific flags of a header (`e_flags`), decoded for machines
+/// which define them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flags {
+    Arm(ArmFlags),
+    Mips(MipsFlags),
+    PowerPC64(PowerPC64Flags),
+    RiscV(RiscVFlags),
+    LoongArch(LoongArchFlags),
+    Other(u32),
+}
+
+impl Flags {
+    /// The raw value.
+    pub fn value(&self) -> u32 {
+        match *self {
+            Flags::Arm(ArmFlags(flags)) |
+            Flags::Mips(MipsFlags(flags)) |
+            Flags::PowerPC64(PowerPC64Flags(flags)) |
+            Flags::RiscV(RiscVFlags(flags)) |
+            Flags::LoongArch(LoongArchFlags(flags)) |
+            Flags::Other(flags) => flags,
+        }
+    }
+}
+
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self.value())?;
+        match *self {
+            Flags::Arm(flags) => write!(f, " ({})", flags),
+            Flags::Mips(flags) => write!(f, " ({})", flags),
+            Flags::PowerPC64(flags) => write!(f, " ({})", flags),
+            Flags::RiscV(flags) => write!(f, " ({})", flags),
+            Flags::LoongArch(flags) => write!(f, " ({})", flags),
+            Flags::Other(_) => Ok(()),
+        }
+    }
+}
+
+/// A floating-point calling convention: which floating-point values are
+/// passed in floating-point registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloatAbi {
+    Soft,
+    Single,
+    Double,
+    Quad,
+}
+
+impl FloatAbi {
+    fn name(self) -> &'static str {
+        match self {
+            FloatAbi::Soft => "soft-float ABI",
+            FloatAbi::Single => "single-float ABI",
+            FloatAbi::Double => "double-float ABI",
+            FloatAbi::Quad => "quad-float ABI",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArmFlags(pub u32);
+
+impl ArmFlags {
+    /// The version of the ARM EABI, or 0 for files from before it.
+    pub fn eabi_version(&self) -> u8 {
+        ((self.0 & EF_ARM_EABIMASK) >> 24) as u8
+    }
+
+    /// Whether floating-point arguments are passed in VFP registers. Files
+    /// with neither this nor `is_soft_float` do not say.
+    pub fn is_hard_float(&self) -> bool {
+        self.0 & EF_ARM_ABI_FLOAT_HARD != 0
+    }
+
+    pub fn is_soft_float(&self) -> bool {
+        self.0 & EF_ARM_ABI_FLOAT_SOFT != 0
+    }
+
+    /// Whether a big-endian executable has little-endian instructions.
+    pub fn is_be8(&self) -> bool {
+        self.0 & EF_ARM_BE8 != 0
+    }
+}
+
+impl fmt::Display for ArmFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.eabi_version() {
+            0 => write!(f, "no EABI")?,
+            version => write!(f, "EABI version {}", version)?,
+        }
+        for &(set, name) in &[(self.is_hard_float(), "hard-float ABI"),
+                              (self.is_soft_float(), "soft-float ABI"),
+                              (self.is_be8(), "BE8")] {
+            if set {
+                write!(f, ", {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MipsFlags(pub u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MipsArch {
+    Mips1,
+    Mips2,
+    Mips3,
+    Mips4,
+    Mips5,
+    Mips32,
+    Mips64,
+    Mips32R2,
+    Mips64R2,
+    Mips32R6,
+    Mips64R6,
+    Other(u32),
+}
+
+/// The ABI in `EF_MIPS_ABI`. The n32 ABI is shown by `EF_MIPS_ABI2` instead,
+/// and n64 by neither in a 64-bit file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MipsAbi {
+    O32,
+    O64,
+    Eabi32,
+    Eabi64,
+    Other(u32),
+}
+
+impl MipsFlags {
+    pub fn arch(&self) -> MipsArch {
+        match self.0 & EF_MIPS_ARCH {
+            EF_MIPS_ARCH_1 => MipsArch::Mips1,
+            EF_MIPS_ARCH_2 => MipsArch::Mips2,
+            EF_MIPS_ARCH_3 => MipsArch::Mips3,
+            EF_MIPS_ARCH_4 => MipsArch::Mips4,
+            EF_MIPS_ARCH_5 => MipsArch::Mips5,
+            EF_MIPS_ARCH_32 => MipsArch::Mips32,
+            EF_MIPS_ARCH_64 => MipsArch::Mips64,
+            EF_MIPS_ARCH_32R2 => MipsArch::Mips32R2,
+            EF_MIPS_ARCH_64R2 => MipsArch::Mips64R2,
+            EF_MIPS_ARCH_32R6 => MipsArch::Mips32R6,
+            EF_MIPS_ARCH_64R6 => MipsArch::Mips64R6,
+            other => MipsArch::Other(other >> 28),
+        }
+    }
+
+    pub fn abi(&self) -> Option<MipsAbi> {
+        match self.0 & EF_MIPS_ABI {
+            0 => None,
+            EF_MIPS_ABI_O32 => Some(MipsAbi::O32),
+            EF_MIPS_ABI_O64 => Some(MipsAbi::O64),
+            EF_MIPS_ABI_EABI32 => Some(MipsAbi::Eabi32),
+            EF_MIPS_ABI_EABI64 => Some(MipsAbi::Eabi64),
+            other => Some(MipsAbi::Other(other >> 12)),
+        }
+    }
+
+    /// Whether this is an n32 file.
+    pub fn is_abi2(&self) -> bool {
+        self.0 & EF_MIPS_ABI2 != 0
+    }
+
+    pub fn is_noreorder(&self) -> bool {
+        self.0 & EF_MIPS_NOREORDER != 0
+    }
+
+    pub fn is_pic(&self) -> bool {
+        self.0 & EF_MIPS_PIC != 0
+    }
+
+    /// Whether the code uses position-independent calls.
+    pub fn is_cpic(&self) -> bool {
+        self.0 & EF_MIPS_CPIC != 0
+    }
+
+    /// Whether floating-point registers are 64 bits wide in a 32-bit file.
+    pub fn is_fp64(&self) -> bool {
+        self.0 & EF_MIPS_FP64 != 0
+    }
+
+    /// Whether NaNs are encoded as in IEEE 754-2008, rather than the legacy
+    /// encoding.
+    pub fn is_nan2008(&self) -> bool {
+        self.0 & EF_MIPS_NAN2008 != 0
+    }
+}
+
+impl fmt::Display for MipsFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.arch() {
+            MipsArch::Other(arch) => write!(f, "arch {:#x}", arch)?,
+            arch => write!(f, "{}", match arch {
+                MipsArch::Mips1 => "mips1",
+                MipsArch::Mips2 => "mips2",
+                MipsArch::Mips3 => "mips3",
+                MipsArch::Mips4 => "mips4",
+                MipsArch::Mips5 => "mips5",
+                MipsArch::Mips32 => "mips32",
+                MipsArch::Mips64 => "mips64",
+                MipsArch::Mips32R2 => "mips32r2",
+                MipsArch::Mips64R2 => "mips64r2",
+                MipsArch::Mips32R6 => "mips32r6",
+                _ => "mips64r6",
+            })?,
+        }
+        match self.abi() {
+            Some(MipsAbi::Other(abi)) => write!(f, ", ABI {:#x}", abi)?,
+            Some(abi) => write!(f, ", {}", match abi {
+                MipsAbi::O32 => "o32",
+                MipsAbi::O64 => "o64",
+                MipsAbi::Eabi32 => "eabi32",
+                _ => "eabi64",
+            })?,
+            None => {}
+        }
+        for &(set, name) in &[(self.is_abi2(), "abi2"),
+                              (self.is_noreorder(), "noreorder"),
+                              (self.is_pic(), "pic"),
+                              (self.is_cpic(), "cpic"),
+                              (self.is_fp64(), "fp64"),
+                              (self.is_nan2008(), "nan2008")] {
+            if set {
+                write!(f, ", {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PowerPC64Flags(pub u32);
+
+impl PowerPC64Flags {
+    /// The version of the ELF ABI: 1 for the one with function descriptors,
+    /// 2 for ELFv2, or 0 if the file does not say.
+    pub fn abi_version(&self) -> u8 {
+        (self.0 & EF_PPC64_ABI) as u8
+    }
+}
+
+impl fmt::Display for PowerPC64Flags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.abi_version() {
+            0 => write!(f, "unspecified ABI"),
+            version => write!(f, "ELFv{} ABI", version),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RiscVFlags(pub u32);
+
+impl RiscVFlags {
+    /// Whether the code uses compressed instructions.
+    pub fn is_rvc(&self) -> bool {
+        self.0 & EF_RISCV_RVC != 0
+    }
+
+    pub fn float_abi(&self) -> FloatAbi {
+        match self.0 & EF_RISCV_FLOAT_ABI {
+            EF_RISCV_FLOAT_ABI_SOFT => FloatAbi::Soft,
+            EF_RISCV_FLOAT_ABI_SINGLE => FloatAbi::Single,
+            EF_RISCV_FLOAT_ABI_DOUBLE => FloatAbi::Double,
+            _ => FloatAbi::Quad,
+        }
+    }
+
+    /// Whether the code is for the embedded base ISA, with 16 registers.
+    pub fn is_rve(&self) -> bool {
+        self.0 & EF_RISCV_RVE != 0
+    }
+
+    /// Whether the code needs the total store ordering memory model.
+    pub fn is_tso(&self) -> bool {
+        self.0 & EF_RISCV_TSO != 0
+    }
+}
+
+impl fmt::Display for RiscVFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.float_abi().name())?;
+        for &(set, name) in &[(self.is_rvc(), "RVC"), (self.is_rve(), "RVE"), (self.is_tso(), "TSO")] {
+            if set {
+                write!(f, ", {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoongArchFlags(pub u32);
+
+impl LoongArchFlags {
+    /// The floating-point ABI, which with the class gives the ABI: `lp64d`
+    /// is a 64-bit file with `Double`. `None` if the modifier is reserved.
+    pub fn float_abi(&self) -> Option<FloatAbi> {
+        match self.0 & EF_LOONGARCH_ABI_MODIFIER_MASK {
+            EF_LOONGARCH_ABI_SOFT_FLOAT => Some(FloatAbi::Soft),
+            EF_LOONGARCH_ABI_SINGLE_FLOAT => Some(FloatAbi::Single),
+            EF_LOONGARCH_ABI_DOUBLE_FLOAT => Some(FloatAbi::Double),
+            _ => None,
+        }
+    }
+
+    /// The version of the object file ABI, which changed how relocations
+    /// are used.
+    pub fn object_abi_version(&self) -> u8 {
+        ((self.0 & EF_LOONGARCH_OBJABI_MASK) >> 6) as u8
+    }
+}
+
+impl fmt::Display for LoongArchFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.float_abi() {
+            Some(abi) => write!(f, "{}", abi.name())?,
+            None => write!(f, "reserved ABI modifier")?,
+        }
+        write!(f, ", object ABI v{}", self.object_abi_version())
+    }
+}
+
 pub fn sanity_check(file: &ElfFile) -> Result<(), &'static str> {
     check!(mem::size_of::<HeaderPt1>() == 16);
     check!(file.header.pt1.magic == MAGIC, "bad magic number");
@@ -451,3 +1153,79 @@ pub fn sanity_check(file: &ElfFile) -> Result<(), &'static str> {
 
     Ok(())
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    // A little-endian header of `class`, with every field after `e_ident`
+    // zero.
+    pub(crate) fn mk_elf_header(class: u8) -> Vec<u8> {
+        let header_size = mem::size_of::<HeaderPt1>() +
+                          match class {
+            1 => mem::size_of::<HeaderPt2_<P32>>(),
+            2 => mem::size_of::<HeaderPt2_<P64>>(),
+            _ => 0,
+        };
+        let mut header = vec![0x7f, b'E', b'L', b'F'];
+        let data = 1u8;
+        let version = 1u8;
+        header.extend_from_slice(&[class, data, version]);
+        header.resize(header_size, 0);
+        header
+    }
+
+    #[test]
+    fn decode_flags() {
+        let mut header = mk_elf_header(1);
+        header[18..20].copy_from_slice(&0x28u16.to_ne_bytes());
+        header[36..40].copy_from_slice(&0x0500_0400u32.to_ne_bytes());
+        let elf = ElfFile::new(&header).unwrap();
+        let flags = match elf.header.flags() {
+            Flags::Arm(flags) => flags,
+            flags => unreachable!("{:?}", flags),
+        };
+        assert_eq!(flags.eabi_version(), 5);
+        assert!(flags.is_hard_float() && !flags.is_soft_float());
+        assert_eq!(elf.header.flags().to_string(), "0x5000400 (EABI version 5, hard-float ABI)");
+
+        header[18..20].copy_from_slice(&0xf3u16.to_ne_bytes());
+        header[36..40].copy_from_slice(&0x15u32.to_ne_bytes());
+        let elf = ElfFile::new(&header).unwrap();
+        let flags = match elf.header.flags() {
+            Flags::RiscV(flags) => flags,
+            flags => unreachable!("{:?}", flags),
+        };
+        assert_eq!(flags.float_abi(), FloatAbi::Double);
+        assert!(flags.is_rvc() && flags.is_tso() && !flags.is_rve());
+
+        // Both MIPS R3000 values use the MIPS flags.
+        for &machine in &[8u16, 10] {
+            header[18..20].copy_from_slice(&machine.to_ne_bytes());
+            header[36..40].copy_from_slice(&(EF_MIPS_ARCH_32R2 | EF_MIPS_ABI_O32 | EF_MIPS_PIC).to_ne_bytes());
+            let elf = ElfFile::new(&header).unwrap();
+            let flags = match elf.header.flags() {
+                Flags::Mips(flags) => flags,
+                flags => unreachable!("{:?}", flags),
+            };
+            assert_eq!(flags.arch(), MipsArch::Mips32R2);
+            assert_eq!(flags.abi(), Some(MipsAbi::O32));
+            assert!(flags.is_pic() && !flags.is_noreorder());
+        }
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(Machine_(0xf7).as_machine(), Machine::BPF);
+        assert_eq!(Machine_(0x8c).as_machine().name(), "TI TMS320C6000");
+        assert_eq!(Machine::LoongArch.value(), 0x102);
+        assert_eq!(Machine_(256).as_machine(), Machine::Kvx);
+        assert_eq!(Machine_(257).as_machine().name(), "WDC 65816/65C816");
+        assert_eq!(Machine::AieCtrlCode.value(), 269);
+        assert_eq!(Machine_(0xfff0).as_machine(), Machine::Other(0xfff0));
+        assert_eq!(OsAbi_(0x61).as_os_abi(), OsAbi::Arm);
+        assert_eq!(OsAbi::FreeBSD.name(), "FreeBSD");
+    }
+}
@@ -0,0 +1,247 @@
+//! Intel HEX images.
+//!
+//! Data records hold 16 bytes each, and extended linear address records are
+//! used for addresses beyond the first 64KiB, so any 32-bit address can be
+//! written.
+
+use core::fmt;
+
+use image::{HexBytes, LoadSegment};
+
+/// The number of data bytes in each data record written.
+pub const DATA_RECORD_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordType {
+    Data,
+    EndOfFile,
+    ExtendedSegmentAddress,
+    StartSegmentAddress,
+    ExtendedLinearAddress,
+    StartLinearAddress,
+}
+
+impl RecordType {
+    fn from_u8(type_: u8) -> Result<RecordType, &'static str> {
+        match type_ {
+            0 => Ok(RecordType::Data),
+            1 => Ok(RecordType::EndOfFile),
+            2 => Ok(RecordType::ExtendedSegmentAddress),
+            3 => Ok(RecordType::StartSegmentAddress),
+            4 => Ok(RecordType::ExtendedLinearAddress),
+            5 => Ok(RecordType::StartLinearAddress),
+            _ => Err("Invalid Intel HEX record type"),
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            RecordType::Data => 0,
+            RecordType::EndOfFile => 1,
+            RecordType::ExtendedSegmentAddress => 2,
+            RecordType::StartSegmentAddress => 3,
+            RecordType::ExtendedLinearAddress => 4,
+            RecordType::StartLinearAddress => 5,
+        }
+    }
+}
+
+/// A single line of an Intel HEX file. The checksum has already been checked.
+#[derive(Clone, Copy, Debug)]
+pub struct Record<'a> {
+    pub type_: RecordType,
+    pub address: u16,
+    pub data: HexBytes<'a>,
+}
+
+/// Writes `segments` as Intel HEX, followed by a start linear address record
+/// for `entry_point` (if there is one) and an end of file record.
+pub fn write<'a, W, I>(out: &mut W, segments: I, entry_point: Option<u64>) -> Result<(), &'static str>
+    where W: fmt::Write,
+          I: IntoIterator<Item = Result<LoadSegment<'a>, &'static str>>
+{
+    let mut upper = 0;
+    for segment in segments {
+        let segment = segment?;
+        check!(segment.end() <= 0x1_0000_0000, "Address does not fit in 32 bits");
+
+        let mut address = segment.address;
+        let mut data = segment.data;
+        while !data.is_empty() {
+            if address >> 16 != upper {
+                upper = address >> 16;
+                write_record(out, RecordType::ExtendedLinearAddress, 0, &(upper as u16).to_be_bytes())?;
+            }
+            // Records can't cross a 64KiB boundary.
+            let len = data.len().min(DATA_RECORD_LEN).min(0x1_0000 - (address & 0xffff) as usize);
+            write_record(out, RecordType::Data, address as u16, &data[..len])?;
+            address += len as u64;
+            data = &data[len..];
+        }
+    }
+
+    if let Some(entry_point) = entry_point {
+        check!(entry_point <= 0xffff_ffff, "Entry point does not fit in 32 bits");
+        write_record(out, RecordType::StartLinearAddress, 0, &(entry_point as u32).to_be_bytes())?;
+    }
+    write_record(out, RecordType::EndOfFile, 0, &[])
+}
+
+fn write_record<W: fmt::Write>(out: &mut W,
+                               type_: RecordType,
+                               address: u16,
+                               data: &[u8])
+                               -> Result<(), &'static str> {
+    fn write_record_<W: fmt::Write>(out: &mut W, type_: u8, address: u16, data: &[u8]) -> fmt::Result {
+        write!(out, ":{:02X}{:04X}{:02X}", data.len(), address, type_)?;
+        let mut sum = (data.len() as u8).wrapping_add((address >> 8) as u8)
+                                        .wrapping_add(address as u8)
+                                        .wrapping_add(type_);
+        for &b in data {
+            write!(out, "{:02X}", b)?;
+            sum = sum.wrapping_add(b);
+        }
+        write!(out, "{:02X}\r\n", sum.wrapping_neg())
+    }
+
+    write_record_(out, type_.as_u8(), address, data).map_err(|_| "Error writing Intel HEX")
+}
+
+/// Iterates over the records in an Intel HEX file. Blank lines are skipped.
+pub fn records(input: &str) -> Records<'_> {
+    Records { lines: input.lines() }
+}
+
+#[derive(Clone, Debug)]
+pub struct Records<'a> {
+    lines: ::core::str::Lines<'a>,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<Record<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?.trim();
+            if !line.is_empty() {
+                return Some(parse_record(line));
+            }
+        }
+    }
+}
+
+fn parse_record(line: &str) -> Result<Record<'_>, &'static str> {
+    check!(line.starts_with(':'), "Intel HEX record does not start with ':'");
+    let bytes = HexBytes::new(&line[1..])?;
+    check!(bytes.len() >= 5, "Intel HEX record is too short");
+    check!(bytes.fold(0u8, |sum, b| sum.wrapping_add(b)) == 0, "Intel HEX checksum mismatch");
+
+    let mut header = bytes;
+    let len = header.next().unwrap();
+    let address = u16::from_be_bytes([header.next().unwrap(), header.next().unwrap()]);
+    let type_ = RecordType::from_u8(header.next().unwrap())?;
+    check!(len as usize == bytes.len() - 5, "Intel HEX record length mismatch");
+    let data = HexBytes::new(&line[9..line.len() - 2])?;
+
+    let expected_len = match type_ {
+        RecordType::Data => None,
+        RecordType::EndOfFile => Some(0),
+        RecordType::ExtendedSegmentAddress | RecordType::ExtendedLinearAddress => Some(2),
+        RecordType::StartSegmentAddress | RecordType::StartLinearAddress => Some(4),
+    };
+    if let Some(expected_len) = expected_len {
+        check!(data.len() == expected_len, "Intel HEX record has the wrong length for its type");
+    }
+    Ok(Record { type_, address, data })
+}
+
+/// Iterates over the data in an Intel HEX file, with extended addresses
+/// applied. Iteration stops at the end of file record.
+pub fn data_chunks(input: &str) -> DataChunks<'_> {
+    DataChunks {
+        records: records(input),
+        base: 0,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DataChunks<'a> {
+    records: Records<'a>,
+    base: u64,
+}
+
+impl<'a> Iterator for DataChunks<'a> {
+    type Item = Result<(u64, HexBytes<'a>), &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            };
+            match record.type_ {
+                RecordType::Data => return Some(Ok((self.base + record.address as u64, record.data))),
+                RecordType::EndOfFile => {
+                    // Make sure we stop here, even if there is more input.
+                    self.records = records("");
+                    return None;
+                }
+                RecordType::ExtendedSegmentAddress => self.base = (be_value(record.data) as u64) << 4,
+                RecordType::ExtendedLinearAddress => self.base = (be_value(record.data) as u64) << 16,
+                RecordType::StartSegmentAddress | RecordType::StartLinearAddress => {}
+            }
+        }
+    }
+}
+
+/// Finds the entry point given by a start linear or start segment address
+/// record, if there is one. A start segment address (CS:IP) is converted to a
+/// linear address.
+pub fn entry_point(input: &str) -> Result<Option<u64>, &'static str> {
+    for record in records(input) {
+        let record = record?;
+        match record.type_ {
+            RecordType::StartLinearAddress => return Ok(Some(be_value(record.data) as u64)),
+            RecordType::StartSegmentAddress => {
+                let cs_ip = be_value(record.data);
+                return Ok(Some(((cs_ip >> 16) << 4) as u64 + (cs_ip & 0xffff) as u64));
+            }
+            RecordType::EndOfFile => break,
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+fn be_value(data: HexBytes) -> u32 {
+    data.fold(0, |value, b| (value << 8) | b as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+    use image::{verify, VerifyError};
+
+    #[test]
+    fn round_trip() {
+        let low = [0xaa; 20];
+        let high = [0x55; 4];
+        let segments = [LoadSegment { address: 0x0800_fff8, data: &low },
+                        LoadSegment { address: 0x2000_0000, data: &high }];
+        let mut out = String::new();
+        write(&mut out, segments.iter().cloned().map(Ok), Some(0x0800_0101)).unwrap();
+        assert_eq!(out.lines().next(), Some(":020000040800F2"));
+        assert_eq!(out.lines().last(), Some(":00000001FF"));
+        // The first segment is split at the 64KiB boundary.
+        assert_eq!(data_chunks(&out).map(|c| c.unwrap().1.len()).collect::<Vec<_>>(), [8, 12, 4]);
+        assert_eq!(entry_point(&out), Ok(Some(0x0800_0101)));
+        assert_eq!(verify(segments.iter().cloned().map(Ok), data_chunks(&out)), Ok(()));
+
+        let changed = out.replacen(":08FFF800AAAA", ":08FFF800AAAB", 1);
+        assert!(data_chunks(&changed).any(|c| c.is_err()));
+        assert_eq!(verify(segments[..1].iter().cloned().map(Ok), data_chunks(&out)),
+                   Err(VerifyError::Unexpected { address: 0x2000_0000 }));
+    }
+}
@@ -0,0 +1,430 @@
+//! Images made from the loadable parts of a file, for flashing or loading
+//! onto a device. This module handles flat binaries in the style of `objcopy
+//! -O binary`, and the things shared by the text formats in `ihex` and `srec`.
+
+use core::cmp;
+use core::str;
+
+use ElfFile;
+use header::Class;
+use program::{ProgramHeader, ProgramIter, Type};
+use sections::{SectionHeader, ShType, SHF_ALLOC};
+
+/// Which of a segment's two addresses is used to place it in an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AddressKind {
+    /// The load memory address (`p_paddr`), i.e., where the bytes live in
+    /// flash or ROM. This is what `objcopy` uses.
+    #[default]
+    Physical,
+    /// The run-time address (`p_vaddr`).
+    Virtual,
+}
+
+/// Bytes to be loaded at an address. These are the file-backed part of a
+/// `PT_LOAD` segment, or the contents of an allocated section.
+#[derive(Clone, Copy, Debug)]
+pub struct LoadSegment<'a> {
+    pub address: u64,
+    pub data: &'a [u8],
+}
+
+impl<'a> LoadSegment<'a> {
+    /// The address one past the last byte of the segment.
+    pub fn end(&self) -> u64 {
+        self.address + self.data.len() as u64
+    }
+
+    fn overlaps(&self, other: &LoadSegment) -> bool {
+        self.address < other.end() && other.address < self.end()
+    }
+}
+
+/// Iterates over the `PT_LOAD` segments which have a non-zero file size, in
+/// program header order. If the header tables can't be read, the only item is
+/// an error.
+#[derive(Debug, Clone)]
+pub struct LoadSegmentIter<'b, 'a: 'b> {
+    inner: ProgramIter<'b, 'a>,
+    kind: AddressKind,
+    error: Option<&'static str>,
+}
+
+impl<'b, 'a> Iterator for LoadSegmentIter<'b, 'a> {
+    type Item = Result<LoadSegment<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            self.inner.next_index = self.inner.file.header.pt2.ph_count();
+            return Some(Err(error));
+        }
+        loop {
+            let ph = self.inner.next()?;
+            if ph.get_type() != Ok(Type::Load) || ph.file_size() == 0 {
+                continue;
+            }
+            return Some(load_segment(ph, self.inner.file, self.kind));
+        }
+    }
+}
+
+pub fn load_segments<'b, 'a>(elf_file: &'b ElfFile<'a>, kind: AddressKind) -> LoadSegmentIter<'b, 'a> {
+    LoadSegmentIter {
+        inner: ProgramIter {
+            file: elf_file,
+            next_index: 0,
+        },
+        kind,
+        error: check_tables(elf_file).err(),
+    }
+}
+
+fn check_tables(elf_file: &ElfFile) -> Result<(), &'static str> {
+    elf_file.check_tables(elf_file.header.pt1.class() == Class::SixtyFour)
+}
+
+fn load_segment<'a>(ph: ProgramHeader<'a>,
+                    elf_file: &ElfFile<'a>,
+                    kind: AddressKind)
+                    -> Result<LoadSegment<'a>, &'static str> {
+    let address = match kind {
+        AddressKind::Physical => ph.physical_addr(),
+        AddressKind::Virtual => ph.virtual_addr(),
+    };
+    if address.checked_add(ph.file_size()).is_none() {
+        return Err("Segment extends past the end of the address space");
+    }
+    let start = ph.offset() as usize;
+    let data = start.checked_add(ph.file_size() as usize)
+        .and_then(|end| elf_file.input.get(start..end))
+        .ok_or("File is shorter than segment data")?;
+    Ok(LoadSegment { address, data })
+}
+
+/// The contents of an allocated section, placed at its load or run-time
+/// address. The load address is found from the `PT_LOAD` segment containing the
+/// section; if there is no such segment, the section's own address is used.
+pub fn section_data<'a>(elf_file: &ElfFile<'a>,
+                        section: SectionHeader<'a>,
+                        kind: AddressKind)
+                        -> Result<LoadSegment<'a>, &'static str> {
+    check_tables(elf_file)?;
+    if section.flags() & SHF_ALLOC == 0 {
+        return Err("Section is not allocated");
+    }
+    if section.get_type()? == ShType::NoBits {
+        return Err("Section has no data in the file");
+    }
+    let start = section.offset() as usize;
+    let data = start.checked_add(section.size() as usize)
+        .and_then(|end| elf_file.input.get(start..end))
+        .ok_or("File is shorter than section data")?;
+
+    let mut address = section.address();
+    if kind == AddressKind::Physical {
+        let section_end = section.offset().checked_add(section.size());
+        let containing = elf_file.program_iter().find(|ph| {
+            let end = ph.offset().checked_add(ph.file_size());
+            ph.get_type() == Ok(Type::Load) && ph.offset() <= section.offset() &&
+            section_end.is_some_and(|section_end| end.is_some_and(|end| section_end <= end))
+        });
+        if let Some(ph) = containing {
+            address = ph.physical_addr().checked_add(section.offset() - ph.offset())
+                .ok_or("Section extends past the end of the address space")?;
+        }
+    }
+    if address.checked_add(section.size()).is_none() {
+        return Err("Section extends past the end of the address space");
+    }
+    Ok(LoadSegment { address, data })
+}
+
+/// A contiguous image spanning every loadable segment, with gaps between
+/// segments filled by a chosen byte.
+///
+/// Only the file-backed part of each segment is included, so zero-initialised
+/// data (the part of a segment beyond its file size) does not grow the image.
+#[derive(Clone, Copy, Debug)]
+pub struct BinaryImage<'b, 'a: 'b> {
+    file: &'b ElfFile<'a>,
+    kind: AddressKind,
+    base: u64,
+    end: u64,
+}
+
+impl<'b, 'a> BinaryImage<'b, 'a> {
+    pub fn new(elf_file: &'b ElfFile<'a>, kind: AddressKind) -> Result<BinaryImage<'b, 'a>, &'static str> {
+        let mut base = u64::MAX;
+        let mut end = 0;
+        for segment in load_segments(elf_file, kind) {
+            let segment = segment?;
+            base = cmp::min(base, segment.address);
+            end = cmp::max(end, segment.end());
+        }
+        if base > end {
+            return Err("No loadable segments with file data");
+        }
+
+        Ok(BinaryImage {
+            file: elf_file,
+            kind,
+            base,
+            end,
+        })
+    }
+
+    /// The address of the first byte of the image.
+    pub fn base_address(&self) -> u64 {
+        self.base
+    }
+
+    /// The size of the image in bytes.
+    pub fn size(&self) -> u64 {
+        self.end - self.base
+    }
+
+    pub fn segments(&self) -> LoadSegmentIter<'b, 'a> {
+        load_segments(self.file, self.kind)
+    }
+
+    /// Returns the first pair of segments (in program header order) whose
+    /// data overlap in the image, if any.
+    pub fn overlapping_segments(&self) -> Option<(LoadSegment<'a>, LoadSegment<'a>)> {
+        for (i, a) in self.segments().enumerate() {
+            let a = a.ok()?;
+            for b in self.segments().skip(i + 1) {
+                let b = b.ok()?;
+                if a.overlaps(&b) {
+                    return Some((a, b));
+                }
+            }
+        }
+        None
+    }
+
+    /// Writes the image into `buf`, which must be exactly `self.size()` bytes
+    /// long. Where segments overlap, later segments overwrite earlier ones.
+    pub fn write(&self, buf: &mut [u8], fill: u8) -> Result<(), &'static str> {
+        if buf.len() as u64 != self.size() {
+            return Err("Buffer size does not match image size");
+        }
+
+        for b in buf.iter_mut() {
+            *b = fill;
+        }
+        for segment in self.segments() {
+            let segment = segment?;
+            let start = (segment.address - self.base) as usize;
+            buf[start..start + segment.data.len()].copy_from_slice(segment.data);
+        }
+        Ok(())
+    }
+}
+
+/// The bytes encoded by a string of hex digit pairs, as found in the records of
+/// text image formats.
+#[derive(Clone, Copy, Debug)]
+pub struct HexBytes<'a> {
+    digits: &'a [u8],
+}
+
+impl<'a> HexBytes<'a> {
+    pub(crate) fn new(digits: &'a str) -> Result<HexBytes<'a>, &'static str> {
+        check!(digits.len() % 2 == 0, "Odd number of hex digits");
+        check!(digits.bytes().all(|b| b.is_ascii_hexdigit()), "Invalid hex digit");
+        Ok(HexBytes { digits: digits.as_bytes() })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.digits.is_empty()
+    }
+}
+
+impl<'a> Iterator for HexBytes<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.digits.is_empty() {
+            return None;
+        }
+        let (byte, rest) = self.digits.split_at(2);
+        self.digits = rest;
+        // Digits are checked when the HexBytes is created.
+        Some(u8::from_str_radix(str::from_utf8(byte).unwrap(), 16).unwrap())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl<'a> ExactSizeIterator for HexBytes<'a> {
+    fn len(&self) -> usize {
+        self.digits.len() / 2
+    }
+}
+
+/// A difference found when checking an image against the segments it should
+/// contain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The image or the ELF file could not be read.
+    Malformed(&'static str),
+    /// The image has a different value for the byte at `address`.
+    Differs { address: u64, expected: u8, found: u8 },
+    /// The image has data at `address`, which is not part of any segment.
+    Unexpected { address: u64 },
+    /// The image has no data for `address`, which is part of a segment.
+    Missing { address: u64 },
+}
+
+/// Checks that the data in an image (given as chunks of bytes and their
+/// addresses) is exactly the data in `segments`.
+///
+/// Chunks may be in any order, but checking is fastest when they are in
+/// ascending address order.
+pub fn verify<'a, S, C, D>(segments: S, chunks: C) -> Result<(), VerifyError>
+    where S: Iterator<Item = Result<LoadSegment<'a>, &'static str>> + Clone,
+          C: Iterator<Item = Result<(u64, D), &'static str>> + Clone,
+          D: ExactSizeIterator<Item = u8>
+{
+    verify_(segments, chunks, None)
+}
+
+/// Like `verify`, but for formats which pad data out to fixed-size blocks. Bytes
+/// in the image which are not part of any segment must be `padding`.
+pub fn verify_padded<'a, S, C, D>(segments: S, chunks: C, padding: u8) -> Result<(), VerifyError>
+    where S: Iterator<Item = Result<LoadSegment<'a>, &'static str>> + Clone,
+          C: Iterator<Item = Result<(u64, D), &'static str>> + Clone,
+          D: ExactSizeIterator<Item = u8>
+{
+    verify_(segments, chunks, Some(padding))
+}
+
+fn verify_<'a, S, C, D>(segments: S, chunks: C, padding: Option<u8>) -> Result<(), VerifyError>
+    where S: Iterator<Item = Result<LoadSegment<'a>, &'static str>> + Clone,
+          C: Iterator<Item = Result<(u64, D), &'static str>> + Clone,
+          D: ExactSizeIterator<Item = u8>
+{
+    for chunk in chunks.clone() {
+        let (address, data) = chunk.map_err(VerifyError::Malformed)?;
+        for (i, found) in data.enumerate() {
+            let address = address.wrapping_add(i as u64);
+            let mut expected = None;
+            for segment in segments.clone() {
+                let segment = segment.map_err(VerifyError::Malformed)?;
+                if segment.address <= address && address < segment.end() {
+                    expected = Some(segment.data[(address - segment.address) as usize]);
+                }
+            }
+            match expected.or(padding) {
+                Some(expected) if expected != found => {
+                    return Err(VerifyError::Differs { address, expected, found });
+                }
+                Some(_) => {}
+                None => return Err(VerifyError::Unexpected { address }),
+            }
+        }
+    }
+
+    // Every byte in the image is right, now check that every byte of every
+    // segment is in the image. Each pass over the chunks moves `covered` past
+    // every chunk which starts at or before it, so chunks in order take one pass.
+    for segment in segments {
+        let segment = segment.map_err(VerifyError::Malformed)?;
+        let mut covered = segment.address;
+        while covered < segment.end() {
+            let before = covered;
+            for chunk in chunks.clone() {
+                let (address, data) = chunk.map_err(VerifyError::Malformed)?;
+                let end = address.saturating_add(data.len() as u64);
+                if address <= covered && covered < end {
+                    covered = end;
+                }
+            }
+            if covered == before {
+                return Err(VerifyError::Missing { address: covered });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    // A little-endian ELF64 file containing a `PT_LOAD` segment for each
+    // `(vaddr, paddr, data)`.
+    fn mk_elf(segments: &[(u64, u64, &[u8])]) -> Vec<u8> {
+        let ph_offset = 64u64;
+        let mut data_offset = ph_offset + 56 * segments.len() as u64;
+        let mut file = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+        file.resize(16, 0);
+        let ehdr: [&[u8]; 12] = [&2u16.to_le_bytes(), &0xf3u16.to_le_bytes(), &1u32.to_le_bytes(),
+                                 &0u64.to_le_bytes(), &ph_offset.to_le_bytes(), &0u64.to_le_bytes(),
+                                 &0u32.to_le_bytes(), &64u16.to_le_bytes(), &56u16.to_le_bytes(),
+                                 &(segments.len() as u16).to_le_bytes(), &64u16.to_le_bytes(),
+                                 &[0; 4]];
+        for field in ehdr.iter() {
+            file.extend_from_slice(field);
+        }
+        for &(vaddr, paddr, data) in segments {
+            let fields: [u64; 6] = [data_offset, vaddr, paddr, data.len() as u64, data.len() as u64, 1];
+            file.extend_from_slice(&1u32.to_le_bytes());
+            file.extend_from_slice(&5u32.to_le_bytes());
+            for field in fields.iter() {
+                file.extend_from_slice(&field.to_le_bytes());
+            }
+            data_offset += data.len() as u64;
+        }
+        for &(_, _, data) in segments {
+            file.extend_from_slice(data);
+        }
+        file
+    }
+
+    #[test]
+    fn flatten_by_load_address() {
+        let input = mk_elf(&[(0x2000_0000, 0x0800_0010, &[5, 6]),
+                             (0x0800_0000, 0x0800_0000, &[1, 2, 3, 4])]);
+        let elf_file = ElfFile::new(&input).unwrap();
+
+        let image = BinaryImage::new(&elf_file, AddressKind::Physical).unwrap();
+        assert_eq!(image.base_address(), 0x0800_0000);
+        assert_eq!(image.size(), 0x12);
+        assert!(image.overlapping_segments().is_none());
+        let mut buf = vec![0; image.size() as usize];
+        image.write(&mut buf, 0xff).unwrap();
+        assert_eq!(&buf[..6], &[1, 2, 3, 4, 0xff, 0xff]);
+        assert_eq!(&buf[0x10..], &[5, 6]);
+
+        let image = BinaryImage::new(&elf_file, AddressKind::Virtual).unwrap();
+        assert_eq!(image.size(), 0x1800_0002);
+    }
+
+    #[test]
+    fn overlapping_segments() {
+        let input = mk_elf(&[(0x100, 0x100, &[1, 2, 3, 4]), (0x102, 0x102, &[5, 6, 7])]);
+        let elf_file = ElfFile::new(&input).unwrap();
+        let image = BinaryImage::new(&elf_file, AddressKind::Physical).unwrap();
+        let (a, b) = image.overlapping_segments().unwrap();
+        assert_eq!((a.address, b.address), (0x100, 0x102));
+        let mut buf = vec![0; image.size() as usize];
+        image.write(&mut buf, 0).unwrap();
+        assert_eq!(buf, [1, 2, 5, 6, 7]);
+    }
+
+    #[test]
+    fn misaligned_program_headers() {
+        let mut input = mk_elf(&[(0x100, 0x100, &[1, 2, 3, 4])]);
+        input[32..40].copy_from_slice(&65u64.to_le_bytes());
+        let elf_file = ElfFile::new(&input).unwrap();
+        let mut segments = load_segments(&elf_file, AddressKind::Physical);
+        assert_eq!(segments.next().map(|segment| segment.err()), Some(Some("Header table is misaligned")));
+        assert!(segments.next().is_none());
+        assert_eq!(BinaryImage::new(&elf_file, AddressKind::Physical).unwrap_err(), "Header table is misaligned");
+    }
+}
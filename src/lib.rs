@@ -1,7 +1,11 @@
 #![no_std]
+// Newer compilers have dropped `box_pointers`, put `size_of` in the prelude and
+// added `is_multiple_of`; the crate sticks to what older ones accept.
+#![allow(renamed_and_removed_lints)]
 #![warn(box_pointers, missing_copy_implementations, missing_debug_implementations)]
 #![warn(unused_extern_crates, unused_import_braces, unused_qualifications, unused_results)]
 #![warn(variant_size_differences)]
+#![allow(unused_qualifications, clippy::manual_is_multiple_of)]
 
 // TODO move to a module
 macro_rules! check {
@@ -17,10 +21,22 @@ macro_rules! check {
     };
 }
 
-#[cfg(feature = "compression")]
+#[cfg(any(feature = "compression", feature = "std", feature = "xz", test))]
+#[macro_use]
 extern crate std;
-#[cfg(feature = "compression")]
+#[cfg(any(feature = "compression", feature = "inflate"))]
+extern crate alloc;
+#[cfg(all(feature = "compression", not(feature = "inflate")))]
 extern crate flate2;
+#[cfg(feature = "inflate")]
+extern crate miniz_oxide;
+#[cfg(feature = "xz")]
+extern crate lzma_rs;
+#[cfg(feature = "gimli")]
+extern crate gimli;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 extern crate zero;
 
@@ -30,8 +46,37 @@ pub mod program;
 pub mod symbol_table;
 pub mod dynamic;
 pub mod hash;
+pub mod symbol_version;
+pub mod core_dump;
+pub mod auxv;
+pub mod gnu_property;
+pub mod security;
+pub mod debug_file;
+pub mod line;
+pub mod eh_frame;
+pub mod unwind;
+pub mod sframe;
+pub mod arm_exidx;
+pub mod attributes;
+pub mod mips;
+pub mod group;
+#[cfg(any(feature = "compression", feature = "inflate"))]
+pub mod compress;
+#[cfg(feature = "gimli")]
+pub mod dwarf;
+#[cfg(feature = "xz")]
+pub mod mini_debug_info;
+pub mod image;
+pub mod ihex;
+pub mod srec;
+pub mod uf2;
+mod reader;
+
+use core::convert::TryFrom;
+use core::str;
 
 use header::Header;
+use gnu_property::GnuProperties;
 use sections::{SectionHeader, SectionIter};
 use program::{ProgramHeader, ProgramIter};
 use zero::{read, read_str};
@@ -72,8 +117,34 @@ impl<'a> ElfFile<'a> {
         }
     }
 
+    // Checks that the program and section header tables can be read, which
+    // the modules reading a possibly malformed file assume.
+    pub(crate) fn check_tables(&self, is_64: bool) -> Result<(), &'static str> {
+        let pt2 = &self.header.pt2;
+        let tables = [(pt2.ph_offset(), pt2.ph_count(), pt2.ph_entry_size(), if is_64 { 56 } else { 32 }),
+                      (pt2.sh_offset(), pt2.sh_count(), pt2.sh_entry_size(), if is_64 { 64 } else { 40 })];
+        let align = if is_64 { 8 } else { 4 };
+        for &(offset, count, entry_size, expected_size) in &tables {
+            if count == 0 {
+                continue;
+            }
+            check!(entry_size == expected_size, "Header table has the wrong entry size");
+            check!(offset % align == 0 && self.input.as_ptr() as u64 % align == 0,
+                   "Header table is misaligned");
+            let end = offset.checked_add(count as u64 * entry_size as u64);
+            check!(end.is_some_and(|end| end <= self.input.len() as u64), "Header table is out of range of the file");
+        }
+        check!(pt2.sh_count() == 0 || pt2.sh_str_index() < pt2.sh_count(),
+               "Section name table index is out of range");
+        Ok(())
+    }
+
     pub fn get_shstr(&self, index: u32) -> Result<&'a str, &'static str> {
-        self.get_shstr_table().map(|shstr_table| read_str(&shstr_table[(index as usize)..]))
+        self.get_shstr_table().and_then(|shstr_table| {
+            let name = shstr_table.get(index as usize..).ok_or("String index out of range")?;
+            let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            str::from_utf8(&name[..end]).map_err(|_| "Section name is not valid UTF-8")
+        })
     }
 
     pub fn get_string(&self, index: u32) -> Result<&'a str, &'static str> {
@@ -81,26 +152,53 @@ impl<'a> ElfFile<'a> {
         if header.get_type()? != sections::ShType::StrTab {
             return Err("expected .strtab to be StrTab");
         }
-        Ok(read_str(&header.raw_data(self)[(index as usize)..]))
+        header.raw_data(self).get(index as usize..).map(read_str).ok_or("String index out of range")
     }
 
     pub fn get_dyn_string(&self, index: u32) -> Result<&'a str, &'static str> {
         let header = self.find_section_by_name(".dynstr").ok_or("no .dynstr section")?;
-        Ok(read_str(&header.raw_data(self)[(index as usize)..]))
+        header.raw_data(self).get(index as usize..).map(read_str).ok_or("String index out of range")
     }
 
     // This is really, stupidly slow. Not sure how to fix that, perhaps keeping
     // a HashTable mapping names to section header indices?
+    /// Finds the section called `name`.
+    ///
+    /// Failing that, a `.debug_*` name finds the `.zdebug_*` section of the
+    /// same name, which is what older toolchains call compressed debug
+    /// sections. So `.debug_info` may find `.zdebug_info`, whose raw data is
+    /// compressed; read it with `SectionHeader::decompressed_data`.
     pub fn find_section_by_name(&self, name: &str) -> Option<SectionHeader<'a>> {
+        let debug_name = name.strip_prefix(".debug_");
+        let mut compressed = None;
         for sect in self.section_iter() {
             if let Ok(sect_name) = sect.get_name(self) {
                 if sect_name == name {
                     return Some(sect);
                 }
+                if compressed.is_none() && debug_name.is_some() && sect_name.strip_prefix(".zdebug_") == debug_name {
+                    compressed = Some(sect);
+                }
             }
         }
 
-        None
+        compressed
+    }
+
+    /// The file's DWARF sections, for reading with gimli.
+    #[cfg(feature = "gimli")]
+    pub fn dwarf(&self) -> Result<dwarf::DwarfSections<'a>, &'static str> {
+        dwarf::DwarfSections::load(self)
+    }
+
+    /// Finds the source file and line of `address` from `.debug_line`, and
+    /// passes them to `f`; see `line`. Compressed sections are decompressed,
+    /// with the `compression` or `inflate` feature, so the location only lives
+    /// as long as the call.
+    pub fn addr2line<F, R>(&self, address: u64, f: F) -> Result<Option<R>, &'static str>
+        where F: FnOnce(line::Location) -> R
+    {
+        line::addr2line(self, address, f)
     }
 
     fn get_shstr_table(&self) -> Result<&'a [u8], &'static str> {
@@ -130,6 +228,15 @@ pub trait Extensions<'a> {
     /// Parse and return the value of the .gnu_debugaltlink section, if it
     /// exists and is well-formed.
     fn get_gnu_debugaltlink(&self) -> Option<(&'a str, &'a [u8])>;
+
+    /// Parse and return the properties in the .note.gnu.property section, or
+    /// the PT_GNU_PROPERTY segment if there are no section headers, if they
+    /// exist and are well-formed.
+    fn get_gnu_properties(&self) -> Option<GnuProperties>;
+
+    /// Return the contents of the .gnu_debugdata section (MiniDebugInfo), an
+    /// xz-compressed ELF file holding a symbol table, if it exists.
+    fn get_gnu_debugdata(&self) -> Option<&'a [u8]>;
 }
 
 impl<'a> Extensions<'a> for ElfFile<'a> {
@@ -185,35 +292,43 @@ impl<'a> Extensions<'a> for ElfFile<'a> {
                 }
             })
     }
-}
 
-#[cfg(test)]
-#[macro_use]
-extern crate std;
+    fn get_gnu_properties(&self) -> Option<GnuProperties> {
+        let (offset, size, align) = match self.find_section_by_name(".note.gnu.property") {
+            Some(header) => (header.offset(), header.size(), header.align()),
+            None => {
+                let header = self.program_iter()
+                    .find(|header| header.get_type() == Ok(program::Type::GnuProperty))?;
+                (header.offset(), header.file_size(), header.align())
+            }
+        };
+        let start = usize::try_from(offset).ok()?;
+        let data = self.input.get(start..start.checked_add(usize::try_from(size).ok()?)?)?;
+        let is_64 = self.header.pt1.class() == header::Class::SixtyFour;
+        let machine = self.header.pt2.machine().as_machine();
+        let mut properties = GnuProperties::default();
+        for note in sections::NoteIter::new(data, align) {
+            let note = note.ok()?;
+            if note.name == "GNU" && note.type_ == gnu_property::NT_GNU_PROPERTY_TYPE_0 {
+                properties.add(note.desc, is_64, machine).ok()?;
+            }
+        }
+        Some(properties)
+    }
+
+    fn get_gnu_debugdata(&self) -> Option<&'a [u8]> {
+        let header = self.find_section_by_name(".gnu_debugdata")?;
+        let start = usize::try_from(header.offset()).ok()?;
+        self.input.get(start..start.checked_add(usize::try_from(header.size()).ok()?)?)
+    }
+}
 
 #[cfg(test)]
 mod test {
     use std::prelude::v1::*;
 
-    use std::mem;
-
     use super::*;
-    use header::{HeaderPt1, HeaderPt2_};
-
-    fn mk_elf_header(class: u8) -> Vec<u8> {
-        let header_size = mem::size_of::<HeaderPt1>() +
-                          match class {
-            1 => mem::size_of::<HeaderPt2_<P32>>(),
-            2 => mem::size_of::<HeaderPt2_<P64>>(),
-            _ => 0,
-        };
-        let mut header = vec![0x7f, 'E' as u8, 'L' as u8, 'F' as u8];
-        let data = 1u8;
-        let version = 1u8;
-        header.extend_from_slice(&[class, data, version]);
-        header.resize(header_size, 0);
-        header
-    }
+    use header::test::mk_elf_header;
 
     #[test]
     fn interpret_class() {
@@ -0,0 +1,662 @@
+//! Source locations from the DWARF line number programs in `.debug_line`,
+//! versions 2 to 5, without allocating unless the sections are compressed.
+//! `ElfFile::addr2line` finds the file and line of an address; `DebugLine`
+//! works on sections found some other way, and `DebugLineSections` loads and
+//! decompresses a file's sections once for many lookups.
+
+#[cfg(any(feature = "compression", feature = "inflate"))]
+use alloc::borrow::Cow;
+use core::str;
+
+use ElfFile;
+use header::Class;
+use reader::{c_str, Reader};
+#[cfg(not(any(feature = "compression", feature = "inflate")))]
+use sections::SHF_COMPRESSED;
+
+// Standard opcodes.
+const DW_LNS_COPY: u8 = 1;
+const DW_LNS_ADVANCE_PC: u8 = 2;
+const DW_LNS_ADVANCE_LINE: u8 = 3;
+const DW_LNS_SET_FILE: u8 = 4;
+const DW_LNS_SET_COLUMN: u8 = 5;
+const DW_LNS_NEGATE_STMT: u8 = 6;
+const DW_LNS_CONST_ADD_PC: u8 = 8;
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 9;
+
+// Extended opcodes.
+const DW_LNE_END_SEQUENCE: u8 = 1;
+const DW_LNE_SET_ADDRESS: u8 = 2;
+
+// Entry content types and forms in version 5 headers.
+const DW_LNCT_PATH: u64 = 1;
+const DW_LNCT_DIRECTORY_INDEX: u64 = 2;
+const DW_FORM_BLOCK: u64 = 0x09;
+const DW_FORM_DATA1: u64 = 0x0b;
+const DW_FORM_DATA2: u64 = 0x05;
+const DW_FORM_DATA4: u64 = 0x06;
+const DW_FORM_DATA8: u64 = 0x07;
+const DW_FORM_DATA16: u64 = 0x1e;
+const DW_FORM_LINE_STRP: u64 = 0x1f;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_STRP: u64 = 0x0e;
+const DW_FORM_UDATA: u64 = 0x0f;
+
+/// A source location.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location<'a> {
+    /// The directory `file` is relative to, if it is relative. For DWARF 4 and
+    /// earlier, the compilation directory is not in `.debug_line`, so this is
+    /// `None` for files in it.
+    pub directory: Option<&'a str>,
+    pub file: &'a str,
+    /// The line, counting from 1, or 0 if unknown.
+    pub line: u64,
+    /// The column, counting from 1, or 0 if unknown.
+    pub column: u64,
+}
+
+/// The sections a line number program reads.
+#[derive(Clone, Copy, Debug)]
+pub struct DebugLine<'a> {
+    debug_line: &'a [u8],
+    debug_line_str: &'a [u8],
+    debug_str: &'a [u8],
+    address_size: u8,
+}
+
+impl<'a> DebugLine<'a> {
+    /// `address_size` is 4 or 8, as for the file's class; it is used for
+    /// versions before 5, which do not give it.
+    pub fn new(debug_line: &'a [u8],
+               debug_line_str: &'a [u8],
+               debug_str: &'a [u8],
+               address_size: u8)
+               -> DebugLine<'a> {
+        DebugLine { debug_line, debug_line_str, debug_str, address_size }
+    }
+
+    /// The line number programs, one for each compilation unit.
+    pub fn programs(&self) -> LinePrograms<'a> {
+        LinePrograms { debug_line: *self, offset: 0 }
+    }
+
+    /// Finds the source location of `address`.
+    pub fn find_location(&self, address: u64) -> Result<Option<Location<'a>>, &'static str> {
+        for program in self.programs() {
+            if let Some(location) = program?.find_location(address)? {
+                return Ok(Some(location));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LinePrograms<'a> {
+    debug_line: DebugLine<'a>,
+    offset: usize,
+}
+
+impl<'a> Iterator for LinePrograms<'a> {
+    type Item = Result<LineProgram<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.debug_line.debug_line.len() {
+            return None;
+        }
+        let result = LineProgram::parse(self.debug_line, self.offset);
+        match result {
+            Ok((_, next)) => self.offset = next,
+            Err(_) => self.offset = self.debug_line.debug_line.len(),
+        }
+        Some(result.map(|(program, _)| program))
+    }
+}
+
+/// The line number program of a compilation unit.
+#[derive(Clone, Copy, Debug)]
+pub struct LineProgram<'a> {
+    pub version: u16,
+    pub address_size: u8,
+    pub minimum_instruction_length: u8,
+    pub default_is_stmt: bool,
+    pub line_base: i8,
+    pub line_range: u8,
+    pub opcode_base: u8,
+    standard_opcode_lengths: &'a [u8],
+    is_dwarf64: bool,
+    // The directory table, then for versions before 5, the file table.
+    directories: EntryTable<'a>,
+    files: EntryTable<'a>,
+    program: &'a [u8],
+    debug_line: DebugLine<'a>,
+}
+
+/// A directory or file in a line number program's header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub path: &'a str,
+    /// For a file, the index of its directory.
+    pub directory_index: u64,
+}
+
+// A directory or file table: for version 5, its entry format (pairs of content
+// type and form) and entry count; for earlier versions, the entries run to an
+// empty path.
+#[derive(Clone, Copy, Debug)]
+struct EntryTable<'a> {
+    format: Option<(&'a [u8], u64)>,
+    is_file: bool,
+    data: &'a [u8],
+}
+
+impl<'a> EntryTable<'a> {
+    // Reads a version 5 entry format and count, followed by the entries.
+    fn parse_format(header: &mut Reader<'a>, is_file: bool) -> Result<EntryTable<'a>, &'static str> {
+        let format_count = header.u8()?;
+        let start = header.data;
+        for _ in 0..2 * format_count as u32 {
+            let _ = header.uleb()?;
+        }
+        let format = &start[..start.len() - header.data.len()];
+        let count = header.uleb()?;
+        Ok(EntryTable { format: Some((format, count)), is_file, data: header.data })
+    }
+}
+
+impl<'a> LineProgram<'a> {
+    // Parses the program at `offset`, returning it and the offset of the next.
+    fn parse(debug_line: DebugLine<'a>, offset: usize) -> Result<(LineProgram<'a>, usize), &'static str> {
+        let mut reader = Reader::new(debug_line.debug_line.get(offset..).ok_or("Line program out of range")?);
+        let (length, is_dwarf64) = match reader.u32()? {
+            0xffff_ffff => (reader.u64()?, true),
+            length if length >= 0xffff_fff0 => return Err("Invalid line program length"),
+            length => (length as u64, false),
+        };
+        let unit = reader.bytes(length)?;
+        let next = debug_line.debug_line.len() - reader.data.len();
+        let mut reader = Reader::new(unit);
+
+        let version = reader.u16()?;
+        check!((2..=5).contains(&version), "Unsupported line program version");
+        let address_size = if version >= 5 {
+            let address_size = reader.u8()?;
+            check!(reader.u8()? == 0, "Segment selectors are not supported");
+            address_size
+        } else {
+            debug_line.address_size
+        };
+        let header_length = reader.offset(is_dwarf64)?;
+        let program = {
+            let mut rest = reader;
+            let _ = rest.bytes(header_length)?;
+            rest.data
+        };
+        let mut header = Reader::new(&reader.data[..reader.data.len() - program.len()]);
+        let minimum_instruction_length = header.u8()?;
+        if version >= 4 {
+            // The maximum operations per instruction, which is only not 1 for
+            // VLIW machines.
+            let _ = header.u8()?;
+        }
+        let default_is_stmt = header.u8()? != 0;
+        let line_base = header.u8()? as i8;
+        let line_range = header.u8()?;
+        check!(line_range != 0, "Line program has a line range of 0");
+        let opcode_base = header.u8()?;
+        check!(opcode_base != 0, "Line program has an opcode base of 0");
+        let standard_opcode_lengths = header.bytes(opcode_base as u64 - 1)?;
+
+        let (directories, files) = if version >= 5 {
+            let directories = EntryTable::parse_format(&mut header, false)?;
+            // Skip the directories to find the files.
+            let mut iter = EntryIter { table: directories, debug_line, is_dwarf64, index: 0 };
+            while iter.next().transpose()?.is_some() {}
+            header.data = iter.table.data;
+            (directories, EntryTable::parse_format(&mut header, true)?)
+        } else {
+            let directories = EntryTable { format: None, is_file: false, data: header.data };
+            let mut iter = EntryIter { table: directories, debug_line, is_dwarf64, index: 0 };
+            while iter.next().transpose()?.is_some() {}
+            // Step over the terminating empty path.
+            let files = iter.table.data.get(1..).ok_or("Unexpected end of line program header")?;
+            (directories, EntryTable { format: None, is_file: true, data: files })
+        };
+
+        Ok((LineProgram {
+            version,
+            address_size,
+            minimum_instruction_length,
+            default_is_stmt,
+            line_base,
+            line_range,
+            opcode_base,
+            standard_opcode_lengths,
+            is_dwarf64,
+            directories,
+            files,
+            program,
+            debug_line,
+        }, next))
+    }
+
+    /// The directory at `index` in the header. For version 5, index 0 is the
+    /// compilation directory; before that, it is not in the table, and index 1
+    /// is the first entry.
+    pub fn directory(&self, index: u64) -> Result<Option<&'a str>, &'static str> {
+        let index = match (self.version, index) {
+            (5.., index) => index,
+            (_, 0) => return Ok(None),
+            (_, index) => index - 1,
+        };
+        Ok(self.entries(self.directories).nth(index as usize).transpose()?.map(|entry| entry.path))
+    }
+
+    /// The file at `index` in the header. For version 5, index 0 is the
+    /// primary source file; before that, the first entry has index 1.
+    pub fn file(&self, index: u64) -> Result<Option<Entry<'a>>, &'static str> {
+        let index = match (self.version, index) {
+            (5.., index) => index,
+            (_, 0) => return Ok(None),
+            (_, index) => index - 1,
+        };
+        self.entries(self.files).nth(index as usize).transpose()
+    }
+
+    fn entries(&self, table: EntryTable<'a>) -> EntryIter<'a> {
+        EntryIter { table, debug_line: self.debug_line, is_dwarf64: self.is_dwarf64, index: 0 }
+    }
+
+    /// The rows of the line number matrix, in the order the program makes
+    /// them.
+    pub fn rows(&self) -> LineRows<'a> {
+        LineRows { program: *self, reader: Reader::new(self.program), row: LineRow::new(self.default_is_stmt) }
+    }
+
+    /// Finds the source location of `address` in this program.
+    pub fn find_location(&self, address: u64) -> Result<Option<Location<'a>>, &'static str> {
+        let mut previous: Option<LineRow> = None;
+        for row in self.rows() {
+            let row = row?;
+            match previous {
+                Some(previous) if previous.address <= address && address < row.address => {
+                    return self.location(&previous).map(Some);
+                }
+                _ => {}
+            }
+            previous = if row.end_sequence { None } else { Some(row) };
+        }
+        Ok(None)
+    }
+
+    /// The source location of `row`.
+    pub fn location(&self, row: &LineRow) -> Result<Location<'a>, &'static str> {
+        let file = self.file(row.file)?.ok_or("Line program file index out of range")?;
+        let directory = if file.path.starts_with('/') { None } else { self.directory(file.directory_index)? };
+        Ok(Location { directory, file: file.path, line: row.line, column: row.column })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct EntryIter<'a> {
+    table: EntryTable<'a>,
+    debug_line: DebugLine<'a>,
+    is_dwarf64: bool,
+    index: u64,
+}
+
+impl<'a> Iterator for EntryIter<'a> {
+    type Item = Result<Entry<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut reader = Reader::new(self.table.data);
+        let entry = match self.table.format {
+            None => {
+                if reader.data.first() == Some(&0) {
+                    return None;
+                }
+                reader.c_str().and_then(|path| {
+                    let mut directory_index = 0;
+                    if self.table.is_file {
+                        directory_index = reader.uleb()?;
+                        // The modification time and length.
+                        let _ = reader.uleb()?;
+                        let _ = reader.uleb()?;
+                    }
+                    Ok(Entry { path, directory_index })
+                })
+            }
+            Some((format, count)) => {
+                if self.index >= count {
+                    return None;
+                }
+                self.entry(&mut reader, format)
+            }
+        };
+        self.index += 1;
+        self.table.data = reader.data;
+        if entry.is_err() {
+            self.table.data = &[];
+            self.table.format = Some((&[], 0));
+        }
+        Some(entry)
+    }
+}
+
+impl<'a> EntryIter<'a> {
+    fn entry(&self, reader: &mut Reader<'a>, format: &'a [u8]) -> Result<Entry<'a>, &'static str> {
+        let mut format = Reader::new(format);
+        let mut entry = Entry { path: "", directory_index: 0 };
+        while !format.data.is_empty() {
+            let content_type = format.uleb()?;
+            let form = format.uleb()?;
+            let mut value = 0;
+            let mut string = None;
+            match form {
+                DW_FORM_STRING => string = Some(reader.c_str()?),
+                DW_FORM_LINE_STRP => {
+                    let offset = reader.offset(self.is_dwarf64)? as usize;
+                    string = Some(c_str(self.debug_line.debug_line_str, offset)?);
+                }
+                DW_FORM_STRP => {
+                    let offset = reader.offset(self.is_dwarf64)? as usize;
+                    string = Some(c_str(self.debug_line.debug_str, offset)?);
+                }
+                DW_FORM_UDATA => value = reader.uleb()?,
+                DW_FORM_DATA1 => value = reader.u8()? as u64,
+                DW_FORM_DATA2 => value = reader.u16()? as u64,
+                DW_FORM_DATA4 => value = reader.u32()? as u64,
+                DW_FORM_DATA8 => value = reader.u64()?,
+                DW_FORM_DATA16 => {
+                    let _ = reader.bytes(16)?;
+                }
+                DW_FORM_BLOCK => {
+                    let length = reader.uleb()?;
+                    let _ = reader.bytes(length)?;
+                }
+                _ => return Err("Unsupported form in line program header"),
+            }
+            match content_type {
+                DW_LNCT_PATH => entry.path = string.ok_or("Line program path is not a string")?,
+                DW_LNCT_DIRECTORY_INDEX => entry.directory_index = value,
+                _ => {}
+            }
+        }
+        Ok(entry)
+    }
+}
+
+/// A row of the line number matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineRow {
+    pub address: u64,
+    pub file: u64,
+    pub line: u64,
+    pub column: u64,
+    pub is_stmt: bool,
+    /// Whether this row is the first address after a sequence of
+    /// instructions, rather than the start of one.
+    pub end_sequence: bool,
+}
+
+impl LineRow {
+    fn new(default_is_stmt: bool) -> LineRow {
+        LineRow { address: 0, file: 1, line: 1, column: 0, is_stmt: default_is_stmt, end_sequence: false }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LineRows<'a> {
+    program: LineProgram<'a>,
+    reader: Reader<'a>,
+    row: LineRow,
+}
+
+impl<'a> Iterator for LineRows<'a> {
+    type Item = Result<LineRow, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.reader.data.is_empty() {
+            match self.step() {
+                Ok(Some(row)) => return Some(Ok(row)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.reader.data = &[];
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a> LineRows<'a> {
+    // Runs one instruction, returning the row it appends, if any.
+    fn step(&mut self) -> Result<Option<LineRow>, &'static str> {
+        let program = &self.program;
+        let row = &mut self.row;
+        let advance = |row: &mut LineRow, operations: u64| {
+            row.address = row.address.wrapping_add(operations.wrapping_mul(program.minimum_instruction_length as u64));
+        };
+        let opcode = self.reader.u8()?;
+        if opcode >= program.opcode_base {
+            let adjusted = opcode - program.opcode_base;
+            advance(row, (adjusted / program.line_range) as u64);
+            let line_advance = program.line_base as i64 + (adjusted % program.line_range) as i64;
+            row.line = row.line.wrapping_add(line_advance as u64);
+            return Ok(Some(self.row));
+        }
+        match opcode {
+            0 => {
+                let length = self.reader.uleb()?;
+                let mut instruction = Reader::new(self.reader.bytes(length)?);
+                match instruction.u8()? {
+                    DW_LNE_END_SEQUENCE => {
+                        row.end_sequence = true;
+                        let end = *row;
+                        *row = LineRow::new(program.default_is_stmt);
+                        return Ok(Some(end));
+                    }
+                    DW_LNE_SET_ADDRESS => {
+                        row.address = match instruction.data.len() {
+                            4 => instruction.u32()? as u64,
+                            8 => instruction.u64()?,
+                            _ => return Err("Unsupported address size in line program"),
+                        };
+                    }
+                    // DW_LNE_define_file, DW_LNE_set_discriminator, and vendor
+                    // extensions.
+                    _ => {}
+                }
+            }
+            DW_LNS_COPY => return Ok(Some(self.row)),
+            DW_LNS_ADVANCE_PC => {
+                let operations = self.reader.uleb()?;
+                advance(row, operations);
+            }
+            DW_LNS_ADVANCE_LINE => {
+                let line_advance = self.reader.sleb()?;
+                row.line = row.line.wrapping_add(line_advance as u64);
+            }
+            DW_LNS_SET_FILE => row.file = self.reader.uleb()?,
+            DW_LNS_SET_COLUMN => row.column = self.reader.uleb()?,
+            DW_LNS_NEGATE_STMT => row.is_stmt = !row.is_stmt,
+            DW_LNS_CONST_ADD_PC => advance(row, ((255 - program.opcode_base) / program.line_range) as u64),
+            DW_LNS_FIXED_ADVANCE_PC => row.address = row.address.wrapping_add(self.reader.u16()? as u64),
+            _ => {
+                // DW_LNS_set_basic_block, DW_LNS_set_prologue_end and the like
+                // only change flags not kept here; skip their operands.
+                let operands = program.standard_opcode_lengths[opcode as usize - 1];
+                for _ in 0..operands {
+                    let _ = self.reader.uleb()?;
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The line number sections of a file, decompressed if they are compressed.
+#[cfg(any(feature = "compression", feature = "inflate"))]
+#[derive(Debug)]
+pub struct DebugLineSections<'a> {
+    debug_line: Cow<'a, [u8]>,
+    debug_line_str: Cow<'a, [u8]>,
+    debug_str: Cow<'a, [u8]>,
+    address_size: u8,
+}
+
+#[cfg(any(feature = "compression", feature = "inflate"))]
+impl<'a> DebugLineSections<'a> {
+    pub fn load(elf_file: &ElfFile<'a>) -> Result<DebugLineSections<'a>, &'static str> {
+        let address_size = address_size(elf_file)?;
+        let section = |name| -> Result<Cow<'a, [u8]>, &'static str> {
+            match elf_file.find_section_by_name(name) {
+                Some(section) => {
+                    let _ = section.data_checked(elf_file)?;
+                    section.decompressed_data(elf_file)
+                }
+                None => Ok(Cow::Borrowed(&[][..])),
+            }
+        };
+        Ok(DebugLineSections {
+            debug_line: section(".debug_line")?,
+            debug_line_str: section(".debug_line_str")?,
+            debug_str: section(".debug_str")?,
+            address_size,
+        })
+    }
+
+    pub fn debug_line(&self) -> DebugLine<'_> {
+        DebugLine::new(&self.debug_line, &self.debug_line_str, &self.debug_str, self.address_size)
+    }
+}
+
+/// Finds the source location of `address` in `elf_file`, and passes it to
+/// `f`. The location borrows from the sections, which are decompressed first
+/// if they are compressed.
+#[cfg(any(feature = "compression", feature = "inflate"))]
+pub fn addr2line<F, R>(elf_file: &ElfFile, address: u64, f: F) -> Result<Option<R>, &'static str>
+    where F: FnOnce(Location) -> R
+{
+    let sections = DebugLineSections::load(elf_file)?;
+    Ok(sections.debug_line().find_location(address)?.map(f))
+}
+
+/// Finds the source location of `address` in `elf_file`, and passes it to
+/// `f`. Compressed sections need the `compression` or `inflate` feature.
+#[cfg(not(any(feature = "compression", feature = "inflate")))]
+pub fn addr2line<F, R>(elf_file: &ElfFile, address: u64, f: F) -> Result<Option<R>, &'static str>
+    where F: FnOnce(Location) -> R
+{
+    let address_size = address_size(elf_file)?;
+    let section = |name| -> Result<&[u8], &'static str> {
+        match elf_file.find_section_by_name(name) {
+            Some(section) => {
+                let compressed = section.flags() & SHF_COMPRESSED != 0 ||
+                                 section.get_name(elf_file).is_ok_and(|name| name.starts_with(".zdebug"));
+                check!(!compressed, "Decompressing debug sections needs the `compression` or `inflate` feature");
+                section.data_checked(elf_file)
+            }
+            None => Ok(&[]),
+        }
+    };
+    let debug_line = DebugLine::new(section(".debug_line")?,
+                                    section(".debug_line_str")?,
+                                    section(".debug_str")?,
+                                    address_size);
+    Ok(debug_line.find_location(address)?.map(f))
+}
+
+fn address_size(elf_file: &ElfFile) -> Result<u8, &'static str> {
+    let is_64 = match elf_file.header.pt1.class() {
+        Class::ThirtyTwo => false,
+        Class::SixtyFour => true,
+        Class::None | Class::Other(_) => return Err("Invalid ELF class"),
+    };
+    elf_file.check_tables(is_64)?;
+    Ok(if is_64 { 8 } else { 4 })
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+    use sections::test::mk_file;
+    #[cfg(any(feature = "compression", feature = "inflate"))]
+    use sections::test::mk_zdebug;
+
+    // A version 4 program with rows for lines 3 and 4 of src/a.c at 0x1000 and
+    // 0x1004, ending at 0x1006.
+    fn mk_debug_line() -> Vec<u8> {
+        let mut header = vec![1, 1, 1, 0xfb, 14, 13, 0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1];
+        header.extend_from_slice(b"src\0\0a.c\0\x01\0\0\0");
+        let mut program = vec![0, 9, 2];
+        program.extend_from_slice(&0x1000u64.to_ne_bytes());
+        program.extend_from_slice(&[20, 75, 2, 2, 0, 1, 1]);
+        let mut unit = 4u16.to_ne_bytes().to_vec();
+        unit.extend_from_slice(&(header.len() as u32).to_ne_bytes());
+        unit.extend_from_slice(&header);
+        unit.extend_from_slice(&program);
+        let mut data = (unit.len() as u32).to_ne_bytes().to_vec();
+        data.extend_from_slice(&unit);
+        data
+    }
+
+    #[test]
+    fn find_location() {
+        let data = mk_debug_line();
+        let debug_line = DebugLine::new(&data, &[], &[], 8);
+        let location = |line| Some(Location { directory: Some("src"), file: "a.c", line, column: 0 });
+        assert_eq!(debug_line.find_location(0x1002), Ok(location(3)));
+        assert_eq!(debug_line.find_location(0x1005), Ok(location(4)));
+        assert_eq!(debug_line.find_location(0x1006), Ok(None));
+        assert_eq!(debug_line.find_location(0xfff), Ok(None));
+        let rows = debug_line.programs().next().unwrap().unwrap().rows().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows.iter().map(|row| (row.address, row.end_sequence)).collect::<Vec<_>>(),
+                   vec![(0x1000, false), (0x1004, false), (0x1006, true)]);
+
+        // A truncated program is an error, not a panic.
+        assert!(DebugLine::new(&data[..data.len() - 3], &[], &[], 8).find_location(0x1002).is_err());
+
+        // So is a DWARF64 unit length running past the end of the section.
+        let data = [0xff; 16];
+        let mut programs = DebugLine::new(&data, &[], &[], 8).programs();
+        assert_eq!(programs.next().map(|program| program.err()), Some(Some("Unexpected end of DWARF data")));
+        assert!(programs.next().is_none());
+    }
+
+    #[test]
+    fn addr2line() {
+        let input = mk_file(&[(".debug_line", &mk_debug_line())]);
+        let elf_file = ElfFile::new(&input).unwrap();
+        let location = elf_file.addr2line(0x1002, |location| (location.file.to_owned(), location.line));
+        assert_eq!(location, Ok(Some(("a.c".to_owned(), 3))));
+        assert_eq!(elf_file.addr2line(0x2000, |location| location.line), Ok(None));
+    }
+
+    #[cfg(any(feature = "compression", feature = "inflate"))]
+    #[test]
+    fn addr2line_compressed() {
+        // zlib data holding `data` in a single stored block.
+        fn stored(data: &[u8]) -> Vec<u8> {
+            let mut zlib = vec![0x78, 0x01, 0x01];
+            zlib.extend_from_slice(&(data.len() as u16).to_le_bytes());
+            zlib.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+            zlib.extend_from_slice(data);
+            let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+                let a = (a + byte as u32) % 65521;
+                (a, (b + a) % 65521)
+            });
+            zlib.extend_from_slice(&(b << 16 | a).to_be_bytes());
+            zlib
+        }
+
+        let data = mk_debug_line();
+        let input = mk_file(&[(".zdebug_line", &mk_zdebug(&stored(&data), data.len() as u64))]);
+        let elf_file = ElfFile::new(&input).unwrap();
+        let location = elf_file.addr2line(0x1005, |location| (location.directory.map(str::to_owned), location.line));
+        assert_eq!(location, Ok(Some((Some("src".to_owned()), 4))));
+    }
+}
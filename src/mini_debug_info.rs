@@ -0,0 +1,115 @@
+//! MiniDebugInfo: the small ELF file, holding just a symbol table, which some
+//! distributions embed xz-compressed in the `.gnu_debugdata` section of
+//! stripped binaries so that backtraces can still name functions.
+
+use std::vec::Vec;
+
+use lzma_rs;
+
+use {ElfFile, Extensions};
+use symbol_table::{self, Symbol};
+
+/// The decompressed contents of a `.gnu_debugdata` section.
+#[derive(Clone, Debug)]
+pub struct MiniDebugInfo {
+    data: Vec<u8>,
+}
+
+impl MiniDebugInfo {
+    /// Decompresses the `.gnu_debugdata` section of `elf_file`, if it has
+    /// one.
+    pub fn new(elf_file: &ElfFile) -> Result<Option<MiniDebugInfo>, &'static str> {
+        let compressed = match elf_file.get_gnu_debugdata() {
+            Some(compressed) => compressed,
+            None => return Ok(None),
+        };
+        let mut data = Vec::new();
+        lzma_rs::xz_decompress(&mut &*compressed, &mut data).map_err(|_| "Decompression error")?;
+        // Check now, so that `elf_file` cannot fail.
+        let _ = ElfFile::new(&data)?;
+        Ok(Some(MiniDebugInfo { data }))
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The embedded file.
+    pub fn elf_file(&self) -> ElfFile<'_> {
+        ElfFile::new(&self.data).unwrap()
+    }
+
+    /// Finds the function or object containing `address` in `elf_file`, the
+    /// file this was read from, falling back to the embedded symbol table.
+    pub fn find_symbol_by_address<'s>(&'s self, elf_file: &ElfFile<'s>, address: u64) -> Option<Symbol<'s>> {
+        symbol_table::find_by_address(elf_file, address)
+            .or_else(|| symbol_table::find_by_address(&self.elf_file(), address))
+    }
+
+    /// Finds the function or object called `name` in `elf_file`, the file
+    /// this was read from, falling back to the embedded symbol table.
+    pub fn find_symbol_by_name<'s>(&'s self, elf_file: &ElfFile<'s>, name: &str) -> Option<Symbol<'s>> {
+        symbol_table::find_by_name(elf_file, name)
+            .or_else(|| symbol_table::find_by_name(&self.elf_file(), name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+    use sections::test::mk_file;
+
+    // The `.gnu_debugdata` of a static x86-64 executable defining `answer`
+    // at 0x401000 (6 bytes) and `_start` after it, made as distributions do:
+    // `objcopy --only-keep-debug`, then `objcopy -S --keep-symbol=...`, then
+    // `xz`.
+    const GNU_DEBUGDATA: &[u8] = &[
+        0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00, 0x00, 0x04, 0xe6, 0xd6, 0xb4, 0x46, 0x04, 0xc0, 0xb0, 0x01,
+        0xa0, 0x05, 0x21, 0x01, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x54, 0xbe, 0x94, 0xdc,
+        0xe0, 0x02, 0x9f, 0x00, 0xa8, 0x5d, 0x00, 0x3f, 0x91, 0x45, 0x84, 0x68, 0x3d, 0x89, 0xa6, 0xda,
+        0x8a, 0xcc, 0x93, 0xe2, 0x4e, 0xd9, 0x03, 0x74, 0x24, 0x08, 0x05, 0x97, 0x52, 0x2e, 0xf8, 0x82,
+        0xfd, 0x54, 0x19, 0xf8, 0x2b, 0x91, 0x2f, 0x75, 0xca, 0xcf, 0xed, 0x5e, 0x20, 0x98, 0x49, 0xd9,
+        0x9f, 0xd1, 0xcc, 0x7f, 0x62, 0xbc, 0x55, 0x80, 0x58, 0x82, 0xe5, 0x1c, 0x6b, 0x77, 0xb0, 0x55,
+        0x42, 0xcc, 0x52, 0x3e, 0xf1, 0x9c, 0x61, 0x3c, 0xb7, 0x43, 0x75, 0x15, 0x81, 0x43, 0xd7, 0x37,
+        0xc4, 0x31, 0xeb, 0xc5, 0xc8, 0x13, 0xeb, 0xb0, 0x7b, 0xd6, 0x2a, 0xcb, 0x66, 0xb7, 0x0d, 0x7a,
+        0xb3, 0x74, 0x79, 0x33, 0x21, 0xbe, 0xd3, 0x3e, 0xf2, 0x2c, 0xa6, 0x6b, 0xdf, 0xc4, 0x27, 0xb2,
+        0x81, 0x98, 0x7d, 0x8f, 0xa0, 0xd3, 0x76, 0xcc, 0x14, 0x88, 0x5d, 0x20, 0xa6, 0x17, 0xe5, 0x77,
+        0x44, 0x95, 0x29, 0x5f, 0xad, 0x55, 0xd0, 0x95, 0xf5, 0x9a, 0x03, 0xa3, 0xe1, 0x00, 0xe3, 0x09,
+        0x84, 0x4a, 0x88, 0xc4, 0xdb, 0x9b, 0x8d, 0x9e, 0xa6, 0x52, 0xce, 0x54, 0x45, 0x35, 0x65, 0x7f,
+        0xc7, 0x1d, 0x0e, 0x70, 0x4f, 0x81, 0xfc, 0x30, 0xcd, 0xd7, 0x33, 0xbd, 0xf6, 0xd6, 0x00, 0x00,
+        0x37, 0x3b, 0xee, 0x31, 0x85, 0x36, 0x53, 0xfd, 0x00, 0x01, 0xcc, 0x01, 0xa0, 0x05, 0x00, 0x00,
+        0x4c, 0xf8, 0xbf, 0xd3, 0xb1, 0xc4, 0x67, 0xfb, 0x02, 0x00, 0x00, 0x00, 0x00, 0x04, 0x59, 0x5a
+    ];
+
+    // A little-endian ELF64 file with just a `.gnu_debugdata` section.
+    fn mk_stripped(gnu_debugdata: &[u8]) -> Vec<u8> {
+        mk_file(&[(".gnu_debugdata", gnu_debugdata)])
+    }
+
+    #[test]
+    fn gnu_debugdata() {
+        let input = mk_stripped(GNU_DEBUGDATA);
+        let elf_file = ElfFile::new(&input).unwrap();
+        let mini = MiniDebugInfo::new(&elf_file).unwrap().unwrap();
+        assert_eq!(mini.elf_file().find_section_by_name(".symtab").map(|s| s.size()), Some(72));
+
+        let answer = Symbol { name: "answer", address: 0x401000, size: 6 };
+        assert_eq!(mini.find_symbol_by_address(&elf_file, 0x401003), Some(answer));
+        let start = Symbol { name: "_start", address: 0x401006, size: 2 };
+        assert_eq!(mini.find_symbol_by_name(&elf_file, "_start"), Some(start));
+        assert_eq!(mini.find_symbol_by_name(&elf_file, "main"), None);
+
+        let mut corrupt = GNU_DEBUGDATA.to_vec();
+        corrupt[40] ^= 0xff;
+        let input = mk_stripped(&corrupt);
+        assert_eq!(MiniDebugInfo::new(&ElfFile::new(&input).unwrap()).err(), Some("Decompression error"));
+
+        // Without the section, there is nothing to decompress.
+        let mut input = mk_stripped(GNU_DEBUGDATA);
+        let name = input.len() - 64;
+        input[name] = 1;
+        assert!(MiniDebugInfo::new(&ElfFile::new(&input).unwrap()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,408 @@
+//! MIPS-specific structures: the packing of 64-bit relocations, the
+//! `.MIPS.abiflags` and `.reginfo` sections, and the dynamic tags which
+//! describe the GOT.
+//!
+//! Dynamically linked MIPS files have no relocations for the GOT. It holds
+//! `DT_MIPS_LOCAL_GOTNO` local entries, followed by one global entry for
+//! each dynamic symbol from `DT_MIPS_GOTSYM` on, in order.
+
+use {ElfFile, P32, P64};
+use dynamic::{self, Tag};
+use header::{Class, Machine};
+use reader::Reader;
+use program::{self, TYPE_MIPS_ABIFLAGS, TYPE_MIPS_REGINFO};
+use sections::{ShType, SHT_MIPS_ABIFLAGS, SHT_MIPS_REGINFO};
+
+pub const DT_MIPS_RLD_VERSION: u64 = 0x70000001;
+pub const DT_MIPS_TIME_STAMP: u64 = 0x70000002;
+pub const DT_MIPS_ICHECKSUM: u64 = 0x70000003;
+pub const DT_MIPS_IVERSION: u64 = 0x70000004;
+pub const DT_MIPS_FLAGS: u64 = 0x70000005;
+pub const DT_MIPS_BASE_ADDRESS: u64 = 0x70000006;
+pub const DT_MIPS_CONFLICT: u64 = 0x70000008;
+pub const DT_MIPS_LIBLIST: u64 = 0x70000009;
+pub const DT_MIPS_LOCAL_GOTNO: u64 = 0x7000000a;
+pub const DT_MIPS_CONFLICTNO: u64 = 0x7000000b;
+pub const DT_MIPS_LIBLISTNO: u64 = 0x70000010;
+pub const DT_MIPS_SYMTABNO: u64 = 0x70000011;
+pub const DT_MIPS_UNREFEXTNO: u64 = 0x70000012;
+pub const DT_MIPS_GOTSYM: u64 = 0x70000013;
+pub const DT_MIPS_HIPAGENO: u64 = 0x70000014;
+pub const DT_MIPS_RLD_MAP: u64 = 0x70000016;
+pub const DT_MIPS_OPTIONS: u64 = 0x70000029;
+pub const DT_MIPS_PLTGOT: u64 = 0x70000032;
+pub const DT_MIPS_RWPLT: u64 = 0x70000034;
+pub const DT_MIPS_RLD_MAP_REL: u64 = 0x70000035;
+
+// Register sizes (AbiFlags::gpr_size, cpr1_size and cpr2_size)
+pub const AFL_REG_NONE: u8 = 0;
+pub const AFL_REG_32: u8 = 1;
+pub const AFL_REG_64: u8 = 2;
+pub const AFL_REG_128: u8 = 3;
+
+// Floating-point ABIs (AbiFlags::fp_abi), as in `.gnu.attributes`
+pub const ABI_FP_ANY: u8 = 0;
+pub const ABI_FP_DOUBLE: u8 = 1;
+pub const ABI_FP_SINGLE: u8 = 2;
+pub const ABI_FP_SOFT: u8 = 3;
+pub const ABI_FP_OLD_64: u8 = 4;
+pub const ABI_FP_XX: u8 = 5;
+pub const ABI_FP_64: u8 = 6;
+pub const ABI_FP_64A: u8 = 7;
+
+// Application-specific extensions (AbiFlags::ases)
+pub const AFL_ASE_DSP: u32 = 0x00000001;
+pub const AFL_ASE_DSPR2: u32 = 0x00000002;
+pub const AFL_ASE_EVA: u32 = 0x00000004;
+pub const AFL_ASE_MCU: u32 = 0x00000008;
+pub const AFL_ASE_MDMX: u32 = 0x00000010;
+pub const AFL_ASE_MIPS3D: u32 = 0x00000020;
+pub const AFL_ASE_MT: u32 = 0x00000040;
+pub const AFL_ASE_SMARTMIPS: u32 = 0x00000080;
+pub const AFL_ASE_VIRT: u32 = 0x00000100;
+pub const AFL_ASE_MSA: u32 = 0x00000200;
+pub const AFL_ASE_MIPS16: u32 = 0x00000400;
+pub const AFL_ASE_MICROMIPS: u32 = 0x00000800;
+pub const AFL_ASE_XPA: u32 = 0x00001000;
+pub const AFL_ASE_DSPR3: u32 = 0x00002000;
+pub const AFL_ASE_MIPS16E2: u32 = 0x00004000;
+pub const AFL_ASE_CRC: u32 = 0x00008000;
+pub const AFL_ASE_GINV: u32 = 0x00020000;
+pub const AFL_ASE_LOONGSON_MMI: u32 = 0x00040000;
+pub const AFL_ASE_LOONGSON_CAM: u32 = 0x00080000;
+pub const AFL_ASE_LOONGSON_EXT: u32 = 0x00100000;
+pub const AFL_ASE_LOONGSON_EXT2: u32 = 0x00200000;
+
+// AbiFlags::flags1
+pub const AFL_FLAGS1_ODDSPREG: u32 = 0x1;
+
+/// The `info` of a MIPS64 relocation, which holds a symbol, a special
+/// symbol and up to three relocation types to apply in turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mips64RelocationInfo {
+    pub symbol: u32,
+    /// Used by some relocation types instead of the symbol, such as
+    /// `RSS_GP` for the GP value.
+    pub special_symbol: u8,
+    /// The types, of which the second and third are 0 if unused.
+    pub types: [u8; 3],
+}
+
+impl Mips64RelocationInfo {
+    /// Decodes `info` as read from the file. The symbol comes first, as a
+    /// word in the file's byte order, then each byte in turn.
+    pub fn from_info(info: u64) -> Mips64RelocationInfo {
+        let bytes = info.to_ne_bytes();
+        Mips64RelocationInfo {
+            symbol: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            special_symbol: bytes[4],
+            types: [bytes[7], bytes[6], bytes[5]],
+        }
+    }
+}
+
+/// The contents of `.MIPS.abiflags` (`PT_MIPS_ABIFLAGS`): the ISA, register
+/// sizes and floating-point ABI a file needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AbiFlags {
+    pub version: u16,
+    pub isa_level: u8,
+    pub isa_rev: u8,
+    pub gpr_size: u8,
+    pub cpr1_size: u8,
+    pub cpr2_size: u8,
+    pub fp_abi: u8,
+    pub isa_ext: u32,
+    pub ases: u32,
+    pub flags1: u32,
+    pub flags2: u32,
+}
+
+impl AbiFlags {
+    pub fn new(data: &[u8]) -> Result<AbiFlags, &'static str> {
+        let mut reader = Reader::new(data);
+        Ok(AbiFlags {
+            version: reader.u16()?,
+            isa_level: reader.u8()?,
+            isa_rev: reader.u8()?,
+            gpr_size: reader.u8()?,
+            cpr1_size: reader.u8()?,
+            cpr2_size: reader.u8()?,
+            fp_abi: reader.u8()?,
+            isa_ext: reader.u32()?,
+            ases: reader.u32()?,
+            flags1: reader.u32()?,
+            flags2: reader.u32()?,
+        })
+    }
+
+    /// The `.MIPS.abiflags` section of `elf_file`, or if it has no section
+    /// headers, the `PT_MIPS_ABIFLAGS` segment.
+    pub fn load(elf_file: &ElfFile) -> Result<Option<AbiFlags>, &'static str> {
+        match load_data(elf_file, SHT_MIPS_ABIFLAGS, TYPE_MIPS_ABIFLAGS)? {
+            Some(data) => AbiFlags::new(data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The width of the general-purpose registers in bits, or `None` for an
+    /// unknown size.
+    pub fn gpr_bits(&self) -> Option<u32> {
+        register_bits(self.gpr_size)
+    }
+
+    /// The width of the floating-point registers in bits.
+    pub fn cpr1_bits(&self) -> Option<u32> {
+        register_bits(self.cpr1_size)
+    }
+
+    pub fn cpr2_bits(&self) -> Option<u32> {
+        register_bits(self.cpr2_size)
+    }
+
+    /// Whether odd-numbered single-precision registers are used.
+    pub fn is_odd_sp_reg(&self) -> bool {
+        self.flags1 & AFL_FLAGS1_ODDSPREG != 0
+    }
+}
+
+fn register_bits(size: u8) -> Option<u32> {
+    match size {
+        AFL_REG_NONE => Some(0),
+        AFL_REG_32 => Some(32),
+        AFL_REG_64 => Some(64),
+        AFL_REG_128 => Some(128),
+        _ => None,
+    }
+}
+
+/// The contents of `.reginfo` (`PT_MIPS_REGINFO`): the registers a file uses,
+/// and its GP value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegInfo {
+    /// The general-purpose registers used, with bit n for register n.
+    pub gpr_mask: u32,
+    /// The registers used of each coprocessor.
+    pub cpr_mask: [u32; 4],
+    pub gp_value: i64,
+}
+
+impl RegInfo {
+    /// The 64-bit form has padding after `gpr_mask` and a 64-bit GP value.
+    pub fn new(data: &[u8], is_64: bool) -> Result<RegInfo, &'static str> {
+        let mut reader = Reader::new(data);
+        let gpr_mask = reader.u32()?;
+        if is_64 {
+            let _ = reader.bytes(4)?;
+        }
+        let cpr_mask = [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?];
+        let gp_value = if is_64 { reader.u64()? as i64 } else { reader.u32()? as i32 as i64 };
+        Ok(RegInfo { gpr_mask, cpr_mask, gp_value })
+    }
+
+    /// The `.reginfo` section of `elf_file`, or if it has no section
+    /// headers, the `PT_MIPS_REGINFO` segment. 64-bit files usually have an
+    /// `ODK_REGINFO` entry in `.MIPS.options` instead.
+    pub fn load(elf_file: &ElfFile) -> Result<Option<RegInfo>, &'static str> {
+        let is_64 = elf_file.header.pt1.class() == Class::SixtyFour;
+        match load_data(elf_file, SHT_MIPS_REGINFO, TYPE_MIPS_REGINFO)? {
+            Some(data) => RegInfo::new(data, is_64).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+// The data of the section of type `sh_type`, or failing that, the segment of
+// type `ph_type`, in a MIPS file.
+fn load_data<'a>(elf_file: &ElfFile<'a>, sh_type: u32, ph_type: u32) -> Result<Option<&'a [u8]>, &'static str> {
+    let is_64 = match elf_file.header.pt1.class() {
+        Class::ThirtyTwo => false,
+        Class::SixtyFour => true,
+        Class::None | Class::Other(_) => return Err("Invalid ELF class"),
+    };
+    let machine = elf_file.header.pt2.machine().as_machine();
+    check!(matches!(machine, Machine::Mips | Machine::MipsRs3Le), "Not a MIPS file");
+    elf_file.check_tables(is_64)?;
+    for section in elf_file.section_iter() {
+        if section.get_type() == Ok(ShType::ProcessorSpecific(sh_type)) {
+            return section.data_checked(elf_file).map(Some);
+        }
+    }
+    for segment in elf_file.program_iter() {
+        if segment.get_type() == Ok(program::Type::ProcessorSpecific(ph_type)) {
+            return segment.data_checked(elf_file).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// The layout of the GOT of a dynamically linked MIPS file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GotLayout {
+    /// The address of the GOT (`DT_PLTGOT`).
+    pub address: u64,
+    /// The size of each entry, which is the word size.
+    pub entry_size: u64,
+    /// The number of local entries (`DT_MIPS_LOCAL_GOTNO`), including the
+    /// ones reserved for the dynamic linker.
+    pub local_count: u64,
+    /// The index of the first dynamic symbol with a global entry
+    /// (`DT_MIPS_GOTSYM`).
+    pub first_global_symbol: u64,
+    /// The number of dynamic symbols (`DT_MIPS_SYMTABNO`).
+    pub symbol_count: u64,
+}
+
+impl GotLayout {
+    /// Reads the layout from the dynamic segment of `elf_file`. Returns
+    /// `None` if it has no GOT.
+    pub fn load(elf_file: &ElfFile) -> Result<Option<GotLayout>, &'static str> {
+        let is_64 = match elf_file.header.pt1.class() {
+            Class::ThirtyTwo => false,
+            Class::SixtyFour => true,
+            Class::None | Class::Other(_) => return Err("Invalid ELF class"),
+        };
+        let machine = elf_file.header.pt2.machine().as_machine();
+        check!(matches!(machine, Machine::Mips | Machine::MipsRs3Le), "Not a MIPS file");
+        elf_file.check_tables(is_64)?;
+        let dynamic = match elf_file.program_iter().find(|ph| ph.get_type() == Ok(program::Type::Dynamic)) {
+            Some(segment) => segment.data_checked(elf_file)?,
+            None => return Ok(None),
+        };
+
+        let mut tags = GotTags::default();
+        if is_64 {
+            for entry in dynamic::entries::<P64>(dynamic)? {
+                if !tags.add(entry.get_tag(), entry.raw_val()) {
+                    break;
+                }
+            }
+        } else {
+            for entry in dynamic::entries::<P32>(dynamic)? {
+                if !tags.add(entry.get_tag(), entry.raw_val()) {
+                    break;
+                }
+            }
+        }
+        let GotTags { address, local_count, first_global_symbol, symbol_count } = tags;
+        let address = match address {
+            Some(address) => address,
+            None => return Ok(None),
+        };
+        let local_count = local_count.ok_or("Dynamic table has no DT_MIPS_LOCAL_GOTNO")?;
+        let symbol_count = symbol_count.unwrap_or(0);
+        Ok(Some(GotLayout {
+            address,
+            entry_size: if is_64 { 8 } else { 4 },
+            local_count,
+            first_global_symbol: first_global_symbol.unwrap_or(symbol_count),
+            symbol_count,
+        }))
+    }
+
+    pub fn global_count(&self) -> u64 {
+        self.symbol_count.saturating_sub(self.first_global_symbol)
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> u64 {
+        self.local_count.saturating_add(self.global_count())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The address of the global entry for the dynamic symbol at `index`, or
+    /// `None` if it has none.
+    pub fn global_entry(&self, index: u64) -> Option<u64> {
+        if index < self.first_global_symbol || index >= self.symbol_count {
+            return None;
+        }
+        let entry = self.local_count.wrapping_add(index - self.first_global_symbol);
+        Some(self.address.wrapping_add(entry.wrapping_mul(self.entry_size)))
+    }
+}
+
+// The dynamic tags which describe the GOT.
+#[derive(Default)]
+struct GotTags {
+    address: Option<u64>,
+    local_count: Option<u64>,
+    first_global_symbol: Option<u64>,
+    symbol_count: Option<u64>,
+}
+
+impl GotTags {
+    // Records an entry of the dynamic table, and returns false for the
+    // `DT_NULL` which ends it.
+    fn add<P: Into<u64>>(&mut self, tag: Result<Tag<P>, &'static str>, value: P) -> bool {
+        let value = Some(value.into());
+        match tag {
+            Ok(Tag::Null) => return false,
+            Ok(Tag::Pltgot) => self.address = value,
+            Ok(Tag::ProcessorSpecific(tag)) => match tag.into() {
+                DT_MIPS_LOCAL_GOTNO => self.local_count = value,
+                DT_MIPS_GOTSYM => self.first_global_symbol = value,
+                DT_MIPS_SYMTABNO => self.symbol_count = value,
+                _ => {}
+            },
+            _ => {}
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+    use header::test::mk_elf_header;
+
+    #[test]
+    fn relocation_info() {
+        // Symbol 2, with R_MIPS_GPREL16, R_MIPS_SUB and R_MIPS_HI16.
+        let mut bytes = 2u32.to_ne_bytes().to_vec();
+        bytes.extend_from_slice(&[0, 5, 24, 7]);
+        let info = Mips64RelocationInfo::from_info(u64::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3],
+                                                                       bytes[4], bytes[5], bytes[6], bytes[7]]));
+        assert_eq!(info, Mips64RelocationInfo { symbol: 2, special_symbol: 0, types: [7, 24, 5] });
+    }
+
+    #[test]
+    fn got_layout() {
+        // A little-endian MIPS64 shared object with just a dynamic segment.
+        let mut input = mk_elf_header(2);
+        for &(offset, value, size) in &[(16, 3, 2), (18, 8, 2), (32, 64, 8), (54, 56, 2), (56, 1, 2)] {
+            input[offset..offset + size].copy_from_slice(&(value as u64).to_le_bytes()[..size]);
+        }
+        let entries = [(3, 0x209b0), (DT_MIPS_LOCAL_GOTNO, 4), (DT_MIPS_GOTSYM, 7), (DT_MIPS_SYMTABNO, 14), (0, 0)];
+        let table_size = entries.len() as u64 * 16;
+        for &(value, size) in &[(2, 4), (6, 4), (120, 8), (0, 8), (0, 8), (table_size, 8), (table_size, 8), (8, 8)] {
+            input.extend_from_slice(&value.to_le_bytes()[..size]);
+        }
+        for &(tag, value) in &entries {
+            input.extend_from_slice(&tag.to_le_bytes());
+            input.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        let elf_file = ElfFile::new(&input).unwrap();
+        let got = GotLayout::load(&elf_file).unwrap().unwrap();
+        assert_eq!(got, GotLayout {
+            address: 0x209b0,
+            entry_size: 8,
+            local_count: 4,
+            first_global_symbol: 7,
+            symbol_count: 14,
+        });
+        assert_eq!((got.len(), got.global_count()), (11, 7));
+        assert_eq!(got.global_entry(6), None);
+        assert_eq!(got.global_entry(7), Some(0x209d0));
+        assert_eq!(got.global_entry(13), Some(0x20a00));
+        assert_eq!(got.global_entry(14), None);
+
+        // Without DT_PLTGOT there is no GOT.
+        input[120..128].copy_from_slice(&0x70000001u64.to_le_bytes());
+        let elf_file = ElfFile::new(&input).unwrap();
+        assert_eq!(GotLayout::load(&elf_file), Ok(None));
+    }
+}
@@ -19,6 +19,9 @@ pub fn parse_program_header<'a>(input: &'a [u8],
 
     let start = pt2.ph_offset() as usize + index as usize * pt2.ph_entry_size() as usize;
     let end = start + pt2.ph_entry_size() as usize;
+    if input.len() < end {
+        return Err("File is shorter than program header offset");
+    }
 
     match header.pt1.class() {
         Class::ThirtyTwo => {
@@ -114,6 +117,15 @@ impl<'a> ProgramHeader<'a> {
         }
     }
 
+    /// The segment's bytes in the file, or an error if they are out of range
+    /// of it, where `raw_data` would panic.
+    pub fn data_checked(&self, elf_file: &ElfFile<'a>) -> Result<&'a [u8], &'static str> {
+        let start = self.offset() as usize;
+        start.checked_add(self.file_size() as usize)
+            .and_then(|end| elf_file.input.get(start..end))
+            .ok_or("Segment is out of range of the file")
+    }
+
     getter!(align, u64);
     getter!(file_size, u64);
     getter!(mem_size, u64);
@@ -121,6 +133,39 @@ impl<'a> ProgramHeader<'a> {
     getter!(physical_addr, u64);
     getter!(virtual_addr, u64);
     getter!(flags, Flags);
+
+    /// An owned copy of the program header's fields.
+    pub fn snapshot(&self) -> SegmentSnapshot {
+        SegmentSnapshot {
+            type_: match *self {
+                ProgramHeader::Ph32(ph) => ph.type_.0,
+                ProgramHeader::Ph64(ph) => ph.type_.0,
+            },
+            flags: self.flags().0,
+            offset: self.offset(),
+            virtual_addr: self.virtual_addr(),
+            physical_addr: self.physical_addr(),
+            file_size: self.file_size(),
+            mem_size: self.mem_size(),
+            align: self.align(),
+        }
+    }
+}
+
+/// The fields of a `ProgramHeader`, as raw values, independent of the file
+/// they were read from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SegmentSnapshot {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub type_: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub virtual_addr: u64,
+    pub physical_addr: u64,
+    pub file_size: u64,
+    pub mem_size: u64,
+    pub align: u64,
 }
 
 impl<'a> fmt::Display for ProgramHeader<'a> {
@@ -142,7 +187,11 @@ macro_rules! ph_impl {
                 self.get_type().map(|typ| match typ {
                     Type::Null => SegmentData::Empty,
                     Type::Load | Type::Interp | Type::ShLib | Type::Phdr | Type::Tls |
-                    Type::GnuRelro | Type::OsSpecific(_) | Type::ProcessorSpecific(_) => {
+                    Type::GnuEhFrame | Type::GnuStack | Type::GnuRelro | Type::GnuProperty |
+                    Type::GnuSframe | Type::GnuMbind(_) | Type::SunwUnwind | Type::SunwBss | Type::SunwStack |
+                    Type::OpenBsdMutable | Type::OpenBsdRandomize | Type::OpenBsdWxNeeded | Type::OpenBsdNoBtCfi |
+                    Type::OpenBsdSyscalls | Type::OpenBsdBootData | Type::OsSpecific(_) |
+                    Type::ProcessorSpecific(_) => {
                         SegmentData::Undefined(self.raw_data(elf_file))
                     }
                     Type::Dynamic => {
@@ -243,9 +292,10 @@ impl fmt::UpperHex for Flags {
 }
 
 #[derive(Copy, Clone, Default)]
-pub struct Type_(u32);
+pub struct Type_(pub u32);
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum Type {
     Null,
     Load,
@@ -255,7 +305,22 @@ pub enum Type {
     ShLib,
     Phdr,
     Tls,
+    GnuEhFrame,
+    GnuStack,
     GnuRelro,
+    GnuProperty,
+    GnuSframe,
+    // The raw value, anywhere from TYPE_GNU_MBIND_LO to TYPE_GNU_MBIND_HI.
+    GnuMbind(u32),
+    SunwUnwind,
+    SunwBss,
+    SunwStack,
+    OpenBsdMutable,
+    OpenBsdRandomize,
+    OpenBsdWxNeeded,
+    OpenBsdNoBtCfi,
+    OpenBsdSyscalls,
+    OpenBsdBootData,
     OsSpecific(u32),
     ProcessorSpecific(u32),
 }
@@ -271,7 +336,21 @@ impl Type_ {
             5 => Ok(Type::ShLib),
             6 => Ok(Type::Phdr),
             7 => Ok(Type::Tls),
+            TYPE_GNU_EH_FRAME => Ok(Type::GnuEhFrame),
+            TYPE_GNU_STACK => Ok(Type::GnuStack),
             TYPE_GNU_RELRO => Ok(Type::GnuRelro),
+            TYPE_GNU_PROPERTY => Ok(Type::GnuProperty),
+            TYPE_GNU_SFRAME => Ok(Type::GnuSframe),
+            t if (TYPE_GNU_MBIND_LO..=TYPE_GNU_MBIND_HI).contains(&t) => Ok(Type::GnuMbind(t)),
+            TYPE_SUNW_UNWIND => Ok(Type::SunwUnwind),
+            TYPE_SUNW_BSS => Ok(Type::SunwBss),
+            TYPE_SUNW_STACK => Ok(Type::SunwStack),
+            TYPE_OPENBSD_MUTABLE => Ok(Type::OpenBsdMutable),
+            TYPE_OPENBSD_RANDOMIZE => Ok(Type::OpenBsdRandomize),
+            TYPE_OPENBSD_WXNEEDED => Ok(Type::OpenBsdWxNeeded),
+            TYPE_OPENBSD_NOBTCFI => Ok(Type::OpenBsdNoBtCfi),
+            TYPE_OPENBSD_SYSCALLS => Ok(Type::OpenBsdSyscalls),
+            TYPE_OPENBSD_BOOTDATA => Ok(Type::OpenBsdBootData),
             t if (TYPE_LOOS..=TYPE_HIOS).contains(&t) => Ok(Type::OsSpecific(t)),
             t if (TYPE_LOPROC..=TYPE_HIPROC).contains(&t) => Ok(Type::ProcessorSpecific(t)),
             _ => Err("Invalid type"),
@@ -279,6 +358,39 @@ impl Type_ {
     }
 }
 
+impl Type {
+    /// The name, as in `PT_GNU_STACK` without the prefix.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Type::Null => "NULL",
+            Type::Load => "LOAD",
+            Type::Dynamic => "DYNAMIC",
+            Type::Interp => "INTERP",
+            Type::Note => "NOTE",
+            Type::ShLib => "SHLIB",
+            Type::Phdr => "PHDR",
+            Type::Tls => "TLS",
+            Type::GnuEhFrame => "GNU_EH_FRAME",
+            Type::GnuStack => "GNU_STACK",
+            Type::GnuRelro => "GNU_RELRO",
+            Type::GnuProperty => "GNU_PROPERTY",
+            Type::GnuSframe => "GNU_SFRAME",
+            Type::GnuMbind(_) => "GNU_MBIND",
+            Type::SunwUnwind => "SUNW_UNWIND",
+            Type::SunwBss => "SUNWBSS",
+            Type::SunwStack => "SUNWSTACK",
+            Type::OpenBsdMutable => "OPENBSD_MUTABLE",
+            Type::OpenBsdRandomize => "OPENBSD_RANDOMIZE",
+            Type::OpenBsdWxNeeded => "OPENBSD_WXNEEDED",
+            Type::OpenBsdNoBtCfi => "OPENBSD_NOBTCFI",
+            Type::OpenBsdSyscalls => "OPENBSD_SYSCALLS",
+            Type::OpenBsdBootData => "OPENBSD_BOOTDATA",
+            Type::OsSpecific(_) => "OS-specific",
+            Type::ProcessorSpecific(_) => "processor-specific",
+        }
+    }
+}
+
 impl fmt::Debug for Type_ {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.as_type().fmt(f)
@@ -300,7 +412,29 @@ pub const TYPE_LOOS: u32 = 0x60000000;
 pub const TYPE_HIOS: u32 = 0x6fffffff;
 pub const TYPE_LOPROC: u32 = 0x70000000;
 pub const TYPE_HIPROC: u32 = 0x7fffffff;
+pub const TYPE_GNU_EH_FRAME: u32 = TYPE_LOOS + 0x474e550;
+pub const TYPE_GNU_STACK: u32 = TYPE_LOOS + 0x474e551;
 pub const TYPE_GNU_RELRO: u32 = TYPE_LOOS + 0x474e552;
+pub const TYPE_GNU_PROPERTY: u32 = TYPE_LOOS + 0x474e553;
+pub const TYPE_GNU_SFRAME: u32 = TYPE_LOOS + 0x474e554;
+pub const TYPE_GNU_MBIND_LO: u32 = TYPE_LOOS + 0x474e555;
+pub const TYPE_GNU_MBIND_HI: u32 = TYPE_GNU_MBIND_LO + 0xfff;
+pub const TYPE_SUNW_UNWIND: u32 = TYPE_LOOS + 0x464e550;
+pub const TYPE_SUNW_BSS: u32 = 0x6ffffffa;
+pub const TYPE_SUNW_STACK: u32 = 0x6ffffffb;
+pub const TYPE_OPENBSD_MUTABLE: u32 = TYPE_LOOS + 0x5a3dbe5;
+pub const TYPE_OPENBSD_RANDOMIZE: u32 = TYPE_LOOS + 0x5a3dbe6;
+pub const TYPE_OPENBSD_WXNEEDED: u32 = TYPE_LOOS + 0x5a3dbe7;
+pub const TYPE_OPENBSD_NOBTCFI: u32 = TYPE_LOOS + 0x5a3dbe8;
+pub const TYPE_OPENBSD_SYSCALLS: u32 = TYPE_LOOS + 0x5a3dbe9;
+pub const TYPE_OPENBSD_BOOTDATA: u32 = TYPE_LOOS + 0x5a41be6;
+// Processor-specific types, whose meaning depends on the machine.
+pub const TYPE_ARM_EXIDX: u32 = TYPE_LOPROC + 1;
+pub const TYPE_RISCV_ATTRIBUTES: u32 = TYPE_LOPROC + 3;
+pub const TYPE_MIPS_REGINFO: u32 = TYPE_LOPROC;
+pub const TYPE_MIPS_RTPROC: u32 = TYPE_LOPROC + 1;
+pub const TYPE_MIPS_OPTIONS: u32 = TYPE_LOPROC + 2;
+pub const TYPE_MIPS_ABIFLAGS: u32 = TYPE_LOPROC + 3;
 
 pub const FLAG_X: u32 = 0x1;
 pub const FLAG_W: u32 = 0x2;
@@ -338,3 +472,42 @@ pub fn sanity_check<'a>(ph: ProgramHeader<'a>, elf_file: &ElfFile<'a>) -> Result
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    #[test]
+    fn type_names() {
+        let types = [(0x6474e550, Type::GnuEhFrame, "GNU_EH_FRAME"),
+                     (0x6474e551, Type::GnuStack, "GNU_STACK"),
+                     (0x6474e552, Type::GnuRelro, "GNU_RELRO"),
+                     (0x6474e553, Type::GnuProperty, "GNU_PROPERTY"),
+                     (0x6474e554, Type::GnuSframe, "GNU_SFRAME"),
+                     (0x6474e555, Type::GnuMbind(0x6474e555), "GNU_MBIND"),
+                     (0x6474f554, Type::GnuMbind(0x6474f554), "GNU_MBIND"),
+                     (0x6464e550, Type::SunwUnwind, "SUNW_UNWIND"),
+                     (0x6ffffffa, Type::SunwBss, "SUNWBSS"),
+                     (0x6ffffffb, Type::SunwStack, "SUNWSTACK"),
+                     (0x65a3dbe5, Type::OpenBsdMutable, "OPENBSD_MUTABLE"),
+                     (0x65a3dbe6, Type::OpenBsdRandomize, "OPENBSD_RANDOMIZE"),
+                     (0x65a3dbe7, Type::OpenBsdWxNeeded, "OPENBSD_WXNEEDED"),
+                     (0x65a3dbe8, Type::OpenBsdNoBtCfi, "OPENBSD_NOBTCFI"),
+                     (0x65a3dbe9, Type::OpenBsdSyscalls, "OPENBSD_SYSCALLS"),
+                     (0x65a41be6, Type::OpenBsdBootData, "OPENBSD_BOOTDATA"),
+                     (0x60000000, Type::OsSpecific(0x60000000), "OS-specific"),
+                     (0x6474f555, Type::OsSpecific(0x6474f555), "OS-specific"),
+                     (0x6fffffff, Type::OsSpecific(0x6fffffff), "OS-specific"),
+                     (0x70000000, Type::ProcessorSpecific(0x70000000), "processor-specific"),
+                     (0x7fffffff, Type::ProcessorSpecific(0x7fffffff), "processor-specific")];
+        for &(value, type_, name) in &types {
+            assert_eq!(Type_(value).as_type(), Ok(type_), "{:#x}", value);
+            assert_eq!(type_.name(), name);
+        }
+        assert!(Type_(8).as_type().is_err());
+        assert!(Type_(0x80000000).as_type().is_err());
+        assert_eq!(format!("{:?}", Type_(0x65a3dbe7)), "Ok(OpenBsdWxNeeded)");
+    }
+}
@@ -0,0 +1,109 @@
+//! Bounds-checked reading of the raw data in sections and segments.
+
+use core::str;
+
+// Reads native-endian values, and the LEB128 and string forms of DWARF.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reader<'a> {
+    pub(crate) data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+
+    pub(crate) fn bytes(&mut self, length: u64) -> Result<&'a [u8], &'static str> {
+        check!(length <= self.data.len() as u64, "Unexpected end of DWARF data");
+        let (bytes, rest) = self.data.split_at(length as usize);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], &'static str> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N as u64)?);
+        Ok(array)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, &'static str> {
+        self.array().map(u8::from_ne_bytes)
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, &'static str> {
+        self.array().map(u16::from_ne_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, &'static str> {
+        self.array().map(u32::from_ne_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, &'static str> {
+        self.array().map(u64::from_ne_bytes)
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, &'static str> {
+        self.array().map(i32::from_ne_bytes)
+    }
+
+    // An unsigned long, 8 bytes in 64-bit files and 4 otherwise.
+    pub(crate) fn word(&mut self, is_64: bool) -> Result<u64, &'static str> {
+        if is_64 { self.u64() } else { self.u32().map(u64::from) }
+    }
+
+    // A long.
+    pub(crate) fn signed_word(&mut self, is_64: bool) -> Result<i64, &'static str> {
+        if is_64 { self.array().map(i64::from_ne_bytes) } else { self.i32().map(i64::from) }
+    }
+
+    // A section offset, 8 bytes in 64-bit DWARF and 4 otherwise.
+    pub(crate) fn offset(&mut self, is_dwarf64: bool) -> Result<u64, &'static str> {
+        if is_dwarf64 { self.u64() } else { self.u32().map(u64::from) }
+    }
+
+    pub(crate) fn uleb(&mut self) -> Result<u64, &'static str> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub(crate) fn sleb(&mut self) -> Result<i64, &'static str> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+
+    pub(crate) fn c_str(&mut self) -> Result<&'a str, &'static str> {
+        let string = c_str(self.data, 0)?;
+        self.data = &self.data[string.len() + 1..];
+        Ok(string)
+    }
+}
+
+// The NUL-terminated string at `offset` in a string table.
+pub(crate) fn c_str(data: &[u8], offset: usize) -> Result<&str, &'static str> {
+    let data = data.get(offset..).ok_or("String offset is out of range")?;
+    let end = data.iter().position(|&b| b == 0).ok_or("String is not terminated")?;
+    str::from_utf8(&data[..end]).map_err(|_| "String is not valid UTF-8")
+}
@@ -1,14 +1,18 @@
-#[cfg(feature = "compression")]
-use std::borrow::Cow;
-#[cfg(feature = "compression")]
-use std::vec::Vec;
+#[cfg(any(feature = "compression", feature = "inflate"))]
+use alloc::borrow::Cow;
+#[cfg(any(feature = "compression", feature = "inflate"))]
+use alloc::vec::Vec;
 
 use core::fmt;
+#[cfg(any(feature = "compression", feature = "inflate"))]
 use core::mem;
 use core::slice;
+use core::str;
 
-#[cfg(feature = "compression")]
-use flate2::{Decompress, FlushDecompress};
+#[cfg(all(feature = "compression", not(feature = "inflate")))]
+use flate2::{Decompress, FlushDecompress, Status};
+#[cfg(feature = "inflate")]
+use miniz_oxide;
 
 use {P32, P64, ElfFile};
 use header::{Header, Class};
@@ -16,6 +20,7 @@ use zero::{read, read_array, read_str, read_strs_to_null, StrReaderIterator, Pod
 use symbol_table;
 use dynamic::Dynamic;
 use hash::HashTable;
+use mips::Mips64RelocationInfo;
 
 pub fn parse_section_header<'a>(input: &'a [u8],
                                 header: Header<'a>,
@@ -26,7 +31,7 @@ pub fn parse_section_header<'a>(input: &'a [u8],
             "Attempt to get section for a reserved index");
 
     let start = (index as u64 * header.pt2.sh_entry_size() as u64 +
-                 header.pt2.sh_offset() as u64) as usize;
+                 header.pt2.sh_offset()) as usize;
     let end = start + header.pt2.sh_entry_size() as usize;
 
     if input.len() < end {
@@ -125,6 +130,35 @@ impl<'a> SectionHeader<'a> {
             ShType::Null | ShType::NoBits => SectionData::Empty,
             ShType::ProgBits |
             ShType::ShLib |
+            ShType::Relr |
+            ShType::Crel |
+            ShType::AndroidRel |
+            ShType::AndroidRela |
+            ShType::LlvmOdrTab |
+            ShType::LlvmLinkerOptions |
+            ShType::LlvmAddrsig |
+            ShType::LlvmDependentLibraries |
+            ShType::LlvmSymPart |
+            ShType::LlvmPartEhdr |
+            ShType::LlvmPartPhdr |
+            ShType::LlvmBbAddrMapV0 |
+            ShType::LlvmCallGraphProfile |
+            ShType::LlvmBbAddrMap |
+            ShType::LlvmOffloading |
+            ShType::LlvmLto |
+            ShType::AndroidRelr |
+            ShType::GnuIncrementalInputs |
+            ShType::GnuSframe |
+            ShType::GnuAttributes |
+            ShType::GnuHash |
+            ShType::GnuLibList |
+            ShType::Checksum |
+            ShType::SunwMove |
+            ShType::SunwComdat |
+            ShType::SunwSymInfo |
+            ShType::GnuVerDef |
+            ShType::GnuVerNeed |
+            ShType::GnuVerSym |
             ShType::OsSpecific(_) |
             ShType::ProcessorSpecific(_) |
             ShType::User(_) => SectionData::Undefined(self.raw_data(elf_file)),
@@ -139,13 +173,12 @@ impl<'a> SectionHeader<'a> {
             ShType::Dynamic => array_data!(Dynamic32, Dynamic64),
             ShType::Group => {
                 let data = self.raw_data(elf_file);
-                unsafe {
-                    let flags: &'a u32 = mem::transmute(&data[0]);
-                    let indicies: &'a [u32] = read_array(&data[4..]);
-                    SectionData::Group {
-                        flags,
-                        indicies,
-                    }
+                if data.len() < 4 || data.len() % 4 != 0 || data.as_ptr() as usize % 4 != 0 {
+                    return Err("Invalid section group");
+                }
+                SectionData::Group {
+                    flags: read(&data[..4]),
+                    indicies: read_array(&data[4..]),
                 }
             }
             ShType::SymTabShIndex => {
@@ -170,25 +203,47 @@ impl<'a> SectionHeader<'a> {
         }))
     }
 
+    /// The section's bytes in the file, or an error if they are out of range
+    /// of it, where `raw_data` would panic. `SHT_NOBITS` sections are empty.
+    pub fn data_checked(&self, elf_file: &ElfFile<'a>) -> Result<&'a [u8], &'static str> {
+        if self.get_type() == Ok(ShType::NoBits) {
+            return Ok(&[]);
+        }
+        let start = self.offset() as usize;
+        start.checked_add(self.size() as usize)
+            .and_then(|end| elf_file.input.get(start..end))
+            .ok_or("Section is out of range of the file")
+    }
+
     pub fn raw_data(&self, elf_file: &ElfFile<'a>) -> &'a [u8] {
         assert_ne!(self.get_type().unwrap(), ShType::Null);
         &elf_file.input[self.offset() as usize..(self.offset() + self.size()) as usize]
     }
 
-    #[cfg(feature = "compression")]
+    /// The section's data, decompressed if it is compressed, either with
+    /// `SHF_COMPRESSED` or in the older GNU format of `.zdebug_*` sections
+    /// ("ZLIB" and a big-endian 64-bit size). zlib needs the `compression` or
+    /// `inflate` feature, and zstd needs `compression`.
+    #[cfg(any(feature = "compression", feature = "inflate"))]
     pub fn decompressed_data(&self, elf_file: &ElfFile<'a>) -> Result<Cow<'a, [u8]>, &'static str> {
         let raw = self.raw_data(elf_file);
         Ok(if (self.flags() & SHF_COMPRESSED) == 0 {
-            Cow::Borrowed(raw)
+            let is_zdebug = || self.get_name(elf_file).is_ok_and(|name| name.starts_with(".zdebug"));
+            if raw.len() >= 12 && raw.starts_with(b"ZLIB") && is_zdebug() {
+                let mut size = [0; 8];
+                size.copy_from_slice(&raw[4..12]);
+                Cow::Owned(inflate(&raw[12..], u64::from_be_bytes(size) as usize)?)
+            } else {
+                Cow::Borrowed(raw)
+            }
         } else {
-            fn read_compression_header<'a, T: Pod + Clone>(raw: &'a [u8]) -> Result<(T, &'a [u8]), &'static str> {
+            fn read_compression_header<T: Pod + Clone>(raw: &[u8]) -> Result<(T, &[u8]), &'static str> {
                 if raw.len() < mem::size_of::<T>() {
                     return Err("Unexpected EOF in compressed section");
                 }
 
                 let (header, rest) = raw.split_at(mem::size_of::<T>());
-                let mut header_bytes = Vec::with_capacity(mem::size_of::<T>());
-                header_bytes.resize(mem::size_of::<T>(), 0);
+                let mut header_bytes = alloc::vec![0; mem::size_of::<T>()];
                 assert!(header_bytes.as_ptr() as usize % mem::align_of::<T>() == 0);
                 header_bytes.copy_from_slice(header);
                 let header: &T = read(&header_bytes);
@@ -207,22 +262,18 @@ impl<'a> SectionHeader<'a> {
             };
 
             match compression_type {
-                Ok(CompressionType::Zlib) => {
-                    let mut decompressed = Vec::with_capacity(size);
-                    let mut decompress = Decompress::new(true);
-                    if let Err(_) = decompress.decompress_vec(
-                        compressed_data, &mut decompressed, FlushDecompress::Finish) {
-                        return Err("Decompression error");
-                    }
-                    Cow::Owned(decompressed)
-                }
+                Ok(CompressionType::Zlib) => Cow::Owned(inflate(compressed_data, size)?),
+                #[cfg(feature = "compression")]
                 Ok(CompressionType::Zstd) => {
-                    let mut decompressed = Vec::with_capacity(size);
+                    let mut decompressed = Vec::with_capacity(capacity(compressed_data, size));
+                    #[allow(clippy::redundant_pattern_matching)]
                     if let Err(_) = zstd::stream::copy_decode(compressed_data, &mut decompressed) {
                         return Err("Decompression error");
                     }
                     Cow::Owned(decompressed)
                 }
+                #[cfg(not(feature = "compression"))]
+                Ok(CompressionType::Zstd) => return Err("zstd decompression needs the `compression` feature"),
                 _ => return Err("Unknown compression type"),
             }
         })
@@ -238,6 +289,43 @@ impl<'a> SectionHeader<'a> {
     getter!(info, u32);
     getter!(entry_size, u32);
     getter!(align, u64);
+
+    /// An owned copy of the section header's fields.
+    pub fn snapshot(&self) -> SectionSnapshot {
+        SectionSnapshot {
+            name: self.name(),
+            type_: self.type_().0,
+            flags: self.flags(),
+            address: self.address(),
+            offset: self.offset(),
+            size: self.size(),
+            link: self.link(),
+            info: self.info(),
+            align: self.align(),
+            entry_size: match *self {
+                SectionHeader::Sh32(sh) => sh.entry_size as u64,
+                SectionHeader::Sh64(sh) => sh.entry_size,
+            },
+        }
+    }
+}
+
+/// The fields of a `SectionHeader`, as raw values, independent of the file
+/// they were read from. `name` is an offset into the section name string table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SectionSnapshot {
+    pub name: u32,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub type_: u32,
+    pub flags: u64,
+    pub address: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub info: u32,
+    pub align: u64,
+    pub entry_size: u64,
 }
 
 impl<'a> fmt::Display for SectionHeader<'a> {
@@ -283,9 +371,10 @@ pub struct SectionHeader_<P> {
 unsafe impl<P> Pod for SectionHeader_<P> {}
 
 #[derive(Copy, Clone)]
-pub struct ShType_(u32);
+pub struct ShType_(pub u32);
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ShType {
     Null,
     ProgBits,
@@ -304,6 +393,35 @@ pub enum ShType {
     PreInitArray,
     Group,
     SymTabShIndex,
+    Relr,
+    Crel,
+    AndroidRel,
+    AndroidRela,
+    LlvmOdrTab,
+    LlvmLinkerOptions,
+    LlvmAddrsig,
+    LlvmDependentLibraries,
+    LlvmSymPart,
+    LlvmPartEhdr,
+    LlvmPartPhdr,
+    LlvmBbAddrMapV0,
+    LlvmCallGraphProfile,
+    LlvmBbAddrMap,
+    LlvmOffloading,
+    LlvmLto,
+    AndroidRelr,
+    GnuIncrementalInputs,
+    GnuSframe,
+    GnuAttributes,
+    GnuHash,
+    GnuLibList,
+    Checksum,
+    SunwMove,
+    SunwComdat,
+    SunwSymInfo,
+    GnuVerDef,
+    GnuVerNeed,
+    GnuVerSym,
     OsSpecific(u32),
     ProcessorSpecific(u32),
     User(u32),
@@ -330,6 +448,35 @@ impl ShType_ {
             16 => Ok(ShType::PreInitArray),
             17 => Ok(ShType::Group),
             18 => Ok(ShType::SymTabShIndex),
+            19 => Ok(ShType::Relr),
+            SHT_CREL => Ok(ShType::Crel),
+            SHT_ANDROID_REL => Ok(ShType::AndroidRel),
+            SHT_ANDROID_RELA => Ok(ShType::AndroidRela),
+            SHT_LLVM_ODRTAB => Ok(ShType::LlvmOdrTab),
+            SHT_LLVM_LINKER_OPTIONS => Ok(ShType::LlvmLinkerOptions),
+            SHT_LLVM_ADDRSIG => Ok(ShType::LlvmAddrsig),
+            SHT_LLVM_DEPENDENT_LIBRARIES => Ok(ShType::LlvmDependentLibraries),
+            SHT_LLVM_SYMPART => Ok(ShType::LlvmSymPart),
+            SHT_LLVM_PART_EHDR => Ok(ShType::LlvmPartEhdr),
+            SHT_LLVM_PART_PHDR => Ok(ShType::LlvmPartPhdr),
+            SHT_LLVM_BB_ADDR_MAP_V0 => Ok(ShType::LlvmBbAddrMapV0),
+            SHT_LLVM_CALL_GRAPH_PROFILE => Ok(ShType::LlvmCallGraphProfile),
+            SHT_LLVM_BB_ADDR_MAP => Ok(ShType::LlvmBbAddrMap),
+            SHT_LLVM_OFFLOADING => Ok(ShType::LlvmOffloading),
+            SHT_LLVM_LTO => Ok(ShType::LlvmLto),
+            SHT_ANDROID_RELR => Ok(ShType::AndroidRelr),
+            SHT_GNU_INCREMENTAL_INPUTS => Ok(ShType::GnuIncrementalInputs),
+            SHT_GNU_SFRAME => Ok(ShType::GnuSframe),
+            SHT_GNU_ATTRIBUTES => Ok(ShType::GnuAttributes),
+            SHT_GNU_HASH => Ok(ShType::GnuHash),
+            SHT_GNU_LIBLIST => Ok(ShType::GnuLibList),
+            SHT_CHECKSUM => Ok(ShType::Checksum),
+            SHT_SUNW_MOVE => Ok(ShType::SunwMove),
+            SHT_SUNW_COMDAT => Ok(ShType::SunwComdat),
+            SHT_SUNW_SYMINFO => Ok(ShType::SunwSymInfo),
+            SHT_GNU_VERDEF => Ok(ShType::GnuVerDef),
+            SHT_GNU_VERNEED => Ok(ShType::GnuVerNeed),
+            SHT_GNU_VERSYM => Ok(ShType::GnuVerSym),
             st if (SHT_LOOS..=SHT_HIOS).contains(&st) => Ok(ShType::OsSpecific(st)),
             st if (SHT_LOPROC..=SHT_HIPROC).contains(&st) => Ok(ShType::ProcessorSpecific(st)),
             st if (SHT_LOUSER..=SHT_HIUSER).contains(&st) => Ok(ShType::User(st)),
@@ -338,16 +485,106 @@ impl ShType_ {
     }
 }
 
+impl ShType {
+    /// The name, as in `SHT_GNU_HASH` without the prefix.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ShType::Null => "NULL",
+            ShType::ProgBits => "PROGBITS",
+            ShType::SymTab => "SYMTAB",
+            ShType::StrTab => "STRTAB",
+            ShType::Rela => "RELA",
+            ShType::Hash => "HASH",
+            ShType::Dynamic => "DYNAMIC",
+            ShType::Note => "NOTE",
+            ShType::NoBits => "NOBITS",
+            ShType::Rel => "REL",
+            ShType::ShLib => "SHLIB",
+            ShType::DynSym => "DYNSYM",
+            ShType::InitArray => "INIT_ARRAY",
+            ShType::FiniArray => "FINI_ARRAY",
+            ShType::PreInitArray => "PREINIT_ARRAY",
+            ShType::Group => "GROUP",
+            ShType::SymTabShIndex => "SYMTAB_SHNDX",
+            ShType::Relr => "RELR",
+            ShType::Crel => "CREL",
+            ShType::AndroidRel => "ANDROID_REL",
+            ShType::AndroidRela => "ANDROID_RELA",
+            ShType::LlvmOdrTab => "LLVM_ODRTAB",
+            ShType::LlvmLinkerOptions => "LLVM_LINKER_OPTIONS",
+            ShType::LlvmAddrsig => "LLVM_ADDRSIG",
+            ShType::LlvmDependentLibraries => "LLVM_DEPENDENT_LIBRARIES",
+            ShType::LlvmSymPart => "LLVM_SYMPART",
+            ShType::LlvmPartEhdr => "LLVM_PART_EHDR",
+            ShType::LlvmPartPhdr => "LLVM_PART_PHDR",
+            ShType::LlvmBbAddrMapV0 => "LLVM_BB_ADDR_MAP_V0",
+            ShType::LlvmCallGraphProfile => "LLVM_CALL_GRAPH_PROFILE",
+            ShType::LlvmBbAddrMap => "LLVM_BB_ADDR_MAP",
+            ShType::LlvmOffloading => "LLVM_OFFLOADING",
+            ShType::LlvmLto => "LLVM_LTO",
+            ShType::AndroidRelr => "ANDROID_RELR",
+            ShType::GnuIncrementalInputs => "GNU_INCREMENTAL_INPUTS",
+            ShType::GnuSframe => "GNU_SFRAME",
+            ShType::GnuAttributes => "GNU_ATTRIBUTES",
+            ShType::GnuHash => "GNU_HASH",
+            ShType::GnuLibList => "GNU_LIBLIST",
+            ShType::Checksum => "CHECKSUM",
+            ShType::SunwMove => "SUNW_MOVE",
+            ShType::SunwComdat => "SUNW_COMDAT",
+            ShType::SunwSymInfo => "SUNW_SYMINFO",
+            ShType::GnuVerDef => "GNU_VERDEF",
+            ShType::GnuVerNeed => "GNU_VERNEED",
+            ShType::GnuVerSym => "GNU_VERSYM",
+            ShType::OsSpecific(_) => "OS-specific",
+            ShType::ProcessorSpecific(_) => "processor-specific",
+            ShType::User(_) => "user",
+        }
+    }
+}
+
 impl fmt::Debug for ShType_ {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.as_sh_type().fmt(f)
     }
 }
 
+// Deflate cannot compress by more than 1032:1.
+#[cfg(feature = "compression")]
+const MAX_DEFLATE_RATIO: usize = 1032;
+
+// The space to reserve for decompressing `data`, whose header claims it
+// decompresses to `size` bytes. The claim comes from the file, so it is not
+// trusted any further than the compressed data could go.
+#[cfg(feature = "compression")]
+fn capacity(data: &[u8], size: usize) -> usize {
+    size.min(data.len().saturating_mul(MAX_DEFLATE_RATIO))
+}
+
+// Decompresses zlib data, which decompresses to `size` bytes. Data which
+// decompresses to more than that is an error.
+#[cfg(all(feature = "compression", not(feature = "inflate")))]
+fn inflate(data: &[u8], size: usize) -> Result<Vec<u8>, &'static str> {
+    // `decompress_vec` only writes to the spare capacity, so this is the limit
+    // as well. A zlib stream is longer than the deflate data inside it, so
+    // the limit is never less than a valid stream needs.
+    let mut decompressed = Vec::with_capacity(capacity(data, size));
+    let mut decompress = Decompress::new(true);
+    match decompress.decompress_vec(data, &mut decompressed, FlushDecompress::Finish) {
+        Ok(Status::StreamEnd) => Ok(decompressed),
+        _ => Err("Decompression error"),
+    }
+}
+
+#[cfg(feature = "inflate")]
+fn inflate(data: &[u8], size: usize) -> Result<Vec<u8>, &'static str> {
+    miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, size).map_err(|_| "Decompression error")
+}
+
 #[derive(Debug)]
 pub enum SectionData<'a> {
     Empty,
     Undefined(&'a [u8]),
+    // See `group::SectionGroup` for the members and signature.
     Group { flags: &'a u32, indicies: &'a [u32] },
     StrArray(&'a [u8]),
     FnArray32(&'a [u32]),
@@ -384,6 +621,7 @@ impl<'a> Iterator for SectionStrings<'a> {
 }
 
 impl<'a> SectionData<'a> {
+    #[allow(clippy::result_unit_err)]
     pub fn strings(&self) -> Result<SectionStrings<'a>, ()> {
         if let SectionData::StrArray(data) = *self {
             Ok(SectionStrings { inner: read_strs_to_null(data) })
@@ -401,6 +639,45 @@ pub const SHT_HIPROC: u32 = 0x7fffffff;
 pub const SHT_LOUSER: u32 = 0x80000000;
 pub const SHT_HIUSER: u32 = 0xffffffff;
 
+pub const SHT_CREL: u32 = 0x40000014;
+
+// OS-specific ShType values used by GNU, LLVM and Android tools.
+pub const SHT_ANDROID_REL: u32 = 0x60000001;
+pub const SHT_ANDROID_RELA: u32 = 0x60000002;
+pub const SHT_LLVM_ODRTAB: u32 = 0x6fff4c00;
+pub const SHT_LLVM_LINKER_OPTIONS: u32 = 0x6fff4c01;
+pub const SHT_LLVM_ADDRSIG: u32 = 0x6fff4c03;
+pub const SHT_LLVM_DEPENDENT_LIBRARIES: u32 = 0x6fff4c04;
+pub const SHT_LLVM_SYMPART: u32 = 0x6fff4c05;
+pub const SHT_LLVM_PART_EHDR: u32 = 0x6fff4c06;
+pub const SHT_LLVM_PART_PHDR: u32 = 0x6fff4c07;
+pub const SHT_LLVM_BB_ADDR_MAP_V0: u32 = 0x6fff4c08;
+pub const SHT_LLVM_CALL_GRAPH_PROFILE: u32 = 0x6fff4c09;
+pub const SHT_LLVM_BB_ADDR_MAP: u32 = 0x6fff4c0a;
+pub const SHT_LLVM_OFFLOADING: u32 = 0x6fff4c0b;
+pub const SHT_LLVM_LTO: u32 = 0x6fff4c0c;
+pub const SHT_ANDROID_RELR: u32 = 0x6fffff00;
+pub const SHT_GNU_INCREMENTAL_INPUTS: u32 = 0x6fff4700;
+pub const SHT_GNU_SFRAME: u32 = 0x6ffffff4;
+pub const SHT_GNU_ATTRIBUTES: u32 = 0x6ffffff5;
+pub const SHT_GNU_HASH: u32 = 0x6ffffff6;
+pub const SHT_GNU_LIBLIST: u32 = 0x6ffffff7;
+pub const SHT_CHECKSUM: u32 = 0x6ffffff8;
+pub const SHT_SUNW_MOVE: u32 = 0x6ffffffa;
+pub const SHT_SUNW_COMDAT: u32 = 0x6ffffffb;
+pub const SHT_SUNW_SYMINFO: u32 = 0x6ffffffc;
+pub const SHT_GNU_VERDEF: u32 = 0x6ffffffd;
+pub const SHT_GNU_VERNEED: u32 = 0x6ffffffe;
+pub const SHT_GNU_VERSYM: u32 = 0x6fffffff;
+
+// Processor-specific ShType values, whose meaning depends on the machine.
+pub const SHT_ARM_EXIDX: u32 = 0x70000001;
+pub const SHT_ARM_ATTRIBUTES: u32 = 0x70000003;
+pub const SHT_RISCV_ATTRIBUTES: u32 = 0x70000003;
+pub const SHT_MIPS_REGINFO: u32 = 0x70000006;
+pub const SHT_MIPS_OPTIONS: u32 = 0x7000000d;
+pub const SHT_MIPS_ABIFLAGS: u32 = 0x7000002a;
+
 // Flags (SectionHeader::flags)
 pub const SHF_WRITE: u64 = 0x1;
 pub const SHF_ALLOC: u64 = 0x2;
@@ -523,6 +800,11 @@ impl Rela<P64> {
     pub fn get_type(&self) -> u32 {
         (self.info & 0xffffffff) as u32
     }
+    /// The symbol and types of a MIPS64 relocation, which are not packed
+    /// into `info` as in other 64-bit files.
+    pub fn get_mips64_info(&self) -> Mips64RelocationInfo {
+        Mips64RelocationInfo::from_info(self.info)
+    }
 }
 impl Rel<P32> {
     pub fn get_offset(&self) -> u32 {
@@ -545,6 +827,11 @@ impl Rel<P64> {
     pub fn get_type(&self) -> u32 {
         (self.info & 0xffffffff) as u32
     }
+    /// The symbol and types of a MIPS64 relocation, which are not packed
+    /// into `info` as in other 64-bit files.
+    pub fn get_mips64_info(&self) -> Mips64RelocationInfo {
+        Mips64RelocationInfo::from_info(self.info)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -579,6 +866,73 @@ impl NoteHeader {
     }
 }
 
+/// A single note from a note section or segment.
+#[derive(Clone, Copy, Debug)]
+pub struct Note<'a> {
+    /// The owner of the note, e.g., "GNU" or "CORE", without the null terminator.
+    pub name: &'a str,
+    pub type_: u32,
+    pub desc: &'a [u8],
+}
+
+/// Iterates over all the notes in the data of a note section or segment.
+#[derive(Clone, Debug)]
+pub struct NoteIter<'a> {
+    data: &'a [u8],
+    align: usize,
+}
+
+impl<'a> NoteIter<'a> {
+    /// `align` is the alignment of the section or segment. Notes are padded to
+    /// 4 bytes, except in 8-byte aligned sections and segments (such as those
+    /// holding `NT_GNU_PROPERTY_TYPE_0` notes in 64-bit files).
+    pub fn new(data: &'a [u8], align: u64) -> NoteIter<'a> {
+        NoteIter {
+            data,
+            align: if align == 8 { 8 } else { 4 },
+        }
+    }
+
+    fn next_note(&mut self) -> Result<Note<'a>, &'static str> {
+        let word = |i: usize| u32::from_ne_bytes([self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]);
+        check!(self.data.len() >= 12, "Note header is truncated");
+        let name_size = word(0) as usize;
+        let desc_size = word(4) as usize;
+        let type_ = word(8);
+
+        let align = |offset: usize| offset.checked_add(self.align - 1).map(|o| o & !(self.align - 1));
+        let name_end = name_size.checked_add(12).ok_or("Note is truncated")?;
+        let desc_start = align(name_end).ok_or("Note is truncated")?;
+        let desc_end = desc_start.checked_add(desc_size).ok_or("Note is truncated")?;
+        check!(desc_end <= self.data.len(), "Note is truncated");
+
+        let mut name = &self.data[12..name_end];
+        while let Some((&0, rest)) = name.split_last() {
+            name = rest;
+        }
+        let name = str::from_utf8(name).map_err(|_| "Note name is not valid UTF-8")?;
+        let desc = &self.data[desc_start..desc_end];
+        let next = align(desc_end).unwrap_or(desc_end).min(self.data.len());
+        self.data = &self.data[next..];
+        Ok(Note { name, type_, desc })
+    }
+}
+
+impl<'a> Iterator for NoteIter<'a> {
+    type Item = Result<Note<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let result = self.next_note();
+        if result.is_err() {
+            self.data = &[];
+        }
+        Some(result)
+    }
+}
+
 pub fn sanity_check<'a>(header: SectionHeader<'a>, _file: &ElfFile<'a>) -> Result<(), &'static str> {
     if header.get_type()? == ShType::Null {
         return Ok(());
@@ -586,3 +940,148 @@ pub fn sanity_check<'a>(header: SectionHeader<'a>, _file: &ElfFile<'a>) -> Resul
     // TODO
     Ok(())
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    #[cfg(any(feature = "compression", feature = "inflate"))]
+    pub(crate) const HELLO: &[u8] = b"xmas-elf says hello, hello, hello\n";
+    #[cfg(any(feature = "compression", feature = "inflate"))]
+    pub(crate) const HELLO_ZLIB: &[u8] = &[0x78, 0xda, 0xab, 0xc8, 0x4d, 0x2c, 0xd6, 0x4d, 0xcd, 0x49, 0x53, 0x28,
+                                           0x4e, 0xac, 0x2c, 0x56, 0xc8, 0x48, 0xcd, 0xc9, 0xc9, 0xd7, 0x41, 0xa1,
+                                           0xb8, 0x00, 0xd9, 0x5c, 0x0b, 0xfc];
+
+    // A little-endian ELF64 relocatable file with a `.shstrtab` and, after
+    // it, a `PROGBITS` section for each `(name, contents)`.
+    pub(crate) fn mk_file(sections: &[(&str, &[u8])]) -> Vec<u8> {
+        let sections: Vec<_> = sections.iter().map(|&(name, data)| (name, 1, 0, 0, 0, data)).collect();
+        mk_file_with(&sections)
+    }
+
+    // A section for `mk_file_with`: its name, type, flags, link, info and
+    // contents.
+    pub(crate) type TestSection<'a> = (&'a str, u32, u64, u32, u32, &'a [u8]);
+
+    // Like `mk_file`, but with the sections' headers given.
+    pub(crate) fn mk_file_with(sections: &[TestSection]) -> Vec<u8> {
+        let mut shstrtab = b"\0.shstrtab\0".to_vec();
+        let mut names = Vec::new();
+        for &(name, ..) in sections {
+            names.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(name.as_bytes());
+            shstrtab.push(0);
+        }
+
+        let mut contents = vec![(1, 3, 0, 0, 0, &shstrtab[..])];
+        contents.extend(names.iter().zip(sections).map(|(&name, &(_, sh_type, flags, link, info, data))| {
+            (name, sh_type, flags, link, info, data)
+        }));
+
+        let mut file = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+        file.resize(64, 0);
+        let mut headers = Vec::new();
+        for (name, sh_type, flags, link, info, data) in contents {
+            headers.push((name, sh_type, flags, link, info, file.len() as u64, data.len() as u64));
+            file.extend_from_slice(data);
+            file.resize(file.len().next_multiple_of(8), 0);
+        }
+        let sh_offset = file.len() as u64;
+        file[16..18].copy_from_slice(&1u16.to_le_bytes());
+        file[18..20].copy_from_slice(&62u16.to_le_bytes());
+        file[20..24].copy_from_slice(&1u32.to_le_bytes());
+        file[40..48].copy_from_slice(&sh_offset.to_le_bytes());
+        file[52..54].copy_from_slice(&64u16.to_le_bytes());
+        file[58..60].copy_from_slice(&64u16.to_le_bytes());
+        file[60..62].copy_from_slice(&(headers.len() as u16 + 1).to_le_bytes());
+        file[62..64].copy_from_slice(&1u16.to_le_bytes());
+        file.extend_from_slice(&[0; 64]);
+        for (name, sh_type, flags, link, info, offset, size) in headers {
+            file.extend_from_slice(&name.to_le_bytes());
+            file.extend_from_slice(&sh_type.to_le_bytes());
+            let words: [u64; 4] = [flags, 0, offset, size];
+            for word in words.iter() {
+                file.extend_from_slice(&word.to_le_bytes());
+            }
+            file.extend_from_slice(&link.to_le_bytes());
+            file.extend_from_slice(&info.to_le_bytes());
+            file.extend_from_slice(&1u64.to_le_bytes());
+            file.extend_from_slice(&0u64.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn find_zdebug_sections() {
+        let input = mk_file(&[(".zdebug_info", b""), (".zdebug_line", b""), (".debug_line", b"")]);
+        let elf_file = ElfFile::new(&input).unwrap();
+        let find = |name| elf_file.find_section_by_name(name).map(|section| section.get_name(&elf_file));
+        assert_eq!(find(".debug_info"), Some(Ok(".zdebug_info")));
+        assert_eq!(find(".zdebug_info"), Some(Ok(".zdebug_info")));
+        // An uncompressed section comes first.
+        assert_eq!(find(".debug_line"), Some(Ok(".debug_line")));
+        assert_eq!(find(".debug_str"), None);
+        assert_eq!(find(".info"), None);
+    }
+
+    // `.zdebug_*` contents: "ZLIB", the big-endian size and the zlib data.
+    #[cfg(any(feature = "compression", feature = "inflate"))]
+    pub(crate) fn mk_zdebug(compressed: &[u8], size: u64) -> Vec<u8> {
+        let mut zdebug = b"ZLIB".to_vec();
+        zdebug.extend_from_slice(&size.to_be_bytes());
+        zdebug.extend_from_slice(compressed);
+        zdebug
+    }
+
+    #[cfg(any(feature = "compression", feature = "inflate"))]
+    #[test]
+    fn zdebug_decompression() {
+        let decompress = |zdebug: &[u8]| {
+            let input = mk_file(&[(".zdebug_str", zdebug)]);
+            let elf_file = ElfFile::new(&input).unwrap();
+            let section = elf_file.find_section_by_name(".debug_str").unwrap();
+            section.decompressed_data(&elf_file).map(|data| data.into_owned())
+        };
+        assert_eq!(decompress(&mk_zdebug(HELLO_ZLIB, HELLO.len() as u64)), Ok(HELLO.to_vec()));
+        // The size is only trusted so far; a huge one must not be allocated.
+        assert_eq!(decompress(&mk_zdebug(HELLO_ZLIB, u64::MAX)), Ok(HELLO.to_vec()));
+        assert_eq!(decompress(&mk_zdebug(HELLO_ZLIB, HELLO.len() as u64 - 1)), Err("Decompression error"));
+        assert_eq!(decompress(&mk_zdebug(&HELLO_ZLIB[..10], HELLO.len() as u64)), Err("Decompression error"));
+        // Without the header, the data is taken as it is.
+        assert_eq!(decompress(b"ZLIB"), Ok(b"ZLIB".to_vec()));
+    }
+
+    fn mk_note(name_size: u32, desc_size: u32, type_: u32, contents: &[u8]) -> Vec<u8> {
+        let mut note = Vec::new();
+        for word in [name_size, desc_size, type_].iter() {
+            note.extend_from_slice(&word.to_ne_bytes());
+        }
+        note.extend_from_slice(contents);
+        note
+    }
+
+    #[test]
+    fn notes() {
+        let mut data = mk_note(4, 3, 3, b"GNU\0\x01\x02\x03\0");
+        data.extend(mk_note(0, 0, 1, &[]));
+        let notes: Vec<_> = NoteIter::new(&data, 4).map(|note| note.map(|n| (n.name, n.type_, n.desc))).collect();
+        assert_eq!(notes, [Ok(("GNU", 3, &[1, 2, 3][..])), Ok(("", 1, &[][..]))]);
+
+        // Sizes which run past the end, or overflow, end the iteration with an
+        // error.
+        for &(name_size, desc_size) in [(u32::MAX, 0), (4, u32::MAX - 3), (4, 8)].iter() {
+            let data = mk_note(name_size, desc_size, 1, b"GNU\0");
+            let mut notes = NoteIter::new(&data, 4);
+            assert_eq!(notes.next().map(|note| note.is_err()), Some(true));
+            assert!(notes.next().is_none());
+        }
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(format!("{:?}", ShType_(0x6ffffff6)), "Ok(GnuHash)");
+        assert_eq!(ShType::LlvmAddrsig.name(), "LLVM_ADDRSIG");
+    }
+}
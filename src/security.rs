@@ -0,0 +1,318 @@
+//! A hardening report for an executable or shared library, like `checksec`:
+//! which of the usual exploit mitigations it was built with.
+//!
+//! Everything is read with bounds checks, so a malformed file gives an error
+//! or a pessimistic answer rather than a panic.
+
+use core::convert::{TryFrom, TryInto};
+use core::str;
+
+use {ElfFile, Extensions, P32, P64};
+use dynamic::{self, Tag, FLAG_1_NOW, FLAG_1_PIE, FLAG_BIND_NOW};
+use gnu_property::GnuProperties;
+use header::{Class, Type};
+use program;
+use reader::c_str;
+use sections::{SectionHeader, ShType, SHN_LORESERVE};
+
+// Imported by code built with `-fstack-protector`.
+const STACK_PROTECTOR_SYMBOLS: &[&str] = &["__stack_chk_fail", "__stack_chk_guard", "__intel_security_cookie"];
+
+/// How much of the relocated data is made read-only after relocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relro {
+    None,
+    /// There is a `PT_GNU_RELRO` segment, but the GOT entries for lazily
+    /// bound functions stay writable.
+    Partial,
+    /// `PT_GNU_RELRO` with immediate binding, so the whole GOT is read-only.
+    Full,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pie {
+    No,
+    Yes,
+    /// A shared library, which is position-independent but not an
+    /// executable.
+    SharedObject,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecurityReport<'a> {
+    pub relro: Relro,
+    /// Whether there is a `PT_GNU_STACK` segment without execute permission.
+    /// Without one, most kernels make the stack executable.
+    pub nx_stack: bool,
+    pub pie: Pie,
+    /// Whether the stack protector's guard or failure function is used.
+    pub stack_protector: bool,
+    /// The number of fortified (`__*_chk`) functions used, which is non-zero
+    /// when built with `_FORTIFY_SOURCE`.
+    pub fortified: usize,
+    pub rpath: Option<&'a str>,
+    pub runpath: Option<&'a str>,
+    /// The number of loadable segments which are both writable and
+    /// executable.
+    pub writable_executable_segments: usize,
+    /// The GNU properties, which record control-flow protection.
+    pub properties: Option<GnuProperties>,
+}
+
+impl<'a> SecurityReport<'a> {
+    pub fn fortify_source(&self) -> bool {
+        self.fortified > 0
+    }
+
+    /// x86 indirect branch tracking.
+    pub fn ibt(&self) -> bool {
+        self.properties.and_then(|p| p.x86_features).is_some_and(|f| f.is_ibt())
+    }
+
+    /// x86 shadow stack.
+    pub fn shstk(&self) -> bool {
+        self.properties.and_then(|p| p.x86_features).is_some_and(|f| f.is_shstk())
+    }
+
+    /// AArch64 branch target identification.
+    pub fn bti(&self) -> bool {
+        self.properties.and_then(|p| p.aarch64_features).is_some_and(|f| f.is_bti())
+    }
+
+    /// AArch64 pointer authentication.
+    pub fn pac(&self) -> bool {
+        self.properties.and_then(|p| p.aarch64_features).is_some_and(|f| f.is_pac())
+    }
+}
+
+/// Reports on the hardening of `elf_file`.
+pub fn report<'a>(elf_file: &ElfFile<'a>) -> Result<SecurityReport<'a>, &'static str> {
+    let is_64 = elf_file.header.pt1.class() == Class::SixtyFour;
+    elf_file.check_tables(is_64)?;
+    let mut has_relro = false;
+    let mut nx_stack = false;
+    let mut has_interp = false;
+    let mut writable_executable_segments = 0;
+    for i in 0..elf_file.header.pt2.ph_count() {
+        let segment = elf_file.program_header(i)?;
+        let flags = segment.flags();
+        match segment.get_type() {
+            Ok(program::Type::GnuRelro) => has_relro = true,
+            Ok(program::Type::GnuStack) => nx_stack = !flags.is_execute(),
+            Ok(program::Type::Interp) => has_interp = true,
+            Ok(program::Type::Load) if flags.is_write() && flags.is_execute() => {
+                writable_executable_segments += 1;
+            }
+            _ => {}
+        }
+    }
+
+    let dynamic = DynamicInfo::read(elf_file, is_64)?;
+    let bind_now = dynamic.bind_now || dynamic.flags & FLAG_BIND_NOW != 0 || dynamic.flags_1 & FLAG_1_NOW != 0;
+    let relro = match (has_relro, bind_now) {
+        (false, _) => Relro::None,
+        (true, false) => Relro::Partial,
+        (true, true) => Relro::Full,
+    };
+    let pie = match elf_file.header.pt2.type_().as_type() {
+        Type::SharedObject if dynamic.flags_1 & FLAG_1_PIE != 0 || has_interp => Pie::Yes,
+        Type::SharedObject => Pie::SharedObject,
+        _ => Pie::No,
+    };
+
+    let mut stack_protector = false;
+    let mut fortified = 0;
+    for name in imported_symbols(elf_file)? {
+        let name = name?;
+        if STACK_PROTECTOR_SYMBOLS.contains(&name) {
+            stack_protector = true;
+        } else if name.starts_with("__") && name.ends_with("_chk") {
+            fortified += 1;
+        }
+    }
+
+    Ok(SecurityReport {
+        relro,
+        nx_stack,
+        pie,
+        stack_protector,
+        fortified,
+        rpath: dynamic.rpath,
+        runpath: dynamic.runpath,
+        writable_executable_segments,
+        properties: elf_file.get_gnu_properties(),
+    })
+}
+
+/// The names of the functions and variables a file uses from other objects:
+/// the undefined symbols in `.dynsym`. A file without `.dynsym` is assumed to
+/// be statically linked, and all the names in `.symtab` are given instead.
+pub fn imported_symbols<'a>(elf_file: &ElfFile<'a>) -> Result<ImportedSymbols<'a>, &'static str> {
+    let is_64 = elf_file.header.pt1.class() == Class::SixtyFour;
+    let mut symtab = None;
+    for section in elf_file.section_iter() {
+        match section.get_type() {
+            Ok(ShType::DynSym) => return ImportedSymbols::new(elf_file, section, is_64, true),
+            Ok(ShType::SymTab) => symtab = Some(section),
+            _ => {}
+        }
+    }
+    match symtab {
+        Some(section) => ImportedSymbols::new(elf_file, section, is_64, false),
+        None => Ok(ImportedSymbols { symbols: &[], strings: &[], entry_size: 1, is_64, undefined_only: true }),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportedSymbols<'a> {
+    symbols: &'a [u8],
+    strings: &'a [u8],
+    entry_size: usize,
+    is_64: bool,
+    undefined_only: bool,
+}
+
+impl<'a> ImportedSymbols<'a> {
+    fn new(elf_file: &ElfFile<'a>, section: SectionHeader<'a>, is_64: bool, undefined_only: bool)
+           -> Result<ImportedSymbols<'a>, &'static str> {
+        let entry_size = if is_64 { 24 } else { 16 };
+        let symbols = section.data_checked(elf_file)?;
+        let strings = u16::try_from(section.link()).ok()
+            .filter(|&link| link < elf_file.header.pt2.sh_count() && link < SHN_LORESERVE)
+            .and_then(|link| elf_file.section_header(link).ok())
+            .ok_or("Symbol table's string table is missing")?;
+        let strings = strings.data_checked(elf_file)?;
+        // Skip the null symbol.
+        let symbols = symbols.get(entry_size..).unwrap_or(&[]);
+        Ok(ImportedSymbols { symbols, strings, entry_size, is_64, undefined_only })
+    }
+}
+
+impl<'a> Iterator for ImportedSymbols<'a> {
+    type Item = Result<&'a str, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.symbols.len() >= self.entry_size {
+            let (symbol, rest) = self.symbols.split_at(self.entry_size);
+            self.symbols = rest;
+            let name = u32::from_ne_bytes(symbol[..4].try_into().unwrap()) as usize;
+            let shndx_offset = if self.is_64 { 6 } else { 14 };
+            let shndx = u16::from_ne_bytes(symbol[shndx_offset..shndx_offset + 2].try_into().unwrap());
+            if name == 0 || (self.undefined_only && shndx != 0) {
+                continue;
+            }
+            return Some(c_str(self.strings, name));
+        }
+        None
+    }
+}
+
+// The parts of the dynamic table which matter for hardening.
+#[derive(Default)]
+struct DynamicInfo<'a> {
+    bind_now: bool,
+    flags: u64,
+    flags_1: u64,
+    rpath: Option<&'a str>,
+    runpath: Option<&'a str>,
+}
+
+// The dynamic string table, and the offsets in it of the search paths.
+#[derive(Default)]
+struct DynamicStrings {
+    strtab: Option<u64>,
+    strsz: Option<u64>,
+    rpath: Option<u64>,
+    runpath: Option<u64>,
+}
+
+impl<'a> DynamicInfo<'a> {
+    fn read(elf_file: &ElfFile<'a>, is_64: bool) -> Result<DynamicInfo<'a>, &'static str> {
+        let mut result = DynamicInfo::default();
+        let segments = || (0..elf_file.header.pt2.ph_count()).filter_map(|i| elf_file.program_header(i).ok());
+        let dynamic = match segments().find(|s| s.get_type() == Ok(program::Type::Dynamic)) {
+            Some(segment) => segment.data_checked(elf_file)?,
+            None => return Ok(result),
+        };
+
+        let mut strings = DynamicStrings::default();
+        if is_64 {
+            for entry in dynamic::entries::<P64>(dynamic)? {
+                if !result.add(&mut strings, entry.get_tag(), entry.raw_val()) {
+                    break;
+                }
+            }
+        } else {
+            for entry in dynamic::entries::<P32>(dynamic)? {
+                if !result.add(&mut strings, entry.get_tag(), entry.raw_val()) {
+                    break;
+                }
+            }
+        }
+
+        let DynamicStrings { strtab, strsz, rpath, runpath } = strings;
+        if rpath.is_some() || runpath.is_some() {
+            // DT_STRTAB is an address; find it in the file.
+            let strtab = strtab.ok_or("Dynamic table has no string table")?;
+            let segment = segments()
+                .find(|s| {
+                    s.get_type() == Ok(program::Type::Load) && strtab >= s.virtual_addr() &&
+                    strtab - s.virtual_addr() < s.file_size()
+                })
+                .ok_or("Dynamic string table is not in a loadable segment")?;
+            let data = segment.data_checked(elf_file)?;
+            let strings = &data[(strtab - segment.virtual_addr()) as usize..];
+            let strings = match strsz {
+                Some(size) => strings.get(..size as usize).unwrap_or(strings),
+                None => strings,
+            };
+            result.rpath = rpath.map(|offset| c_str(strings, offset as usize)).transpose()?;
+            result.runpath = runpath.map(|offset| c_str(strings, offset as usize)).transpose()?;
+        }
+        Ok(result)
+    }
+
+    // Records an entry of the dynamic table, and returns false for the
+    // `DT_NULL` which ends it. Tags which are not understood are skipped.
+    fn add<P: Into<u64>>(&mut self,
+                         strings: &mut DynamicStrings,
+                         tag: Result<Tag<P>, &'static str>,
+                         value: P)
+                         -> bool {
+        let value = value.into();
+        match tag {
+            Ok(Tag::Null) => return false,
+            Ok(Tag::StrTab) => strings.strtab = Some(value),
+            Ok(Tag::StrSize) => strings.strsz = Some(value),
+            Ok(Tag::RPath) => strings.rpath = Some(value),
+            Ok(Tag::RunPath) => strings.runpath = Some(value),
+            Ok(Tag::BindNow) => self.bind_now = true,
+            Ok(Tag::Flags) => self.flags = value,
+            Ok(Tag::Flags1) => self.flags_1 = value,
+            _ => {}
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+
+    #[test]
+    fn imported_symbols() {
+        let strings = b"\0__stack_chk_fail\0main\0";
+        let mut symbols = Vec::new();
+        for &(name, shndx) in &[(1u32, 0u16), (18, 14)] {
+            symbols.extend_from_slice(&name.to_ne_bytes());
+            symbols.extend_from_slice(&[0x12, 0]);
+            symbols.extend_from_slice(&shndx.to_ne_bytes());
+            symbols.extend_from_slice(&[0; 16]);
+        }
+        let imports = ImportedSymbols { symbols: &symbols, strings, entry_size: 24, is_64: true, undefined_only: true };
+        assert_eq!(imports.collect::<Vec<_>>(), vec![Ok("__stack_chk_fail")]);
+        let all = ImportedSymbols { symbols: &symbols, strings, entry_size: 24, is_64: true, undefined_only: false };
+        assert_eq!(all.collect::<Vec<_>>(), vec![Ok("__stack_chk_fail"), Ok("main")]);
+    }
+}
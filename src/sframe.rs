@@ -0,0 +1,396 @@
+//! SFrame (`.sframe`, the `PT_GNU_SFRAME` segment), a compact format which
+//! gives, for each address in a function, where to find the canonical frame
+//! address, the frame pointer and the return address. Versions 1 and 2 can be
+//! read.
+
+use ElfFile;
+use header::Class;
+use reader::Reader;
+use program;
+
+pub const SFRAME_MAGIC: u16 = 0xdee2;
+pub const SFRAME_VERSION_1: u8 = 1;
+pub const SFRAME_VERSION_2: u8 = 2;
+
+// Flags (SFrame::flags)
+pub const SFRAME_F_FDE_SORTED: u8 = 0x1;
+pub const SFRAME_F_FRAME_POINTER: u8 = 0x2;
+/// Function start addresses are relative to the field holding them, rather
+/// than to the start of the section.
+pub const SFRAME_F_FDE_FUNC_START_PCREL: u8 = 0x4;
+
+// The size of the header, without the auxiliary header.
+const HEADER_SIZE: usize = 28;
+
+/// The architecture and byte order the frame information is for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Abi {
+    AArch64BigEndian,
+    AArch64LittleEndian,
+    Amd64LittleEndian,
+    Other(u8),
+}
+
+impl Abi {
+    fn new(value: u8) -> Abi {
+        match value {
+            1 => Abi::AArch64BigEndian,
+            2 => Abi::AArch64LittleEndian,
+            3 => Abi::Amd64LittleEndian,
+            _ => Abi::Other(value),
+        }
+    }
+}
+
+/// An SFrame section.
+#[derive(Clone, Copy, Debug)]
+pub struct SFrame<'a> {
+    pub version: u8,
+    pub flags: u8,
+    pub abi: Abi,
+    /// The offset of the frame pointer from the CFA, if it is the same
+    /// everywhere, or 0.
+    pub cfa_fixed_fp_offset: i8,
+    /// The offset of the return address from the CFA, if it is the same
+    /// everywhere (as on x86-64), or 0.
+    pub cfa_fixed_ra_offset: i8,
+    pub num_fdes: u32,
+    pub num_fres: u32,
+    fdes: &'a [u8],
+    fres: &'a [u8],
+    // The address of the section, and of the FDE table.
+    address: u64,
+    fdes_address: u64,
+}
+
+impl<'a> SFrame<'a> {
+    /// `address` is where `data` is loaded.
+    pub fn new(data: &'a [u8], address: u64) -> Result<SFrame<'a>, &'static str> {
+        let mut reader = Reader::new(data);
+        check!(reader.u16()? == SFRAME_MAGIC, "Not SFrame data, or not in the host's byte order");
+        let version = reader.u8()?;
+        check!(version == SFRAME_VERSION_1 || version == SFRAME_VERSION_2, "Unsupported SFrame version");
+        let flags = reader.u8()?;
+        let abi = Abi::new(reader.u8()?);
+        let cfa_fixed_fp_offset = reader.u8()? as i8;
+        let cfa_fixed_ra_offset = reader.u8()? as i8;
+        let auxiliary_header_len = reader.u8()?;
+        let num_fdes = reader.u32()?;
+        let num_fres = reader.u32()?;
+        let fre_len = reader.u32()?;
+        let fde_offset = reader.u32()?;
+        let fre_offset = reader.u32()?;
+
+        let start = HEADER_SIZE + auxiliary_header_len as usize;
+        let sub_section = |offset: u32, len: u64| {
+            let start = (start as u64).checked_add(offset as u64);
+            let end = start.and_then(|start| start.checked_add(len));
+            match (start, end) {
+                (Some(start), Some(end)) if end <= data.len() as u64 => Ok(&data[start as usize..end as usize]),
+                _ => Err("SFrame table out of range"),
+            }
+        };
+        let fde_size = if version == SFRAME_VERSION_1 { 17 } else { 20 };
+        let fdes = sub_section(fde_offset, num_fdes as u64 * fde_size)?;
+        let fres = sub_section(fre_offset, fre_len as u64)?;
+        Ok(SFrame {
+            version,
+            flags,
+            abi,
+            cfa_fixed_fp_offset,
+            cfa_fixed_ra_offset,
+            num_fdes,
+            num_fres,
+            fdes,
+            fres,
+            address,
+            fdes_address: address.wrapping_add(start as u64 + fde_offset as u64),
+        })
+    }
+
+    /// The `.sframe` section of `elf_file`, or if it has no section headers,
+    /// the `PT_GNU_SFRAME` segment.
+    pub fn load(elf_file: &ElfFile<'a>) -> Result<Option<SFrame<'a>>, &'static str> {
+        let is_64 = match elf_file.header.pt1.class() {
+            Class::ThirtyTwo => false,
+            Class::SixtyFour => true,
+            Class::None | Class::Other(_) => return Err("Invalid ELF class"),
+        };
+        elf_file.check_tables(is_64)?;
+        if let Some(section) = elf_file.find_section_by_name(".sframe") {
+            let data = section.data_checked(elf_file)?;
+            return SFrame::new(data, section.address()).map(Some);
+        }
+        let segment = elf_file.program_iter().find(|ph| ph.get_type() == Ok(program::Type::GnuSframe));
+        match segment {
+            Some(segment) => {
+                let data = segment.data_checked(elf_file)?;
+                SFrame::new(data, segment.virtual_addr()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The function descriptor entries.
+    pub fn fdes(&self) -> impl Iterator<Item = Result<FuncDesc<'a>, &'static str>> + 'a {
+        let sframe = *self;
+        (0..self.num_fdes as usize).map(move |i| sframe.fde(i))
+    }
+
+    /// The function descriptor entry at `index`.
+    pub fn fde(&self, index: usize) -> Result<FuncDesc<'a>, &'static str> {
+        check!(index < self.num_fdes as usize, "SFrame FDE index out of range");
+        let entry_size = self.fdes.len() / self.num_fdes as usize;
+        let mut reader = Reader::new(&self.fdes[index * entry_size..(index + 1) * entry_size]);
+        let start_address = reader.u32()? as i32 as u64;
+        let size = reader.u32()?;
+        let fres_offset = reader.u32()?;
+        let num_fres = reader.u32()?;
+        let info = reader.u8()?;
+        let is_mask = info & 0x10 != 0;
+        // Version 1 has no repetition size, but only uses 16-byte blocks.
+        let repetition_size = if self.version == SFRAME_VERSION_1 { 16 } else { reader.u8()? };
+
+        let base = if self.flags & SFRAME_F_FDE_FUNC_START_PCREL != 0 {
+            self.fdes_address.wrapping_add((index * entry_size) as u64)
+        } else {
+            self.address
+        };
+        let fre_type = match info & 0xf {
+            0 => 1,
+            1 => 2,
+            2 => 4,
+            _ => return Err("Unknown SFrame FRE type"),
+        };
+        check!(!is_mask || repetition_size > 0, "SFrame repetition size is zero");
+        Ok(FuncDesc {
+            start: base.wrapping_add(start_address),
+            size,
+            num_fres,
+            is_mask,
+            pauth_key: (info >> 5) & 1,
+            repetition_size,
+            address_size: fre_type,
+            fres: self.fres.get(fres_offset as usize..).ok_or("SFrame FRE out of range")?,
+            sframe: *self,
+        })
+    }
+
+    /// Finds the function descriptor entry covering `pc`.
+    pub fn find_fde(&self, pc: u64) -> Result<Option<FuncDesc<'a>>, &'static str> {
+        if self.flags & SFRAME_F_FDE_SORTED == 0 {
+            for fde in self.fdes() {
+                let fde = fde?;
+                if fde.contains(pc) {
+                    return Ok(Some(fde));
+                }
+            }
+            return Ok(None);
+        }
+        let (mut low, mut high) = (0, self.num_fdes as usize);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.fde(middle)?.start <= pc {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        if low == 0 {
+            return Ok(None);
+        }
+        let fde = self.fde(low - 1)?;
+        Ok(if fde.contains(pc) { Some(fde) } else { None })
+    }
+
+    /// Finds the frame row entry for `pc`.
+    pub fn find_fre(&self, pc: u64) -> Result<Option<FrameRow>, &'static str> {
+        match self.find_fde(pc)? {
+            Some(fde) => fde.find_fre(pc),
+            None => Ok(None),
+        }
+    }
+}
+
+/// A function descriptor entry, for one function or block of code.
+#[derive(Clone, Copy, Debug)]
+pub struct FuncDesc<'a> {
+    pub start: u64,
+    pub size: u32,
+    pub num_fres: u32,
+    /// Whether the code is a repeated block, as in a PLT, and the FREs'
+    /// addresses are offsets within each repetition.
+    pub is_mask: bool,
+    /// The AArch64 pointer authentication key used to sign the return
+    /// address: 0 for A, 1 for B.
+    pub pauth_key: u8,
+    pub repetition_size: u8,
+    // The size of the FREs' start addresses.
+    address_size: u8,
+    fres: &'a [u8],
+    sframe: SFrame<'a>,
+}
+
+impl<'a> FuncDesc<'a> {
+    pub fn end(&self) -> u64 {
+        self.start.wrapping_add(self.size as u64)
+    }
+
+    pub fn contains(&self, pc: u64) -> bool {
+        self.start <= pc && pc < self.end()
+    }
+
+    /// The frame row entries, in order.
+    pub fn fres(&self) -> FrameRows<'a> {
+        FrameRows { fde: *self, reader: Reader::new(self.fres), remaining: self.num_fres }
+    }
+
+    /// Finds the frame row entry for `pc`.
+    pub fn find_fre(&self, pc: u64) -> Result<Option<FrameRow>, &'static str> {
+        if !self.contains(pc) {
+            return Ok(None);
+        }
+        let mut offset = pc - self.start;
+        if self.is_mask {
+            offset %= self.repetition_size as u64;
+        }
+        let mut found = None;
+        for fre in self.fres() {
+            let fre = fre?;
+            if fre.start_offset as u64 > offset {
+                break;
+            }
+            found = Some(fre);
+        }
+        Ok(found)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameRows<'a> {
+    fde: FuncDesc<'a>,
+    reader: Reader<'a>,
+    remaining: u32,
+}
+
+impl<'a> Iterator for FrameRows<'a> {
+    type Item = Result<FrameRow, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let fre = self.read();
+        if fre.is_err() {
+            self.remaining = 0;
+        }
+        Some(fre)
+    }
+}
+
+impl<'a> FrameRows<'a> {
+    fn read(&mut self) -> Result<FrameRow, &'static str> {
+        let reader = &mut self.reader;
+        let start_offset = match self.fde.address_size {
+            1 => reader.u8()? as u32,
+            2 => reader.u16()? as u32,
+            _ => reader.u32()?,
+        };
+        let info = reader.u8()?;
+        let count = (info >> 1) & 0xf;
+        let mut offsets = [0; 15];
+        for offset in &mut offsets[..count as usize] {
+            *offset = match (info >> 5) & 3 {
+                0 => reader.u8()? as i8 as i32,
+                1 => reader.u16()? as i16 as i32,
+                2 => reader.u32()? as i32,
+                _ => return Err("Unknown SFrame offset size"),
+            };
+        }
+
+        let sframe = &self.fde.sframe;
+        let offset = |index: usize| if index < count as usize { Some(offsets[index]) } else { None };
+        let (ra_offset, fp_index) = match sframe.cfa_fixed_ra_offset {
+            0 => (offset(1), 2),
+            fixed => (Some(fixed as i32), 1),
+        };
+        let fp_offset = match sframe.cfa_fixed_fp_offset {
+            0 => offset(fp_index),
+            fixed => Some(fixed as i32),
+        };
+        Ok(FrameRow {
+            start_offset,
+            cfa_base: if info & 1 == 0 { CfaBase::Fp } else { CfaBase::Sp },
+            cfa_offset: offset(0),
+            fp_offset,
+            ra_offset: if count == 0 { None } else { ra_offset },
+            is_ra_mangled: info & 0x80 != 0,
+        })
+    }
+}
+
+/// The register the CFA is an offset from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CfaBase {
+    Fp,
+    Sp,
+}
+
+/// A frame row entry, for the addresses from `start_offset` to the next
+/// one's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameRow {
+    /// The offset from the start of the function, or for FDEs with `is_mask`,
+    /// from the start of each repetition.
+    pub start_offset: u32,
+    pub cfa_base: CfaBase,
+    /// The offset of the CFA from `cfa_base`. `None` in the outermost frame,
+    /// whose return address is undefined.
+    pub cfa_offset: Option<i32>,
+    /// The offset from the CFA of the saved frame pointer, if it is saved.
+    pub fp_offset: Option<i32>,
+    /// The offset from the CFA of the return address, or `None` if it has
+    /// not been saved (as on AArch64 in leaf functions) or is undefined.
+    pub ra_offset: Option<i32>,
+    /// Whether the return address is signed, for AArch64 pointer
+    /// authentication.
+    pub is_ra_mangled: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+
+    #[test]
+    fn find_fre() {
+        // A version 2 AArch64 section at 0x1000, with one function at 0x2000
+        // whose start address is relative to the FDE.
+        let mut data = Vec::new();
+        data.extend_from_slice(&SFRAME_MAGIC.to_ne_bytes());
+        data.extend_from_slice(&[SFRAME_VERSION_2, SFRAME_F_FDE_SORTED | SFRAME_F_FDE_FUNC_START_PCREL, 2, 0, 0, 0]);
+        for &value in &[1u32, 2, 8, 0, 20] {
+            data.extend_from_slice(&value.to_ne_bytes());
+        }
+        for &value in &[0x2000u32 - 0x101c, 0x40, 0, 2] {
+            data.extend_from_slice(&value.to_ne_bytes());
+        }
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        // The CFA is sp at first, then sp + 16 with the return address and
+        // frame pointer saved below it.
+        data.extend_from_slice(&[0, 0x03, 0]);
+        data.extend_from_slice(&[8, 0x07, 16, -8i8 as u8, -16i8 as u8]);
+
+        let sframe = SFrame::new(&data, 0x1000).unwrap();
+        assert_eq!(sframe.abi, Abi::AArch64LittleEndian);
+        assert_eq!(sframe.fde(0).unwrap().start, 0x2000);
+        let fre = sframe.find_fre(0x2004).unwrap().unwrap();
+        assert_eq!((fre.cfa_base, fre.cfa_offset, fre.ra_offset, fre.fp_offset), (CfaBase::Sp, Some(0), None, None));
+        let fre = sframe.find_fre(0x203f).unwrap().unwrap();
+        assert_eq!(fre.start_offset, 8);
+        assert_eq!((fre.cfa_offset, fre.ra_offset, fre.fp_offset), (Some(16), Some(-8), Some(-16)));
+        assert!(sframe.find_fre(0x2040).unwrap().is_none());
+        assert!(sframe.find_fre(0x1fff).unwrap().is_none());
+    }
+}
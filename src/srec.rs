@@ -0,0 +1,299 @@
+//! Motorola S-record images (S19, S28 and S37).
+
+use core::fmt;
+
+use image::{HexBytes, LoadSegment};
+
+/// The number of data bytes in each data record written.
+pub const DATA_RECORD_LEN: usize = 16;
+
+/// The size of the addresses in data and start address records, which is what
+/// distinguishes S19 (16-bit), S28 (24-bit) and S37 (32-bit) files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressSize {
+    Bits16,
+    Bits24,
+    Bits32,
+}
+
+impl AddressSize {
+    /// The smallest address size which can hold `address`.
+    pub fn for_address(address: u64) -> Option<AddressSize> {
+        match address {
+            0..=0xffff => Some(AddressSize::Bits16),
+            0x1_0000..=0xff_ffff => Some(AddressSize::Bits24),
+            0x100_0000..=0xffff_ffff => Some(AddressSize::Bits32),
+            _ => None,
+        }
+    }
+
+    fn bytes(self) -> usize {
+        match self {
+            AddressSize::Bits16 => 2,
+            AddressSize::Bits24 => 3,
+            AddressSize::Bits32 => 4,
+        }
+    }
+
+    fn max_address(self) -> u64 {
+        (1 << (8 * self.bytes())) - 1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordType {
+    /// S0
+    Header,
+    /// S1, S2 or S3
+    Data(AddressSize),
+    /// S5 or S6
+    Count(AddressSize),
+    /// S9, S8 or S7
+    StartAddress(AddressSize),
+}
+
+impl RecordType {
+    fn from_digit(digit: u8) -> Result<RecordType, &'static str> {
+        match digit {
+            b'0' => Ok(RecordType::Header),
+            b'1' => Ok(RecordType::Data(AddressSize::Bits16)),
+            b'2' => Ok(RecordType::Data(AddressSize::Bits24)),
+            b'3' => Ok(RecordType::Data(AddressSize::Bits32)),
+            b'5' => Ok(RecordType::Count(AddressSize::Bits16)),
+            b'6' => Ok(RecordType::Count(AddressSize::Bits24)),
+            b'7' => Ok(RecordType::StartAddress(AddressSize::Bits32)),
+            b'8' => Ok(RecordType::StartAddress(AddressSize::Bits24)),
+            b'9' => Ok(RecordType::StartAddress(AddressSize::Bits16)),
+            _ => Err("Invalid S-record type"),
+        }
+    }
+
+    fn as_digit(self) -> char {
+        match self {
+            RecordType::Header => '0',
+            RecordType::Data(AddressSize::Bits16) => '1',
+            RecordType::Data(AddressSize::Bits24) => '2',
+            RecordType::Data(AddressSize::Bits32) => '3',
+            RecordType::Count(AddressSize::Bits16) => '5',
+            RecordType::Count(_) => '6',
+            RecordType::StartAddress(AddressSize::Bits32) => '7',
+            RecordType::StartAddress(AddressSize::Bits24) => '8',
+            RecordType::StartAddress(AddressSize::Bits16) => '9',
+        }
+    }
+
+    fn address_size(self) -> AddressSize {
+        match self {
+            RecordType::Header => AddressSize::Bits16,
+            RecordType::Data(size) | RecordType::Count(size) | RecordType::StartAddress(size) => size,
+        }
+    }
+}
+
+/// A single line of an S-record file. The checksum and byte count have already
+/// been checked. For count records, `address` holds the count.
+#[derive(Clone, Copy, Debug)]
+pub struct Record<'a> {
+    pub type_: RecordType,
+    pub address: u32,
+    pub data: HexBytes<'a>,
+}
+
+/// Writes `segments` as S-records, using `address_size` for all addresses.
+///
+/// The file starts with a header record holding `header`, and ends with a
+/// record count (if the count fits in 24 bits) and a start address record
+/// holding `entry_point`, or zero if there is no entry point.
+pub fn write<'a, W, I>(out: &mut W,
+                       segments: I,
+                       address_size: AddressSize,
+                       header: &[u8],
+                       entry_point: Option<u64>)
+                       -> Result<(), &'static str>
+    where W: fmt::Write,
+          I: IntoIterator<Item = Result<LoadSegment<'a>, &'static str>>
+{
+    check!(header.len() <= 0xff - 3, "S-record header is too long");
+    write_record(out, RecordType::Header, 0, header)?;
+
+    let mut count = 0u32;
+    for segment in segments {
+        let segment = segment?;
+        check!(segment.data.is_empty() || segment.end() - 1 <= address_size.max_address(),
+               "Address does not fit in the S-record address size");
+        let mut address = segment.address;
+        for chunk in segment.data.chunks(DATA_RECORD_LEN) {
+            write_record(out, RecordType::Data(address_size), address as u32, chunk)?;
+            address += chunk.len() as u64;
+            count = count.saturating_add(1);
+        }
+    }
+
+    if count <= 0xffff {
+        write_record(out, RecordType::Count(AddressSize::Bits16), count, &[])?;
+    } else if count <= 0xff_ffff {
+        write_record(out, RecordType::Count(AddressSize::Bits24), count, &[])?;
+    }
+    let entry_point = entry_point.unwrap_or(0);
+    check!(entry_point <= address_size.max_address(),
+           "Entry point does not fit in the S-record address size");
+    write_record(out, RecordType::StartAddress(address_size), entry_point as u32, &[])
+}
+
+fn write_record<W: fmt::Write>(out: &mut W,
+                               type_: RecordType,
+                               address: u32,
+                               data: &[u8])
+                               -> Result<(), &'static str> {
+    fn write_record_<W: fmt::Write>(out: &mut W, type_: RecordType, address: u32, data: &[u8]) -> fmt::Result {
+        let address_bytes = &address.to_be_bytes()[4 - type_.address_size().bytes()..];
+        let count = (address_bytes.len() + data.len() + 1) as u8;
+        write!(out, "S{}{:02X}", type_.as_digit(), count)?;
+        let mut sum = count;
+        for &b in address_bytes.iter().chain(data) {
+            write!(out, "{:02X}", b)?;
+            sum = sum.wrapping_add(b);
+        }
+        write!(out, "{:02X}\r\n", !sum)
+    }
+
+    write_record_(out, type_, address, data).map_err(|_| "Error writing S-records")
+}
+
+/// Iterates over the records in an S-record file. Blank lines are skipped.
+pub fn records(input: &str) -> Records<'_> {
+    Records { lines: input.lines() }
+}
+
+#[derive(Clone, Debug)]
+pub struct Records<'a> {
+    lines: ::core::str::Lines<'a>,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<Record<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?.trim();
+            if !line.is_empty() {
+                return Some(parse_record(line));
+            }
+        }
+    }
+}
+
+fn parse_record(line: &str) -> Result<Record<'_>, &'static str> {
+    check!(line.len() >= 2 && line.starts_with('S'), "S-record does not start with 'S'");
+    let type_ = RecordType::from_digit(line.as_bytes()[1])?;
+    let bytes = HexBytes::new(&line[2..])?;
+    let address_len = type_.address_size().bytes();
+    check!(bytes.len() >= address_len + 2, "S-record is too short");
+    check!(bytes.fold(0u8, |sum, b| sum.wrapping_add(b)) == 0xff, "S-record checksum mismatch");
+
+    let mut header = bytes;
+    let count = header.next().unwrap();
+    check!(count as usize == bytes.len() - 1, "S-record byte count mismatch");
+    let address = header.take(address_len).fold(0, |address, b| (address << 8) | b as u32);
+    let data = HexBytes::new(&line[4 + 2 * address_len..line.len() - 2])?;
+    Ok(Record { type_, address, data })
+}
+
+/// Iterates over the data records in an S-record file.
+pub fn data_chunks(input: &str) -> DataChunks<'_> {
+    DataChunks { records: records(input) }
+}
+
+#[derive(Clone, Debug)]
+pub struct DataChunks<'a> {
+    records: Records<'a>,
+}
+
+impl<'a> Iterator for DataChunks<'a> {
+    type Item = Result<(u64, HexBytes<'a>), &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.records.next()? {
+                Ok(Record { type_: RecordType::Data(_), address, data }) => {
+                    return Some(Ok((address as u64, data)));
+                }
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Finds the address in the start address record, if there is one.
+pub fn entry_point(input: &str) -> Result<Option<u64>, &'static str> {
+    for record in records(input) {
+        let record = record?;
+        if let RecordType::StartAddress(_) = record.type_ {
+            return Ok(Some(record.address as u64));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+    use image::{verify, VerifyError};
+
+    fn round_trip(address: u64, address_size: AddressSize, start_record: &str) {
+        let data: Vec<u8> = (0..20).collect();
+        let segments = [LoadSegment { address, data: &data }];
+        let mut out = String::new();
+        write(&mut out, segments.iter().cloned().map(Ok), address_size, b"hi", Some(address)).unwrap();
+
+        let types: Vec<_> = records(&out).map(|r| r.unwrap().type_).collect();
+        assert_eq!(types, [RecordType::Header,
+                           RecordType::Data(address_size),
+                           RecordType::Data(address_size),
+                           RecordType::Count(AddressSize::Bits16),
+                           RecordType::StartAddress(address_size)]);
+        assert_eq!(out.lines().next(), Some("S0050000686929"));
+        assert_eq!(out.lines().nth(3), Some("S5030002FA"));
+        assert_eq!(out.lines().last(), Some(start_record));
+        assert_eq!(entry_point(&out), Ok(Some(address)));
+        assert_eq!(data_chunks(&out).map(|c| c.unwrap().0).collect::<Vec<_>>(), [address, address + 16]);
+        assert_eq!(verify(segments.iter().cloned().map(Ok), data_chunks(&out)), Ok(()));
+    }
+
+    #[test]
+    fn round_trip_s19() {
+        round_trip(0x100, AddressSize::Bits16, "S9030100FB");
+    }
+
+    #[test]
+    fn round_trip_s28() {
+        round_trip(0x12_3456, AddressSize::Bits24, "S8041234565F");
+    }
+
+    #[test]
+    fn round_trip_s37() {
+        round_trip(0x0800_0000, AddressSize::Bits32, "S70508000000F2");
+    }
+
+    #[test]
+    fn errors() {
+        let data = [0xaa; 4];
+        let segments = [LoadSegment { address: 0x1_0000, data: &data }];
+        let mut out = String::new();
+        assert!(write(&mut out, segments.iter().cloned().map(Ok), AddressSize::Bits16, b"", None).is_err());
+
+        out.clear();
+        write(&mut out, segments.iter().cloned().map(Ok), AddressSize::Bits24, b"", None).unwrap();
+        let changed = out.replacen("010000AAAA", "010000AAAB", 1);
+        assert_eq!(data_chunks(&changed).next().unwrap().err(), Some("S-record checksum mismatch"));
+        assert_eq!(verify(segments.iter().cloned().map(Ok), data_chunks(&changed)),
+                   Err(VerifyError::Malformed("S-record checksum mismatch")));
+
+        // The checksum is right, but the count is one more than the bytes which follow it.
+        assert_eq!(records("S1040000FB").next().unwrap().err(), Some("S-record byte count mismatch"));
+        assert_eq!(records("S1030000FC").next().unwrap().unwrap().data.len(), 0);
+    }
+}
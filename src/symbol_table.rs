@@ -3,6 +3,7 @@ use sections;
 
 use zero::Pod;
 
+use core::cmp;
 use core::fmt;
 
 #[derive(Debug)]
@@ -74,6 +75,18 @@ pub trait Entry {
         Type_(self.info() & 0xf).as_type()
     }
 
+    /// An owned copy of the entry's fields.
+    fn snapshot(&self) -> SymbolSnapshot {
+        SymbolSnapshot {
+            name: self.name(),
+            info: self.info(),
+            other: self.other().0,
+            shndx: self.shndx(),
+            value: self.value(),
+            size: self.size(),
+        }
+    }
+
     fn get_section_header<'a>(&'a self,
                               elf_file: &ElfFile<'a>,
                               self_index: usize)
@@ -106,6 +119,19 @@ pub trait Entry {
     }
 }
 
+/// The fields of a symbol table `Entry`, as raw values, independent of the
+/// file they were read from. `name` is an offset into the linked string table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SymbolSnapshot {
+    pub name: u32,
+    pub info: u8,
+    pub other: u8,
+    pub shndx: u16,
+    pub value: u64,
+    pub size: u64,
+}
+
 impl fmt::Display for dyn Entry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Symbol table entry:")?;
@@ -223,3 +249,63 @@ impl Type_ {
         }
     }
 }
+
+/// A function or object symbol, found by name or address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbol<'a> {
+    pub name: &'a str,
+    pub address: u64,
+    pub size: u64,
+}
+
+impl<'a> Symbol<'a> {
+    /// Whether `address` is within the symbol. A symbol with no size
+    /// contains only its own address.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.address && address - self.address < cmp::max(self.size, 1)
+    }
+}
+
+/// Finds the defined function or object containing `address`, in `.symtab`
+/// or else in `.dynsym`.
+pub fn find_by_address<'a>(elf_file: &ElfFile<'a>, address: u64) -> Option<Symbol<'a>> {
+    find(elf_file, |symbol| symbol.contains(address))
+}
+
+/// Finds the defined function or object called `name`, in `.symtab` or else
+/// in `.dynsym`.
+pub fn find_by_name<'a>(elf_file: &ElfFile<'a>, name: &str) -> Option<Symbol<'a>> {
+    find(elf_file, |symbol| symbol.name == name)
+}
+
+fn find<'a, F: Fn(&Symbol<'a>) -> bool>(elf_file: &ElfFile<'a>, predicate: F) -> Option<Symbol<'a>> {
+    for &type_ in &[sections::ShType::SymTab, sections::ShType::DynSym] {
+        for section in elf_file.section_iter().filter(|section| section.get_type() == Ok(type_)) {
+            let found = match section.get_data(elf_file) {
+                Ok(sections::SectionData::SymbolTable32(entries)) => find_in(elf_file, entries, &predicate),
+                Ok(sections::SectionData::SymbolTable64(entries)) => find_in(elf_file, entries, &predicate),
+                Ok(sections::SectionData::DynSymbolTable32(entries)) => find_in(elf_file, entries, &predicate),
+                Ok(sections::SectionData::DynSymbolTable64(entries)) => find_in(elf_file, entries, &predicate),
+                _ => None,
+            };
+            if found.is_some() {
+                return found;
+            }
+        }
+    }
+    None
+}
+
+fn find_in<'a, E, F>(elf_file: &ElfFile<'a>, entries: &'a [E], predicate: &F) -> Option<Symbol<'a>>
+    where E: Entry,
+          F: Fn(&Symbol<'a>) -> bool
+{
+    entries.iter()
+        .filter(|entry| {
+            entry.shndx() != sections::SHN_UNDEF && matches!(entry.get_type(), Ok(Type::Func) | Ok(Type::Object))
+        })
+        .filter_map(|entry| {
+            Some(Symbol { name: entry.get_name(elf_file).ok()?, address: entry.value(), size: entry.size() })
+        })
+        .find(|symbol| predicate(symbol))
+}
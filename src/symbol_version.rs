@@ -0,0 +1,475 @@
+//! GNU symbol versioning: the `.gnu.version` (version symbol),
+//! `.gnu.version_d` (version definition) and `.gnu.version_r` (version
+//! requirement) sections.
+
+use ElfFile;
+use sections::{SectionHeader, ShType, SHT_GNU_VERDEF, SHT_GNU_VERNEED, SHT_GNU_VERSYM};
+use zero::read_str;
+
+// Distinguished version indices.
+pub const VER_NDX_LOCAL: u16 = 0;
+pub const VER_NDX_GLOBAL: u16 = 1;
+
+// Parts of a version symbol entry.
+pub const VERSYM_VERSION: u16 = 0x7fff;
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+
+// Flags (Verdef::flags and Vernaux::flags).
+pub const VER_FLG_BASE: u16 = 0x1;
+pub const VER_FLG_WEAK: u16 = 0x2;
+pub const VER_FLG_INFO: u16 = 0x4;
+
+/// An entry of the version symbol section, giving the version of the dynamic
+/// symbol with the same index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionIndex(pub u16);
+
+impl VersionIndex {
+    pub fn index(self) -> u16 {
+        self.0 & VERSYM_VERSION
+    }
+
+    pub fn is_hidden(self) -> bool {
+        self.0 & VERSYM_HIDDEN != 0
+    }
+
+    pub fn is_local(self) -> bool {
+        self.index() == VER_NDX_LOCAL
+    }
+
+    pub fn is_global(self) -> bool {
+        self.index() == VER_NDX_GLOBAL
+    }
+}
+
+/// The contents of a version symbol section.
+#[derive(Clone, Copy, Debug)]
+pub struct VersionSymbols<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> VersionSymbols<'a> {
+    pub fn new(data: &'a [u8]) -> VersionSymbols<'a> {
+        VersionSymbols { data }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The version of the dynamic symbol at `symbol_index`.
+    pub fn get(&self, symbol_index: usize) -> Option<VersionIndex> {
+        read_u16(self.data, symbol_index * 2).ok().map(VersionIndex)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = VersionIndex> + 'a {
+        self.data.chunks_exact(2).map(|b| VersionIndex(u16::from_ne_bytes([b[0], b[1]])))
+    }
+}
+
+/// An entry of the version definition section.
+#[derive(Clone, Copy, Debug)]
+pub struct Verdef<'a> {
+    /// The offset of the entry within the section.
+    pub offset: usize,
+    pub version: u16,
+    pub flags: u16,
+    /// The version index used for this version in the version symbol section.
+    pub index: u16,
+    pub aux_count: u16,
+    pub hash: u32,
+    aux: u32,
+    data: &'a [u8],
+}
+
+impl<'a> Verdef<'a> {
+    /// Iterates over the names of this version. The first is the name of the
+    /// version itself, the rest are the versions it inherits from.
+    pub fn names(&self) -> VerdauxIter<'a> {
+        VerdauxIter {
+            data: self.data,
+            offset: self.offset.checked_add(self.aux as usize),
+            remaining: self.aux_count,
+        }
+    }
+}
+
+/// A name of a version definition.
+#[derive(Clone, Copy, Debug)]
+pub struct Verdaux {
+    /// The offset of the entry within the section.
+    pub offset: usize,
+    /// The offset of the name in the linked string table.
+    pub name: u32,
+}
+
+/// Iterates over the entries of a version definition section.
+#[derive(Clone, Debug)]
+pub struct VerdefIter<'a> {
+    data: &'a [u8],
+    offset: Option<usize>,
+}
+
+impl<'a> VerdefIter<'a> {
+    pub fn new(data: &'a [u8]) -> VerdefIter<'a> {
+        VerdefIter {
+            data,
+            offset: if data.is_empty() { None } else { Some(0) },
+        }
+    }
+
+    fn read(&self, offset: usize) -> Result<(Verdef<'a>, u32), &'static str> {
+        Ok((Verdef {
+            offset,
+            version: read_u16(self.data, offset)?,
+            flags: read_u16(self.data, offset + 2)?,
+            index: read_u16(self.data, offset + 4)?,
+            aux_count: read_u16(self.data, offset + 6)?,
+            hash: read_u32(self.data, offset + 8)?,
+            aux: read_u32(self.data, offset + 12)?,
+            data: self.data,
+        }, read_u32(self.data, offset + 16)?))
+    }
+}
+
+impl<'a> Iterator for VerdefIter<'a> {
+    type Item = Result<Verdef<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset?;
+        Some(self.read(offset).map(|(verdef, next)| {
+            self.offset = next_offset(offset, next);
+            verdef
+        }).inspect_err(|_| {
+            self.offset = None;
+        }))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct VerdauxIter<'a> {
+    data: &'a [u8],
+    offset: Option<usize>,
+    remaining: u16,
+}
+
+impl<'a> Iterator for VerdauxIter<'a> {
+    type Item = Result<Verdaux, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let offset = match self.offset {
+            Some(offset) => offset,
+            None => {
+                self.remaining = 0;
+                return Some(Err("Version definition auxiliary entry out of range"));
+            }
+        };
+        let result = read_u32(self.data, offset).and_then(|name| {
+            let next = read_u32(self.data, offset + 4)?;
+            Ok((Verdaux { offset, name }, next))
+        });
+        Some(result.map(|(verdaux, next)| {
+            self.offset = offset.checked_add(next as usize);
+            verdaux
+        }).inspect_err(|_| {
+            self.remaining = 0;
+        }))
+    }
+}
+
+/// An entry of the version requirement section, i.e., the versions needed
+/// from one shared object.
+#[derive(Clone, Copy, Debug)]
+pub struct Verneed<'a> {
+    /// The offset of the entry within the section.
+    pub offset: usize,
+    pub version: u16,
+    pub aux_count: u16,
+    /// The offset of the shared object's name in the linked string table.
+    pub file: u32,
+    aux: u32,
+    data: &'a [u8],
+}
+
+impl<'a> Verneed<'a> {
+    /// Iterates over the versions needed from this shared object.
+    pub fn versions(&self) -> VernauxIter<'a> {
+        VernauxIter {
+            data: self.data,
+            offset: self.offset.checked_add(self.aux as usize),
+            remaining: self.aux_count,
+        }
+    }
+}
+
+/// A version needed from a shared object.
+#[derive(Clone, Copy, Debug)]
+pub struct Vernaux {
+    /// The offset of the entry within the section.
+    pub offset: usize,
+    pub hash: u32,
+    pub flags: u16,
+    /// The version index used for this version in the version symbol section.
+    pub other: u16,
+    /// The offset of the name in the linked string table.
+    pub name: u32,
+}
+
+/// Iterates over the entries of a version requirement section.
+#[derive(Clone, Debug)]
+pub struct VerneedIter<'a> {
+    data: &'a [u8],
+    offset: Option<usize>,
+}
+
+impl<'a> VerneedIter<'a> {
+    pub fn new(data: &'a [u8]) -> VerneedIter<'a> {
+        VerneedIter {
+            data,
+            offset: if data.is_empty() { None } else { Some(0) },
+        }
+    }
+
+    fn read(&self, offset: usize) -> Result<(Verneed<'a>, u32), &'static str> {
+        Ok((Verneed {
+            offset,
+            version: read_u16(self.data, offset)?,
+            aux_count: read_u16(self.data, offset + 2)?,
+            file: read_u32(self.data, offset + 4)?,
+            aux: read_u32(self.data, offset + 8)?,
+            data: self.data,
+        }, read_u32(self.data, offset + 12)?))
+    }
+}
+
+impl<'a> Iterator for VerneedIter<'a> {
+    type Item = Result<Verneed<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset?;
+        Some(self.read(offset).map(|(verneed, next)| {
+            self.offset = next_offset(offset, next);
+            verneed
+        }).inspect_err(|_| {
+            self.offset = None;
+        }))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct VernauxIter<'a> {
+    data: &'a [u8],
+    offset: Option<usize>,
+    remaining: u16,
+}
+
+impl<'a> Iterator for VernauxIter<'a> {
+    type Item = Result<Vernaux, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let offset = match self.offset {
+            Some(offset) => offset,
+            None => {
+                self.remaining = 0;
+                return Some(Err("Version requirement auxiliary entry out of range"));
+            }
+        };
+        let data = self.data;
+        let read = || -> Result<(Vernaux, u32), &'static str> {
+            Ok((Vernaux {
+                offset,
+                hash: read_u32(data, offset)?,
+                flags: read_u16(data, offset + 4)?,
+                other: read_u16(data, offset + 6)?,
+                name: read_u32(data, offset + 8)?,
+            }, read_u32(data, offset + 12)?))
+        };
+        Some(read().map(|(vernaux, next)| {
+            self.offset = offset.checked_add(next as usize);
+            vernaux
+        }).inspect_err(|_| {
+            self.remaining = 0;
+        }))
+    }
+}
+
+/// The version of a dynamic symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolVersion<'a> {
+    /// A version defined by this file.
+    Defined { name: &'a str, hidden: bool },
+    /// A version required from the shared object `file`.
+    Needed { name: &'a str, file: &'a str },
+}
+
+/// Looks up a version index in the version definition and requirement
+/// sections of `elf_file`. Returns `None` for the local and global indices, and
+/// for indices which are not defined or required.
+pub fn lookup<'a>(elf_file: &ElfFile<'a>, index: VersionIndex) -> Result<Option<SymbolVersion<'a>>, &'static str> {
+    if index.is_local() || index.is_global() {
+        return Ok(None);
+    }
+
+    if let Some(section) = find_section(elf_file, SHT_GNU_VERDEF) {
+        for verdef in VerdefIter::new(section.data_checked(elf_file)?) {
+            let verdef = verdef?;
+            if verdef.index == index.index() {
+                if let Some(verdaux) = verdef.names().next() {
+                    let name = linked_string(elf_file, section, verdaux?.name)?;
+                    return Ok(Some(SymbolVersion::Defined { name, hidden: index.is_hidden() }));
+                }
+            }
+        }
+    }
+    if let Some(section) = find_section(elf_file, SHT_GNU_VERNEED) {
+        for verneed in VerneedIter::new(section.data_checked(elf_file)?) {
+            let verneed = verneed?;
+            for vernaux in verneed.versions() {
+                let vernaux = vernaux?;
+                if vernaux.other == index.index() {
+                    return Ok(Some(SymbolVersion::Needed {
+                        name: linked_string(elf_file, section, vernaux.name)?,
+                        file: linked_string(elf_file, section, verneed.file)?,
+                    }));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The version symbol section of `elf_file`, if there is one.
+pub fn version_symbols<'a>(elf_file: &ElfFile<'a>) -> Result<Option<VersionSymbols<'a>>, &'static str> {
+    find_section(elf_file, SHT_GNU_VERSYM)
+        .map(|section| section.data_checked(elf_file).map(VersionSymbols::new))
+        .transpose()
+}
+
+fn find_section<'a>(elf_file: &ElfFile<'a>, sh_type: u32) -> Option<SectionHeader<'a>> {
+    elf_file.section_iter().find(|s| s.type_().0 == sh_type)
+}
+
+/// Reads a string from the string table linked to by `section`.
+pub fn linked_string<'a>(elf_file: &ElfFile<'a>,
+                         section: SectionHeader<'a>,
+                         offset: u32)
+                         -> Result<&'a str, &'static str> {
+    let strings = elf_file.section_header(section.link() as u16)?;
+    check!(strings.get_type()? == ShType::StrTab, "Linked section is not a string table");
+    strings.data_checked(elf_file)?.get(offset as usize..).map(read_str).ok_or("String index out of range")
+}
+
+fn next_offset(offset: usize, next: u32) -> Option<usize> {
+    if next == 0 {
+        None
+    } else {
+        offset.checked_add(next as usize)
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, &'static str> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+        .ok_or("Symbol version data is truncated")
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, &'static str> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or("Symbol version data is truncated")
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+    use sections::test::mk_file;
+
+    fn push_u16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&value.to_ne_bytes());
+    }
+
+    fn push_u32(data: &mut Vec<u8>, value: u32) {
+        data.extend_from_slice(&value.to_ne_bytes());
+    }
+
+    #[test]
+    fn version_requirements() {
+        let mut data = Vec::new();
+        // One Verneed for file name 1, with its two Vernaux entries following.
+        push_u16(&mut data, 1);
+        push_u16(&mut data, 2);
+        push_u32(&mut data, 1);
+        push_u32(&mut data, 16);
+        push_u32(&mut data, 0);
+        for &(other, name, next) in &[(2, 11, 16), (3, 23, 0)] {
+            push_u32(&mut data, 0x1234);
+            push_u16(&mut data, 0);
+            push_u16(&mut data, other);
+            push_u32(&mut data, name);
+            push_u32(&mut data, next);
+        }
+
+        let mut needs = VerneedIter::new(&data);
+        let verneed = needs.next().unwrap().unwrap();
+        assert!(needs.next().is_none());
+        assert_eq!((verneed.version, verneed.aux_count, verneed.file), (1, 2, 1));
+        let versions: Vec<_> = verneed.versions().map(|v| v.map(|v| (v.offset, v.other, v.name))).collect();
+        assert_eq!(versions, vec![Ok((16, 2, 11)), Ok((32, 3, 23))]);
+
+        // A count which runs past the end of the data is an error, not a panic.
+        data.truncate(40);
+        let verneed = VerneedIter::new(&data).next().unwrap().unwrap();
+        let versions: Vec<_> = verneed.versions().map(|v| v.map(|v| v.other)).collect();
+        assert_eq!(versions, vec![Ok(2), Err("Symbol version data is truncated")]);
+    }
+
+    #[test]
+    fn version_indices() {
+        let mut data = Vec::new();
+        for &version in &[0, 1, 0x8002] {
+            push_u16(&mut data, version);
+        }
+        let versions = VersionSymbols::new(&data);
+        assert_eq!(versions.len(), 3);
+        assert!(versions.get(0).unwrap().is_local());
+        assert!(versions.get(1).unwrap().is_global());
+        let hidden = versions.get(2).unwrap();
+        assert_eq!((hidden.index(), hidden.is_hidden()), (2, true));
+        assert!(versions.get(3).is_none());
+    }
+
+    #[test]
+    fn sections_out_of_range() {
+        // A version section whose size runs past the end of the file.
+        let mut input = mk_file(&[(".gnu.version", &[0; 4])]);
+        let header = input.len() - 64;
+        input[header + 4..header + 8].copy_from_slice(&SHT_GNU_VERSYM.to_le_bytes());
+        input[header + 32..header + 40].copy_from_slice(&u64::MAX.to_le_bytes());
+        let elf_file = ElfFile::new(&input).unwrap();
+        assert_eq!(version_symbols(&elf_file).err(), Some("Section is out of range of the file"));
+
+        input[header + 4..header + 8].copy_from_slice(&SHT_GNU_VERDEF.to_le_bytes());
+        let elf_file = ElfFile::new(&input).unwrap();
+        assert_eq!(lookup(&elf_file, VersionIndex(2)), Err("Section is out of range of the file"));
+
+        // A string table linked to by itself.
+        input[header + 4..header + 8].copy_from_slice(&3u32.to_le_bytes());
+        input[header + 40..header + 44].copy_from_slice(&2u32.to_le_bytes());
+        let elf_file = ElfFile::new(&input).unwrap();
+        let section = elf_file.section_header(2).unwrap();
+        assert_eq!(linked_string(&elf_file, section, 0), Err("Section is out of range of the file"));
+    }
+}
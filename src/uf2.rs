@@ -0,0 +1,287 @@
+//! UF2 firmware images, as flashed by copying to a microcontroller's USB mass
+//! storage bootloader.
+//!
+//! Each 512 byte block carries 256 bytes of payload for a 256-byte aligned
+//! target address, which is what most bootloaders (including the RP2040's)
+//! require. Parts of a block which are not covered by a segment are zero.
+
+use image::{self, LoadSegment, VerifyError};
+
+pub const BLOCK_SIZE: usize = 512;
+pub const PAYLOAD_SIZE: usize = 256;
+/// The most payload a block can hold, for blocks read from other writers.
+pub const MAX_PAYLOAD_SIZE: usize = 476;
+
+pub const MAGIC_START0: u32 = 0x0A324655;
+pub const MAGIC_START1: u32 = 0x9E5D5157;
+pub const MAGIC_END: u32 = 0x0AB16F30;
+
+// Block flags.
+pub const FLAG_NOT_MAIN_FLASH: u32 = 0x00000001;
+pub const FLAG_FILE_CONTAINER: u32 = 0x00001000;
+pub const FLAG_FAMILY_ID_PRESENT: u32 = 0x00002000;
+pub const FLAG_MD5_CHECKSUM_PRESENT: u32 = 0x00004000;
+pub const FLAG_EXTENSION_TAGS_PRESENT: u32 = 0x00008000;
+
+// Some well-known family IDs.
+pub const FAMILY_ID_SAMD21: u32 = 0x68ed2b88;
+pub const FAMILY_ID_SAMD51: u32 = 0x55114460;
+pub const FAMILY_ID_NRF52: u32 = 0x1b57745f;
+pub const FAMILY_ID_NRF52833: u32 = 0x621e937a;
+pub const FAMILY_ID_NRF52840: u32 = 0xada52840;
+pub const FAMILY_ID_STM32F4: u32 = 0x57755a57;
+pub const FAMILY_ID_ESP32S2: u32 = 0xbfdd4eee;
+pub const FAMILY_ID_RP2040: u32 = 0xe48bff56;
+pub const FAMILY_ID_RP2350_ARM_S: u32 = 0xe48bff59;
+pub const FAMILY_ID_RP2350_RISCV: u32 = 0xe48bff5a;
+pub const FAMILY_ID_RP2350_ARM_NS: u32 = 0xe48bff5b;
+
+/// Iterates over the blocks of a UF2 image of `segments`. The segments are
+/// laid out in address order; where they overlap, later segments win.
+#[derive(Clone, Debug)]
+pub struct Blocks<S> {
+    segments: S,
+    family_id: Option<u32>,
+    next_address: u64,
+    block_no: u32,
+    num_blocks: u32,
+}
+
+/// Makes a UF2 image of `segments`, with `family_id` in every block if given.
+///
+/// All segment addresses must fit in 32 bits.
+pub fn blocks<'a, S>(segments: S, family_id: Option<u32>) -> Result<Blocks<S>, &'static str>
+    where S: Iterator<Item = Result<LoadSegment<'a>, &'static str>> + Clone
+{
+    for segment in segments.clone() {
+        check!(segment?.end() <= 0x1_0000_0000, "Address does not fit in 32 bits");
+    }
+
+    let mut result = Blocks {
+        segments,
+        family_id,
+        next_address: 0,
+        block_no: 0,
+        num_blocks: 0,
+    };
+    let mut num_blocks = 0u32;
+    while let Some(page) = result.next_page() {
+        num_blocks = num_blocks.checked_add(1).ok_or("Too many UF2 blocks")?;
+        result.next_address = page + PAYLOAD_SIZE as u64;
+    }
+    result.next_address = 0;
+    result.num_blocks = num_blocks;
+    Ok(result)
+}
+
+impl<'a, S> Blocks<S>
+    where S: Iterator<Item = Result<LoadSegment<'a>, &'static str>> + Clone
+{
+    pub fn num_blocks(&self) -> u32 {
+        self.num_blocks
+    }
+
+    // The lowest page at or after next_address containing segment data.
+    // Segments have all been checked when the Blocks was created.
+    fn next_page(&self) -> Option<u64> {
+        self.segments.clone().filter_map(|segment| {
+            let segment = segment.unwrap();
+            let start = segment.address.max(self.next_address);
+            if start < segment.end() {
+                Some(start & !(PAYLOAD_SIZE as u64 - 1))
+            } else {
+                None
+            }
+        }).min()
+    }
+}
+
+impl<'a, S> Iterator for Blocks<S>
+    where S: Iterator<Item = Result<LoadSegment<'a>, &'static str>> + Clone
+{
+    type Item = [u8; BLOCK_SIZE];
+
+    fn next(&mut self) -> Option<[u8; BLOCK_SIZE]> {
+        let page = self.next_page()?;
+        let page_end = page + PAYLOAD_SIZE as u64;
+
+        let mut block = [0; BLOCK_SIZE];
+        let mut flags = 0;
+        if self.family_id.is_some() {
+            flags |= FLAG_FAMILY_ID_PRESENT;
+        }
+        let header = [MAGIC_START0,
+                      MAGIC_START1,
+                      flags,
+                      page as u32,
+                      PAYLOAD_SIZE as u32,
+                      self.block_no,
+                      self.num_blocks,
+                      self.family_id.unwrap_or(0)];
+        for (i, word) in header.iter().enumerate() {
+            block[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        block[BLOCK_SIZE - 4..].copy_from_slice(&MAGIC_END.to_le_bytes());
+
+        let payload = &mut block[32..32 + PAYLOAD_SIZE];
+        for segment in self.segments.clone() {
+            let segment = segment.unwrap();
+            let start = segment.address.max(page);
+            let end = segment.end().min(page_end);
+            if start < end {
+                let data = &segment.data[(start - segment.address) as usize..(end - segment.address) as usize];
+                payload[(start - page) as usize..(end - page) as usize].copy_from_slice(data);
+            }
+        }
+
+        self.next_address = page_end;
+        self.block_no += 1;
+        Some(block)
+    }
+}
+
+/// A block read from a UF2 file.
+#[derive(Clone, Copy, Debug)]
+pub struct Block<'a> {
+    pub flags: u32,
+    pub target_address: u32,
+    pub block_no: u32,
+    pub num_blocks: u32,
+    /// The file size, or the family ID if `FLAG_FAMILY_ID_PRESENT` is set.
+    pub file_size_or_family_id: u32,
+    pub payload: &'a [u8],
+}
+
+impl<'a> Block<'a> {
+    pub fn family_id(&self) -> Option<u32> {
+        if self.flags & FLAG_FAMILY_ID_PRESENT != 0 {
+            Some(self.file_size_or_family_id)
+        } else {
+            None
+        }
+    }
+}
+
+/// Iterates over the blocks in a UF2 file.
+pub fn read_blocks(input: &[u8]) -> ReadBlocks<'_> {
+    ReadBlocks { input }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReadBlocks<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Iterator for ReadBlocks<'a> {
+    type Item = Result<Block<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+        if self.input.len() < BLOCK_SIZE {
+            self.input = &[];
+            return Some(Err("UF2 file is not a whole number of blocks"));
+        }
+        let (block, rest) = self.input.split_at(BLOCK_SIZE);
+        self.input = rest;
+        Some(parse_block(block))
+    }
+}
+
+fn parse_block(block: &[u8]) -> Result<Block<'_>, &'static str> {
+    let word = |i: usize| u32::from_le_bytes([block[i], block[i + 1], block[i + 2], block[i + 3]]);
+    check!(word(0) == MAGIC_START0 && word(4) == MAGIC_START1 && word(BLOCK_SIZE - 4) == MAGIC_END,
+           "Invalid UF2 block magic");
+    let payload_size = word(16) as usize;
+    check!(payload_size <= MAX_PAYLOAD_SIZE, "UF2 payload is too large");
+    let block_no = word(20);
+    let num_blocks = word(24);
+    check!(block_no < num_blocks, "UF2 block number out of range");
+    Ok(Block {
+        flags: word(8),
+        target_address: word(12),
+        block_no,
+        num_blocks,
+        file_size_or_family_id: word(28),
+        payload: &block[32..32 + payload_size],
+    })
+}
+
+/// Iterates over the data in a UF2 file which is meant for main flash, i.e.,
+/// skipping file container blocks and blocks marked as not for main flash.
+pub fn data_chunks(input: &[u8]) -> DataChunks<'_> {
+    DataChunks { blocks: read_blocks(input) }
+}
+
+#[derive(Clone, Debug)]
+pub struct DataChunks<'a> {
+    blocks: ReadBlocks<'a>,
+}
+
+impl<'a> Iterator for DataChunks<'a> {
+    type Item = Result<(u64, ::core::iter::Cloned<::core::slice::Iter<'a, u8>>), &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.blocks.next()? {
+                Ok(block) => {
+                    if block.flags & (FLAG_NOT_MAIN_FLASH | FLAG_FILE_CONTAINER) == 0 {
+                        return Some(Ok((block.target_address as u64, block.payload.iter().cloned())));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Checks that a UF2 file holds exactly the data in `segments`, with
+/// consistent block numbering and, if given, the expected family ID in every
+/// block.
+pub fn verify<'a, S>(segments: S, input: &[u8], family_id: Option<u32>) -> Result<(), VerifyError>
+    where S: Iterator<Item = Result<LoadSegment<'a>, &'static str>> + Clone
+{
+    let mut num_blocks = None;
+    for (i, block) in read_blocks(input).enumerate() {
+        let block = block.map_err(VerifyError::Malformed)?;
+        if block.block_no as usize != i || *num_blocks.get_or_insert(block.num_blocks) != block.num_blocks {
+            return Err(VerifyError::Malformed("UF2 blocks are not numbered consistently"));
+        }
+        if family_id.is_some() && block.family_id() != family_id {
+            return Err(VerifyError::Malformed("UF2 block has the wrong family ID"));
+        }
+    }
+    if num_blocks.is_some_and(|n| n as usize != input.len() / BLOCK_SIZE) {
+        return Err(VerifyError::Malformed("UF2 file has the wrong number of blocks"));
+    }
+
+    image::verify_padded(segments, data_chunks(input), 0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let flash = [0x11; 300];
+        let ram = [0x22; 8];
+        let segments = [LoadSegment { address: 0x1000_0080, data: &flash },
+                        LoadSegment { address: 0x1000_0400, data: &ram }];
+        let blocks = blocks(segments.iter().cloned().map(Ok), Some(FAMILY_ID_RP2040)).unwrap();
+        assert_eq!(blocks.num_blocks(), 3);
+        let file: Vec<u8> = blocks.flat_map(|b| b.to_vec()).collect();
+
+        let read: Vec<_> = read_blocks(&file).map(|b| b.unwrap()).collect();
+        assert_eq!(read.iter().map(|b| b.target_address).collect::<Vec<_>>(),
+                   [0x1000_0000, 0x1000_0100, 0x1000_0400]);
+        assert_eq!(read[0].family_id(), Some(FAMILY_ID_RP2040));
+        assert_eq!(read[0].payload[0x7f..0x81], [0, 0x11]);
+        assert_eq!(verify(segments.iter().cloned().map(Ok), &file, Some(FAMILY_ID_RP2040)), Ok(()));
+        assert_eq!(verify(segments[..1].iter().cloned().map(Ok), &file, None),
+                   Err(VerifyError::Differs { address: 0x1000_0400, expected: 0, found: 0x22 }));
+    }
+}
@@ -0,0 +1,924 @@
+//! Stack unwinding with the call frame information in `.eh_frame` or
+//! `.debug_frame`: given a frame's registers, running the FDE's instructions
+//! gives the rules for finding its caller's. Memory is read with a callback,
+//! so a stack can be unwound in a core file (see `core_dump::CoreFile`) as
+//! well as in a live process.
+//!
+//! ```ignore
+//! let unwinder = Unwinder::new(&elf_file, 0)?;
+//! let mut registers = Registers::from_core(&thread.registers)?;
+//! let mut read_memory = |address, buf: &mut [u8]| core_file.read_memory(address, buf).is_ok();
+//! while let Some(caller) = unwinder.step(&registers, &mut read_memory)? {
+//!     registers = caller;
+//! }
+//! ```
+//!
+//! Only x86-64 and AArch64 are supported.
+
+use core::convert::TryFrom;
+
+use ElfFile;
+use core_dump::{self, Arch};
+use eh_frame::{self, EhFrame, EhFrameHdr, Fde, DW_EH_PE_ABSPTR, DW_EH_PE_OMIT};
+use reader::Reader;
+
+/// The number of registers tracked: the general registers, the stack
+/// pointer and the program counter, by their DWARF numbers.
+pub const MAX_REGISTERS: usize = 33;
+
+// How deeply `DW_CFA_remember_state` can nest.
+const MAX_STATES: usize = 8;
+// The sizes of the DWARF expression stack, and how many operations an
+// expression may run, so that loops end.
+const MAX_STACK: usize = 64;
+const MAX_OPERATIONS: usize = 10000;
+
+/// A frame's registers, by their DWARF numbers. Unknown registers are `None`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Registers {
+    arch: Arch,
+    values: [Option<u64>; MAX_REGISTERS],
+    // Whether the program counter is a return address, which is after the
+    // call, rather than the instruction which was interrupted.
+    pc_is_return_address: bool,
+    pointer_auth_mask: u64,
+}
+
+impl Registers {
+    /// No known registers, for an innermost frame.
+    pub fn new(arch: Arch) -> Result<Registers, &'static str> {
+        check!(arch == Arch::X86_64 || arch == Arch::AArch64, "Unsupported architecture");
+        Ok(Registers {
+            arch,
+            values: [None; MAX_REGISTERS],
+            pc_is_return_address: false,
+            pointer_auth_mask: 0,
+        })
+    }
+
+    /// The registers of a thread in a core file.
+    pub fn from_core(core_registers: &core_dump::Registers) -> Result<Registers, &'static str> {
+        let mut registers = Registers::new(core_registers.arch())?;
+        let names = match registers.arch {
+            Arch::X86_64 => X86_64_REGISTERS,
+            _ => AARCH64_REGISTERS,
+        };
+        for (register, name) in names.iter().enumerate() {
+            registers.values[register] = core_registers.by_name(name);
+        }
+        Ok(registers)
+    }
+
+    pub fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    pub fn get(&self, register: u16) -> Option<u64> {
+        self.values.get(register as usize).copied().flatten()
+    }
+
+    /// Sets a register. Registers past `MAX_REGISTERS` are ignored.
+    pub fn set(&mut self, register: u16, value: Option<u64>) {
+        if let Some(slot) = self.values.get_mut(register as usize) {
+            *slot = value;
+        }
+    }
+
+    pub fn pc(&self) -> Option<u64> {
+        self.get(self.pc_register())
+    }
+
+    pub fn set_pc(&mut self, value: u64) {
+        let register = self.pc_register();
+        self.set(register, Some(value));
+    }
+
+    pub fn sp(&self) -> Option<u64> {
+        self.get(self.sp_register())
+    }
+
+    pub fn set_sp(&mut self, value: u64) {
+        let register = self.sp_register();
+        self.set(register, Some(value));
+    }
+
+    /// The address to look up CFI for: the program counter, or in callers,
+    /// the byte before it, since the return address may be past the end of
+    /// the calling function.
+    pub fn lookup_pc(&self) -> Option<u64> {
+        let pc = self.pc()?;
+        Some(if self.pc_is_return_address { pc.wrapping_sub(1) } else { pc })
+    }
+
+    /// Sets the bits of AArch64 return addresses which hold pointer
+    /// authentication codes, to be cleared in signed ones. The kernel gives
+    /// this in the `NT_ARM_PAC_MASK` note of core files.
+    pub fn set_pointer_auth_mask(&mut self, mask: u64) {
+        self.pointer_auth_mask = mask;
+    }
+
+    fn pc_register(&self) -> u16 {
+        match self.arch {
+            Arch::X86_64 => 16,
+            _ => 32,
+        }
+    }
+
+    fn sp_register(&self) -> u16 {
+        match self.arch {
+            Arch::X86_64 => 7,
+            _ => 31,
+        }
+    }
+}
+
+// The registers' names in `core_dump::Arch::register_names`, by DWARF number.
+const X86_64_REGISTERS: &[&str] = &["rax", "rdx", "rcx", "rbx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10",
+                                    "r11", "r12", "r13", "r14", "r15", "rip"];
+const AARCH64_REGISTERS: &[&str] = &["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11",
+                                     "x12", "x13", "x14", "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22",
+                                     "x23", "x24", "x25", "x26", "x27", "x28", "x29", "x30", "sp", "pc"];
+
+/// How to find the canonical frame address, the value of the stack pointer
+/// at the call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CfaRule<'a> {
+    RegisterOffset { register: u16, offset: i64 },
+    /// A DWARF expression computing it.
+    Expression(&'a [u8]),
+}
+
+/// How to find the value a register had in the caller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterRule<'a> {
+    Undefined,
+    SameValue,
+    /// Saved at this offset from the CFA.
+    Offset(i64),
+    /// The CFA plus this offset.
+    ValOffset(i64),
+    /// In another register.
+    Register(u16),
+    /// Saved at the address a DWARF expression computes, with the CFA pushed
+    /// first.
+    Expression(&'a [u8]),
+    /// The value a DWARF expression computes, with the CFA pushed first.
+    ValExpression(&'a [u8]),
+}
+
+// The rules at one address, which `DW_CFA_remember_state` saves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct State<'a> {
+    cfa: CfaRule<'a>,
+    registers: [Option<RegisterRule<'a>>; MAX_REGISTERS],
+    return_address_signed: bool,
+}
+
+/// The rules for unwinding from some address in a function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Row<'a> {
+    /// The addresses the rules apply to.
+    pub start: u64,
+    pub end: u64,
+    state: State<'a>,
+    return_address_register: u16,
+    is_signal_frame: bool,
+    address_size: u8,
+}
+
+impl<'a> Row<'a> {
+    /// Runs the instructions of `fde` up to `pc`.
+    pub fn new(fde: &Fde<'a>, pc: u64) -> Result<Row<'a>, &'static str> {
+        check!(fde.contains(pc), "Address is not covered by the FDE");
+        let cie = &fde.cie;
+        let mut row = Row {
+            start: fde.pc_begin,
+            end: fde.pc_end(),
+            state: State {
+                cfa: CfaRule::RegisterOffset { register: 0, offset: 0 },
+                registers: [None; MAX_REGISTERS],
+                return_address_signed: false,
+            },
+            return_address_register: u16::try_from(cie.return_address_register)
+                .map_err(|_| "Invalid return address register")?,
+            is_signal_frame: cie.is_signal_frame,
+            address_size: cie.address_size,
+        };
+        let mut program = Program { row: &mut row, fde, pc, initial: None, stack: [None; MAX_STATES], depth: 0 };
+        program.run(cie.initial_instructions)?;
+        program.initial = Some(program.row.state);
+        program.run(fde.instructions)?;
+        Ok(row)
+    }
+
+    pub fn cfa(&self) -> CfaRule<'a> {
+        self.state.cfa
+    }
+
+    /// The rule for `register`. Registers without one are taken to have the
+    /// same value in the caller, as most unwinders do.
+    pub fn register(&self, register: u16) -> RegisterRule<'a> {
+        self.state.registers.get(register as usize).copied().flatten().unwrap_or(RegisterRule::SameValue)
+    }
+
+    /// Whether the return address is signed, for AArch64 pointer
+    /// authentication.
+    pub fn return_address_signed(&self) -> bool {
+        self.state.return_address_signed
+    }
+
+    /// The caller's registers, or `None` if this is the outermost frame.
+    /// `read_memory` fills its buffer from an address, or returns false if
+    /// that memory cannot be read.
+    pub fn unwind<F>(&self, registers: &Registers, mut read_memory: F) -> Result<Option<Registers>, &'static str>
+        where F: FnMut(u64, &mut [u8]) -> bool
+    {
+        let cfa = match self.state.cfa {
+            CfaRule::RegisterOffset { register, offset } => {
+                let value = registers.get(register).ok_or("CFA register is unknown")?;
+                value.wrapping_add(offset as u64)
+            }
+            CfaRule::Expression(expression) => {
+                evaluate(expression, None, registers, &mut read_memory, self.address_size)?
+            }
+        };
+
+        let mut caller = *registers;
+        for register in 0..MAX_REGISTERS as u16 {
+            let value = match self.register(register) {
+                RegisterRule::Undefined => None,
+                RegisterRule::SameValue => registers.get(register),
+                RegisterRule::Offset(offset) => Some(read_word(&mut read_memory, cfa.wrapping_add(offset as u64))?),
+                RegisterRule::ValOffset(offset) => Some(cfa.wrapping_add(offset as u64)),
+                RegisterRule::Register(other) => registers.get(other),
+                RegisterRule::Expression(expression) => {
+                    let address = evaluate(expression, Some(cfa), registers, &mut read_memory, self.address_size)?;
+                    Some(read_word(&mut read_memory, address)?)
+                }
+                RegisterRule::ValExpression(expression) => {
+                    Some(evaluate(expression, Some(cfa), registers, &mut read_memory, self.address_size)?)
+                }
+            };
+            caller.set(register, value);
+        }
+        if self.state.registers.get(registers.sp_register() as usize).copied().flatten().is_none() {
+            caller.set_sp(cfa);
+        }
+
+        let mut return_address = match caller.get(self.return_address_register) {
+            Some(0) | None => return Ok(None),
+            Some(address) => address,
+        };
+        if self.state.return_address_signed {
+            return_address &= !registers.pointer_auth_mask;
+        }
+        caller.set_pc(return_address);
+        caller.pc_is_return_address = !self.is_signal_frame;
+        Ok(Some(caller))
+    }
+}
+
+// Runs call frame instructions.
+struct Program<'r, 'f, 'a: 'r + 'f> {
+    row: &'r mut Row<'a>,
+    fde: &'f Fde<'a>,
+    pc: u64,
+    // The state after the CIE's instructions, for `DW_CFA_restore`.
+    initial: Option<State<'a>>,
+    stack: [Option<State<'a>>; MAX_STATES],
+    depth: usize,
+}
+
+impl<'r, 'f, 'a> Program<'r, 'f, 'a> {
+    fn run(&mut self, instructions: &'a [u8]) -> Result<(), &'static str> {
+        let cie = &self.fde.cie;
+        let data_alignment = |factored: i64| factored.wrapping_mul(cie.data_alignment_factor);
+        let mut reader = Reader::new(instructions);
+        while !reader.data.is_empty() {
+            let opcode = reader.u8()?;
+            let operand = opcode & 0x3f;
+            match opcode >> 6 {
+                // DW_CFA_advance_loc
+                1 => {
+                    if self.advance(operand as u64)? {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                // DW_CFA_offset
+                2 => {
+                    let offset = data_alignment(reader.uleb()? as i64);
+                    self.set_rule(operand as u64, RegisterRule::Offset(offset));
+                    continue;
+                }
+                // DW_CFA_restore
+                3 => {
+                    self.restore(operand as u64)?;
+                    continue;
+                }
+                _ => {}
+            }
+            let done = match opcode {
+                // DW_CFA_nop
+                0x00 => false,
+                // DW_CFA_set_loc
+                0x01 => {
+                    let encoding = if self.fde.cie.fde_encoding == DW_EH_PE_OMIT {
+                        DW_EH_PE_ABSPTR
+                    } else {
+                        self.fde.cie.fde_encoding
+                    };
+                    check!(encoding & 0x70 == 0, "Unsupported DW_CFA_set_loc encoding");
+                    let address = eh_frame::read_value(&mut reader, encoding & 0x0f, cie.address_size)?;
+                    check!(address >= self.row.start, "DW_CFA_set_loc moves backwards");
+                    self.move_to(address)
+                }
+                // DW_CFA_advance_loc1, 2 and 4
+                0x02 => self.advance(reader.u8()? as u64)?,
+                0x03 => self.advance(reader.u16()? as u64)?,
+                0x04 => self.advance(reader.u32()? as u64)?,
+                // DW_CFA_offset_extended
+                0x05 => {
+                    let register = reader.uleb()?;
+                    let offset = data_alignment(reader.uleb()? as i64);
+                    self.set_rule(register, RegisterRule::Offset(offset));
+                    false
+                }
+                // DW_CFA_restore_extended
+                0x06 => {
+                    self.restore(reader.uleb()?)?;
+                    false
+                }
+                // DW_CFA_undefined
+                0x07 => {
+                    self.set_rule(reader.uleb()?, RegisterRule::Undefined);
+                    false
+                }
+                // DW_CFA_same_value
+                0x08 => {
+                    self.set_rule(reader.uleb()?, RegisterRule::SameValue);
+                    false
+                }
+                // DW_CFA_register
+                0x09 => {
+                    let register = reader.uleb()?;
+                    let other = register_number(reader.uleb()?)?;
+                    self.set_rule(register, RegisterRule::Register(other));
+                    false
+                }
+                // DW_CFA_remember_state
+                0x0a => {
+                    check!(self.depth < MAX_STATES, "DW_CFA_remember_state nested too deeply");
+                    self.stack[self.depth] = Some(self.row.state);
+                    self.depth += 1;
+                    false
+                }
+                // DW_CFA_restore_state
+                0x0b => {
+                    check!(self.depth > 0, "DW_CFA_restore_state without DW_CFA_remember_state");
+                    self.depth -= 1;
+                    self.row.state = self.stack[self.depth].take().unwrap();
+                    false
+                }
+                // DW_CFA_def_cfa
+                0x0c => {
+                    let register = register_number(reader.uleb()?)?;
+                    let offset = reader.uleb()? as i64;
+                    self.row.state.cfa = CfaRule::RegisterOffset { register, offset };
+                    false
+                }
+                // DW_CFA_def_cfa_register
+                0x0d => {
+                    let register = register_number(reader.uleb()?)?;
+                    match self.row.state.cfa {
+                        CfaRule::RegisterOffset { register: ref mut cfa_register, .. } => *cfa_register = register,
+                        CfaRule::Expression(_) => return Err("DW_CFA_def_cfa_register with a CFA expression"),
+                    }
+                    false
+                }
+                // DW_CFA_def_cfa_offset
+                0x0e => {
+                    self.set_cfa_offset(reader.uleb()? as i64)?;
+                    false
+                }
+                // DW_CFA_def_cfa_expression
+                0x0f => {
+                    let length = reader.uleb()?;
+                    self.row.state.cfa = CfaRule::Expression(reader.bytes(length)?);
+                    false
+                }
+                // DW_CFA_expression and DW_CFA_val_expression
+                0x10 | 0x16 => {
+                    let register = reader.uleb()?;
+                    let length = reader.uleb()?;
+                    let expression = reader.bytes(length)?;
+                    let rule = if opcode == 0x10 {
+                        RegisterRule::Expression(expression)
+                    } else {
+                        RegisterRule::ValExpression(expression)
+                    };
+                    self.set_rule(register, rule);
+                    false
+                }
+                // DW_CFA_offset_extended_sf
+                0x11 => {
+                    let register = reader.uleb()?;
+                    let offset = data_alignment(reader.sleb()?);
+                    self.set_rule(register, RegisterRule::Offset(offset));
+                    false
+                }
+                // DW_CFA_def_cfa_sf
+                0x12 => {
+                    let register = register_number(reader.uleb()?)?;
+                    let offset = data_alignment(reader.sleb()?);
+                    self.row.state.cfa = CfaRule::RegisterOffset { register, offset };
+                    false
+                }
+                // DW_CFA_def_cfa_offset_sf
+                0x13 => {
+                    let offset = data_alignment(reader.sleb()?);
+                    self.set_cfa_offset(offset)?;
+                    false
+                }
+                // DW_CFA_val_offset and DW_CFA_val_offset_sf
+                0x14 | 0x15 => {
+                    let register = reader.uleb()?;
+                    let factored = if opcode == 0x14 { reader.uleb()? as i64 } else { reader.sleb()? };
+                    self.set_rule(register, RegisterRule::ValOffset(data_alignment(factored)));
+                    false
+                }
+                // DW_CFA_AARCH64_negate_ra_state, which is DW_CFA_GNU_window_save
+                // on SPARC.
+                0x2d => {
+                    self.row.state.return_address_signed = !self.row.state.return_address_signed;
+                    false
+                }
+                // DW_CFA_GNU_args_size
+                0x2e => {
+                    let _ = reader.uleb()?;
+                    false
+                }
+                // DW_CFA_GNU_negative_offset_extended
+                0x2f => {
+                    let register = reader.uleb()?;
+                    let offset = data_alignment((reader.uleb()? as i64).wrapping_neg());
+                    self.set_rule(register, RegisterRule::Offset(offset));
+                    false
+                }
+                _ => return Err("Unknown call frame instruction"),
+            };
+            if done {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    // Advances the location by `delta` code alignment units, returning true
+    // if that passes `pc`.
+    fn advance(&mut self, delta: u64) -> Result<bool, &'static str> {
+        check!(self.initial.is_some(), "CIE instructions advance the location");
+        let address = self.row.start.wrapping_add(delta.wrapping_mul(self.fde.cie.code_alignment_factor));
+        Ok(self.move_to(address))
+    }
+
+    fn move_to(&mut self, address: u64) -> bool {
+        if address > self.pc {
+            self.row.end = address;
+            true
+        } else {
+            self.row.start = address;
+            false
+        }
+    }
+
+    // Registers which are not tracked are ignored.
+    fn set_rule(&mut self, register: u64, rule: RegisterRule<'a>) {
+        if let Some(slot) = self.row.state.registers.get_mut(register as usize) {
+            *slot = Some(rule);
+        }
+    }
+
+    fn restore(&mut self, register: u64) -> Result<(), &'static str> {
+        let initial = self.initial.ok_or("DW_CFA_restore in a CIE")?;
+        if let Some(slot) = self.row.state.registers.get_mut(register as usize) {
+            *slot = initial.registers[register as usize];
+        }
+        Ok(())
+    }
+
+    fn set_cfa_offset(&mut self, new_offset: i64) -> Result<(), &'static str> {
+        match self.row.state.cfa {
+            CfaRule::RegisterOffset { ref mut offset, .. } => {
+                *offset = new_offset;
+                Ok(())
+            }
+            CfaRule::Expression(_) => Err("DW_CFA_def_cfa_offset with a CFA expression"),
+        }
+    }
+}
+
+fn register_number(register: u64) -> Result<u16, &'static str> {
+    u16::try_from(register).map_err(|_| "Invalid register number")
+}
+
+fn read_word<F>(read_memory: &mut F, address: u64) -> Result<u64, &'static str>
+    where F: FnMut(u64, &mut [u8]) -> bool
+{
+    let mut buf = [0; 8];
+    check!(read_memory(address, &mut buf), "Memory cannot be read");
+    Ok(u64::from_ne_bytes(buf))
+}
+
+// Evaluates a DWARF expression, as allowed in call frame information.
+fn evaluate<F>(expression: &[u8],
+               initial: Option<u64>,
+               registers: &Registers,
+               read_memory: &mut F,
+               address_size: u8)
+               -> Result<u64, &'static str>
+    where F: FnMut(u64, &mut [u8]) -> bool
+{
+    let mut stack = [0u64; MAX_STACK];
+    let mut depth = 0;
+    macro_rules! push {
+        ($value:expr) => {{
+            let value = $value;
+            check!(depth < MAX_STACK, "DWARF expression stack overflow");
+            stack[depth] = value;
+            depth += 1;
+        }}
+    }
+    macro_rules! pop {
+        () => {{
+            check!(depth > 0, "DWARF expression stack underflow");
+            depth -= 1;
+            stack[depth]
+        }}
+    }
+    if let Some(value) = initial {
+        push!(value);
+    }
+
+    let mut reader = Reader::new(expression);
+    let mut operations = 0;
+    while !reader.data.is_empty() {
+        operations += 1;
+        check!(operations <= MAX_OPERATIONS, "DWARF expression does not end");
+        let opcode = reader.u8()?;
+        match opcode {
+            // DW_OP_addr
+            0x03 => push!(if address_size == 4 { reader.u32()? as u64 } else { reader.u64()? }),
+            // DW_OP_deref
+            0x06 => {
+                let address = pop!();
+                push!(read_word(read_memory, address)?);
+            }
+            // DW_OP_const1u, 1s, 2u, 2s, 4u, 4s, 8u, 8s, u and s
+            0x08 => push!(reader.u8()? as u64),
+            0x09 => push!(reader.u8()? as i8 as u64),
+            0x0a => push!(reader.u16()? as u64),
+            0x0b => push!(reader.u16()? as i16 as u64),
+            0x0c => push!(reader.u32()? as u64),
+            0x0d => push!(reader.u32()? as i32 as u64),
+            0x0e | 0x0f => push!(reader.u64()?),
+            0x10 => push!(reader.uleb()?),
+            0x11 => push!(reader.sleb()? as u64),
+            // DW_OP_dup
+            0x12 => {
+                let value = pop!();
+                push!(value);
+                push!(value);
+            }
+            // DW_OP_drop
+            0x13 => {
+                let _ = pop!();
+            }
+            // DW_OP_over and DW_OP_pick
+            0x14 | 0x15 => {
+                let index = if opcode == 0x14 { 1 } else { reader.u8()? as usize };
+                check!(index < depth, "DWARF expression stack underflow");
+                push!(stack[depth - 1 - index]);
+            }
+            // DW_OP_swap
+            0x16 => {
+                check!(depth >= 2, "DWARF expression stack underflow");
+                stack.swap(depth - 1, depth - 2);
+            }
+            // DW_OP_rot
+            0x17 => {
+                check!(depth >= 3, "DWARF expression stack underflow");
+                stack[depth - 3..depth].rotate_right(1);
+            }
+            // DW_OP_abs, DW_OP_neg and DW_OP_not
+            0x19 => {
+                let value = pop!() as i64;
+                push!(value.wrapping_abs() as u64);
+            }
+            0x1f => {
+                let value = pop!();
+                push!(value.wrapping_neg());
+            }
+            0x20 => {
+                let value = pop!();
+                push!(!value);
+            }
+            // DW_OP_plus_uconst
+            0x23 => {
+                let value = pop!();
+                push!(value.wrapping_add(reader.uleb()?));
+            }
+            // Binary operations.
+            0x1a..=0x1e | 0x21 | 0x22 | 0x24..=0x27 | 0x29..=0x2e => {
+                let b = pop!();
+                let a = pop!();
+                push!(match opcode {
+                    0x1a => a & b,
+                    0x1b => {
+                        check!(b != 0, "Division by zero in DWARF expression");
+                        (a as i64).wrapping_div(b as i64) as u64
+                    }
+                    0x1c => a.wrapping_sub(b),
+                    0x1d => {
+                        check!(b != 0, "Division by zero in DWARF expression");
+                        a % b
+                    }
+                    0x1e => a.wrapping_mul(b),
+                    0x21 => a | b,
+                    0x22 => a.wrapping_add(b),
+                    0x24 => a.checked_shl(b as u32).filter(|_| b < 64).unwrap_or(0),
+                    0x25 => a.checked_shr(b as u32).filter(|_| b < 64).unwrap_or(0),
+                    0x26 => (a as i64).wrapping_shr(b.min(63) as u32) as u64,
+                    0x27 => a ^ b,
+                    0x29 => (a == b) as u64,
+                    0x2a => (a as i64 >= b as i64) as u64,
+                    0x2b => (a as i64 > b as i64) as u64,
+                    0x2c => (a as i64 <= b as i64) as u64,
+                    0x2d => ((a as i64) < b as i64) as u64,
+                    _ => (a != b) as u64,
+                });
+            }
+            // DW_OP_skip and DW_OP_bra
+            0x2f | 0x28 => {
+                let offset = reader.u16()? as i16;
+                if opcode == 0x2f || pop!() != 0 {
+                    let position = (expression.len() - reader.data.len()) as isize + offset as isize;
+                    check!(position >= 0 && position as usize <= expression.len(),
+                           "DWARF expression branch out of range");
+                    reader = Reader::new(&expression[position as usize..]);
+                }
+            }
+            // DW_OP_lit0 to DW_OP_lit31
+            0x30..=0x4f => push!((opcode - 0x30) as u64),
+            // DW_OP_breg0 to DW_OP_breg31, and DW_OP_bregx
+            0x70..=0x8f | 0x92 => {
+                let register = match opcode {
+                    0x92 => register_number(reader.uleb()?)?,
+                    _ => (opcode - 0x70) as u16,
+                };
+                let offset = reader.sleb()?;
+                let value = registers.get(register).ok_or("Register in DWARF expression is unknown")?;
+                push!(value.wrapping_add(offset as u64));
+            }
+            // DW_OP_deref_size
+            0x94 => {
+                let size = reader.u8()? as usize;
+                check!((1..=8).contains(&size), "Invalid DW_OP_deref_size size");
+                let address = pop!();
+                let mut buf = [0; 8];
+                let bytes = if cfg!(target_endian = "little") { &mut buf[..size] } else { &mut buf[8 - size..] };
+                check!(read_memory(address, bytes), "Memory cannot be read");
+                push!(u64::from_ne_bytes(buf));
+            }
+            // DW_OP_nop
+            0x96 => {}
+            _ => return Err("Unsupported DWARF expression operation"),
+        }
+    }
+    Ok(pop!())
+}
+
+/// Unwinds through one file: finds the FDE for each frame in its
+/// `.eh_frame`, or failing that, `.debug_frame`.
+#[derive(Clone, Copy, Debug)]
+pub struct Unwinder<'a> {
+    eh_frame: Option<EhFrame<'a>>,
+    header: Option<EhFrameHdr<'a>>,
+    debug_frame: Option<EhFrame<'a>>,
+    bias: u64,
+}
+
+impl<'a> Unwinder<'a> {
+    /// `bias` is the difference between where `elf_file` was loaded and
+    /// its addresses, as for shared libraries and position independent
+    /// executables.
+    pub fn new(elf_file: &ElfFile<'a>, bias: u64) -> Result<Unwinder<'a>, &'static str> {
+        let eh_frame = EhFrame::load(elf_file)?;
+        let header = match eh_frame {
+            Some(_) => EhFrameHdr::load(elf_file)?.filter(|header| header.fde_count > 0),
+            None => None,
+        };
+        // A compressed `.debug_frame` is only needed if there is no
+        // `.eh_frame`.
+        let debug_frame = match EhFrame::load_debug_frame(elf_file) {
+            Ok(debug_frame) => debug_frame,
+            Err(_) if eh_frame.is_some() => None,
+            Err(e) => return Err(e),
+        };
+        Ok(Unwinder { eh_frame, header, debug_frame, bias })
+    }
+
+    /// Finds the FDE covering `pc`, an address in the file rather than in
+    /// memory.
+    pub fn find_fde(&self, pc: u64) -> Result<Option<Fde<'a>>, &'static str> {
+        if let Some(eh_frame) = self.eh_frame {
+            let fde = match self.header {
+                Some(header) => match header.lookup(pc)? {
+                    Some(address) => {
+                        let offset = address.wrapping_sub(eh_frame.address());
+                        let fde = eh_frame.fde_at(usize::try_from(offset).map_err(|_| "FDE out of range")?)?;
+                        Some(fde).filter(|fde| fde.contains(pc))
+                    }
+                    None => None,
+                },
+                None => eh_frame.find_fde(pc)?,
+            };
+            if fde.is_some() {
+                return Ok(fde);
+            }
+        }
+        match self.debug_frame {
+            Some(debug_frame) => debug_frame.find_fde(pc),
+            None => Ok(None),
+        }
+    }
+
+    /// The caller's registers, or `None` if there is no CFI for the
+    /// program counter or this is the outermost frame. See `Row::unwind`
+    /// for `read_memory`.
+    pub fn step<F>(&self, registers: &Registers, read_memory: F) -> Result<Option<Registers>, &'static str>
+        where F: FnMut(u64, &mut [u8]) -> bool
+    {
+        let pc = registers.lookup_pc().ok_or("Program counter is unknown")?.wrapping_sub(self.bias);
+        let fde = match self.find_fde(pc)? {
+            Some(fde) => fde,
+            None => return Ok(None),
+        };
+        Row::new(&fde, pc)?.unwind(registers, read_memory)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::prelude::v1::*;
+    use super::*;
+    use header::test::mk_elf_header;
+    use sections::test::mk_file;
+
+    #[test]
+    fn unwind() {
+        let mut data = Vec::new();
+        // A CIE: the CFA is rsp + 8, and the return address is at CFA - 8.
+        data.extend_from_slice(&16u32.to_ne_bytes());
+        data.extend_from_slice(&0u32.to_ne_bytes());
+        data.extend_from_slice(&[1, 0, 1, 0x78, 16, 0x0c, 7, 8, 0x90, 1, 0, 0]);
+        // An FDE for 0x2000..0x2100 which pushes rbp, then remembers the
+        // state around another push.
+        data.extend_from_slice(&32u32.to_ne_bytes());
+        data.extend_from_slice(&24u32.to_ne_bytes());
+        data.extend_from_slice(&0x2000u64.to_ne_bytes());
+        data.extend_from_slice(&0x100u64.to_ne_bytes());
+        data.extend_from_slice(&[0x41, 0x0e, 16, 0x86, 2, 0x0a, 0x41, 0x0e, 24, 0x41, 0x0b, 0]);
+        let eh_frame = EhFrame::new(&data, 0x1000, 8);
+        let fde = eh_frame.fde_at(20).unwrap();
+
+        let cfa = |pc| Row::new(&fde, pc).unwrap().cfa();
+        assert_eq!(cfa(0x2000), CfaRule::RegisterOffset { register: 7, offset: 8 });
+        assert_eq!(cfa(0x2002), CfaRule::RegisterOffset { register: 7, offset: 24 });
+        assert_eq!(cfa(0x20ff), CfaRule::RegisterOffset { register: 7, offset: 16 });
+        let row = Row::new(&fde, 0x2001).unwrap();
+        assert_eq!((row.start, row.end), (0x2001, 0x2002));
+        assert_eq!(row.register(6), RegisterRule::Offset(-16));
+        assert_eq!(row.register(3), RegisterRule::SameValue);
+
+        let mut registers = Registers::new(Arch::X86_64).unwrap();
+        registers.set_pc(0x2001);
+        registers.set_sp(0x7000);
+        registers.set(3, Some(1));
+        let stack = |address: u64, buf: &mut [u8]| {
+            let value: u64 = match address {
+                0x6ff8 => 0,
+                0x7000 => 0x4000,
+                0x7008 => 0x3000,
+                _ => return false,
+            };
+            buf.copy_from_slice(&value.to_ne_bytes());
+            true
+        };
+        let caller = row.unwind(&registers, stack).unwrap().unwrap();
+        assert_eq!((caller.pc(), caller.sp()), (Some(0x3000), Some(0x7010)));
+        assert_eq!((caller.get(6), caller.get(3)), (Some(0x4000), Some(1)));
+        assert_eq!(caller.lookup_pc(), Some(0x2fff));
+
+        registers.set_sp(0x6ff8);
+        assert!(Row::new(&fde, 0x2000).unwrap().unwind(&registers, stack).unwrap().is_none());
+        registers.set_sp(0x8000);
+        assert!(Row::new(&fde, 0x2000).unwrap().unwind(&registers, stack).is_err());
+    }
+
+    #[test]
+    fn unwind_aarch64() {
+        if cfg!(target_endian = "big") {
+            return;
+        }
+        let mut data = Vec::new();
+        // A CIE: instructions are four bytes, the CFA is sp, and the return
+        // address is in x30.
+        data.extend_from_slice(&16u32.to_ne_bytes());
+        data.extend_from_slice(&0u32.to_ne_bytes());
+        data.extend_from_slice(&[1, 0, 4, 0x78, 30, 0x0c, 31, 0, 0, 0, 0, 0]);
+        // An FDE for 0x2000..0x2100 which signs the return address, then
+        // saves x29 and x30 below a 16-byte frame.
+        data.extend_from_slice(&32u32.to_ne_bytes());
+        data.extend_from_slice(&24u32.to_ne_bytes());
+        data.extend_from_slice(&0x2000u64.to_ne_bytes());
+        data.extend_from_slice(&0x100u64.to_ne_bytes());
+        data.extend_from_slice(&[0x41, 0x2d, 0x41, 0x0e, 16, 0x9d, 2, 0x9e, 1, 0, 0, 0]);
+        // An FDE for 0x3000..0x3100 whose CFA is the word at sp + 8, plus 16.
+        data.extend_from_slice(&32u32.to_ne_bytes());
+        data.extend_from_slice(&60u32.to_ne_bytes());
+        data.extend_from_slice(&0x3000u64.to_ne_bytes());
+        data.extend_from_slice(&0x100u64.to_ne_bytes());
+        data.extend_from_slice(&[0x0f, 5, 0x8f, 8, 0x06, 0x23, 16, 0x9d, 2, 0x9e, 1, 0]);
+
+        let eh_frame = EhFrame::new(&data, 0x1000, 8);
+        let fde = eh_frame.fde_at(20).unwrap();
+        let row = Row::new(&fde, 0x2000).unwrap();
+        assert_eq!(row.cfa(), CfaRule::RegisterOffset { register: 31, offset: 0 });
+        assert!(!row.return_address_signed());
+        let row = Row::new(&fde, 0x2008).unwrap();
+        assert_eq!(row.cfa(), CfaRule::RegisterOffset { register: 31, offset: 16 });
+        assert_eq!((row.register(29), row.register(30)), (RegisterRule::Offset(-16), RegisterRule::Offset(-8)));
+        assert!(row.return_address_signed());
+        let fde = eh_frame.fde_at(56).unwrap();
+        assert_eq!(Row::new(&fde, 0x3010).unwrap().cfa(), CfaRule::Expression(&[0x8f, 8, 0x06, 0x23, 16]));
+
+        // A core file with one thread, stopped at 0x2008 with sp 0x7000.
+        let arch = Arch::AArch64;
+        let registers_offset = 16 + 2 * 8 + 16 + 8 * 8;
+        let mut desc = vec![0; registers_offset + arch.register_names().len() * 8 + 4];
+        for &(register, value) in &[(29, 0x7100u64), (31, 0x7000), (32, 0x2008)] {
+            let offset = registers_offset + register * 8;
+            desc[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        }
+        let mut core = mk_elf_header(2);
+        core[16..18].copy_from_slice(&4u16.to_le_bytes());
+        core[18..20].copy_from_slice(&183u16.to_le_bytes());
+        core[32..40].copy_from_slice(&64u64.to_le_bytes());
+        core[54..56].copy_from_slice(&56u16.to_le_bytes());
+        core[56..58].copy_from_slice(&1u16.to_le_bytes());
+        let notes_size = 12 + 8 + desc.len() as u64;
+        for &(value, size) in &[(4, 4), (0, 4), (120, 8), (0, 8), (0, 8), (notes_size, 8), (0, 8), (4, 8),
+                                (5, 4), (desc.len() as u64, 4), (1, 4)] {
+            core.extend_from_slice(&value.to_le_bytes()[..size]);
+        }
+        core.extend_from_slice(b"CORE\0\0\0\0");
+        core.extend_from_slice(&desc);
+        let core_elf = ElfFile::new(&core).unwrap();
+        let core_file = core_dump::CoreFile::new(&core_elf).unwrap();
+        let thread = core_file.threads().next().unwrap().unwrap();
+        let mut registers = Registers::from_core(&thread.registers).unwrap();
+        assert_eq!(registers.arch(), Arch::AArch64);
+        assert_eq!((registers.get(31), registers.get(32)), (Some(0x7000), Some(0x2008)));
+        assert_eq!((registers.sp(), registers.pc(), registers.get(29)), (Some(0x7000), Some(0x2008), Some(0x7100)));
+        assert_eq!(registers.get(30), Some(0));
+        registers.set_pointer_auth_mask(0xff7f_0000_0000_0000);
+
+        let input = mk_file(&[(".eh_frame", &data)]);
+        let elf_file = ElfFile::new(&input).unwrap();
+        let unwinder = Unwinder::new(&elf_file, 0).unwrap();
+        let stack = |address: u64, buf: &mut [u8]| {
+            let value: u64 = match address {
+                // The first frame's x29, and x30 with an authentication code.
+                0x7000 => 0x7100,
+                0x7008 => 0x0012_0000_0000_3010,
+                // The second frame's CFA, x29 and x30, which is not signed.
+                0x7018 => 0x7100,
+                0x7100 => 0,
+                0x7108 => 0x5000,
+                _ => return false,
+            };
+            buf.copy_from_slice(&value.to_ne_bytes());
+            true
+        };
+        let caller = unwinder.step(&registers, stack).unwrap().unwrap();
+        assert_eq!((caller.get(32), caller.get(31)), (Some(0x3010), Some(0x7010)));
+        assert_eq!((caller.get(29), caller.get(30)), (Some(0x7100), Some(0x0012_0000_0000_3010)));
+        assert_eq!(caller.lookup_pc(), Some(0x300f));
+
+        let caller = unwinder.step(&caller, stack).unwrap().unwrap();
+        assert_eq!((caller.pc(), caller.sp()), (Some(0x5000), Some(0x7110)));
+        assert_eq!(caller.get(29), Some(0));
+        assert!(unwinder.step(&caller, stack).unwrap().is_none());
+    }
+}